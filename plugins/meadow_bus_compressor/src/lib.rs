@@ -0,0 +1,311 @@
+use atomic_float::AtomicF32;
+use meadow_bus_compressor_dsp::{Attack, BusCompressorDsp, BusCompressorParams, Ratio, Release};
+use meadow_meter_editor::EguiState;
+use nih_plug::prelude::*;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+
+#[cfg(test)]
+mod tests;
+
+/// Where the editor's meter ends. Bus compression rarely goes past a few decibels.
+const MAX_METER_DB: f32 = 20.0;
+
+pub struct MeadowBusCompressor {
+    params: Arc<MeadowBusCompressorParams>,
+    dsp: BusCompressorDsp,
+
+    /// The gain reduction during the last processed block in decibels. Shared with the
+    /// editor's meter.
+    gain_reduction_db: Arc<AtomicF32>,
+}
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+enum RatioParam {
+    #[id = "2"]
+    #[name = "2:1"]
+    R2,
+    #[id = "4"]
+    #[name = "4:1"]
+    R4,
+    #[id = "10"]
+    #[name = "10:1"]
+    R10,
+}
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+enum AttackParam {
+    #[id = "0.1ms"]
+    #[name = "0.1 ms"]
+    Ms0_1,
+    #[id = "0.3ms"]
+    #[name = "0.3 ms"]
+    Ms0_3,
+    #[id = "1ms"]
+    #[name = "1 ms"]
+    Ms1,
+    #[id = "3ms"]
+    #[name = "3 ms"]
+    Ms3,
+    #[id = "10ms"]
+    #[name = "10 ms"]
+    Ms10,
+    #[id = "30ms"]
+    #[name = "30 ms"]
+    Ms30,
+}
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+enum ReleaseParam {
+    #[id = "0.1s"]
+    #[name = "0.1 s"]
+    Ms100,
+    #[id = "0.3s"]
+    #[name = "0.3 s"]
+    Ms300,
+    #[id = "0.6s"]
+    #[name = "0.6 s"]
+    Ms600,
+    #[id = "1.2s"]
+    #[name = "1.2 s"]
+    Ms1200,
+    #[id = "auto"]
+    Auto,
+}
+
+#[derive(Params)]
+struct MeadowBusCompressorParams {
+    #[persist = "editor-state"]
+    editor_state: Arc<EguiState>,
+
+    #[id = "threshold_db"]
+    pub threshold_db: FloatParam,
+    #[id = "ratio"]
+    pub ratio: EnumParam<RatioParam>,
+    #[id = "attack"]
+    pub attack: EnumParam<AttackParam>,
+    #[id = "release"]
+    pub release: EnumParam<ReleaseParam>,
+    #[id = "makeup_db"]
+    pub makeup_db: FloatParam,
+    #[id = "mix"]
+    pub mix: FloatParam,
+
+    #[id = "sc_hpf_enabled"]
+    pub sc_hpf_enabled: BoolParam,
+    #[id = "sc_hpf_hz"]
+    pub sc_hpf_hz: FloatParam,
+}
+
+impl Default for MeadowBusCompressor {
+    fn default() -> Self {
+        Self {
+            params: Arc::new(MeadowBusCompressorParams::default()),
+            dsp: BusCompressorDsp::new(44_100.0),
+            gain_reduction_db: Arc::new(AtomicF32::new(0.0)),
+        }
+    }
+}
+
+impl MeadowBusCompressor {
+    /// The gain reduction during the last processed block as a positive number of decibels.
+    /// Meant for a gain reduction meter.
+    pub fn gain_reduction_db(&self) -> Arc<AtomicF32> {
+        self.gain_reduction_db.clone()
+    }
+
+    /// Compresses one block and updates the meter. This is everything [`Plugin::process()`]
+    /// does once the channels are taken out of the buffer.
+    fn process_block(&mut self, out_l: &mut [f32], out_r: &mut [f32]) {
+        self.dsp.set_params(self.params.bus_compressor_params());
+        self.dsp.process(out_l, out_r);
+        self.gain_reduction_db
+            .store(self.dsp.gain_reduction_db(), Ordering::Relaxed);
+    }
+}
+
+impl Default for MeadowBusCompressorParams {
+    fn default() -> Self {
+        let defaults = BusCompressorParams::default();
+
+        Self {
+            editor_state: meadow_meter_editor::default_state(),
+
+            threshold_db: FloatParam::new(
+                "Threshold",
+                defaults.threshold_db,
+                FloatRange::Linear {
+                    min: -40.0,
+                    max: 0.0,
+                },
+            )
+            .with_unit(" dB"),
+            ratio: EnumParam::new("Ratio", RatioParam::R2),
+            attack: EnumParam::new("Attack", AttackParam::Ms10),
+            release: EnumParam::new("Release", ReleaseParam::Auto),
+            makeup_db: FloatParam::new(
+                "Makeup",
+                defaults.makeup_db,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 20.0,
+                },
+            )
+            .with_unit(" dB"),
+            mix: FloatParam::new(
+                "Mix",
+                defaults.mix * 100.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 100.0,
+                },
+            )
+            .with_unit(" %")
+            .with_step_size(1.0),
+
+            sc_hpf_enabled: BoolParam::new("Sidechain HPF enabled", defaults.sidechain_hpf_enabled),
+            sc_hpf_hz: FloatParam::new(
+                "Sidechain HPF cutoff",
+                defaults.sidechain_hpf_hz,
+                FloatRange::Skewed {
+                    min: 20.0,
+                    max: 500.0,
+                    factor: FloatRange::skew_factor(-1.0),
+                },
+            )
+            .with_unit(" Hz"),
+        }
+    }
+}
+
+impl MeadowBusCompressorParams {
+    /// Collects the current parameter values into the form used by the DSP.
+    pub fn bus_compressor_params(&self) -> BusCompressorParams {
+        BusCompressorParams {
+            threshold_db: self.threshold_db.value(),
+            ratio: match self.ratio.value() {
+                RatioParam::R2 => Ratio::R2,
+                RatioParam::R4 => Ratio::R4,
+                RatioParam::R10 => Ratio::R10,
+            },
+            attack: match self.attack.value() {
+                AttackParam::Ms0_1 => Attack::Ms0_1,
+                AttackParam::Ms0_3 => Attack::Ms0_3,
+                AttackParam::Ms1 => Attack::Ms1,
+                AttackParam::Ms3 => Attack::Ms3,
+                AttackParam::Ms10 => Attack::Ms10,
+                AttackParam::Ms30 => Attack::Ms30,
+            },
+            release: match self.release.value() {
+                ReleaseParam::Ms100 => Release::Ms100,
+                ReleaseParam::Ms300 => Release::Ms300,
+                ReleaseParam::Ms600 => Release::Ms600,
+                ReleaseParam::Ms1200 => Release::Ms1200,
+                ReleaseParam::Auto => Release::Auto,
+            },
+            makeup_db: self.makeup_db.value(),
+            mix: self.mix.value() / 100.0,
+            sidechain_hpf_enabled: self.sc_hpf_enabled.value(),
+            sidechain_hpf_hz: self.sc_hpf_hz.value(),
+        }
+    }
+}
+
+impl Plugin for MeadowBusCompressor {
+    const NAME: &'static str = "Meadow Bus Compressor";
+    const VENDOR: &'static str = "Billy Messenger";
+    const URL: &'static str = env!("CARGO_PKG_HOMEPAGE");
+    const EMAIL: &'static str = "60663878+BillyDM@users.noreply.github.com";
+
+    const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+
+    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[AudioIOLayout {
+        main_input_channels: NonZeroU32::new(2),
+        main_output_channels: NonZeroU32::new(2),
+
+        aux_input_ports: &[],
+        aux_output_ports: &[],
+
+        names: PortNames::const_default(),
+    }];
+
+    const MIDI_INPUT: MidiConfig = MidiConfig::None;
+    const MIDI_OUTPUT: MidiConfig = MidiConfig::None;
+
+    const SAMPLE_ACCURATE_AUTOMATION: bool = true;
+
+    type SysExMessage = ();
+    type BackgroundTask = ();
+
+    fn params(&self) -> Arc<dyn Params> {
+        self.params.clone()
+    }
+
+    fn editor(&mut self, _async_executor: AsyncExecutor<Self>) -> Option<Box<dyn Editor>> {
+        meadow_meter_editor::create(
+            self.params.clone(),
+            self.params.editor_state.clone(),
+            self.gain_reduction_db.clone(),
+            MAX_METER_DB,
+        )
+    }
+
+    fn initialize(
+        &mut self,
+        _audio_io_layout: &AudioIOLayout,
+        config: &BufferConfig,
+        _context: &mut impl InitContext<Self>,
+    ) -> bool {
+        self.dsp = BusCompressorDsp::new(config.sample_rate as f64);
+        self.dsp.set_params(self.params.bus_compressor_params());
+        self.dsp.reset();
+
+        true
+    }
+
+    fn reset(&mut self) {
+        self.dsp.reset();
+        self.gain_reduction_db.store(0.0, Ordering::Relaxed);
+    }
+
+    fn process(
+        &mut self,
+        buffer: &mut Buffer,
+        _aux: &mut AuxiliaryBuffers,
+        _context: &mut impl ProcessContext<Self>,
+    ) -> ProcessStatus {
+        let [out_l, out_r, ..] = buffer.as_slice() else {
+            return ProcessStatus::Normal;
+        };
+
+        self.process_block(out_l, out_r);
+
+        ProcessStatus::Normal
+    }
+}
+
+impl ClapPlugin for MeadowBusCompressor {
+    const CLAP_ID: &'static str = "app.meadowlark.meadow-bus-compressor";
+    const CLAP_DESCRIPTION: Option<&'static str> =
+        Some("A glue compressor in the style of classic console bus compressors");
+    const CLAP_MANUAL_URL: Option<&'static str> = Some(Self::URL);
+    const CLAP_SUPPORT_URL: Option<&'static str> = None;
+
+    const CLAP_FEATURES: &'static [ClapFeature] = &[
+        ClapFeature::AudioEffect,
+        ClapFeature::Stereo,
+        ClapFeature::Compressor,
+    ];
+}
+
+impl Vst3Plugin for MeadowBusCompressor {
+    const VST3_CLASS_ID: [u8; 16] = *b"Meadowlark.BusCo";
+
+    const VST3_SUBCATEGORIES: &'static [Vst3SubCategory] =
+        &[Vst3SubCategory::Fx, Vst3SubCategory::Dynamics];
+}
+
+#[cfg(not(feature = "bundled"))]
+nih_export_clap!(MeadowBusCompressor);
+#[cfg(not(feature = "bundled"))]
+nih_export_vst3!(MeadowBusCompressor);
@@ -0,0 +1,16 @@
+use meadow_meter_editor::testing::check_meter;
+
+use crate::MeadowBusCompressor;
+
+#[test]
+fn meter_shows_the_gain_reduction() {
+    let mut plugin = MeadowBusCompressor::default();
+    let meter = plugin.gain_reduction_db();
+
+    // A full scale sine is 10 dB over the default threshold.
+    let gain_reduction_db = check_meter(&mut plugin, &meter, 1.0, |plugin, l, r| {
+        plugin.process_block(l, r);
+        plugin.dsp.gain_reduction_db()
+    });
+    assert!(gain_reduction_db > 3.0, "{gain_reduction_db} dB");
+}
@@ -0,0 +1,297 @@
+//! A "glue" bus compressor in the style of the SSL 4000 E/G series bus compressors.
+//!
+//! Like those, it has stepped ratios, attack and release times, an auto release, and a
+//! feed-back topology: the detector listens to the compressed output rather than the input.
+//! The gain computer is set up so the static curve still has the selected ratio. The
+//! detector goes through an optional highpass so the low end doesn't pump the mix.
+//!
+//! The gain reduction is worked out in decibels. It moves towards its target with the attack
+//! time and back with the release time, both as time constants. The attack time is that of
+//! the whole feed-back loop, and the release time is how long it takes to recover once the
+//! signal drops below the threshold.
+
+use meadow_common_dsp::{Ramp, db_to_gain, gain_to_db, time_constant};
+use meadow_eq_dsp::{DEFAULT_Q, SvfCoeff, SvfState};
+
+#[cfg(test)]
+mod tests;
+
+/// How long it takes to move to new makeup gains and mixes.
+pub const SMOOTHING_MS: f64 = 20.0;
+
+/// The release time of the auto release's fast stage, for transients.
+pub const AUTO_FAST_RELEASE_MS: f32 = 100.0;
+/// How long sustained gain reduction takes to charge the auto release's slow stage.
+pub const AUTO_SLOW_CHARGE_MS: f32 = 1000.0;
+/// The release time of the auto release's slow stage, once it has charged.
+pub const AUTO_SLOW_RELEASE_MS: f32 = 1200.0;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Ratio {
+    #[default]
+    R2,
+    R4,
+    R10,
+}
+
+impl Ratio {
+    pub fn ratio(self) -> f32 {
+        match self {
+            Self::R2 => 2.0,
+            Self::R4 => 4.0,
+            Self::R10 => 10.0,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Attack {
+    Ms0_1,
+    Ms0_3,
+    Ms1,
+    Ms3,
+    #[default]
+    Ms10,
+    Ms30,
+}
+
+impl Attack {
+    pub fn ms(self) -> f32 {
+        match self {
+            Self::Ms0_1 => 0.1,
+            Self::Ms0_3 => 0.3,
+            Self::Ms1 => 1.0,
+            Self::Ms3 => 3.0,
+            Self::Ms10 => 10.0,
+            Self::Ms30 => 30.0,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Release {
+    Ms100,
+    Ms300,
+    Ms600,
+    Ms1200,
+    /// Recovers quickly from short peaks and slowly from sustained compression, see
+    /// [`AUTO_FAST_RELEASE_MS`] and [`AUTO_SLOW_RELEASE_MS`].
+    #[default]
+    Auto,
+}
+
+impl Release {
+    /// The release time, or `None` for the auto release.
+    pub fn ms(self) -> Option<f32> {
+        match self {
+            Self::Ms100 => Some(100.0),
+            Self::Ms300 => Some(300.0),
+            Self::Ms600 => Some(600.0),
+            Self::Ms1200 => Some(1200.0),
+            Self::Auto => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BusCompressorParams {
+    pub threshold_db: f32,
+    pub ratio: Ratio,
+    pub attack: Attack,
+    pub release: Release,
+    pub makeup_db: f32,
+    /// From `0.0` (only the dry signal) to `1.0` (only the compressed signal).
+    pub mix: f32,
+    pub sidechain_hpf_enabled: bool,
+    pub sidechain_hpf_hz: f32,
+}
+
+impl Default for BusCompressorParams {
+    fn default() -> Self {
+        Self {
+            threshold_db: -10.0,
+            ratio: Ratio::default(),
+            attack: Attack::default(),
+            release: Release::default(),
+            makeup_db: 0.0,
+            mix: 1.0,
+            sidechain_hpf_enabled: false,
+            sidechain_hpf_hz: 60.0,
+        }
+    }
+}
+
+pub struct BusCompressorDsp {
+    params: BusCompressorParams,
+    sample_rate: f64,
+    ramp_len: usize,
+
+    attack_coeff: f32,
+    release_coeff: f32,
+    auto_coeffs: AutoReleaseCoeffs,
+    hpf: Option<SvfCoeff>,
+    hpf_state: [SvfState; 2],
+    makeup: Ramp,
+    mix: Ramp,
+
+    /// The gain reduction in decibels. With the auto release, this is the fast stage.
+    reduction_db: f32,
+    /// The auto release's slow stage.
+    slow_reduction_db: f32,
+
+    /// The most gain reduction during the last call to `process()`.
+    gain_reduction_db: f32,
+}
+
+impl BusCompressorDsp {
+    pub fn new(sample_rate: f64) -> Self {
+        assert!(sample_rate > 0.0);
+
+        let params = BusCompressorParams::default();
+        let mut new_self = Self {
+            params,
+            sample_rate,
+            ramp_len: ((SMOOTHING_MS / 1000.0 * sample_rate).round() as usize).max(1),
+            attack_coeff: 0.0,
+            release_coeff: 0.0,
+            auto_coeffs: AutoReleaseCoeffs::new(sample_rate),
+            hpf: None,
+            hpf_state: [SvfState::default(); 2],
+            makeup: Ramp::new(db_to_gain(params.makeup_db)),
+            mix: Ramp::new(params.mix),
+            reduction_db: 0.0,
+            slow_reduction_db: 0.0,
+            gain_reduction_db: 0.0,
+        };
+        new_self.update_coeffs();
+
+        new_self
+    }
+
+    pub fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+
+    fn update_coeffs(&mut self) {
+        let params = &self.params;
+        // Around the loop, every decibel of gain reduction lowers the detector's target by
+        // `ratio - 1` dB, which speeds up the attack by the ratio. Slowing the smoother down
+        // by the same amount makes the loop as a whole settle with the attack time, and keeps
+        // it stable at short attack times and high ratios.
+        let attack = time_constant(params.attack.ms(), self.sample_rate);
+        self.attack_coeff = 1.0 - (1.0 - attack) / params.ratio.ratio();
+        self.release_coeff = time_constant(
+            params.release.ms().unwrap_or(AUTO_FAST_RELEASE_MS),
+            self.sample_rate,
+        );
+        self.hpf = params.sidechain_hpf_enabled.then(|| {
+            SvfCoeff::highpass_ord2(
+                params.sidechain_hpf_hz as f64,
+                self.sample_rate.recip(),
+                DEFAULT_Q as f64,
+            )
+        });
+    }
+
+    pub fn set_params(&mut self, params: BusCompressorParams) {
+        if self.params == params {
+            return;
+        }
+
+        if params.makeup_db != self.params.makeup_db {
+            self.makeup
+                .set_target(db_to_gain(params.makeup_db), self.ramp_len);
+        }
+        if params.mix != self.params.mix {
+            self.mix
+                .set_target(params.mix.clamp(0.0, 1.0), self.ramp_len);
+        }
+
+        self.params = params;
+        self.update_coeffs();
+    }
+
+    /// The most gain reduction during the last call to [`Self::process()`], as a positive
+    /// number of decibels.
+    pub fn gain_reduction_db(&self) -> f32 {
+        self.gain_reduction_db
+    }
+
+    pub fn reset(&mut self) {
+        self.makeup.snap();
+        self.mix.snap();
+        self.hpf_state = [SvfState::default(); 2];
+        self.reduction_db = 0.0;
+        self.slow_reduction_db = 0.0;
+        self.gain_reduction_db = 0.0;
+    }
+
+    pub fn process(&mut self, buf_l: &mut [f32], buf_r: &mut [f32]) {
+        let threshold_db = self.params.threshold_db;
+        // With the detector after the gain, this slope gives the ratio's static curve.
+        let slope = self.params.ratio.ratio() - 1.0;
+        let auto_release = self.params.release.ms().is_none();
+        let mut max_reduction_db = 0.0f32;
+
+        for (l, r) in buf_l.iter_mut().zip(buf_r.iter_mut()) {
+            let makeup = self.makeup.next();
+            let mix = self.mix.next();
+
+            let reduction_db = if auto_release {
+                self.reduction_db.max(self.slow_reduction_db)
+            } else {
+                self.reduction_db
+            };
+            max_reduction_db = max_reduction_db.max(reduction_db);
+
+            let gain = db_to_gain(-reduction_db);
+            let wet = [*l * gain, *r * gain];
+
+            // The feed-back detector.
+            let mut detector = wet;
+            if let Some(hpf) = &self.hpf {
+                for (x, state) in detector.iter_mut().zip(self.hpf_state.iter_mut()) {
+                    *x = state.tick(*x, hpf);
+                }
+            }
+            let level_db = gain_to_db(detector[0].abs().max(detector[1].abs()));
+            let target_db = slope * (level_db - threshold_db).max(0.0);
+
+            let coeff = if target_db > self.reduction_db {
+                self.attack_coeff
+            } else {
+                self.release_coeff
+            };
+            self.reduction_db = target_db + coeff * (self.reduction_db - target_db);
+
+            if auto_release {
+                let coeff = if self.reduction_db > self.slow_reduction_db {
+                    self.auto_coeffs.slow_charge
+                } else {
+                    self.auto_coeffs.slow_release
+                };
+                self.slow_reduction_db =
+                    self.reduction_db + coeff * (self.slow_reduction_db - self.reduction_db);
+            }
+
+            *l = makeup * (wet[0] * mix + *l * (1.0 - mix));
+            *r = makeup * (wet[1] * mix + *r * (1.0 - mix));
+        }
+
+        self.gain_reduction_db = max_reduction_db;
+    }
+}
+
+struct AutoReleaseCoeffs {
+    slow_charge: f32,
+    slow_release: f32,
+}
+
+impl AutoReleaseCoeffs {
+    fn new(sample_rate: f64) -> Self {
+        Self {
+            slow_charge: time_constant(AUTO_SLOW_CHARGE_MS, sample_rate),
+            slow_release: time_constant(AUTO_SLOW_RELEASE_MS, sample_rate),
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use meadow_common_dsp::{db_to_gain, gain_to_db};
+use meadow_test_signals::sine;
+use std::f32::consts::E;
+
+use crate::{Attack, BusCompressorDsp, BusCompressorParams, Ratio, Release};
+
+const SAMPLE_RATES: [f64; 3] = [44_100.0, 48_000.0, 192_000.0];
+const RATIOS: [Ratio; 3] = [Ratio::R2, Ratio::R4, Ratio::R10];
+const ATTACKS: [Attack; 6] = [
+    Attack::Ms0_1,
+    Attack::Ms0_3,
+    Attack::Ms1,
+    Attack::Ms3,
+    Attack::Ms10,
+    Attack::Ms30,
+];
+
+fn new_dsp(sample_rate: f64, params: BusCompressorParams) -> BusCompressorDsp {
+    let mut dsp = BusCompressorDsp::new(sample_rate);
+    dsp.set_params(params);
+    dsp.reset();
+
+    dsp
+}
+
+/// Runs `len` samples of a constant signal at `level_db` and returns the gain of each sample
+/// in decibels.
+fn constant_gains_db(dsp: &mut BusCompressorDsp, level_db: f32, len: usize) -> Vec<f32> {
+    let mut buf_l = vec![db_to_gain(level_db); len];
+    let mut buf_r = buf_l.clone();
+    dsp.process(&mut buf_l, &mut buf_r);
+
+    buf_l.iter().map(|&x| gain_to_db(x) - level_db).collect()
+}
+
+#[test]
+fn static_curve_has_the_selected_ratio() {
+    // Despite the detector listening to the output.
+    for sample_rate in SAMPLE_RATES {
+        for ratio in RATIOS {
+            for attack in ATTACKS {
+                let params = BusCompressorParams {
+                    threshold_db: -20.0,
+                    ratio,
+                    attack,
+                    ..Default::default()
+                };
+                let mut dsp = new_dsp(sample_rate, params);
+
+                for level_db in [-8.0, 0.0] {
+                    let gains_db = constant_gains_db(&mut dsp, level_db, sample_rate as usize);
+                    let output_db = level_db + gains_db.last().unwrap();
+                    let expected_db = -20.0 + (level_db + 20.0) / ratio.ratio();
+                    assert!(
+                        (output_db - expected_db).abs() < 0.01,
+                        "{ratio:?}, {attack:?}, {level_db} dB, {sample_rate} Hz: {output_db} dB instead of {expected_db} dB"
+                    );
+                    assert!((dsp.gain_reduction_db() - (level_db - expected_db)).abs() < 0.01);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn leaves_signals_below_the_threshold_alone() {
+    let mut dsp = new_dsp(48_000.0, BusCompressorParams::default());
+    let input: Vec<f32> = sine(1000.0, 48_000.0, 48_000)
+        .iter()
+        .map(|x| x * db_to_gain(-12.0))
+        .collect();
+    let mut buf_l = input.clone();
+    let mut buf_r = input.clone();
+    dsp.process(&mut buf_l, &mut buf_r);
+
+    assert_eq!(buf_l, input);
+    assert_eq!(buf_r, input);
+    assert_eq!(dsp.gain_reduction_db(), 0.0);
+}
+
+#[test]
+fn attack_times_are_the_loops_time_constants() {
+    for sample_rate in SAMPLE_RATES {
+        for ratio in RATIOS {
+            for attack in ATTACKS {
+                let params = BusCompressorParams {
+                    threshold_db: -20.0,
+                    ratio,
+                    attack,
+                    ..Default::default()
+                };
+                let mut dsp = new_dsp(sample_rate, params);
+                let gains_db = constant_gains_db(&mut dsp, 0.0, sample_rate as usize);
+
+                let settled_db = -20.0 * (1.0 - ratio.ratio().recip());
+                // The attack time rounded to whole samples, which matters at 0.1 ms.
+                let attack_len = attack.ms() as f64 / 1000.0 * sample_rate;
+                let n = attack_len.round() as usize;
+                let expected_db = settled_db * (1.0 - (-(n as f64) / attack_len).exp() as f32);
+                assert!(
+                    (gains_db[n] - expected_db).abs() < 0.02 * settled_db.abs(),
+                    "{ratio:?}, {attack:?}, {sample_rate} Hz: {} dB instead of {expected_db} dB",
+                    gains_db[n]
+                );
+                // Without ringing on the way.
+                assert!(gains_db.windows(2).all(|w| w[1] <= w[0] + 1e-4));
+            }
+        }
+    }
+}
+
+#[test]
+fn release_times_are_the_recoverys_time_constants() {
+    for sample_rate in SAMPLE_RATES {
+        for release in [
+            Release::Ms100,
+            Release::Ms300,
+            Release::Ms600,
+            Release::Ms1200,
+        ] {
+            let params = BusCompressorParams {
+                threshold_db: -20.0,
+                ratio: Ratio::R4,
+                attack: Attack::Ms1,
+                release,
+                ..Default::default()
+            };
+            let mut dsp = new_dsp(sample_rate, params);
+            constant_gains_db(&mut dsp, 0.0, sample_rate as usize / 2);
+            let reduction_db = dsp.gain_reduction_db();
+
+            let release_ms = release.ms().unwrap();
+            let n = (release_ms as f64 / 1000.0 * sample_rate).round() as usize;
+            let gains_db = constant_gains_db(&mut dsp, -60.0, n + 1);
+            let expected_db = -reduction_db / E;
+            assert!(
+                (gains_db[n] - expected_db).abs() < 0.01 * reduction_db,
+                "{release:?}, {sample_rate} Hz: {} dB instead of {expected_db} dB",
+                gains_db[n]
+            );
+        }
+    }
+}
+
+#[test]
+fn auto_release_recovers_slower_after_sustained_compression() {
+    for sample_rate in SAMPLE_RATES {
+        let params = BusCompressorParams {
+            threshold_db: -20.0,
+            ratio: Ratio::R4,
+            attack: Attack::Ms0_1,
+            release: Release::Auto,
+            ..Default::default()
+        };
+        let quarter_second = sample_rate as usize / 4;
+
+        // How much of the gain reduction is left a quarter of a second after a burst.
+        let recovery = |burst_len: usize| {
+            let mut dsp = new_dsp(sample_rate, params);
+            constant_gains_db(&mut dsp, 0.0, burst_len);
+            let reduction_db = dsp.gain_reduction_db();
+            let gains_db = constant_gains_db(&mut dsp, -60.0, quarter_second);
+
+            -gains_db.last().unwrap() / reduction_db
+        };
+        let after_transient = recovery(sample_rate as usize / 100);
+        let after_sustained = recovery(sample_rate as usize * 4);
+
+        // A 10 ms burst mostly recovers with the fast release.
+        assert!(after_transient < 0.1, "{after_transient}");
+        // Four seconds of compression charge the slow release.
+        assert!(after_sustained > 0.75, "{after_sustained}");
+    }
+}
+
+#[test]
+fn mix_blends_in_the_dry_signal() {
+    let sample_rate = 48_000.0;
+    let input = sine(1000.0, sample_rate, sample_rate as usize);
+    let run = |mix: f32, makeup_db: f32| {
+        let params = BusCompressorParams {
+            threshold_db: -20.0,
+            ratio: Ratio::R10,
+            mix,
+            makeup_db,
+            ..Default::default()
+        };
+        let mut dsp = new_dsp(sample_rate, params);
+        let mut buf_l = input.clone();
+        let mut buf_r = input.clone();
+        dsp.process(&mut buf_l, &mut buf_r);
+
+        buf_l
+    };
+
+    // Fully dry, the makeup gain still applies.
+    let dry = run(0.0, 6.0);
+    for (x, y) in input.iter().zip(dry.iter()) {
+        assert!((x * db_to_gain(6.0) - y).abs() < 1e-5);
+    }
+
+    let wet = run(1.0, 0.0);
+    let half = run(0.5, 0.0);
+    for ((x, w), h) in input.iter().zip(wet.iter()).zip(half.iter()) {
+        assert!((0.5 * (x + w) - h).abs() < 1e-5);
+    }
+}
+
+#[test]
+fn sidechain_highpass_ignores_the_low_end() {
+    for sample_rate in SAMPLE_RATES {
+        let len = sample_rate as usize;
+        let input = sine(30.0, sample_rate, len);
+        let run = |sidechain_hpf_enabled: bool| {
+            let params = BusCompressorParams {
+                threshold_db: -20.0,
+                ratio: Ratio::R4,
+                sidechain_hpf_enabled,
+                sidechain_hpf_hz: 150.0,
+                ..Default::default()
+            };
+            let mut dsp = new_dsp(sample_rate, params);
+            let mut buf_l = input.clone();
+            let mut buf_r = input.clone();
+            dsp.process(&mut buf_l, &mut buf_r);
+
+            dsp.gain_reduction_db()
+        };
+
+        // 30 Hz is about 28 dB down through a 150 Hz 2nd order highpass.
+        assert!(run(false) > 12.0, "{}", run(false));
+        assert!(run(true) < 2.0, "{}", run(true));
+    }
+}
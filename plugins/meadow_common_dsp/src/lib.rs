@@ -0,0 +1,101 @@
+//! Small building blocks that several of the plugins' DSP crates use.
+
+use std::ops::{Div, Mul, Sub};
+
+#[cfg(test)]
+mod tests;
+
+pub fn db_to_gain(db: f32) -> f32 {
+    10.0f32.powf(db / 20.0)
+}
+
+/// Converts to decibels, with silence at -160 dB.
+pub fn gain_to_db(gain: f32) -> f32 {
+    if gain > 1e-8 {
+        20.0 * gain.log10()
+    } else {
+        -160.0
+    }
+}
+
+/// The coefficient of a one-pole smoother that settles to within `1 - 1/e` of a step after
+/// `time_ms`.
+pub fn time_constant(time_ms: f32, sample_rate: f64) -> f32 {
+    (-1000.0 / (time_ms.max(0.01) as f64 * sample_rate)).exp() as f32
+}
+
+/// The float types a [`Ramp`] can move.
+pub trait RampValue: Copy + Sub<Output = Self> + Mul<Output = Self> + Div<Output = Self> {
+    const ZERO: Self;
+
+    fn from_len(len: usize) -> Self;
+}
+
+impl RampValue for f32 {
+    const ZERO: Self = 0.0;
+
+    fn from_len(len: usize) -> Self {
+        len as f32
+    }
+}
+
+impl RampValue for f64 {
+    const ZERO: Self = 0.0;
+
+    fn from_len(len: usize) -> Self {
+        len as f64
+    }
+}
+
+/// A value that moves linearly to its target over a fixed number of samples.
+#[derive(Debug, Clone, Copy)]
+pub struct Ramp<T = f32> {
+    value: T,
+    target: T,
+    step: T,
+    remaining: usize,
+}
+
+impl<T: RampValue> Ramp<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            value,
+            target: value,
+            step: T::ZERO,
+            remaining: 0,
+        }
+    }
+
+    /// The value the last call to [`Self::next()`] returned.
+    pub fn value(&self) -> T {
+        self.value
+    }
+
+    pub fn set_target(&mut self, target: T, len: usize) {
+        self.target = target;
+        self.remaining = len;
+        self.step = (target - self.value) / T::from_len(len);
+    }
+
+    /// Jumps to the target.
+    pub fn snap(&mut self) {
+        self.value = self.target;
+        self.remaining = 0;
+    }
+
+    pub fn is_ramping(&self) -> bool {
+        self.remaining > 0
+    }
+
+    // Named like nih-plug's smoothers. A ramp never ends, so it isn't an iterator.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> T {
+        if self.remaining > 0 {
+            self.remaining -= 1;
+            // Counted back from the target so rounding errors can't build up.
+            self.value = self.target - self.step * T::from_len(self.remaining);
+        }
+
+        self.value
+    }
+}
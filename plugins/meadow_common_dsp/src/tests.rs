@@ -0,0 +1,41 @@
+use crate::{Ramp, db_to_gain, gain_to_db, time_constant};
+
+#[test]
+fn ramp_lands_exactly_on_its_target() {
+    let mut ramp = Ramp::new(0.1f32);
+    ramp.set_target(0.7, 3);
+
+    let values: Vec<f32> = (0..5).map(|_| ramp.next()).collect();
+    assert!(values[0] > 0.1 && values[0] < values[1]);
+    assert_eq!(&values[2..], &[0.7; 3]);
+    assert!(!ramp.is_ramping());
+
+    // A ramp that's cut short jumps straight to its target.
+    let mut ramp = Ramp::new(-48_000.25f64);
+    ramp.set_target(12.5, 1_000);
+    ramp.next();
+    assert!(ramp.is_ramping());
+    ramp.snap();
+    assert_eq!(ramp.next(), 12.5);
+    assert_eq!(ramp.value(), 12.5);
+}
+
+#[test]
+fn decibels_round_trip() {
+    for db in [-60.0, -6.0, 0.0, 12.0] {
+        assert!((gain_to_db(db_to_gain(db)) - db).abs() < 1e-4, "{db} dB");
+    }
+
+    assert_eq!(gain_to_db(0.0), -160.0);
+}
+
+#[test]
+fn time_constant_settles_in_time() {
+    // 10 ms at 48 kHz.
+    let coeff = time_constant(10.0, 48_000.0);
+    let mut value = 1.0f32;
+    for _ in 0..480 {
+        value *= coeff;
+    }
+    assert!((value - (-1.0f32).exp()).abs() < 1e-4, "{value}");
+}
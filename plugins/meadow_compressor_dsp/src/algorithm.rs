@@ -0,0 +1,34 @@
+//! The algorithms that turn the detected level into a gain.
+//!
+//! A new algorithm implements [`CompressorAlgorithm`], gets a variant in
+//! [`Algorithm`](crate::Algorithm) and a field in [`CompressorDsp`](crate::CompressorDsp).
+//! Every algorithm is kept alive so switching between them never allocates.
+
+use crate::CompressorParams;
+
+mod darc;
+mod pressure4;
+mod zam_comp;
+
+pub use darc::Darc;
+pub use pressure4::Pressure4;
+pub use zam_comp::ZamComp;
+
+pub trait CompressorAlgorithm {
+    /// Called whenever the parameters change. The algorithm is free to ignore the parameters
+    /// it has no use for.
+    fn set_params(&mut self, params: &CompressorParams, sample_rate: f64);
+
+    /// Forgets about the signal so far.
+    fn reset(&mut self);
+
+    /// Takes the level of one frame of the sidechain as a linear amplitude and returns the
+    /// gain for that frame, which is at most `1.0`.
+    fn tick(&mut self, level: f32) -> f32;
+
+    /// A gain the algorithm applies on top of the one from [`Self::tick()`]. It doesn't count
+    /// towards the gain reduction.
+    fn makeup_gain(&self) -> f32 {
+        1.0
+    }
+}
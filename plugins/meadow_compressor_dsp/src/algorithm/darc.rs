@@ -0,0 +1,62 @@
+use meadow_common_dsp::{db_to_gain, gain_to_db, time_constant};
+
+use super::CompressorAlgorithm;
+use crate::{CompressorParams, gain_computer};
+
+/// After x42's darc: the target gain is smoothed as a linear gain, with the attack time while
+/// it falls and the release time while it rises. This gives it a gentler, more program
+/// dependent release than [`ZamComp`](super::ZamComp). Works best with RMS detection.
+#[derive(Debug, Clone, Copy)]
+pub struct Darc {
+    threshold_db: f32,
+    ratio: f32,
+    knee_db: f32,
+    attack_coeff: f32,
+    release_coeff: f32,
+
+    gain: f32,
+}
+
+impl Default for Darc {
+    fn default() -> Self {
+        Self {
+            threshold_db: 0.0,
+            ratio: 1.0,
+            knee_db: 0.0,
+            attack_coeff: 0.0,
+            release_coeff: 0.0,
+            gain: 1.0,
+        }
+    }
+}
+
+impl CompressorAlgorithm for Darc {
+    fn set_params(&mut self, params: &CompressorParams, sample_rate: f64) {
+        self.threshold_db = params.threshold_db;
+        self.ratio = params.ratio;
+        self.knee_db = params.knee_db;
+        self.attack_coeff = time_constant(params.attack_ms, sample_rate);
+        self.release_coeff = time_constant(params.release_ms, sample_rate);
+    }
+
+    fn reset(&mut self) {
+        self.gain = 1.0;
+    }
+
+    #[inline(always)]
+    fn tick(&mut self, level: f32) -> f32 {
+        let level_db = gain_to_db(level);
+        let target = db_to_gain(
+            gain_computer(level_db, self.threshold_db, self.ratio, self.knee_db) - level_db,
+        );
+
+        let coeff = if target < self.gain {
+            self.attack_coeff
+        } else {
+            self.release_coeff
+        };
+        self.gain = target + coeff * (self.gain - target);
+
+        self.gain
+    }
+}
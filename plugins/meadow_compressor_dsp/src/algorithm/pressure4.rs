@@ -0,0 +1,79 @@
+use meadow_common_dsp::db_to_gain;
+
+use super::CompressorAlgorithm;
+use crate::CompressorParams;
+
+/// After Airwindows' Pressure4: a program dependent "mu" compressor. It has no ratio or knee;
+/// the gain reduction grows with how far and how long the signal stays above the threshold.
+/// It also makes up for the threshold, so signals near full scale come out as loud as they
+/// went in.
+///
+/// Only the threshold and release parameters are used. The release time sets how fast the
+/// compressor can recover, which also slows it down the harder it's driven. Two sets of state
+/// take turns on alternating samples, as in the original.
+#[derive(Debug, Clone, Copy)]
+pub struct Pressure4 {
+    threshold: f32,
+    release: f32,
+    fastest: f32,
+
+    speed: [f32; 2],
+    coefficient: [f32; 2],
+    flip: usize,
+}
+
+impl Default for Pressure4 {
+    fn default() -> Self {
+        Self {
+            threshold: 1.0,
+            release: 0.0,
+            fastest: 0.0,
+            speed: [INITIAL_SPEED; 2],
+            coefficient: [1.0; 2],
+            flip: 0,
+        }
+    }
+}
+
+const INITIAL_SPEED: f32 = 10_000.0;
+
+impl CompressorAlgorithm for Pressure4 {
+    fn set_params(&mut self, params: &CompressorParams, sample_rate: f64) {
+        // Same range as the original's "pressure" knob.
+        self.threshold = db_to_gain(params.threshold_db).clamp(0.05, 1.0);
+        self.release = ((params.release_ms as f64 / 1000.0 * sample_rate) as f32).max(1.0);
+        self.fastest = self.release.sqrt();
+    }
+
+    fn reset(&mut self) {
+        self.speed = [INITIAL_SPEED; 2];
+        self.coefficient = [1.0; 2];
+        self.flip = 0;
+    }
+
+    #[inline(always)]
+    fn tick(&mut self, level: f32) -> f32 {
+        let sense = level * self.makeup_gain();
+        let speed = &mut self.speed[self.flip];
+        let coefficient = &mut self.coefficient[self.flip];
+        self.flip ^= 1;
+
+        if sense > self.threshold {
+            // Past full scale, it stops pulling harder.
+            let vary = (self.threshold / sense).max(self.threshold);
+            let attack = speed.sqrt();
+            *coefficient = (*coefficient * (attack - 1.0) + vary) / attack;
+        } else {
+            let speed_squared = *speed * *speed;
+            *coefficient = (*coefficient * (speed_squared - 1.0) + 1.0) / speed_squared;
+        }
+        *speed = (*speed * (*speed - 1.0) + sense * self.release + self.fastest) / *speed;
+
+        // The squared coefficient corresponds to the original's default "mewiness".
+        (*coefficient * *coefficient).min(1.0)
+    }
+
+    fn makeup_gain(&self) -> f32 {
+        self.threshold.recip()
+    }
+}
@@ -0,0 +1,63 @@
+use meadow_common_dsp::{db_to_gain, gain_to_db, time_constant};
+
+use super::CompressorAlgorithm;
+use crate::{CompressorParams, gain_computer};
+
+/// After ZamCompX2: the gain reduction is worked out in decibels and smoothed by a decoupled
+/// peak detector, which releases first and then attacks, so it doesn't overshoot.
+#[derive(Debug, Clone, Copy)]
+pub struct ZamComp {
+    threshold_db: f32,
+    ratio: f32,
+    knee_db: f32,
+    attack_coeff: f32,
+    release_coeff: f32,
+
+    /// The released gain reduction in decibels.
+    released_db: f32,
+    /// The attacked gain reduction in decibels.
+    reduction_db: f32,
+}
+
+impl Default for ZamComp {
+    fn default() -> Self {
+        Self {
+            threshold_db: 0.0,
+            ratio: 1.0,
+            knee_db: 0.0,
+            attack_coeff: 0.0,
+            release_coeff: 0.0,
+            released_db: 0.0,
+            reduction_db: 0.0,
+        }
+    }
+}
+
+impl CompressorAlgorithm for ZamComp {
+    fn set_params(&mut self, params: &CompressorParams, sample_rate: f64) {
+        self.threshold_db = params.threshold_db;
+        self.ratio = params.ratio;
+        self.knee_db = params.knee_db;
+        self.attack_coeff = time_constant(params.attack_ms, sample_rate);
+        self.release_coeff = time_constant(params.release_ms, sample_rate);
+    }
+
+    fn reset(&mut self) {
+        self.released_db = 0.0;
+        self.reduction_db = 0.0;
+    }
+
+    #[inline(always)]
+    fn tick(&mut self, level: f32) -> f32 {
+        let level_db = gain_to_db(level);
+        let target_db =
+            level_db - gain_computer(level_db, self.threshold_db, self.ratio, self.knee_db);
+
+        self.released_db =
+            target_db.max(target_db + self.release_coeff * (self.released_db - target_db));
+        self.reduction_db =
+            self.released_db + self.attack_coeff * (self.reduction_db - self.released_db);
+
+        db_to_gain(-self.reduction_db)
+    }
+}
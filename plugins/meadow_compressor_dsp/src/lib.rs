@@ -0,0 +1,272 @@
+//! A single-band compressor with switchable algorithms.
+//!
+//! The compressor takes care of everything around the algorithm: the input and output gains,
+//! the sidechain and its filters, and detecting the sidechain's level. The algorithm turns
+//! that level into a gain, see [`CompressorAlgorithm`].
+
+use meadow_common_dsp::{Ramp, db_to_gain, gain_to_db, time_constant};
+use meadow_eq_dsp::sidechain_filter::SidechainFilter;
+
+pub mod algorithm;
+#[cfg(test)]
+mod tests;
+
+pub use algorithm::CompressorAlgorithm;
+use algorithm::{Darc, Pressure4, ZamComp};
+pub use meadow_eq_dsp::sidechain_filter::SidechainFilterParams;
+
+/// How long it takes to move to new input and output gains.
+pub const SMOOTHING_MS: f64 = 20.0;
+
+/// The averaging time of [`Detection::Rms`].
+pub const RMS_WINDOW_MS: f64 = 10.0;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Algorithm {
+    /// After x42's darc. See [`algorithm::Darc`].
+    #[default]
+    Darc,
+    /// After ZamCompX2. See [`algorithm::ZamComp`].
+    ZamCompX2,
+    /// After Airwindows' Pressure4. See [`algorithm::Pressure4`].
+    Pressure4,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Detection {
+    /// The larger of the two channels' absolute values.
+    #[default]
+    Peak,
+    /// The RMS of both channels over [`RMS_WINDOW_MS`].
+    Rms,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Sidechain {
+    /// Compress based on the main input, after the input gain.
+    #[default]
+    Internal,
+    /// Compress based on the external sidechain input, or the main input if there is none.
+    External,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CompressorParams {
+    pub algorithm: Algorithm,
+
+    pub input_gain_db: f32,
+    pub threshold_db: f32,
+    pub ratio: f32,
+    /// The width of the soft knee around the threshold. `0.0` is a hard knee.
+    pub knee_db: f32,
+    pub attack_ms: f32,
+    pub release_ms: f32,
+    pub detection: Detection,
+    pub output_gain_db: f32,
+
+    pub sidechain: Sidechain,
+    pub sidechain_filter: SidechainFilterParams,
+}
+
+impl Default for CompressorParams {
+    fn default() -> Self {
+        Self {
+            algorithm: Algorithm::default(),
+            input_gain_db: 0.0,
+            threshold_db: -20.0,
+            ratio: 4.0,
+            knee_db: 6.0,
+            attack_ms: 10.0,
+            release_ms: 100.0,
+            detection: Detection::default(),
+            output_gain_db: 0.0,
+            sidechain: Sidechain::default(),
+            sidechain_filter: SidechainFilterParams::default(),
+        }
+    }
+}
+
+/// The output level in dB of a compressor with a soft knee for an input level of `level_db`.
+pub fn gain_computer(level_db: f32, threshold_db: f32, ratio: f32, knee_db: f32) -> f32 {
+    let over_db = level_db - threshold_db;
+    let slope = 1.0 / ratio.max(1.0) - 1.0;
+
+    if 2.0 * over_db < -knee_db {
+        level_db
+    } else if knee_db > 0.0 && 2.0 * over_db.abs() <= knee_db {
+        let into_knee = over_db + 0.5 * knee_db;
+        level_db + slope * into_knee * into_knee / (2.0 * knee_db)
+    } else {
+        level_db + slope * over_db
+    }
+}
+
+pub struct CompressorDsp {
+    params: CompressorParams,
+    sample_rate: f64,
+    ramp_len: usize,
+
+    darc: Darc,
+    zam_comp: ZamComp,
+    pressure4: Pressure4,
+
+    input_gain: Ramp,
+    output_gain: Ramp,
+    sidechain_filter: SidechainFilter,
+    detector: LevelDetector,
+
+    /// The most gain reduction during the last call to `process()`.
+    gain_reduction_db: f32,
+}
+
+impl CompressorDsp {
+    pub fn new(sample_rate: f64) -> Self {
+        assert!(sample_rate > 0.0);
+
+        let params = CompressorParams::default();
+        let mut new_self = Self {
+            params,
+            sample_rate,
+            ramp_len: ((SMOOTHING_MS / 1000.0 * sample_rate).round() as usize).max(1),
+            darc: Darc::default(),
+            zam_comp: ZamComp::default(),
+            pressure4: Pressure4::default(),
+            input_gain: Ramp::new(db_to_gain(params.input_gain_db)),
+            output_gain: Ramp::new(db_to_gain(params.output_gain_db)),
+            sidechain_filter: SidechainFilter::new(&params.sidechain_filter, sample_rate),
+            detector: LevelDetector::new(sample_rate),
+            gain_reduction_db: 0.0,
+        };
+        new_self.algorithm_mut().set_params(&params, sample_rate);
+
+        new_self
+    }
+
+    pub fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+
+    fn algorithm_mut(&mut self) -> &mut dyn CompressorAlgorithm {
+        match self.params.algorithm {
+            Algorithm::Darc => &mut self.darc,
+            Algorithm::ZamCompX2 => &mut self.zam_comp,
+            Algorithm::Pressure4 => &mut self.pressure4,
+        }
+    }
+
+    pub fn set_params(&mut self, params: CompressorParams) {
+        if self.params == params {
+            return;
+        }
+
+        if params.input_gain_db != self.params.input_gain_db {
+            self.input_gain
+                .set_target(db_to_gain(params.input_gain_db), self.ramp_len);
+        }
+        if params.output_gain_db != self.params.output_gain_db {
+            self.output_gain
+                .set_target(db_to_gain(params.output_gain_db), self.ramp_len);
+        }
+        if params.sidechain_filter != self.params.sidechain_filter {
+            self.sidechain_filter
+                .set(&params.sidechain_filter, self.sample_rate);
+        }
+
+        let switched_algorithm = params.algorithm != self.params.algorithm;
+        self.params = params;
+
+        let sample_rate = self.sample_rate;
+        let algorithm = self.algorithm_mut();
+        if switched_algorithm {
+            // It hasn't seen the signal since it was last used.
+            algorithm.reset();
+        }
+        algorithm.set_params(&params, sample_rate);
+    }
+
+    /// The most gain reduction during the last call to [`Self::process()`], as a positive
+    /// number of decibels.
+    pub fn gain_reduction_db(&self) -> f32 {
+        self.gain_reduction_db
+    }
+
+    pub fn reset(&mut self) {
+        self.input_gain.snap();
+        self.output_gain.snap();
+        self.sidechain_filter.reset();
+        self.detector.reset();
+        self.gain_reduction_db = 0.0;
+        self.algorithm_mut().reset();
+    }
+
+    /// Compresses the main signal in place. With [`Sidechain::External`], the level is taken
+    /// from `sidechain` where it's available and from the main input elsewhere.
+    pub fn process(
+        &mut self,
+        buf_l: &mut [f32],
+        buf_r: &mut [f32],
+        sidechain: Option<(&[f32], &[f32])>,
+    ) {
+        let sidechain = sidechain.filter(|_| self.params.sidechain == Sidechain::External);
+        let detection = self.params.detection;
+        let mut min_gain = 1.0f32;
+
+        for (i, (l, r)) in buf_l.iter_mut().zip(buf_r.iter_mut()).enumerate() {
+            let input_gain = self.input_gain.next();
+            let output_gain = self.output_gain.next();
+            *l *= input_gain;
+            *r *= input_gain;
+
+            let detector_input = sidechain
+                .and_then(|(sc_l, sc_r)| Some([*sc_l.get(i)?, *sc_r.get(i)?]))
+                .unwrap_or([*l, *r]);
+            let detector_input = self.sidechain_filter.tick(detector_input);
+            let level = self.detector.tick(detector_input, detection);
+
+            let algorithm: &mut dyn CompressorAlgorithm = match self.params.algorithm {
+                Algorithm::Darc => &mut self.darc,
+                Algorithm::ZamCompX2 => &mut self.zam_comp,
+                Algorithm::Pressure4 => &mut self.pressure4,
+            };
+            let gain = algorithm.tick(level);
+            min_gain = min_gain.min(gain);
+
+            let gain = gain * algorithm.makeup_gain() * output_gain;
+            *l *= gain;
+            *r *= gain;
+        }
+
+        self.gain_reduction_db = -gain_to_db(min_gain);
+    }
+}
+
+/// Turns the stereo sidechain into the level the algorithms work with.
+struct LevelDetector {
+    mean_square: f32,
+    rms_coeff: f32,
+}
+
+impl LevelDetector {
+    fn new(sample_rate: f64) -> Self {
+        Self {
+            mean_square: 0.0,
+            rms_coeff: time_constant(RMS_WINDOW_MS as f32, sample_rate),
+        }
+    }
+
+    fn reset(&mut self) {
+        self.mean_square = 0.0;
+    }
+
+    #[inline(always)]
+    fn tick(&mut self, [l, r]: [f32; 2], detection: Detection) -> f32 {
+        // The mean square is kept up to date in peak mode too, so switching is seamless.
+        let square = 0.5 * (l * l + r * r);
+        self.mean_square = square + self.rms_coeff * (self.mean_square - square);
+
+        match detection {
+            Detection::Peak => l.abs().max(r.abs()),
+            Detection::Rms => self.mean_square.sqrt(),
+        }
+    }
+}
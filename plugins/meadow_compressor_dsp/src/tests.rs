@@ -0,0 +1,263 @@
+use meadow_common_dsp::{db_to_gain, gain_to_db};
+use meadow_test_signals::sine;
+
+use crate::{
+    Algorithm, CompressorDsp, CompressorParams, Detection, Sidechain, SidechainFilterParams,
+    gain_computer,
+};
+
+const SAMPLE_RATES: [f64; 3] = [44_100.0, 48_000.0, 192_000.0];
+const ALGORITHMS: [Algorithm; 3] = [Algorithm::Darc, Algorithm::ZamCompX2, Algorithm::Pressure4];
+
+/// Compresses a constant signal at `level_db` for a second and returns the gain of the last
+/// sample in decibels.
+fn settled_gain_db(dsp: &mut CompressorDsp, level_db: f32) -> f32 {
+    let len = dsp.sample_rate() as usize;
+    let mut buf_l = vec![db_to_gain(level_db); len];
+    let mut buf_r = buf_l.clone();
+    dsp.process(&mut buf_l, &mut buf_r, None);
+
+    gain_to_db(buf_l[len - 1]) - level_db
+}
+
+#[test]
+fn gain_computer_follows_the_curve() {
+    // Below the knee, on the knee's corners, at the threshold and above the knee.
+    assert_eq!(gain_computer(-40.0, -20.0, 4.0, 12.0), -40.0);
+    assert!((gain_computer(-26.0, -20.0, 4.0, 12.0) + 26.0).abs() < 1e-5);
+    assert!((gain_computer(-14.0, -20.0, 4.0, 12.0) + 18.5).abs() < 1e-5);
+    assert!((gain_computer(-20.0, -20.0, 4.0, 12.0) + 21.125).abs() < 1e-5);
+    assert!((gain_computer(0.0, -20.0, 4.0, 12.0) + 15.0).abs() < 1e-5);
+    // A hard knee, right at the threshold and above it, and a ratio of 1 which never
+    // compresses.
+    assert_eq!(gain_computer(-20.0, -20.0, 4.0, 0.0), -20.0);
+    assert!((gain_computer(-8.0, -20.0, 4.0, 0.0) + 17.0).abs() < 1e-5);
+    assert_eq!(gain_computer(0.0, -20.0, 1.0, 6.0), 0.0);
+}
+
+#[test]
+fn static_curve_matches_the_gain_computer() {
+    for sample_rate in SAMPLE_RATES {
+        for algorithm in [Algorithm::Darc, Algorithm::ZamCompX2] {
+            for detection in [Detection::Peak, Detection::Rms] {
+                for (level_db, knee_db) in [(-40.0, 6.0), (-20.0, 12.0), (-8.0, 0.0), (0.0, 6.0)] {
+                    let params = CompressorParams {
+                        algorithm,
+                        detection,
+                        knee_db,
+                        ..Default::default()
+                    };
+                    let mut dsp = CompressorDsp::new(sample_rate);
+                    dsp.set_params(params);
+
+                    let gain_db = settled_gain_db(&mut dsp, level_db);
+                    let expected_db = gain_computer(level_db, -20.0, 4.0, knee_db) - level_db;
+                    assert!(
+                        (gain_db - expected_db).abs() < 0.01,
+                        "{algorithm:?}, {detection:?}, {level_db} dB, {sample_rate} Hz: {gain_db} dB instead of {expected_db} dB"
+                    );
+                    assert!((dsp.gain_reduction_db() + expected_db).abs() < 0.01);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn pressure4_compresses_above_the_threshold() {
+    for sample_rate in SAMPLE_RATES {
+        let params = CompressorParams {
+            algorithm: Algorithm::Pressure4,
+            ..Default::default()
+        };
+
+        let mut dsp = CompressorDsp::new(sample_rate);
+        dsp.set_params(params);
+        let quiet_db = settled_gain_db(&mut dsp, -50.0);
+        assert!(dsp.gain_reduction_db().abs() < 0.01);
+        // Only the makeup gain for the -20 dB threshold.
+        assert!((quiet_db - 20.0).abs() < 0.01, "{quiet_db} dB");
+
+        let mut dsp = CompressorDsp::new(sample_rate);
+        dsp.set_params(params);
+        let loud_db = settled_gain_db(&mut dsp, -6.0);
+        assert!(
+            dsp.gain_reduction_db() > 6.0,
+            "{} dB",
+            dsp.gain_reduction_db()
+        );
+        // Nothing gets pushed past full scale by much.
+        assert!(loud_db - 6.0 < 3.0, "{loud_db} dB");
+    }
+}
+
+#[test]
+fn zam_comp_attacks_in_the_attack_time() {
+    for sample_rate in SAMPLE_RATES {
+        let params = CompressorParams {
+            algorithm: Algorithm::ZamCompX2,
+            knee_db: 0.0,
+            attack_ms: 10.0,
+            release_ms: 100.0,
+            ..Default::default()
+        };
+        let mut dsp = CompressorDsp::new(sample_rate);
+        dsp.set_params(params);
+
+        // 12 dB over the threshold is 9 dB of gain reduction at 4:1.
+        let attack_len = (0.01 * sample_rate) as usize;
+        let mut buf_l = vec![db_to_gain(-8.0); attack_len];
+        let mut buf_r = buf_l.clone();
+        dsp.process(&mut buf_l, &mut buf_r, None);
+        let reduction_db = -8.0 - gain_to_db(buf_l[attack_len - 1]);
+        let expected_db = 9.0 * (1.0 - (-1.0f32).exp());
+        assert!(
+            (reduction_db - expected_db).abs() < 0.1,
+            "{sample_rate} Hz: {reduction_db} dB after the attack time"
+        );
+
+        // And lets go of most of it in the release time.
+        let release_len = (0.1 * sample_rate) as usize;
+        let mut buf_l = vec![db_to_gain(-40.0); release_len];
+        let mut buf_r = buf_l.clone();
+        dsp.process(&mut buf_l, &mut buf_r, None);
+        let reduction_db = -40.0 - gain_to_db(buf_l[release_len - 1]);
+        assert!(
+            reduction_db < 9.0 / 2.0,
+            "{sample_rate} Hz: {reduction_db} dB after the release time"
+        );
+    }
+}
+
+#[test]
+fn sidechain_filters_ignore_filtered_content() {
+    let sample_rate = 48_000.0;
+    let len = sample_rate as usize;
+    let bass = sine(50.0, sample_rate, len);
+
+    for algorithm in ALGORITHMS {
+        for (hp_enabled, lp_enabled, expect_reduction) in [
+            (false, false, true),
+            (true, false, false),
+            (false, true, true),
+        ] {
+            let params = CompressorParams {
+                algorithm,
+                sidechain: Sidechain::External,
+                sidechain_filter: SidechainFilterParams {
+                    hp_enabled,
+                    hp_cutoff_hz: 2_000.0,
+                    lp_enabled,
+                    lp_cutoff_hz: 2_000.0,
+                },
+                ..Default::default()
+            };
+            let mut dsp = CompressorDsp::new(sample_rate);
+            dsp.set_params(params);
+
+            // A quiet main signal, keyed by loud bass.
+            let mut buf_l = vec![db_to_gain(-60.0); len];
+            let mut buf_r = buf_l.clone();
+            dsp.process(&mut buf_l, &mut buf_r, Some((&bass, &bass)));
+
+            let reduction_db = dsp.gain_reduction_db();
+            assert_eq!(
+                reduction_db > 1.0,
+                expect_reduction,
+                "{algorithm:?}, hp {hp_enabled}, lp {lp_enabled}: {reduction_db} dB"
+            );
+        }
+    }
+}
+
+#[test]
+fn internal_sidechain_ignores_the_sidechain_input() {
+    let sample_rate = 48_000.0;
+    let len = sample_rate as usize;
+    let loud = vec![1.0f32; len];
+
+    let mut dsp = CompressorDsp::new(sample_rate);
+    let mut buf_l = vec![db_to_gain(-60.0); len];
+    let mut buf_r = buf_l.clone();
+    dsp.process(&mut buf_l, &mut buf_r, Some((&loud, &loud)));
+
+    assert_eq!(dsp.gain_reduction_db(), 0.0);
+    assert_eq!(buf_l[len - 1], db_to_gain(-60.0));
+}
+
+#[test]
+fn algorithms_switch_and_reset_cleanly() {
+    let sample_rate = 48_000.0;
+    let input = sine(1_000.0, sample_rate, 4_096);
+
+    for algorithm in ALGORITHMS {
+        for other in ALGORITHMS {
+            let params = CompressorParams {
+                algorithm,
+                ..Default::default()
+            };
+            let mut dsp = CompressorDsp::new(sample_rate);
+            dsp.set_params(params);
+
+            let mut first_l = input.clone();
+            let mut first_r = input.clone();
+            dsp.process(&mut first_l, &mut first_r, None);
+
+            // Runs a different algorithm for a while, then switches back and resets.
+            dsp.set_params(CompressorParams {
+                algorithm: other,
+                ..params
+            });
+            let mut buf_l = input.clone();
+            let mut buf_r = input.clone();
+            dsp.process(&mut buf_l, &mut buf_r, None);
+            assert!(buf_l.iter().all(|x| x.is_finite()));
+
+            dsp.set_params(params);
+            dsp.reset();
+            let mut buf_l = input.clone();
+            let mut buf_r = input.clone();
+            dsp.process(&mut buf_l, &mut buf_r, None);
+
+            assert_eq!(buf_l, first_l, "{algorithm:?} after {other:?}");
+            assert_eq!(buf_r, first_r, "{algorithm:?} after {other:?}");
+        }
+    }
+}
+
+#[test]
+fn gains_are_smoothed() {
+    let sample_rate = 48_000.0;
+    let len = sample_rate as usize;
+    let params = CompressorParams {
+        // Keeps the compressor out of the way.
+        threshold_db: 0.0,
+        ratio: 1.0,
+        ..Default::default()
+    };
+
+    for algorithm in [Algorithm::Darc, Algorithm::ZamCompX2] {
+        let mut dsp = CompressorDsp::new(sample_rate);
+        dsp.set_params(CompressorParams {
+            algorithm,
+            ..params
+        });
+        dsp.set_params(CompressorParams {
+            algorithm,
+            input_gain_db: -6.0,
+            output_gain_db: -6.0,
+            ..params
+        });
+
+        let mut buf_l = vec![0.25f32; len];
+        let mut buf_r = buf_l.clone();
+        dsp.process(&mut buf_l, &mut buf_r, None);
+
+        let max_step = buf_l
+            .windows(2)
+            .map(|pair| (pair[1] - pair[0]).abs())
+            .fold(0.0f32, f32::max);
+        assert!(max_step < 1e-3, "{algorithm:?}: steps of {max_step}");
+        assert!((gain_to_db(buf_l[len - 1]) - gain_to_db(0.25) + 12.0).abs() < 0.01);
+    }
+}
@@ -0,0 +1,310 @@
+use meadow_dynamic_eq_dsp::{DynamicBandParams, DynamicEqDsp, DynamicEqParams, Sidechain};
+use meadow_eq_dsp::{BandParams, BandType, DEFAULT_Q};
+use nih_plug::prelude::*;
+use std::sync::Arc;
+
+const NUM_BANDS: usize = 4;
+
+const DEFAULT_BAND_CUTOFFS_HZ: [f32; NUM_BANDS] = [100.0, 1000.0, 4000.0, 8000.0];
+
+pub struct MeadowDynamicEq {
+    params: Arc<MeadowDynamicEqParams>,
+    dsp: DynamicEqDsp<NUM_BANDS>,
+}
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+enum SidechainSource {
+    #[id = "internal"]
+    Internal,
+    #[id = "external"]
+    External,
+}
+
+#[derive(Params)]
+struct MeadowDynamicEqParams {
+    #[nested(id_prefix = "band_1", group = "Band 1")]
+    pub band_1: DynamicBandParamsGroup,
+    #[nested(id_prefix = "band_2", group = "Band 2")]
+    pub band_2: DynamicBandParamsGroup,
+    #[nested(id_prefix = "band_3", group = "Band 3")]
+    pub band_3: DynamicBandParamsGroup,
+    #[nested(id_prefix = "band_4", group = "Band 4")]
+    pub band_4: DynamicBandParamsGroup,
+}
+
+#[derive(Params)]
+struct DynamicBandParamsGroup {
+    #[id = "enabled"]
+    pub enabled: BoolParam,
+    #[id = "type"]
+    pub band_type: IntParam,
+    #[id = "cutoff_hz"]
+    pub cutoff_hz: FloatParam,
+    #[id = "q"]
+    pub q: FloatParam,
+    #[id = "gain_db"]
+    pub gain_db: FloatParam,
+
+    #[id = "threshold_db"]
+    pub threshold_db: FloatParam,
+    #[id = "ratio"]
+    pub ratio: FloatParam,
+    #[id = "attack_ms"]
+    pub attack_ms: FloatParam,
+    #[id = "release_ms"]
+    pub release_ms: FloatParam,
+    #[id = "range_db"]
+    pub range_db: FloatParam,
+    #[id = "sidechain"]
+    pub sidechain: EnumParam<SidechainSource>,
+}
+
+impl Default for MeadowDynamicEq {
+    fn default() -> Self {
+        Self {
+            params: Arc::new(MeadowDynamicEqParams::default()),
+            dsp: DynamicEqDsp::new(44_100.0),
+        }
+    }
+}
+
+impl Default for MeadowDynamicEqParams {
+    fn default() -> Self {
+        Self {
+            band_1: DynamicBandParamsGroup::new(0),
+            band_2: DynamicBandParamsGroup::new(1),
+            band_3: DynamicBandParamsGroup::new(2),
+            band_4: DynamicBandParamsGroup::new(3),
+        }
+    }
+}
+
+fn gain_range() -> FloatRange {
+    FloatRange::SymmetricalSkewed {
+        min: -30.0,
+        max: 30.0,
+        factor: 0.4,
+        center: 0.0,
+    }
+}
+
+impl DynamicBandParamsGroup {
+    fn new(band_index: usize) -> Self {
+        let n = band_index + 1;
+
+        Self {
+            enabled: BoolParam::new(format!("Band {n} enabled"), false),
+            // Notches and allpasses have no gain to follow the sidechain.
+            band_type: IntParam::new(
+                format!("Band {n} type"),
+                0,
+                IntRange::Linear { min: 0, max: 2 },
+            )
+            .with_value_to_string(Arc::new(|v| match v {
+                0 => String::from("bell"),
+                1 => String::from("low shelf"),
+                _ => String::from("high shelf"),
+            })),
+            cutoff_hz: FloatParam::new(
+                format!("Band {n} cutoff"),
+                DEFAULT_BAND_CUTOFFS_HZ[band_index],
+                FloatRange::Skewed {
+                    min: 20.0,
+                    max: 21_480.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" Hz"),
+            q: FloatParam::new(
+                format!("Band {n} Q"),
+                DEFAULT_Q,
+                FloatRange::SymmetricalSkewed {
+                    min: 0.02,
+                    max: 40.0,
+                    factor: 0.85,
+                    center: 2.5,
+                },
+            ),
+            gain_db: FloatParam::new(format!("Band {n} Gain"), 0.0, gain_range()).with_unit(" dB"),
+
+            threshold_db: FloatParam::new(
+                format!("Band {n} threshold"),
+                -20.0,
+                FloatRange::Linear {
+                    min: -60.0,
+                    max: 0.0,
+                },
+            )
+            .with_unit(" dB"),
+            ratio: FloatParam::new(
+                format!("Band {n} ratio"),
+                4.0,
+                FloatRange::Skewed {
+                    min: 1.0,
+                    max: 20.0,
+                    factor: FloatRange::skew_factor(-1.5),
+                },
+            )
+            .with_value_to_string(Arc::new(|v| format!("{v:.1}:1"))),
+            attack_ms: FloatParam::new(
+                format!("Band {n} attack"),
+                5.0,
+                FloatRange::Skewed {
+                    min: 0.1,
+                    max: 100.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" ms"),
+            release_ms: FloatParam::new(
+                format!("Band {n} release"),
+                100.0,
+                FloatRange::Skewed {
+                    min: 5.0,
+                    max: 1000.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" ms"),
+            range_db: FloatParam::new(format!("Band {n} range"), -12.0, gain_range())
+                .with_unit(" dB"),
+            sidechain: EnumParam::new(format!("Band {n} sidechain"), SidechainSource::Internal),
+        }
+    }
+
+    fn band_params(&self) -> DynamicBandParams {
+        DynamicBandParams {
+            band: BandParams {
+                enabled: self.enabled.value(),
+                band_type: BandType::from_u32(self.band_type.value() as u32),
+                cutoff_hz: self.cutoff_hz.value(),
+                q: self.q.value(),
+                gain_db: self.gain_db.value(),
+            },
+            threshold_db: self.threshold_db.value(),
+            ratio: self.ratio.value(),
+            attack_ms: self.attack_ms.value(),
+            release_ms: self.release_ms.value(),
+            range_db: self.range_db.value(),
+            sidechain: match self.sidechain.value() {
+                SidechainSource::Internal => Sidechain::Internal,
+                SidechainSource::External => Sidechain::External,
+            },
+        }
+    }
+}
+
+impl MeadowDynamicEqParams {
+    pub fn bands(&self) -> [&DynamicBandParamsGroup; NUM_BANDS] {
+        [&self.band_1, &self.band_2, &self.band_3, &self.band_4]
+    }
+
+    /// Collects the current parameter values into the form used by the DSP.
+    pub fn eq_params(&self) -> DynamicEqParams<NUM_BANDS> {
+        let bands = self.bands();
+
+        DynamicEqParams {
+            bands: std::array::from_fn(|i| bands[i].band_params()),
+        }
+    }
+}
+
+impl Plugin for MeadowDynamicEq {
+    const NAME: &'static str = "Meadow Dynamic Eq";
+    const VENDOR: &'static str = "Billy Messenger";
+    const URL: &'static str = env!("CARGO_PKG_HOMEPAGE");
+    const EMAIL: &'static str = "60663878+BillyDM@users.noreply.github.com";
+
+    const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+
+    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[AudioIOLayout {
+        main_input_channels: NonZeroU32::new(2),
+        main_output_channels: NonZeroU32::new(2),
+
+        aux_input_ports: &[new_nonzero_u32(2)],
+        aux_output_ports: &[],
+
+        names: PortNames {
+            aux_inputs: &["Sidechain"],
+            ..PortNames::const_default()
+        },
+    }];
+
+    const MIDI_INPUT: MidiConfig = MidiConfig::None;
+    const MIDI_OUTPUT: MidiConfig = MidiConfig::None;
+
+    const SAMPLE_ACCURATE_AUTOMATION: bool = true;
+
+    type SysExMessage = ();
+    type BackgroundTask = ();
+
+    fn params(&self) -> Arc<dyn Params> {
+        self.params.clone()
+    }
+
+    fn initialize(
+        &mut self,
+        _audio_io_layout: &AudioIOLayout,
+        config: &BufferConfig,
+        _context: &mut impl InitContext<Self>,
+    ) -> bool {
+        self.dsp = DynamicEqDsp::new(config.sample_rate as f64);
+
+        true
+    }
+
+    fn reset(&mut self) {
+        self.dsp.reset();
+    }
+
+    fn process(
+        &mut self,
+        buffer: &mut Buffer,
+        aux: &mut AuxiliaryBuffers,
+        _context: &mut impl ProcessContext<Self>,
+    ) -> ProcessStatus {
+        self.dsp.set_params(self.params.eq_params());
+
+        let [out_l, out_r, ..] = buffer.as_slice() else {
+            return ProcessStatus::Normal;
+        };
+        let sidechain = match aux.inputs.first_mut().map(|sidechain| sidechain.as_slice()) {
+            Some([sc_l, sc_r, ..]) => Some((&**sc_l, &**sc_r)),
+            _ => None,
+        };
+
+        self.dsp.process(out_l, out_r, sidechain);
+
+        ProcessStatus::Normal
+    }
+}
+
+impl ClapPlugin for MeadowDynamicEq {
+    const CLAP_ID: &'static str = "app.meadowlark.meadow-dynamic-eq";
+    const CLAP_DESCRIPTION: Option<&'static str> =
+        Some("A dynamic EQ that also works as a de-esser");
+    const CLAP_MANUAL_URL: Option<&'static str> = Some(Self::URL);
+    const CLAP_SUPPORT_URL: Option<&'static str> = None;
+
+    const CLAP_FEATURES: &'static [ClapFeature] = &[
+        ClapFeature::AudioEffect,
+        ClapFeature::Stereo,
+        ClapFeature::Equalizer,
+        ClapFeature::Compressor,
+    ];
+}
+
+impl Vst3Plugin for MeadowDynamicEq {
+    const VST3_CLASS_ID: [u8; 16] = *b"Meadowlark.DynEQ";
+
+    const VST3_SUBCATEGORIES: &'static [Vst3SubCategory] = &[
+        Vst3SubCategory::Fx,
+        Vst3SubCategory::Eq,
+        Vst3SubCategory::Dynamics,
+    ];
+}
+
+#[cfg(not(feature = "bundled"))]
+nih_export_clap!(MeadowDynamicEq);
+#[cfg(not(feature = "bundled"))]
+nih_export_vst3!(MeadowDynamicEq);
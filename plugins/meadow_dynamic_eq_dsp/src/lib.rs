@@ -0,0 +1,309 @@
+//! A dynamic EQ, where the gain of each band follows the level of a sidechain signal like a
+//! compressor or an expander would. With a high shelf or a bell in the sibilance range it
+//! doubles as a de-esser.
+
+use meadow_common_dsp::{db_to_gain, time_constant};
+use meadow_eq_dsp::{BandParams, BandType, DEFAULT_Q, SvfCoeff, SvfState, prewarp};
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Sidechain {
+    /// The band listens to the main input.
+    Internal,
+    /// The band listens to the external sidechain input, or to the main input if there is
+    /// none.
+    External,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DynamicBandParams {
+    /// The filter and its gain while the sidechain is below the threshold.
+    pub band: BandParams,
+
+    /// The sidechain level in dBFS above which the gain starts to change.
+    pub threshold_db: f32,
+    /// Every `ratio` decibels the sidechain rises above the threshold change the gain by
+    /// `ratio - 1` decibels. A ratio of 1 keeps the band static.
+    pub ratio: f32,
+    pub attack_ms: f32,
+    pub release_ms: f32,
+    /// The most the gain can change. Negative values cut when the sidechain is above the
+    /// threshold, positive values boost.
+    pub range_db: f32,
+
+    pub sidechain: Sidechain,
+}
+
+impl Default for DynamicBandParams {
+    fn default() -> Self {
+        Self {
+            band: BandParams::default(),
+            threshold_db: -20.0,
+            ratio: 4.0,
+            attack_ms: 5.0,
+            release_ms: 100.0,
+            range_db: -12.0,
+            sidechain: Sidechain::Internal,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DynamicEqParams<const NUM_BANDS: usize> {
+    pub bands: [DynamicBandParams; NUM_BANDS],
+}
+
+impl<const NUM_BANDS: usize> Default for DynamicEqParams<NUM_BANDS> {
+    fn default() -> Self {
+        Self {
+            bands: [DynamicBandParams::default(); NUM_BANDS],
+        }
+    }
+}
+
+pub struct DynamicEqDsp<const NUM_BANDS: usize> {
+    params: DynamicEqParams<NUM_BANDS>,
+    sample_rate: f64,
+
+    bands: [DynamicBand; NUM_BANDS],
+}
+
+impl<const NUM_BANDS: usize> DynamicEqDsp<NUM_BANDS> {
+    pub fn new(sample_rate: f64) -> Self {
+        assert!(sample_rate > 0.0);
+
+        let params = DynamicEqParams::default();
+
+        Self {
+            params,
+            sample_rate,
+            bands: params
+                .bands
+                .map(|band_params| DynamicBand::new(&band_params, sample_rate)),
+        }
+    }
+
+    pub fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+
+    pub fn set_params(&mut self, params: DynamicEqParams<NUM_BANDS>) {
+        if self.params == params {
+            return;
+        }
+
+        for ((band, band_params), old_band_params) in self
+            .bands
+            .iter_mut()
+            .zip(params.bands.iter())
+            .zip(self.params.bands.iter())
+        {
+            if band_params != old_band_params {
+                band.set(band_params, self.sample_rate);
+            }
+        }
+
+        self.params = params;
+    }
+
+    /// The current gain change of each band in decibels, on top of its static gain.
+    pub fn gain_changes_db(&self) -> [f32; NUM_BANDS] {
+        std::array::from_fn(|i| self.bands[i].gain_change_db)
+    }
+
+    pub fn reset(&mut self) {
+        for band in self.bands.iter_mut() {
+            band.reset();
+        }
+    }
+
+    /// Processes the main signal in place. Bands that listen to the external sidechain fall
+    /// back to the main input where `sidechain` is missing or too short.
+    pub fn process(
+        &mut self,
+        buf_l: &mut [f32],
+        buf_r: &mut [f32],
+        sidechain: Option<(&[f32], &[f32])>,
+    ) {
+        for (i, (l, r)) in buf_l.iter_mut().zip(buf_r.iter_mut()).enumerate() {
+            let main = 0.5 * (*l + *r);
+            let external = sidechain
+                .and_then(|(sc_l, sc_r)| Some(0.5 * (sc_l.get(i)? + sc_r.get(i)?)))
+                .unwrap_or(main);
+
+            for band in self.bands.iter_mut().filter(|band| band.enabled) {
+                band.follow(if band.external { external } else { main });
+
+                *l = band.state[0].tick(*l, &band.coeff);
+                *r = band.state[1].tick(*r, &band.coeff);
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct DynamicBand {
+    enabled: bool,
+    band_type: BandType,
+    /// The prewarped cutoff, so only the gain has to be recomputed every sample.
+    g: f64,
+    q: f64,
+    gain_db: f64,
+
+    coeff: SvfCoeff,
+    state: [SvfState; 2],
+
+    /// Whether the band type has a gain that can follow the sidechain at all.
+    dynamic: bool,
+    external: bool,
+    threshold: f32,
+    threshold_db: f32,
+    /// The gain change per decibel above the threshold.
+    slope: f32,
+    range_db: f32,
+    attack: f32,
+    release: f32,
+
+    /// Limits the sidechain to the part of the spectrum the band affects.
+    detector_coeff: SvfCoeff,
+    detector_state: SvfState,
+    envelope: f32,
+    /// The gain change the coefficients were computed for.
+    gain_change_db: f32,
+}
+
+impl DynamicBand {
+    fn new(params: &DynamicBandParams, sample_rate: f64) -> Self {
+        let mut new_self = Self {
+            enabled: false,
+            band_type: BandType::Bell,
+            g: 0.0,
+            q: 0.0,
+            gain_db: 0.0,
+            coeff: SvfCoeff::default(),
+            state: [SvfState::default(); 2],
+            dynamic: false,
+            external: false,
+            threshold: 0.0,
+            threshold_db: 0.0,
+            slope: 0.0,
+            range_db: 0.0,
+            attack: 0.0,
+            release: 0.0,
+            detector_coeff: SvfCoeff::default(),
+            detector_state: SvfState::default(),
+            envelope: 0.0,
+            gain_change_db: 0.0,
+        };
+        new_self.set(params, sample_rate);
+
+        new_self
+    }
+
+    fn set(&mut self, params: &DynamicBandParams, sample_rate: f64) {
+        let sample_rate_recip = sample_rate.recip();
+        let BandParams {
+            enabled,
+            band_type,
+            cutoff_hz,
+            q,
+            gain_db,
+        } = params.band;
+        let cutoff_hz = cutoff_hz as f64;
+
+        self.enabled = enabled;
+        self.band_type = band_type;
+        self.g = prewarp(cutoff_hz, sample_rate_recip);
+        self.q = q as f64;
+        self.gain_db = gain_db as f64;
+
+        self.external = params.sidechain == Sidechain::External;
+        self.threshold = db_to_gain(params.threshold_db);
+        self.threshold_db = params.threshold_db;
+        self.slope = 1.0 - params.ratio.max(1.0).recip();
+        self.range_db = params.range_db;
+        self.attack = time_constant(params.attack_ms, sample_rate);
+        self.release = time_constant(params.release_ms, sample_rate);
+
+        (self.dynamic, self.detector_coeff) = match band_type {
+            BandType::Bell => (
+                true,
+                SvfCoeff::bandpass(cutoff_hz, sample_rate_recip, self.q),
+            ),
+            BandType::LowShelf => (
+                true,
+                SvfCoeff::lowpass_ord2(cutoff_hz, sample_rate_recip, DEFAULT_Q as f64),
+            ),
+            BandType::HighShelf => (
+                true,
+                SvfCoeff::highpass_ord2(cutoff_hz, sample_rate_recip, DEFAULT_Q as f64),
+            ),
+            BandType::Notch | BandType::Allpass => (false, SvfCoeff::default()),
+        };
+        if !self.dynamic {
+            self.envelope = 0.0;
+            self.gain_change_db = 0.0;
+        }
+
+        self.coeff = match band_type {
+            BandType::Notch => SvfCoeff::notch(cutoff_hz, sample_rate_recip, self.q),
+            BandType::Allpass => SvfCoeff::allpass(cutoff_hz, sample_rate_recip, self.q),
+            _ => self.dynamic_coeff(),
+        };
+    }
+
+    fn reset(&mut self) {
+        self.state = [SvfState::default(); 2];
+        self.detector_state = SvfState::default();
+        self.envelope = 0.0;
+
+        if self.gain_change_db != 0.0 {
+            self.gain_change_db = 0.0;
+            self.coeff = self.dynamic_coeff();
+        }
+    }
+
+    /// Updates the envelope with the next sidechain sample, and the coefficients if that
+    /// changes the gain.
+    #[inline(always)]
+    fn follow(&mut self, sidechain: f32) {
+        if !self.dynamic {
+            return;
+        }
+
+        let level = self
+            .detector_state
+            .tick(sidechain, &self.detector_coeff)
+            .abs();
+        let coeff = if level > self.envelope {
+            self.attack
+        } else {
+            self.release
+        };
+        self.envelope = level + coeff * (self.envelope - level);
+
+        let gain_change_db = if self.envelope > self.threshold {
+            let over_db = 20.0 * self.envelope.log10() - self.threshold_db;
+            (over_db * self.slope).min(self.range_db.abs()) * self.range_db.signum()
+        } else {
+            0.0
+        };
+
+        if gain_change_db != self.gain_change_db {
+            self.gain_change_db = gain_change_db;
+            self.coeff = self.dynamic_coeff();
+        }
+    }
+
+    fn dynamic_coeff(&self) -> SvfCoeff {
+        let gain_db = self.gain_db + self.gain_change_db as f64;
+
+        match self.band_type {
+            BandType::LowShelf => SvfCoeff::low_shelf_prewarped(self.g, self.q, gain_db),
+            BandType::HighShelf => SvfCoeff::high_shelf_prewarped(self.g, self.q, gain_db),
+            _ => SvfCoeff::bell_prewarped(self.g, self.q, gain_db),
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use meadow_common_dsp::gain_to_db;
+use meadow_eq_dsp::{BandParams, BandType, EqParams, MeadowEqDsp};
+use meadow_test_signals::{rms, sine};
+
+use crate::{DynamicBandParams, DynamicEqDsp, DynamicEqParams, Sidechain};
+
+const NUM_BANDS: usize = 4;
+const SAMPLE_RATE: f64 = 48_000.0;
+
+/// Long enough for every envelope in these tests to settle.
+const SETTLE_LEN: usize = 48_000;
+const MEASURE_LEN: usize = 4_800;
+
+fn band(band_type: BandType, cutoff_hz: f32, gain_db: f32) -> DynamicBandParams {
+    DynamicBandParams {
+        band: BandParams {
+            enabled: true,
+            band_type,
+            cutoff_hz,
+            q: 1.0,
+            gain_db,
+        },
+        threshold_db: -20.0,
+        ratio: 4.0,
+        attack_ms: 0.1,
+        release_ms: 200.0,
+        range_db: -12.0,
+        sidechain: Sidechain::Internal,
+    }
+}
+
+fn with_bands(bands: &[DynamicBandParams]) -> DynamicEqParams<NUM_BANDS> {
+    let mut params = DynamicEqParams::default();
+    params.bands[..bands.len()].copy_from_slice(bands);
+    params
+}
+
+fn sine_at(freq_hz: f32, amplitude: f32, len: usize) -> Vec<f32> {
+    sine(freq_hz, SAMPLE_RATE, len)
+        .into_iter()
+        .map(|x| amplitude * x)
+        .collect()
+}
+
+/// Runs `input` through `dsp` and returns the level of the last [`MEASURE_LEN`] samples of
+/// the left channel in dB, relative to the input's.
+fn gain_db(dsp: &mut DynamicEqDsp<NUM_BANDS>, input: &[f32], sidechain: Option<&[f32]>) -> f32 {
+    let mut buf_l = input.to_vec();
+    let mut buf_r = input.to_vec();
+    for (i, (l, r)) in buf_l.chunks_mut(256).zip(buf_r.chunks_mut(256)).enumerate() {
+        let sidechain = sidechain.map(|sc| {
+            let sc = &sc[i * 256..i * 256 + l.len()];
+            (sc, sc)
+        });
+        dsp.process(l, r, sidechain);
+    }
+
+    let tail = input.len() - MEASURE_LEN;
+    gain_to_db(rms(&buf_l[tail..])) - gain_to_db(rms(&input[tail..]))
+}
+
+#[test]
+fn below_the_threshold_the_bands_match_the_static_eq() {
+    let bands = [
+        band(BandType::Bell, 500.0, 6.0),
+        band(BandType::LowShelf, 100.0, -4.0),
+        band(BandType::HighShelf, 8_000.0, 3.0),
+        band(BandType::Notch, 2_000.0, 0.0),
+    ];
+
+    let mut dsp = DynamicEqDsp::<NUM_BANDS>::new(SAMPLE_RATE);
+    dsp.set_params(DynamicEqParams {
+        bands: bands.map(|band| DynamicBandParams {
+            threshold_db: 0.0,
+            ..band
+        }),
+    });
+    let mut eq = MeadowEqDsp::<NUM_BANDS>::new(SAMPLE_RATE);
+    eq.set_params(EqParams {
+        bands: bands.map(|band| band.band),
+        ..EqParams::default()
+    });
+
+    // Noise at -30 dBFS stays well below the threshold in every band.
+    let mut rng_state = 1u32;
+    let input: Vec<f32> = (0..8192)
+        .map(|_| {
+            rng_state ^= rng_state << 13;
+            rng_state ^= rng_state >> 17;
+            rng_state ^= rng_state << 5;
+            ((rng_state as f32 / u32::MAX as f32) * 2.0 - 1.0) * 0.03
+        })
+        .collect();
+
+    let (mut dynamic_l, mut dynamic_r) = (input.clone(), input.clone());
+    dsp.process(&mut dynamic_l, &mut dynamic_r, None);
+    let (mut static_l, mut static_r) = (input.clone(), input);
+    eq.process(&mut static_l, &mut static_r);
+
+    assert_eq!(dsp.gain_changes_db(), [0.0; NUM_BANDS]);
+    for (n, (dynamic, expected)) in dynamic_l.iter().zip(static_l.iter()).enumerate() {
+        assert!(
+            (dynamic - expected).abs() < 1e-6,
+            "sample {n}: {dynamic}, expected {expected}"
+        );
+    }
+}
+
+#[test]
+fn bell_compresses_above_the_threshold() {
+    let mut dsp = DynamicEqDsp::<NUM_BANDS>::new(SAMPLE_RATE);
+    dsp.set_params(with_bands(&[band(BandType::Bell, 1_000.0, 0.0)]));
+
+    // A sine at -6 dBFS peak is 14 dB above the threshold, which a ratio of 4 turns into a
+    // 10.5 dB cut.
+    let gain = gain_db(&mut dsp, &sine_at(1_000.0, 0.5, SETTLE_LEN), None);
+    assert!((gain + 10.5).abs() < 0.5, "{gain} dB");
+
+    // Far away from the bell, the same level passes through.
+    dsp.reset();
+    let gain = gain_db(&mut dsp, &sine_at(100.0, 0.5, SETTLE_LEN), None);
+    assert!(gain.abs() < 0.1, "{gain} dB");
+}
+
+#[test]
+fn range_limits_the_gain_change() {
+    for (range_db, expected_db) in [(-6.0, -6.0), (6.0, 6.0), (0.0, 0.0)] {
+        let mut dsp = DynamicEqDsp::<NUM_BANDS>::new(SAMPLE_RATE);
+        dsp.set_params(with_bands(&[DynamicBandParams {
+            range_db,
+            ..band(BandType::Bell, 1_000.0, 0.0)
+        }]));
+
+        let gain = gain_db(&mut dsp, &sine_at(1_000.0, 0.5, SETTLE_LEN), None);
+        assert!(
+            (gain - expected_db).abs() < 0.2,
+            "range {range_db} dB: {gain} dB"
+        );
+    }
+}
+
+#[test]
+fn external_sidechain_drives_the_band() {
+    // Far enough above the shelf's cutoff for its full gain.
+    let input = sine_at(16_000.0, 0.01, SETTLE_LEN);
+    let sidechain = sine_at(16_000.0, 0.5, SETTLE_LEN);
+
+    for (source, expected_db) in [(Sidechain::Internal, 0.0), (Sidechain::External, -10.5)] {
+        let mut dsp = DynamicEqDsp::<NUM_BANDS>::new(SAMPLE_RATE);
+        dsp.set_params(with_bands(&[DynamicBandParams {
+            sidechain: source,
+            ..band(BandType::HighShelf, 2_000.0, 0.0)
+        }]));
+
+        let gain = gain_db(&mut dsp, &input, Some(&sidechain));
+        assert!(
+            (gain - expected_db).abs() < 0.5,
+            "{source:?} sidechain: {gain} dB"
+        );
+    }
+}
+
+/// Extreme settings with the gain of every band swinging back and forth as fast as the
+/// envelopes allow. The output is allowed to get as loud as every band boosting by the
+/// maximum of 30 dB at once, with some headroom for the noise's peaks.
+#[test]
+fn fast_modulation_stays_stable() {
+    let max_amplitude = 10.0 * 10.0f32.powf(NUM_BANDS as f32 * 30.0 / 20.0);
+
+    let band_types = [BandType::Bell, BandType::LowShelf, BandType::HighShelf];
+    let mut rng_state = 7u32;
+    let mut random = move || {
+        rng_state ^= rng_state << 13;
+        rng_state ^= rng_state >> 17;
+        rng_state ^= rng_state << 5;
+        rng_state as f32 / u32::MAX as f32
+    };
+
+    for sample_rate in [22_050.0, 48_000.0, 192_000.0] {
+        let mut dsp = DynamicEqDsp::<NUM_BANDS>::new(sample_rate);
+        let mut buf_l = vec![0.0; 512];
+        let mut buf_r = vec![0.0; 512];
+
+        for block in 0..400 {
+            let mut params = DynamicEqParams::<NUM_BANDS>::default();
+            for band in params.bands.iter_mut() {
+                *band = DynamicBandParams {
+                    band: BandParams {
+                        enabled: true,
+                        band_type: band_types[(random() * 3.0) as usize % 3],
+                        cutoff_hz: 20.0 * 1000.0f32.powf(random()),
+                        q: 0.02 * 2000.0f32.powf(random()),
+                        gain_db: random() * 60.0 - 30.0,
+                    },
+                    threshold_db: -60.0 * random(),
+                    ratio: 1.0 + 19.0 * random(),
+                    attack_ms: 0.1,
+                    release_ms: 5.0,
+                    range_db: random() * 60.0 - 30.0,
+                    sidechain: Sidechain::Internal,
+                };
+            }
+            dsp.set_params(params);
+
+            // Bursts of full scale noise and silence.
+            let level = if block % 2 == 0 { 1.0 } else { 0.0 };
+            for (l, r) in buf_l.iter_mut().zip(buf_r.iter_mut()) {
+                *l = (random() * 2.0 - 1.0) * level;
+                *r = (random() * 2.0 - 1.0) * level;
+            }
+            dsp.process(&mut buf_l, &mut buf_r, None);
+
+            for x in buf_l.iter().chain(buf_r.iter()) {
+                assert!(
+                    x.is_finite() && x.abs() < max_amplitude,
+                    "{sample_rate} Hz, block {block}: {x}"
+                );
+            }
+        }
+    }
+}
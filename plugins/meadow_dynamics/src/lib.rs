@@ -0,0 +1,296 @@
+use meadow_dynamics_dsp::{BandMode, DynamicsBandParams, DynamicsDsp, DynamicsParams};
+use nih_plug::prelude::*;
+use std::sync::Arc;
+
+pub struct MeadowDynamics {
+    params: Arc<MeadowDynamicsParams>,
+    dsp: DynamicsDsp,
+}
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+enum BandModeParam {
+    #[id = "single"]
+    #[name = "Single band"]
+    Single,
+    #[id = "multiband"]
+    Multiband,
+}
+
+#[derive(Params)]
+struct MeadowDynamicsParams {
+    #[id = "mode"]
+    pub mode: EnumParam<BandModeParam>,
+    #[id = "low_crossover_hz"]
+    pub low_crossover_hz: FloatParam,
+    #[id = "high_crossover_hz"]
+    pub high_crossover_hz: FloatParam,
+    #[id = "attack_ms"]
+    pub attack_ms: FloatParam,
+    #[id = "release_ms"]
+    pub release_ms: FloatParam,
+
+    #[nested(id_prefix = "low", group = "Low")]
+    pub low: DynamicsBandParamsGroup,
+    #[nested(id_prefix = "mid", group = "Mid")]
+    pub mid: DynamicsBandParamsGroup,
+    #[nested(id_prefix = "high", group = "High")]
+    pub high: DynamicsBandParamsGroup,
+}
+
+#[derive(Params)]
+struct DynamicsBandParamsGroup {
+    #[id = "lower_threshold_db"]
+    pub lower_threshold_db: FloatParam,
+    #[id = "upper_threshold_db"]
+    pub upper_threshold_db: FloatParam,
+    #[id = "lower_ratio"]
+    pub lower_ratio: FloatParam,
+    #[id = "upper_ratio"]
+    pub upper_ratio: FloatParam,
+    #[id = "gain_db"]
+    pub gain_db: FloatParam,
+}
+
+impl Default for MeadowDynamics {
+    fn default() -> Self {
+        Self {
+            params: Arc::new(MeadowDynamicsParams::default()),
+            dsp: DynamicsDsp::new(44_100.0),
+        }
+    }
+}
+
+fn crossover_range() -> FloatRange {
+    FloatRange::Skewed {
+        min: 20.0,
+        max: 20_000.0,
+        factor: FloatRange::skew_factor(-2.0),
+    }
+}
+
+fn threshold_range() -> FloatRange {
+    FloatRange::Linear {
+        min: -80.0,
+        max: 0.0,
+    }
+}
+
+impl Default for MeadowDynamicsParams {
+    fn default() -> Self {
+        let defaults = DynamicsParams::default();
+
+        Self {
+            mode: EnumParam::new("Mode", BandModeParam::Multiband),
+            low_crossover_hz: FloatParam::new(
+                "Low crossover",
+                defaults.crossovers_hz[0],
+                crossover_range(),
+            )
+            .with_unit(" Hz"),
+            high_crossover_hz: FloatParam::new(
+                "High crossover",
+                defaults.crossovers_hz[1],
+                crossover_range(),
+            )
+            .with_unit(" Hz"),
+            attack_ms: FloatParam::new(
+                "Attack",
+                defaults.attack_ms,
+                FloatRange::Skewed {
+                    min: 0.1,
+                    max: 100.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" ms"),
+            release_ms: FloatParam::new(
+                "Release",
+                defaults.release_ms,
+                FloatRange::Skewed {
+                    min: 5.0,
+                    max: 1000.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" ms"),
+
+            low: DynamicsBandParamsGroup::new("Low", &defaults.bands[0]),
+            mid: DynamicsBandParamsGroup::new("Mid", &defaults.bands[1]),
+            high: DynamicsBandParamsGroup::new("High", &defaults.bands[2]),
+        }
+    }
+}
+
+impl DynamicsBandParamsGroup {
+    fn new(name: &str, defaults: &DynamicsBandParams) -> Self {
+        Self {
+            lower_threshold_db: FloatParam::new(
+                format!("{name} lower threshold"),
+                defaults.lower_threshold_db,
+                threshold_range(),
+            )
+            .with_unit(" dB"),
+            upper_threshold_db: FloatParam::new(
+                format!("{name} upper threshold"),
+                defaults.upper_threshold_db,
+                threshold_range(),
+            )
+            .with_unit(" dB"),
+            // Negative lower ratios expand downwards instead of compressing upwards.
+            lower_ratio: FloatParam::new(
+                format!("{name} lower ratio"),
+                defaults.lower_ratio * 100.0,
+                FloatRange::Linear {
+                    min: -100.0,
+                    max: 100.0,
+                },
+            )
+            .with_unit(" %")
+            .with_step_size(1.0),
+            upper_ratio: FloatParam::new(
+                format!("{name} upper ratio"),
+                defaults.upper_ratio * 100.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 100.0,
+                },
+            )
+            .with_unit(" %")
+            .with_step_size(1.0),
+            gain_db: FloatParam::new(
+                format!("{name} gain"),
+                defaults.gain_db,
+                FloatRange::Linear {
+                    min: -30.0,
+                    max: 30.0,
+                },
+            )
+            .with_unit(" dB"),
+        }
+    }
+
+    fn band_params(&self) -> DynamicsBandParams {
+        DynamicsBandParams {
+            lower_threshold_db: self.lower_threshold_db.value(),
+            upper_threshold_db: self.upper_threshold_db.value(),
+            lower_ratio: self.lower_ratio.value() / 100.0,
+            upper_ratio: self.upper_ratio.value() / 100.0,
+            gain_db: self.gain_db.value(),
+        }
+    }
+}
+
+impl MeadowDynamicsParams {
+    /// Collects the current parameter values into the form used by the DSP.
+    pub fn dynamics_params(&self) -> DynamicsParams {
+        DynamicsParams {
+            mode: match self.mode.value() {
+                BandModeParam::Single => BandMode::Single,
+                BandModeParam::Multiband => BandMode::Multiband,
+            },
+            crossovers_hz: [
+                self.low_crossover_hz.value(),
+                self.high_crossover_hz.value(),
+            ],
+            attack_ms: self.attack_ms.value(),
+            release_ms: self.release_ms.value(),
+            bands: [
+                self.low.band_params(),
+                self.mid.band_params(),
+                self.high.band_params(),
+            ],
+        }
+    }
+}
+
+impl Plugin for MeadowDynamics {
+    const NAME: &'static str = "Meadow Dynamics";
+    const VENDOR: &'static str = "Billy Messenger";
+    const URL: &'static str = env!("CARGO_PKG_HOMEPAGE");
+    const EMAIL: &'static str = "60663878+BillyDM@users.noreply.github.com";
+
+    const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+
+    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[AudioIOLayout {
+        main_input_channels: NonZeroU32::new(2),
+        main_output_channels: NonZeroU32::new(2),
+
+        aux_input_ports: &[],
+        aux_output_ports: &[],
+
+        names: PortNames::const_default(),
+    }];
+
+    const MIDI_INPUT: MidiConfig = MidiConfig::None;
+    const MIDI_OUTPUT: MidiConfig = MidiConfig::None;
+
+    const SAMPLE_ACCURATE_AUTOMATION: bool = true;
+
+    type SysExMessage = ();
+    type BackgroundTask = ();
+
+    fn params(&self) -> Arc<dyn Params> {
+        self.params.clone()
+    }
+
+    fn initialize(
+        &mut self,
+        _audio_io_layout: &AudioIOLayout,
+        config: &BufferConfig,
+        _context: &mut impl InitContext<Self>,
+    ) -> bool {
+        self.dsp = DynamicsDsp::new(config.sample_rate as f64);
+        self.dsp.set_params(self.params.dynamics_params());
+        self.dsp.reset();
+
+        true
+    }
+
+    fn reset(&mut self) {
+        self.dsp.reset();
+    }
+
+    fn process(
+        &mut self,
+        buffer: &mut Buffer,
+        _aux: &mut AuxiliaryBuffers,
+        _context: &mut impl ProcessContext<Self>,
+    ) -> ProcessStatus {
+        self.dsp.set_params(self.params.dynamics_params());
+
+        let [out_l, out_r, ..] = buffer.as_slice() else {
+            return ProcessStatus::Normal;
+        };
+
+        self.dsp.process(out_l, out_r);
+
+        ProcessStatus::Normal
+    }
+}
+
+impl ClapPlugin for MeadowDynamics {
+    const CLAP_ID: &'static str = "app.meadowlark.meadow-dynamics";
+    const CLAP_DESCRIPTION: Option<&'static str> =
+        Some("An upward and downward compressor with single-band and 3-band modes");
+    const CLAP_MANUAL_URL: Option<&'static str> = Some(Self::URL);
+    const CLAP_SUPPORT_URL: Option<&'static str> = None;
+
+    const CLAP_FEATURES: &'static [ClapFeature] = &[
+        ClapFeature::AudioEffect,
+        ClapFeature::Stereo,
+        ClapFeature::Compressor,
+        ClapFeature::Expander,
+    ];
+}
+
+impl Vst3Plugin for MeadowDynamics {
+    const VST3_CLASS_ID: [u8; 16] = *b"Meadowlark.Dynam";
+
+    const VST3_SUBCATEGORIES: &'static [Vst3SubCategory] =
+        &[Vst3SubCategory::Fx, Vst3SubCategory::Dynamics];
+}
+
+#[cfg(not(feature = "bundled"))]
+nih_export_clap!(MeadowDynamics);
+#[cfg(not(feature = "bundled"))]
+nih_export_vst3!(MeadowDynamics);
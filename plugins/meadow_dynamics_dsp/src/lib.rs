@@ -0,0 +1,356 @@
+//! An upward and downward compressor that works on the whole signal or on three bands, after
+//! the compressor module in Vitalium.
+//!
+//! Each band follows its RMS level. Above the band's upper threshold the level is pulled down
+//! by the upper ratio, and below its lower threshold it is pushed up by the lower ratio, or
+//! pushed further down when the lower ratio is negative.
+//!
+//! The bands are split with 4th order Linkwitz-Riley crossovers. The low band also goes
+//! through the mid/high crossover's allpass, so with no dynamics applied the bands add back
+//! up to the input with only its phase changed.
+
+use meadow_common_dsp::{Ramp, db_to_gain, time_constant};
+use meadow_eq_dsp::SvfState;
+use meadow_splitter_dsp::{Crossover, CrossoverState};
+
+#[cfg(test)]
+mod tests;
+
+pub const NUM_BANDS: usize = 3;
+
+/// How long it takes to move to new parameters.
+pub const SMOOTHING_MS: f64 = 20.0;
+
+/// The crossovers are the ones in Meadow Splitter, and so is their range.
+pub use meadow_splitter_dsp::{MAX_CROSSOVER_HZ, MIN_CROSSOVER_HZ};
+
+/// The most a band's lower ratio can boost a quiet signal.
+pub const MAX_UPWARD_GAIN_DB: f32 = 30.0;
+
+/// What the meters read for silence or for an unused band.
+pub const SILENCE_DB: f32 = -160.0;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum BandMode {
+    /// The whole signal goes through the mid band's settings.
+    Single,
+    /// The signal is split into low, mid and high bands.
+    #[default]
+    Multiband,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DynamicsBandParams {
+    pub lower_threshold_db: f32,
+    pub upper_threshold_db: f32,
+    /// From `-1.0` to `1.0`. Positive values raise levels below the lower threshold towards
+    /// it (upward compression), with `1.0` raising them all the way. Negative values push
+    /// them further down (downward expansion).
+    pub lower_ratio: f32,
+    /// From `0.0` to `1.0`. Pulls levels above the upper threshold towards it, with `1.0`
+    /// pulling them all the way.
+    pub upper_ratio: f32,
+    /// The band's output gain.
+    pub gain_db: f32,
+}
+
+impl Default for DynamicsBandParams {
+    fn default() -> Self {
+        Self {
+            lower_threshold_db: -40.0,
+            upper_threshold_db: -20.0,
+            lower_ratio: 0.0,
+            upper_ratio: 0.0,
+            gain_db: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DynamicsParams {
+    pub mode: BandMode,
+    /// The low/mid and mid/high crossovers. The second one is kept at or above the first.
+    pub crossovers_hz: [f32; 2],
+    pub attack_ms: f32,
+    pub release_ms: f32,
+    /// The low, mid and high bands.
+    pub bands: [DynamicsBandParams; NUM_BANDS],
+}
+
+impl Default for DynamicsParams {
+    fn default() -> Self {
+        Self {
+            mode: BandMode::default(),
+            crossovers_hz: [120.0, 2500.0],
+            attack_ms: 2.8,
+            release_ms: 40.0,
+            bands: [DynamicsBandParams::default(); NUM_BANDS],
+        }
+    }
+}
+
+/// The level of one band at the end of the last processed block.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BandMeter {
+    /// The RMS level going into the band's dynamics.
+    pub input_db: f32,
+    /// The same level after the band's dynamics and gain.
+    pub output_db: f32,
+}
+
+impl Default for BandMeter {
+    fn default() -> Self {
+        Self {
+            input_db: SILENCE_DB,
+            output_db: SILENCE_DB,
+        }
+    }
+}
+
+pub struct DynamicsDsp {
+    params: DynamicsParams,
+    sample_rate: f64,
+    ramp_len: usize,
+
+    crossovers: [Crossover; 2],
+    states: [[CrossoverState; 2]; 2],
+    /// Matches the low band's phase to the mid/high crossover's.
+    low_allpass: [SvfState; 2],
+    bands: [Band; NUM_BANDS],
+    attack_coeff: f32,
+    release_coeff: f32,
+
+    meters: [BandMeter; NUM_BANDS],
+}
+
+impl DynamicsDsp {
+    pub fn new(sample_rate: f64) -> Self {
+        assert!(sample_rate > 0.0);
+
+        let params = DynamicsParams::default();
+
+        Self {
+            params,
+            sample_rate,
+            ramp_len: ((SMOOTHING_MS / 1000.0 * sample_rate).round() as usize).max(1),
+            crossovers: params
+                .crossovers_hz
+                .map(|crossover_hz| Crossover::new(crossover_hz, sample_rate)),
+            states: [[CrossoverState::default(); 2]; 2],
+            low_allpass: [SvfState::default(); 2],
+            bands: params.bands.map(|band| Band::new(&band)),
+            attack_coeff: time_constant(params.attack_ms, sample_rate),
+            release_coeff: time_constant(params.release_ms, sample_rate),
+            meters: [BandMeter::default(); NUM_BANDS],
+        }
+    }
+
+    pub fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+
+    /// Sets new parameters. Everything but the mode and the attack and release times is
+    /// ramped to over [`SMOOTHING_MS`].
+    pub fn set_params(&mut self, params: DynamicsParams) {
+        if self.params == params {
+            return;
+        }
+
+        let [low_hz, high_hz] = params.crossovers_hz;
+        self.crossovers[0].set_target(low_hz, self.ramp_len);
+        self.crossovers[1].set_target(high_hz.max(low_hz), self.ramp_len);
+        for (band, band_params) in self.bands.iter_mut().zip(&params.bands) {
+            band.set_target(band_params, self.ramp_len);
+        }
+        self.attack_coeff = time_constant(params.attack_ms, self.sample_rate);
+        self.release_coeff = time_constant(params.release_ms, self.sample_rate);
+
+        if params.mode != self.params.mode {
+            // The bands now see a different signal.
+            self.clear();
+        }
+
+        self.params = params;
+    }
+
+    /// The levels of the low, mid and high bands at the end of the last call to
+    /// [`Self::process()`]. In single-band mode only the mid band is used.
+    pub fn meters(&self) -> [BandMeter; NUM_BANDS] {
+        self.meters
+    }
+
+    /// Clears the filters and jumps to the current parameters without ramping.
+    pub fn reset(&mut self) {
+        for crossover in self.crossovers.iter_mut() {
+            crossover.snap(self.sample_rate);
+        }
+        for band in self.bands.iter_mut() {
+            band.snap();
+        }
+        self.clear();
+    }
+
+    fn clear(&mut self) {
+        self.states = [[CrossoverState::default(); 2]; 2];
+        self.low_allpass = [SvfState::default(); 2];
+        for band in self.bands.iter_mut() {
+            band.mean_square = 0.0;
+        }
+        self.meters = [BandMeter::default(); NUM_BANDS];
+    }
+
+    pub fn process(&mut self, buf_l: &mut [f32], buf_r: &mut [f32]) {
+        let coeffs = [self.attack_coeff, self.release_coeff];
+
+        match self.params.mode {
+            BandMode::Single => {
+                for (l, r) in buf_l.iter_mut().zip(buf_r.iter_mut()) {
+                    for crossover in self.crossovers.iter_mut() {
+                        crossover.next(self.sample_rate);
+                    }
+                    for band in self.bands.iter_mut() {
+                        band.next_params();
+                    }
+
+                    let gain = self.bands[1].gain([*l, *r], coeffs);
+                    *l *= gain;
+                    *r *= gain;
+                }
+            }
+            BandMode::Multiband => {
+                for (l, r) in buf_l.iter_mut().zip(buf_r.iter_mut()) {
+                    for crossover in self.crossovers.iter_mut() {
+                        crossover.next(self.sample_rate);
+                    }
+                    for band in self.bands.iter_mut() {
+                        band.next_params();
+                    }
+
+                    let mut split = [[0.0f32; 2]; NUM_BANDS];
+                    for (channel, x) in [*l, *r].into_iter().enumerate() {
+                        let [low_state, high_state] = &mut self.states[channel];
+                        let (low, rest) = low_state.split(x, &self.crossovers[0]);
+                        let (mid, high) = high_state.split(rest, &self.crossovers[1]);
+                        let low = self.low_allpass[channel].tick(low, self.crossovers[1].allpass());
+
+                        split[0][channel] = low;
+                        split[1][channel] = mid;
+                        split[2][channel] = high;
+                    }
+
+                    let mut output = [0.0f32; 2];
+                    for (band, [band_l, band_r]) in self.bands.iter_mut().zip(split) {
+                        let gain = band.gain([band_l, band_r], coeffs);
+                        output[0] += gain * band_l;
+                        output[1] += gain * band_r;
+                    }
+                    [*l, *r] = output;
+                }
+            }
+        }
+
+        for (i, (meter, band)) in self.meters.iter_mut().zip(&self.bands).enumerate() {
+            *meter = if self.params.mode == BandMode::Single && i != 1 {
+                BandMeter::default()
+            } else {
+                let input_db = band.level_db();
+                BandMeter {
+                    input_db,
+                    output_db: input_db + band.gain_db(input_db),
+                }
+            };
+        }
+    }
+}
+
+/// The level detector and gain curve of one band.
+struct Band {
+    lower_threshold_db: Ramp,
+    upper_threshold_db: Ramp,
+    lower_ratio: Ramp,
+    upper_ratio: Ramp,
+    gain_db: Ramp,
+
+    mean_square: f32,
+}
+
+impl Band {
+    fn new(params: &DynamicsBandParams) -> Self {
+        Self {
+            lower_threshold_db: Ramp::new(params.lower_threshold_db),
+            upper_threshold_db: Ramp::new(params.upper_threshold_db),
+            lower_ratio: Ramp::new(params.lower_ratio.clamp(-1.0, 1.0)),
+            upper_ratio: Ramp::new(params.upper_ratio.clamp(0.0, 1.0)),
+            gain_db: Ramp::new(params.gain_db),
+            mean_square: 0.0,
+        }
+    }
+
+    fn set_target(&mut self, params: &DynamicsBandParams, ramp_len: usize) {
+        self.lower_threshold_db
+            .set_target(params.lower_threshold_db, ramp_len);
+        self.upper_threshold_db
+            .set_target(params.upper_threshold_db, ramp_len);
+        self.lower_ratio
+            .set_target(params.lower_ratio.clamp(-1.0, 1.0), ramp_len);
+        self.upper_ratio
+            .set_target(params.upper_ratio.clamp(0.0, 1.0), ramp_len);
+        self.gain_db.set_target(params.gain_db, ramp_len);
+    }
+
+    fn snap(&mut self) {
+        self.lower_threshold_db.snap();
+        self.upper_threshold_db.snap();
+        self.lower_ratio.snap();
+        self.upper_ratio.snap();
+        self.gain_db.snap();
+    }
+
+    #[inline(always)]
+    fn next_params(&mut self) {
+        self.lower_threshold_db.next();
+        self.upper_threshold_db.next();
+        self.lower_ratio.next();
+        self.upper_ratio.next();
+        self.gain_db.next();
+    }
+
+    fn level_db(&self) -> f32 {
+        if self.mean_square > 1e-16 {
+            10.0 * self.mean_square.log10()
+        } else {
+            SILENCE_DB
+        }
+    }
+
+    /// The change in level, including the band's gain, for a level of `level_db`.
+    fn gain_db(&self, level_db: f32) -> f32 {
+        let upper_threshold_db = self.upper_threshold_db.value();
+        // The lower threshold can't go past the upper one.
+        let lower_threshold_db = self.lower_threshold_db.value().min(upper_threshold_db);
+
+        let dynamics_db = if level_db > upper_threshold_db {
+            (upper_threshold_db - level_db) * self.upper_ratio.value()
+        } else if level_db < lower_threshold_db {
+            ((lower_threshold_db - level_db) * self.lower_ratio.value()).min(MAX_UPWARD_GAIN_DB)
+        } else {
+            0.0
+        };
+
+        dynamics_db + self.gain_db.value()
+    }
+
+    /// Follows the level of one stereo frame and returns the gain for it.
+    #[inline(always)]
+    fn gain(&mut self, [l, r]: [f32; 2], [attack_coeff, release_coeff]: [f32; 2]) -> f32 {
+        let square = 0.5 * (l * l + r * r);
+        let coeff = if square > self.mean_square {
+            attack_coeff
+        } else {
+            release_coeff
+        };
+        self.mean_square = square + coeff * (self.mean_square - square);
+
+        db_to_gain(self.gain_db(self.level_db()))
+    }
+}
@@ -0,0 +1,212 @@
+use meadow_common_dsp::{db_to_gain, gain_to_db};
+use meadow_eq_dsp::{DEFAULT_Q, SvfCoeff, SvfState};
+use meadow_test_signals::{noise, rms, sine};
+
+use crate::{
+    BandMode, DynamicsBandParams, DynamicsDsp, DynamicsParams, MAX_UPWARD_GAIN_DB, SILENCE_DB,
+};
+
+const SAMPLE_RATES: [f64; 3] = [44_100.0, 48_000.0, 192_000.0];
+const LEN: usize = 16_384;
+
+/// How far below the input the difference to the reference may be.
+const NULL_DB: f32 = -90.0;
+
+/// Runs `input` through the allpasses the two crossovers sum to.
+fn reference_allpass(input: &[f32], crossovers_hz: &[f32], sample_rate: f64) -> Vec<f32> {
+    let mut output = input.to_vec();
+    for &crossover_hz in crossovers_hz {
+        let coeff = SvfCoeff::allpass(crossover_hz as f64, sample_rate.recip(), DEFAULT_Q as f64);
+        let mut state = SvfState::default();
+        for x in output.iter_mut() {
+            *x = state.tick(*x, &coeff);
+        }
+    }
+
+    output
+}
+
+/// Single-band settings with every level `level_db` applied to a constant signal for a
+/// second. Returns the gain of the last sample in decibels.
+fn settled_gain_db(band: DynamicsBandParams, level_db: f32) -> f32 {
+    let sample_rate = 48_000.0;
+    let mut dsp = DynamicsDsp::new(sample_rate);
+    dsp.set_params(DynamicsParams {
+        mode: BandMode::Single,
+        bands: [band; 3],
+        ..Default::default()
+    });
+    dsp.reset();
+
+    let len = sample_rate as usize;
+    let mut buf_l = vec![db_to_gain(level_db); len];
+    let mut buf_r = buf_l.clone();
+    dsp.process(&mut buf_l, &mut buf_r);
+
+    gain_to_db(buf_l[len - 1]) - level_db
+}
+
+#[test]
+fn bands_recombine_flat_without_dynamics() {
+    for sample_rate in SAMPLE_RATES {
+        let params = DynamicsParams::default();
+        let mut dsp = DynamicsDsp::new(sample_rate);
+        dsp.set_params(params);
+
+        let input_l = noise(LEN, 1);
+        let input_r = noise(LEN, 2);
+        let (mut buf_l, mut buf_r) = (input_l.clone(), input_r.clone());
+        dsp.process(&mut buf_l, &mut buf_r);
+
+        for (output, input) in [(&buf_l, &input_l), (&buf_r, &input_r)] {
+            let reference = reference_allpass(input, &params.crossovers_hz, sample_rate);
+            let difference: Vec<f32> = output.iter().zip(&reference).map(|(a, b)| a - b).collect();
+            let null_db = gain_to_db(rms(&difference) / rms(input));
+            assert!(
+                null_db < NULL_DB,
+                "{sample_rate} Hz: the bands only null to {null_db} dB"
+            );
+        }
+
+        // Without the crossovers, nothing changes at all.
+        dsp.set_params(DynamicsParams {
+            mode: BandMode::Single,
+            ..params
+        });
+        let (mut buf_l, mut buf_r) = (input_l.clone(), input_r.clone());
+        dsp.process(&mut buf_l, &mut buf_r);
+        assert_eq!(buf_l, input_l);
+        assert_eq!(buf_r, input_r);
+    }
+}
+
+#[test]
+fn upper_ratio_compresses_down() {
+    let band = DynamicsBandParams {
+        upper_threshold_db: -20.0,
+        upper_ratio: 0.5,
+        ..Default::default()
+    };
+
+    assert!((settled_gain_db(band, -10.0) + 5.0).abs() < 0.01);
+    assert!(settled_gain_db(band, -30.0).abs() < 0.01);
+}
+
+#[test]
+fn lower_ratio_compresses_up_and_expands_down() {
+    let upward = DynamicsBandParams {
+        lower_threshold_db: -30.0,
+        lower_ratio: 0.5,
+        ..Default::default()
+    };
+    let downward = DynamicsBandParams {
+        lower_ratio: -0.5,
+        ..upward
+    };
+
+    assert!((settled_gain_db(upward, -50.0) - 10.0).abs() < 0.01);
+    assert!((settled_gain_db(downward, -50.0) + 10.0).abs() < 0.01);
+    assert!(settled_gain_db(upward, -25.0).abs() < 0.01);
+
+    // Quiet signals only get boosted so far.
+    let full = DynamicsBandParams {
+        lower_ratio: 1.0,
+        ..upward
+    };
+    assert!((settled_gain_db(full, -90.0) - MAX_UPWARD_GAIN_DB).abs() < 0.01);
+}
+
+#[test]
+fn band_gain_applies_everywhere() {
+    let band = DynamicsBandParams {
+        gain_db: 6.0,
+        ..Default::default()
+    };
+
+    for level_db in [-60.0, -30.0, -6.0] {
+        assert!((settled_gain_db(band, level_db) - 6.0).abs() < 0.01);
+    }
+}
+
+#[test]
+fn bands_are_independent() {
+    let sample_rate = 48_000.0;
+    let len = sample_rate as usize;
+    let mut params = DynamicsParams::default();
+    params.bands[0] = DynamicsBandParams {
+        upper_threshold_db: -30.0,
+        upper_ratio: 1.0,
+        ..Default::default()
+    };
+    params.bands[2] = params.bands[0];
+
+    let mut dsp = DynamicsDsp::new(sample_rate);
+    dsp.set_params(params);
+    dsp.reset();
+
+    // Loud bass and a quiet top end.
+    let bass = sine(40.0, sample_rate, len);
+    let top = sine(10_000.0, sample_rate, len);
+    let input: Vec<f32> = bass.iter().zip(&top).map(|(b, t)| b + 0.01 * t).collect();
+    let (mut buf_l, mut buf_r) = (input.clone(), input.clone());
+    dsp.process(&mut buf_l, &mut buf_r);
+
+    let [low, mid, high] = dsp.meters();
+    // The detector attacks faster than it releases, so it reads above the RMS levels.
+    assert!(low.input_db > -4.0 && low.input_db < 0.0, "{low:?}");
+    assert!((low.output_db + 30.0).abs() < 0.01, "{low:?}");
+    assert!(mid.input_db < -30.0, "{mid:?}");
+    assert_eq!(mid.output_db, mid.input_db);
+    assert!(high.input_db > -44.0 && high.input_db < -40.0, "{high:?}");
+    assert_eq!(high.output_db, high.input_db);
+}
+
+#[test]
+fn single_band_meters_only_the_mid_band() {
+    let sample_rate = 48_000.0;
+    let mut dsp = DynamicsDsp::new(sample_rate);
+    dsp.set_params(DynamicsParams {
+        mode: BandMode::Single,
+        ..Default::default()
+    });
+
+    let mut buf_l = vec![db_to_gain(-12.0); sample_rate as usize];
+    let mut buf_r = buf_l.clone();
+    dsp.process(&mut buf_l, &mut buf_r);
+
+    let [low, mid, high] = dsp.meters();
+    assert_eq!(low.input_db, SILENCE_DB);
+    assert_eq!(high.input_db, SILENCE_DB);
+    assert!((mid.input_db + 12.0).abs() < 0.01, "{mid:?}");
+}
+
+#[test]
+fn parameter_changes_are_smooth() {
+    let sample_rate = 48_000.0;
+    let len = sample_rate as usize;
+    let mut dsp = DynamicsDsp::new(sample_rate);
+
+    let mut buf_l = vec![0.5f32; len];
+    let mut buf_r = buf_l.clone();
+    dsp.set_params(DynamicsParams {
+        mode: BandMode::Single,
+        ..Default::default()
+    });
+    dsp.process(&mut buf_l[..len / 2], &mut buf_r[..len / 2]);
+
+    // Jumping from no compression to full compression of the settled signal.
+    let mut params = DynamicsParams {
+        mode: BandMode::Single,
+        ..Default::default()
+    };
+    params.bands[1].upper_ratio = 1.0;
+    dsp.set_params(params);
+    dsp.process(&mut buf_l[len / 2..], &mut buf_r[len / 2..]);
+
+    let max_step = buf_l
+        .windows(2)
+        .map(|pair| (pair[1] - pair[0]).abs())
+        .fold(0.0f32, f32::max);
+    assert!(max_step < 1e-3, "steps of {max_step}");
+    assert!((gain_to_db(buf_l[len - 1]) + 20.0).abs() < 0.01);
+}
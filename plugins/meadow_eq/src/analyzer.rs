@@ -0,0 +1,143 @@
+use realfft::num_complex::Complex32;
+use realfft::{RealFftPlanner, RealToComplex};
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+pub const SPECTRUM_FFT_SIZE: usize = 4096;
+pub const SPECTRUM_NUM_BINS: usize = SPECTRUM_FFT_SIZE / 2 + 1;
+
+const HOP_SIZE: usize = SPECTRUM_FFT_SIZE / 4;
+const RELEASE_SECONDS: f32 = 0.25;
+
+/// The floor of the analyzer in decibels. Bins below this are clamped to it.
+pub const SPECTRUM_MIN_DB: f32 = -100.0;
+
+/// The magnitude of each FFT bin in decibels, where 0 dB is a full-scale sine wave.
+pub type Spectrum = [f32; SPECTRUM_NUM_BINS];
+
+pub type SpectrumOutput = triple_buffer::Output<Spectrum>;
+
+/// Collects samples on the audio thread and periodically publishes a smoothed magnitude
+/// spectrum for the editor.
+///
+/// All buffers are allocated up front so [`SpectrumInput::process`] is realtime-safe.
+pub struct SpectrumInput {
+    output: triple_buffer::Input<Spectrum>,
+
+    fft: Arc<dyn RealToComplex<f32>>,
+    window: Vec<f32>,
+    window_gain_recip: f32,
+
+    ring: Vec<f32>,
+    ring_pos: usize,
+    samples_until_fft: usize,
+
+    fft_input: Vec<f32>,
+    fft_output: Vec<Complex32>,
+    fft_scratch: Vec<Complex32>,
+
+    magnitudes: Vec<f32>,
+    decay: f32,
+}
+
+impl SpectrumInput {
+    pub fn new(sample_rate: f32) -> (Self, SpectrumOutput) {
+        let (output, spectrum_output) =
+            triple_buffer::TripleBuffer::new(&[SPECTRUM_MIN_DB; SPECTRUM_NUM_BINS]).split();
+
+        let fft = RealFftPlanner::<f32>::new().plan_fft_forward(SPECTRUM_FFT_SIZE);
+
+        // Hann window
+        let window: Vec<f32> = (0..SPECTRUM_FFT_SIZE)
+            .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / SPECTRUM_FFT_SIZE as f32).cos())
+            .collect();
+        // A full-scale sine should read as 0 dB.
+        let window_gain_recip = 2.0 / window.iter().sum::<f32>();
+
+        let mut new_self = Self {
+            output,
+            fft_input: fft.make_input_vec(),
+            fft_output: fft.make_output_vec(),
+            fft_scratch: fft.make_scratch_vec(),
+            fft,
+            window,
+            window_gain_recip,
+            ring: vec![0.0; SPECTRUM_FFT_SIZE],
+            ring_pos: 0,
+            samples_until_fft: HOP_SIZE,
+            magnitudes: vec![0.0; SPECTRUM_NUM_BINS],
+            decay: 0.0,
+        };
+        new_self.set_sample_rate(sample_rate);
+
+        (new_self, spectrum_output)
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.decay = (-(HOP_SIZE as f32) / (RELEASE_SECONDS * sample_rate)).exp();
+    }
+
+    pub fn reset(&mut self) {
+        self.ring.fill(0.0);
+        self.ring_pos = 0;
+        self.samples_until_fft = HOP_SIZE;
+        self.magnitudes.fill(0.0);
+    }
+
+    pub fn process(&mut self, buf_l: &[f32], buf_r: &[f32]) {
+        for (&l, &r) in buf_l.iter().zip(buf_r.iter()) {
+            self.ring[self.ring_pos] = (l + r) * 0.5;
+            self.ring_pos = (self.ring_pos + 1) % SPECTRUM_FFT_SIZE;
+
+            self.samples_until_fft -= 1;
+            if self.samples_until_fft == 0 {
+                self.samples_until_fft = HOP_SIZE;
+                self.compute_spectrum();
+            }
+        }
+    }
+
+    fn compute_spectrum(&mut self) {
+        // Unroll the ring buffer so the oldest sample comes first.
+        let (newest, oldest) = self.ring.split_at(self.ring_pos);
+        for ((out, &s), &w) in self
+            .fft_input
+            .iter_mut()
+            .zip(oldest.iter().chain(newest.iter()))
+            .zip(self.window.iter())
+        {
+            *out = s * w;
+        }
+
+        if self
+            .fft
+            .process_with_scratch(
+                &mut self.fft_input,
+                &mut self.fft_output,
+                &mut self.fft_scratch,
+            )
+            .is_err()
+        {
+            return;
+        }
+
+        let spectrum = self.output.input_buffer();
+        for ((bin, mag), db) in self
+            .fft_output
+            .iter()
+            .zip(self.magnitudes.iter_mut())
+            .zip(spectrum.iter_mut())
+        {
+            let new_mag = bin.norm() * self.window_gain_recip;
+            *mag = new_mag.max(*mag * self.decay);
+
+            *db = if *mag > 1e-9 {
+                (20.0 * mag.log10()).max(SPECTRUM_MIN_DB)
+            } else {
+                SPECTRUM_MIN_DB
+            };
+        }
+
+        self.output.publish();
+    }
+}
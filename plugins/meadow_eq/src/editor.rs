@@ -0,0 +1,666 @@
+//! An EQ5-style editor: a response graph with draggable band handles on top, and the controls
+//! for every band laid out side by side below it.
+
+use atomic_float::AtomicF32;
+use meadow_eq_dsp::preset::{EqPreset, factory_presets};
+use meadow_eq_dsp::{BandType, MeadowEqDsp};
+use meadow_eq_params::{BandParamsGroup, NUM_BANDS};
+use nih_plug::prelude::*;
+use nih_plug_egui::egui::{self, Align2, Color32, FontId, Pos2, Rect, Sense, Shape, Stroke, Vec2};
+use nih_plug_egui::{EguiState, create_egui_editor, widgets};
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+
+use crate::MeadowEqParams;
+use crate::analyzer::{SPECTRUM_FFT_SIZE, SPECTRUM_MIN_DB, SPECTRUM_NUM_BINS, SpectrumOutput};
+use crate::presets;
+use crate::snapshots::{self, Snapshot};
+
+const EDITOR_WIDTH: u32 = 960;
+const EDITOR_HEIGHT: u32 = 590;
+
+const GRAPH_HEIGHT: f32 = 280.0;
+const COLUMN_WIDTH: f32 = 84.0;
+
+const MIN_FREQ_HZ: f32 = 20.0;
+const MAX_FREQ_HZ: f32 = 22_000.0;
+/// The graph shows gains from `-GRAPH_DB_RANGE` to `+GRAPH_DB_RANGE`.
+const GRAPH_DB_RANGE: f32 = 30.0;
+
+const HANDLE_RADIUS: f32 = 7.0;
+const HANDLE_GRAB_RADIUS: f32 = 14.0;
+/// How much one pixel of scrolling moves the normalized Q value.
+const SCROLL_Q_SENSITIVITY: f32 = 0.002;
+
+const BAND_COLORS: [Color32; NUM_BANDS] = [
+    Color32::from_rgb(0xe0, 0x5a, 0x47),
+    Color32::from_rgb(0xe8, 0x9a, 0x3c),
+    Color32::from_rgb(0xd9, 0xcf, 0x4a),
+    Color32::from_rgb(0x7b, 0xc8, 0x5a),
+    Color32::from_rgb(0x4a, 0xc0, 0xb0),
+    Color32::from_rgb(0x4a, 0x8f, 0xe0),
+    Color32::from_rgb(0x8a, 0x6a, 0xe0),
+    Color32::from_rgb(0xd0, 0x5a, 0xc0),
+];
+const CUT_COLOR: Color32 = Color32::from_rgb(0xb0, 0xb0, 0xb0);
+const CURVE_COLOR: Color32 = Color32::from_rgb(0xf0, 0xf0, 0xf0);
+const GRID_COLOR: Color32 = Color32::from_rgb(0x3a, 0x3a, 0x3a);
+
+pub(crate) fn default_state() -> Arc<EguiState> {
+    EguiState::from_size(EDITOR_WIDTH, EDITOR_HEIGHT)
+}
+
+/// A point on the graph that can be dragged around with the mouse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Handle {
+    Hp,
+    Lp,
+    Band(usize),
+}
+
+struct EditorState {
+    spectrum_output: Arc<Mutex<SpectrumOutput>>,
+    sample_rate: Arc<AtomicF32>,
+
+    /// A private copy of the DSP that is only used to query the response curve, so the curve
+    /// drawn is exactly what the audio thread is playing.
+    response_dsp: MeadowEqDsp<NUM_BANDS>,
+
+    dragged_handle: Option<Handle>,
+
+    factory_presets: Vec<EqPreset>,
+    /// Rescanned every time the editor is opened.
+    user_presets: Vec<EqPreset>,
+    /// The name the next user preset will be saved as.
+    preset_name: String,
+    /// The result of the last preset operation, shown next to the preset controls.
+    preset_status: String,
+}
+
+pub(crate) fn create(
+    params: Arc<MeadowEqParams>,
+    spectrum_output: Arc<Mutex<SpectrumOutput>>,
+    sample_rate: Arc<AtomicF32>,
+) -> Option<Box<dyn Editor>> {
+    let editor_state = EditorState {
+        response_dsp: MeadowEqDsp::new(sample_rate.load(Ordering::Relaxed) as f64),
+        spectrum_output,
+        sample_rate,
+        dragged_handle: None,
+        factory_presets: factory_presets(),
+        user_presets: Vec::new(),
+        preset_name: String::new(),
+        preset_status: String::new(),
+    };
+
+    create_egui_editor(
+        params.editor_state.clone(),
+        editor_state,
+        |_, state| {
+            state.user_presets = presets::load_user_presets();
+        },
+        move |egui_ctx, setter, state| {
+            let sample_rate = state.sample_rate.load(Ordering::Relaxed) as f64;
+            if state.response_dsp.sample_rate() != sample_rate {
+                state.response_dsp = MeadowEqDsp::new(sample_rate);
+            }
+            state.response_dsp.set_params(params.eq.eq_params());
+
+            egui::CentralPanel::default().show(egui_ctx, |ui| {
+                draw_preset_bar(ui, &params, setter, state);
+
+                ui.add_space(4.0);
+
+                draw_graph(ui, &params, setter, state);
+
+                ui.add_space(8.0);
+
+                draw_band_columns(ui, &params, setter);
+            });
+
+            // The spectrum and the curve should follow the audio even without user input.
+            egui_ctx.request_repaint();
+        },
+    )
+}
+
+fn draw_preset_bar(
+    ui: &mut egui::Ui,
+    params: &MeadowEqParams,
+    setter: &ParamSetter,
+    state: &mut EditorState,
+) {
+    ui.horizontal(|ui| {
+        let mut selected: Option<EqPreset> = None;
+
+        egui::ComboBox::from_label("Preset")
+            .selected_text("Load...")
+            .show_ui(ui, |ui| {
+                ui.label("Factory");
+                for preset in &state.factory_presets {
+                    if ui.selectable_label(false, preset.name.as_str()).clicked() {
+                        selected = Some(preset.clone());
+                    }
+                }
+
+                if !state.user_presets.is_empty() {
+                    ui.separator();
+                    ui.label("User");
+                    for preset in &state.user_presets {
+                        if ui.selectable_label(false, preset.name.as_str()).clicked() {
+                            selected = Some(preset.clone());
+                        }
+                    }
+                }
+            });
+
+        if let Some(preset) = selected {
+            state.preset_status = match presets::apply_preset(&preset, params, setter) {
+                Ok(()) => {
+                    state.preset_name = preset.name.clone();
+                    format!("Loaded \"{}\"", preset.name)
+                }
+                Err(e) => format!("Could not load \"{}\": {e}", preset.name),
+            };
+        }
+
+        ui.separator();
+
+        ui.add(egui::TextEdit::singleline(&mut state.preset_name).desired_width(160.0));
+        let can_save = !state.preset_name.trim().is_empty();
+        if ui
+            .add_enabled(can_save, egui::Button::new("Save"))
+            .clicked()
+        {
+            state.preset_status = match presets::save_user_preset(&state.preset_name, params) {
+                Ok(path) => {
+                    state.user_presets = presets::load_user_presets();
+                    format!("Saved to {}", path.display())
+                }
+                Err(e) => format!("Could not save preset: {e}"),
+            };
+        }
+
+        ui.label(state.preset_status.as_str());
+
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            draw_snapshot_controls(ui, params, setter);
+        });
+    });
+}
+
+/// Buttons for switching between the snapshots, and for copying the active one to the
+/// others. This is laid out right to left.
+fn draw_snapshot_controls(ui: &mut egui::Ui, params: &MeadowEqParams, setter: &ParamSetter) {
+    let active = params.snapshot.value();
+
+    for snapshot in Snapshot::ALL.into_iter().rev() {
+        if snapshot != active
+            && ui
+                .small_button(format!("{active:?}→{snapshot:?}"))
+                .on_hover_text(format!("Copy {active:?} to {snapshot:?}"))
+                .clicked()
+        {
+            snapshots::copy(active, snapshot, params, setter);
+        }
+    }
+
+    ui.separator();
+
+    for snapshot in Snapshot::ALL.into_iter().rev() {
+        if ui
+            .selectable_label(snapshot == active, format!("{snapshot:?}"))
+            .clicked()
+        {
+            snapshots::switch(snapshot, params, setter);
+        }
+    }
+}
+
+fn draw_graph(
+    ui: &mut egui::Ui,
+    params: &MeadowEqParams,
+    setter: &ParamSetter,
+    state: &mut EditorState,
+) {
+    let (response, painter) = ui.allocate_painter(
+        Vec2::new(ui.available_width(), GRAPH_HEIGHT),
+        Sense::click_and_drag(),
+    );
+    let rect = response.rect;
+
+    painter.rect_filled(rect, 0.0, Color32::from_rgb(0x16, 0x16, 0x18));
+
+    if params.analyzer_mode.value() != crate::AnalyzerMode::Off {
+        let mut spectrum_output = state.spectrum_output.lock().unwrap();
+        draw_spectrum(
+            &painter,
+            rect,
+            spectrum_output.read(),
+            state.response_dsp.sample_rate(),
+        );
+    }
+
+    draw_grid(&painter, rect);
+
+    // Individual band curves, drawn faintly beneath the combined curve.
+    let bands = params.eq.bands();
+    for (band_i, band) in bands.iter().enumerate() {
+        if !band.enabled.value() {
+            continue;
+        }
+
+        let points = curve_points(rect, |freq| {
+            let h = state.response_dsp.band_response(band_i, freq as f64);
+            20.0 * (h.norm() as f32).max(1e-6).log10()
+        });
+        painter.add(Shape::line(
+            points,
+            Stroke::new(1.0, BAND_COLORS[band_i].gamma_multiply(0.5)),
+        ));
+    }
+
+    // The natural phase mode follows the analog prototypes instead of the digital filters.
+    let natural_phase = params.natural_phase.value();
+    let points = curve_points(rect, |freq| {
+        if natural_phase {
+            let h = state.response_dsp.analog_response(freq as f64);
+            20.0 * (h.norm() as f32).max(1e-6).log10()
+        } else {
+            state.response_dsp.magnitude_db(freq as f64) as f32
+        }
+    });
+    painter.add(Shape::line(points, Stroke::new(2.0, CURVE_COLOR)));
+
+    // Handles
+    for handle in active_handles(params) {
+        let pos = handle_pos(rect, params, handle);
+        let (color, label) = match handle {
+            Handle::Hp => (CUT_COLOR, String::from("HP")),
+            Handle::Lp => (CUT_COLOR, String::from("LP")),
+            Handle::Band(i) => (BAND_COLORS[i], format!("{}", i + 1)),
+        };
+
+        let stroke = if state.dragged_handle == Some(handle) {
+            Stroke::new(2.0, Color32::WHITE)
+        } else {
+            Stroke::new(1.0, Color32::BLACK)
+        };
+        painter.circle(pos, HANDLE_RADIUS, color, stroke);
+        painter.text(
+            pos + Vec2::new(0.0, -HANDLE_RADIUS - 2.0),
+            Align2::CENTER_BOTTOM,
+            label,
+            FontId::proportional(11.0),
+            color,
+        );
+    }
+
+    handle_graph_input(ui, &response, rect, params, setter, state);
+}
+
+fn handle_graph_input(
+    ui: &egui::Ui,
+    response: &egui::Response,
+    rect: Rect,
+    params: &MeadowEqParams,
+    setter: &ParamSetter,
+    state: &mut EditorState,
+) {
+    if response.drag_started()
+        && let Some(pointer) = response.interact_pointer_pos()
+    {
+        state.dragged_handle = nearest_handle(rect, params, pointer);
+        if let Some(handle) = state.dragged_handle {
+            begin_handle_gesture(params, setter, handle);
+        }
+    }
+
+    if response.dragged()
+        && let (Some(handle), Some(pointer)) =
+            (state.dragged_handle, response.interact_pointer_pos())
+    {
+        let freq = x_to_freq(rect, pointer.x);
+        let gain_db = y_to_db(rect, pointer.y);
+
+        match handle {
+            Handle::Hp => setter.set_parameter(&params.eq.hp_cutoff_hz, freq),
+            Handle::Lp => setter.set_parameter(&params.eq.lp_cutoff_hz, freq),
+            Handle::Band(i) => {
+                let band = params.eq.bands()[i];
+                setter.set_parameter(&band.cutoff_hz, freq);
+                if band_has_gain(band) {
+                    setter.set_parameter(&band.gain_db, gain_db);
+                }
+            }
+        }
+    }
+
+    if response.drag_stopped()
+        && let Some(handle) = state.dragged_handle.take()
+    {
+        end_handle_gesture(params, setter, handle);
+    }
+
+    // Scrolling over a handle changes its Q.
+    let scroll = ui.input(|i| i.smooth_scroll_delta.y);
+    if scroll != 0.0
+        && let Some(pointer) = response.hover_pos()
+        && let Some(handle) = nearest_handle(rect, params, pointer)
+    {
+        let q = match handle {
+            Handle::Hp => &params.eq.hp_q,
+            Handle::Lp => &params.eq.lp_q,
+            Handle::Band(i) => &params.eq.bands()[i].q,
+        };
+
+        let normalized =
+            (q.unmodulated_normalized_value() + scroll * SCROLL_Q_SENSITIVITY).clamp(0.0, 1.0);
+        setter.begin_set_parameter(q);
+        setter.set_parameter_normalized(q, normalized);
+        setter.end_set_parameter(q);
+    }
+
+    // Double clicking a band handle resets its gain.
+    if response.double_clicked()
+        && let Some(pointer) = response.interact_pointer_pos()
+        && let Some(Handle::Band(i)) = nearest_handle(rect, params, pointer)
+    {
+        let band = params.eq.bands()[i];
+        setter.begin_set_parameter(&band.gain_db);
+        setter.set_parameter(&band.gain_db, band.gain_db.default_plain_value());
+        setter.end_set_parameter(&band.gain_db);
+    }
+}
+
+fn begin_handle_gesture(params: &MeadowEqParams, setter: &ParamSetter, handle: Handle) {
+    match handle {
+        Handle::Hp => setter.begin_set_parameter(&params.eq.hp_cutoff_hz),
+        Handle::Lp => setter.begin_set_parameter(&params.eq.lp_cutoff_hz),
+        Handle::Band(i) => {
+            let band = params.eq.bands()[i];
+            setter.begin_set_parameter(&band.cutoff_hz);
+            setter.begin_set_parameter(&band.gain_db);
+        }
+    }
+}
+
+fn end_handle_gesture(params: &MeadowEqParams, setter: &ParamSetter, handle: Handle) {
+    match handle {
+        Handle::Hp => setter.end_set_parameter(&params.eq.hp_cutoff_hz),
+        Handle::Lp => setter.end_set_parameter(&params.eq.lp_cutoff_hz),
+        Handle::Band(i) => {
+            let band = params.eq.bands()[i];
+            setter.end_set_parameter(&band.cutoff_hz);
+            setter.end_set_parameter(&band.gain_db);
+        }
+    }
+}
+
+fn draw_band_columns(ui: &mut egui::Ui, params: &MeadowEqParams, setter: &ParamSetter) {
+    egui::ScrollArea::horizontal().show(ui, |ui| {
+        ui.horizontal_top(|ui| {
+            ui.vertical(|ui| {
+                ui.set_width(COLUMN_WIDTH);
+                ui.colored_label(CUT_COLOR, "HP");
+                toggle(ui, setter, &params.eq.hp_enabled, "On");
+                param_slider(ui, setter, &params.eq.hp_cutoff_hz);
+                param_slider(ui, setter, &params.eq.hp_q);
+                param_slider(ui, setter, &params.eq.hp_order);
+            });
+
+            ui.separator();
+
+            let bands = params.eq.bands();
+            for (band_i, band) in bands.iter().enumerate() {
+                if !band.enabled.value() {
+                    continue;
+                }
+
+                ui.vertical(|ui| {
+                    ui.set_width(COLUMN_WIDTH);
+                    ui.horizontal(|ui| {
+                        ui.colored_label(BAND_COLORS[band_i], format!("Band {}", band_i + 1));
+                        if ui.small_button("x").clicked() {
+                            set_bool(setter, &band.enabled, false);
+                        }
+                    });
+                    param_slider(ui, setter, &band.band_type);
+                    param_slider(ui, setter, &band.cutoff_hz);
+                    ui.add_enabled_ui(band_has_gain(band), |ui| {
+                        param_slider(ui, setter, &band.gain_db);
+                    });
+                    param_slider(ui, setter, &band.q);
+                });
+
+                ui.separator();
+            }
+
+            if let Some(next_band) = bands.iter().find(|b| !b.enabled.value()) {
+                if ui.button("+ Band").clicked() {
+                    set_bool(setter, &next_band.enabled, true);
+                }
+                ui.separator();
+            }
+
+            ui.vertical(|ui| {
+                ui.set_width(COLUMN_WIDTH);
+                ui.colored_label(CUT_COLOR, "LP");
+                toggle(ui, setter, &params.eq.lp_enabled, "On");
+                param_slider(ui, setter, &params.eq.lp_cutoff_hz);
+                param_slider(ui, setter, &params.eq.lp_q);
+                param_slider(ui, setter, &params.eq.lp_order);
+            });
+
+            ui.separator();
+
+            ui.vertical(|ui| {
+                ui.set_width(COLUMN_WIDTH);
+                ui.label("Phase");
+                toggle(ui, setter, &params.natural_phase, "Natural");
+            });
+
+            ui.separator();
+
+            ui.vertical(|ui| {
+                ui.set_width(COLUMN_WIDTH);
+                ui.label("Analyzer");
+                param_slider(ui, setter, &params.analyzer_mode);
+            });
+        });
+    });
+}
+
+fn param_slider<P: Param>(ui: &mut egui::Ui, setter: &ParamSetter, param: &P) {
+    ui.label(param.name());
+    ui.add(widgets::ParamSlider::for_param(param, setter).with_width(COLUMN_WIDTH));
+}
+
+fn toggle(ui: &mut egui::Ui, setter: &ParamSetter, param: &BoolParam, label: &str) {
+    let mut value = param.value();
+    if ui.checkbox(&mut value, label).changed() {
+        set_bool(setter, param, value);
+    }
+}
+
+fn set_bool(setter: &ParamSetter, param: &BoolParam, value: bool) {
+    setter.begin_set_parameter(param);
+    setter.set_parameter(param, value);
+    setter.end_set_parameter(param);
+}
+
+fn band_has_gain(band: &BandParamsGroup) -> bool {
+    matches!(
+        BandType::from_u32(band.band_type.value() as u32),
+        BandType::Bell | BandType::LowShelf | BandType::HighShelf
+    )
+}
+
+fn active_handles(params: &MeadowEqParams) -> impl Iterator<Item = Handle> + '_ {
+    let hp = params.eq.hp_enabled.value().then_some(Handle::Hp);
+    let lp = params.eq.lp_enabled.value().then_some(Handle::Lp);
+    let bands = params
+        .eq
+        .bands()
+        .into_iter()
+        .enumerate()
+        .filter(|(_, b)| b.enabled.value())
+        .map(|(i, _)| Handle::Band(i));
+
+    hp.into_iter().chain(bands).chain(lp)
+}
+
+fn handle_pos(rect: Rect, params: &MeadowEqParams, handle: Handle) -> Pos2 {
+    match handle {
+        Handle::Hp => Pos2::new(
+            freq_to_x(rect, params.eq.hp_cutoff_hz.value()),
+            db_to_y(rect, 0.0),
+        ),
+        Handle::Lp => Pos2::new(
+            freq_to_x(rect, params.eq.lp_cutoff_hz.value()),
+            db_to_y(rect, 0.0),
+        ),
+        Handle::Band(i) => {
+            let band = params.eq.bands()[i];
+            let gain_db = if band_has_gain(band) {
+                band.gain_db.value()
+            } else {
+                0.0
+            };
+
+            Pos2::new(
+                freq_to_x(rect, band.cutoff_hz.value()),
+                db_to_y(rect, gain_db),
+            )
+        }
+    }
+}
+
+fn nearest_handle(rect: Rect, params: &MeadowEqParams, pointer: Pos2) -> Option<Handle> {
+    active_handles(params)
+        .map(|handle| (handle, handle_pos(rect, params, handle).distance(pointer)))
+        .filter(|(_, distance)| *distance <= HANDLE_GRAB_RADIUS)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(handle, _)| handle)
+}
+
+fn curve_points(rect: Rect, mut magnitude_db: impl FnMut(f32) -> f32) -> Vec<Pos2> {
+    let num_points = rect.width().max(2.0) as usize;
+
+    (0..num_points)
+        .map(|i| {
+            let x = rect.left() + i as f32;
+            let db = magnitude_db(x_to_freq(rect, x));
+            Pos2::new(x, db_to_y(rect, db).clamp(rect.top(), rect.bottom()))
+        })
+        .collect()
+}
+
+/// Draws the analyzer as a "wash of color" rather than a line graph, where brighter and warmer
+/// colors mean more energy at that frequency.
+fn draw_spectrum(painter: &egui::Painter, rect: Rect, spectrum: &[f32], sample_rate: f64) {
+    const COLUMN_PX: f32 = 2.0;
+
+    let bins_per_hz = SPECTRUM_FFT_SIZE as f32 / sample_rate as f32;
+
+    let mut x = rect.left();
+    while x < rect.right() {
+        let bin = x_to_freq(rect, x + COLUMN_PX * 0.5) * bins_per_hz;
+        let bin_i = (bin as usize).min(SPECTRUM_NUM_BINS - 2);
+        let t = (bin - bin_i as f32).clamp(0.0, 1.0);
+        let db = spectrum[bin_i] + (spectrum[bin_i + 1] - spectrum[bin_i]) * t;
+
+        let intensity = ((db - SPECTRUM_MIN_DB) / -SPECTRUM_MIN_DB).clamp(0.0, 1.0);
+        if intensity > 0.0 {
+            painter.rect_filled(
+                Rect::from_min_max(
+                    Pos2::new(x, rect.top()),
+                    Pos2::new(x + COLUMN_PX, rect.bottom()),
+                ),
+                0.0,
+                spectrum_color(intensity),
+            );
+        }
+
+        x += COLUMN_PX;
+    }
+}
+
+fn spectrum_color(intensity: f32) -> Color32 {
+    // Dark blue -> teal -> yellow -> red
+    const STOPS: [(f32, [f32; 3]); 4] = [
+        (0.0, [0.05, 0.05, 0.20]),
+        (0.4, [0.05, 0.45, 0.50]),
+        (0.75, [0.85, 0.80, 0.20]),
+        (1.0, [0.95, 0.25, 0.15]),
+    ];
+
+    let i = STOPS
+        .windows(2)
+        .position(|w| intensity <= w[1].0)
+        .unwrap_or(STOPS.len() - 2);
+    let (t0, c0) = STOPS[i];
+    let (t1, c1) = STOPS[i + 1];
+    let t = ((intensity - t0) / (t1 - t0)).clamp(0.0, 1.0);
+
+    let channel = |j: usize| ((c0[j] + (c1[j] - c0[j]) * t) * 255.0) as u8;
+    let alpha = (intensity * 0.8 * 255.0) as u8;
+
+    Color32::from_rgba_unmultiplied(channel(0), channel(1), channel(2), alpha)
+}
+
+fn draw_grid(painter: &egui::Painter, rect: Rect) {
+    let stroke = Stroke::new(1.0, GRID_COLOR);
+    let font = FontId::proportional(10.0);
+
+    for freq in [
+        50.0, 100.0, 200.0, 500.0, 1000.0, 2000.0, 5000.0, 10_000.0, 20_000.0,
+    ] {
+        let x = freq_to_x(rect, freq);
+        painter.vline(x, rect.y_range(), stroke);
+
+        let label = if freq >= 1000.0 {
+            format!("{}k", freq / 1000.0)
+        } else {
+            format!("{freq}")
+        };
+        painter.text(
+            Pos2::new(x + 2.0, rect.bottom() - 2.0),
+            Align2::LEFT_BOTTOM,
+            label,
+            font.clone(),
+            GRID_COLOR.gamma_multiply(2.0),
+        );
+    }
+
+    for db in [-24.0, -12.0, 0.0, 12.0, 24.0] {
+        let y = db_to_y(rect, db);
+        painter.hline(rect.x_range(), y, stroke);
+        painter.text(
+            Pos2::new(rect.left() + 2.0, y - 1.0),
+            Align2::LEFT_BOTTOM,
+            format!("{db:+}"),
+            font.clone(),
+            GRID_COLOR.gamma_multiply(2.0),
+        );
+    }
+}
+
+fn freq_to_x(rect: Rect, freq_hz: f32) -> f32 {
+    let t = (freq_hz / MIN_FREQ_HZ).ln() / (MAX_FREQ_HZ / MIN_FREQ_HZ).ln();
+    rect.left() + rect.width() * t
+}
+
+fn x_to_freq(rect: Rect, x: f32) -> f32 {
+    let t = (x - rect.left()) / rect.width();
+    MIN_FREQ_HZ * (MAX_FREQ_HZ / MIN_FREQ_HZ).powf(t)
+}
+
+fn db_to_y(rect: Rect, db: f32) -> f32 {
+    rect.center().y - (db / GRAPH_DB_RANGE) * (rect.height() * 0.5)
+}
+
+fn y_to_db(rect: Rect, y: f32) -> f32 {
+    ((rect.center().y - y) / (rect.height() * 0.5) * GRAPH_DB_RANGE)
+        .clamp(-GRAPH_DB_RANGE, GRAPH_DB_RANGE)
+}
@@ -1,122 +1,108 @@
-use meadow_eq_dsp::{BandParams, BandType, DEFAULT_Q, EqParams, FilterOrder, MeadowEqDsp};
+use atomic_float::AtomicF32;
+use meadow_eq_dsp::EqParams;
+use meadow_eq_dsp::crossfade::CrossfadeEqDsp;
+use meadow_eq_dsp::natural_phase::NaturalPhaseEqDsp;
+use meadow_eq_params::{EqParamsGroup, NUM_BANDS};
 use nih_plug::prelude::*;
-use std::sync::Arc;
+use nih_plug_egui::EguiState;
+use std::sync::{Arc, Mutex};
 
-const NUM_BANDS: usize = 1;
+mod analyzer;
+mod editor;
+mod presets;
+mod snapshots;
 
-struct MeadowEq {
+use analyzer::{SpectrumInput, SpectrumOutput};
+use snapshots::{Snapshot, SnapshotSlots};
+
+/// While the parameters are smoothing, the filters are updated once every this many samples
+/// rather than every sample. That keeps the coefficients from being recomputed for every
+/// sample, and the natural phase convolver from being fed single samples.
+const SMOOTHING_BLOCK_LEN: usize = 32;
+
+pub struct MeadowEq {
     params: Arc<MeadowEqParams>,
-    dsp: MeadowEqDsp<NUM_BANDS>,
+    dsp: CrossfadeEqDsp<NUM_BANDS>,
+    natural_phase_dsp: NaturalPhaseEqDsp<NUM_BANDS>,
+    /// Whether the natural phase DSP is the one in use, so switching modes can be detected.
+    natural_phase: bool,
+    /// The active snapshot, so switching to another one can be crossfaded.
+    snapshot: Snapshot,
+    /// The settings stored for the snapshot being crossfaded to. These are used instead of the
+    /// parameters until the crossfade is done, as the editor sets the parameters afterwards.
+    snapshot_params: Option<EqParams<NUM_BANDS>>,
+
+    spectrum_input: SpectrumInput,
+    /// Shared with the editor. Only the editor ever locks this.
+    spectrum_output: Arc<Mutex<SpectrumOutput>>,
+    /// The current sample rate, so the editor can compute the same response curve as the DSP.
+    sample_rate: Arc<AtomicF32>,
+}
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+enum AnalyzerMode {
+    #[id = "off"]
+    Off,
+    #[id = "pre"]
+    #[name = "Pre EQ"]
+    Pre,
+    #[id = "post"]
+    #[name = "Post EQ"]
+    Post,
 }
 
 #[derive(Params)]
 struct MeadowEqParams {
-    #[id = "lp_enabled"]
-    pub lp_enabled: BoolParam,
-    #[id = "lp_cutoff_hz"]
-    pub lp_cutoff_hz: FloatParam,
-    #[id = "lp_q"]
-    pub lp_q: FloatParam,
-    #[id = "lp_order"]
-    pub lp_order: IntParam,
-
-    #[id = "hp_enabled"]
-    pub hp_enabled: BoolParam,
-    #[id = "hp_cutoff_hz"]
-    pub hp_cutoff_hz: FloatParam,
-    #[id = "hp_q"]
-    pub hp_q: FloatParam,
-    #[id = "hp_order"]
-    pub hp_order: IntParam,
-
-    #[id = "band_1_enabled"]
-    pub band_1_enabled: BoolParam,
-    #[id = "band_1_type"]
-    pub band_1_type: IntParam,
-    #[id = "band_1_cutoff_hz"]
-    pub band_1_cutoff_hz: FloatParam,
-    #[id = "band_1_q"]
-    pub band_1_q: FloatParam,
-    #[id = "band_1_gain_db"]
-    pub band_1_gain_db: FloatParam,
+    #[persist = "editor-state"]
+    editor_state: Arc<EguiState>,
+
+    #[nested]
+    pub eq: EqParamsGroup,
+
+    #[id = "natural_phase"]
+    pub natural_phase: BoolParam,
+
+    /// Only the editor changes this, see the [`snapshots`] module.
+    #[id = "snapshot"]
+    pub snapshot: EnumParam<Snapshot>,
+    #[persist = "snapshots"]
+    pub snapshots: Mutex<SnapshotSlots>,
+
+    #[id = "analyzer_mode"]
+    pub analyzer_mode: EnumParam<AnalyzerMode>,
 }
 
 impl Default for MeadowEq {
     fn default() -> Self {
+        let (spectrum_input, spectrum_output) = SpectrumInput::new(44_100.0);
+
         Self {
             params: Arc::new(MeadowEqParams::default()),
-            dsp: MeadowEqDsp::new(44_100.0),
+            dsp: CrossfadeEqDsp::new(44_100.0),
+            natural_phase_dsp: NaturalPhaseEqDsp::new(44_100.0),
+            natural_phase: false,
+            snapshot: Snapshot::A,
+            snapshot_params: None,
+            spectrum_input,
+            spectrum_output: Arc::new(Mutex::new(spectrum_output)),
+            sample_rate: Arc::new(AtomicF32::new(44_100.0)),
         }
     }
 }
 
 impl Default for MeadowEqParams {
     fn default() -> Self {
-        let cutoff_range = FloatRange::Skewed {
-            min: 20.0,
-            max: 21_480.0,
-            factor: FloatRange::skew_factor(-2.0),
-        };
-        let q_range_1 = FloatRange::SymmetricalSkewed {
-            min: 0.3,
-            max: 8.0,
-            factor: 0.85,
-            center: 1.5,
-        };
-        let q_range_2 = FloatRange::SymmetricalSkewed {
-            min: 0.02,
-            max: 40.0,
-            factor: 0.85,
-            center: 2.5,
-        };
-
         Self {
-            lp_enabled: BoolParam::new("LP enabled", false),
-            lp_cutoff_hz: FloatParam::new("LP cutoff", 21_480.0, cutoff_range.clone()),
-            lp_q: FloatParam::new("LP Q", DEFAULT_Q, q_range_1.clone()),
-            lp_order: IntParam::new("LP order", 1, IntRange::Linear { min: 0, max: 4 })
-                .with_value_to_string(Arc::new(|v| match v {
-                    0 => String::from("x1"),
-                    1 => String::from("x2"),
-                    2 => String::from("x4"),
-                    3 => String::from("x6"),
-                    _ => String::from("x8"),
-                })),
-
-            hp_enabled: BoolParam::new("HP enabled", false),
-            hp_cutoff_hz: FloatParam::new("HP cutoff", 20.0, cutoff_range.clone()),
-            hp_q: FloatParam::new("HP Q", DEFAULT_Q, q_range_1.clone()),
-            hp_order: IntParam::new("HP order", 1, IntRange::Linear { min: 0, max: 4 })
-                .with_value_to_string(Arc::new(|v| match v {
-                    0 => String::from("x1"),
-                    1 => String::from("x2"),
-                    2 => String::from("x4"),
-                    3 => String::from("x6"),
-                    _ => String::from("x8"),
-                })),
-
-            band_1_enabled: BoolParam::new("Band 1 enabled", false),
-            band_1_type: IntParam::new("Band 1 type", 0, IntRange::Linear { min: 0, max: 4 })
-                .with_value_to_string(Arc::new(|v| match v {
-                    0 => String::from("bell"),
-                    1 => String::from("low shelf"),
-                    2 => String::from("high shelf"),
-                    3 => String::from("notch"),
-                    _ => String::from("allpass"),
-                })),
-            band_1_cutoff_hz: FloatParam::new("Band 1 cutoff", 1000.0, cutoff_range.clone()),
-            band_1_q: FloatParam::new("Band 1 Q", DEFAULT_Q, q_range_2.clone()),
-            band_1_gain_db: FloatParam::new(
-                "Band 1 Gain",
-                0.0,
-                FloatRange::SymmetricalSkewed {
-                    min: -30.0,
-                    max: 30.0,
-                    factor: 0.4,
-                    center: 0.0,
-                },
-            )
-            .with_unit(" dB"),
+            editor_state: editor::default_state(),
+
+            eq: EqParamsGroup::default(),
+
+            natural_phase: BoolParam::new("Natural phase", false),
+
+            snapshot: EnumParam::new("Snapshot", Snapshot::A).non_automatable(),
+            snapshots: Mutex::new(SnapshotSlots::default()),
+
+            analyzer_mode: EnumParam::new("Analyzer", AnalyzerMode::Post),
         }
     }
 }
@@ -162,13 +148,29 @@ impl Plugin for MeadowEq {
         self.params.clone()
     }
 
+    fn editor(&mut self, _async_executor: AsyncExecutor<Self>) -> Option<Box<dyn Editor>> {
+        editor::create(
+            self.params.clone(),
+            self.spectrum_output.clone(),
+            self.sample_rate.clone(),
+        )
+    }
+
     fn initialize(
         &mut self,
         _audio_io_layout: &AudioIOLayout,
         config: &BufferConfig,
-        _context: &mut impl InitContext<Self>,
+        context: &mut impl InitContext<Self>,
     ) -> bool {
-        self.dsp = MeadowEqDsp::new(config.sample_rate as f64);
+        self.dsp = CrossfadeEqDsp::new(config.sample_rate as f64);
+        self.natural_phase_dsp = NaturalPhaseEqDsp::new(config.sample_rate as f64);
+        self.natural_phase = self.params.natural_phase.value();
+        self.snapshot = self.params.snapshot.value();
+        self.snapshot_params = None;
+        context.set_latency_samples(self.latency_samples());
+        self.spectrum_input.set_sample_rate(config.sample_rate);
+        self.sample_rate
+            .store(config.sample_rate, std::sync::atomic::Ordering::Relaxed);
 
         // Resize buffers and perform other potentially expensive initialization operations here.
         // The `reset()` function is always called right after this function. You can remove this
@@ -179,45 +181,120 @@ impl Plugin for MeadowEq {
     fn reset(&mut self) {
         // Reset buffers and envelopes here. This can be called from the audio thread and may not
         // allocate. You can remove this function if you do not need it.
+        self.dsp.reset();
+        self.natural_phase_dsp.reset();
+        self.spectrum_input.reset();
     }
 
     fn process(
         &mut self,
         buffer: &mut Buffer,
         _aux: &mut AuxiliaryBuffers,
-        _context: &mut impl ProcessContext<Self>,
+        context: &mut impl ProcessContext<Self>,
     ) -> ProcessStatus {
-        let params = EqParams {
-            lp_enabled: self.params.lp_enabled.value(),
-            lp_cutoff_hz: self.params.lp_cutoff_hz.value(),
-            lp_q: self.params.lp_q.value(),
-            lp_order: FilterOrder::from_u32(self.params.lp_order.value() as u32),
-
-            hp_enabled: self.params.hp_enabled.value(),
-            hp_cutoff_hz: self.params.hp_cutoff_hz.value(),
-            hp_q: self.params.hp_q.value(),
-            hp_order: FilterOrder::from_u32(self.params.hp_order.value() as u32),
-
-            bands: [BandParams {
-                enabled: self.params.band_1_enabled.value(),
-                band_type: BandType::from_u32(self.params.band_1_type.value() as u32),
-                cutoff_hz: self.params.band_1_cutoff_hz.value(),
-                q: self.params.band_1_q.value(),
-                gain_db: self.params.band_1_gain_db.value(),
-            }],
+        let natural_phase = self.params.natural_phase.value();
+        if natural_phase != self.natural_phase {
+            self.natural_phase = natural_phase;
+            context.set_latency_samples(self.latency_samples());
+
+            // The other DSP has not seen the signal in a while.
+            self.dsp.reset();
+            self.natural_phase_dsp.reset();
+        }
+
+        let [out_l, out_r, ..] = buffer.as_slice() else {
+            return ProcessStatus::Normal;
         };
 
-        self.dsp.set_params(params);
+        let analyzer_mode = self.params.analyzer_mode.value();
+        if analyzer_mode == AnalyzerMode::Pre {
+            self.spectrum_input.process(out_l, out_r);
+        }
 
-        let out = buffer.as_slice();
-        let (out_l, out_r) = out.split_first_mut().unwrap();
+        let snapshot = self.params.snapshot.value();
+        if snapshot != self.snapshot {
+            self.switch_snapshot(snapshot);
+        }
+
+        if let Some(params) = self.snapshot_params {
+            self.process_eq(params, out_l, out_r);
+            if !self.is_crossfading() {
+                self.snapshot_params = None;
+                // The parameters have caught up with the snapshot by now.
+                self.params.eq.reset_smoothers();
+            }
+        } else if self.params.eq.is_smoothing() {
+            for (l, r) in out_l
+                .chunks_mut(SMOOTHING_BLOCK_LEN)
+                .zip(out_r.chunks_mut(SMOOTHING_BLOCK_LEN))
+            {
+                let params = self.params.eq.next_smoothed_eq_params(l.len() as u32);
+                self.process_eq(params, l, r);
+            }
+        } else {
+            let params = self.params.eq.eq_params();
+            self.process_eq(params, out_l, out_r);
+        }
 
-        self.dsp.process(out_l, out_r[0]);
+        if analyzer_mode == AnalyzerMode::Post {
+            self.spectrum_input.process(out_l, out_r);
+        }
 
         ProcessStatus::Normal
     }
 }
 
+impl MeadowEq {
+    /// Crossfades to the settings stored for `snapshot`, see the [`snapshots`] module.
+    fn switch_snapshot(&mut self, snapshot: Snapshot) {
+        // The editor only holds the lock briefly, so this is tried again next block.
+        let Ok(slots) = self.params.snapshots.try_lock() else {
+            return;
+        };
+        let params = slots[snapshot as usize]
+            .as_ref()
+            .and_then(|stored| stored.to_params(&self.params.eq.default_eq_params()).ok())
+            .unwrap_or_else(|| self.params.eq.eq_params());
+        drop(slots);
+
+        self.snapshot = snapshot;
+        // The crossfade already takes care of the transition.
+        self.params.eq.reset_smoothers();
+        if self.natural_phase {
+            self.natural_phase_dsp.crossfade_to(params);
+        } else {
+            self.dsp.crossfade_to(params);
+        }
+        self.snapshot_params = Some(params);
+    }
+
+    fn process_eq(&mut self, params: EqParams<NUM_BANDS>, l: &mut [f32], r: &mut [f32]) {
+        if self.natural_phase {
+            self.natural_phase_dsp.set_params(params);
+            self.natural_phase_dsp.process(l, r);
+        } else {
+            self.dsp.set_params(params);
+            self.dsp.process(l, r);
+        }
+    }
+
+    fn is_crossfading(&self) -> bool {
+        if self.natural_phase {
+            self.natural_phase_dsp.is_crossfading()
+        } else {
+            self.dsp.is_crossfading()
+        }
+    }
+
+    fn latency_samples(&self) -> u32 {
+        if self.natural_phase {
+            self.natural_phase_dsp.latency_samples()
+        } else {
+            0
+        }
+    }
+}
+
 impl ClapPlugin for MeadowEq {
     const CLAP_ID: &'static str = "app.meadowlark.meadow-eq";
     const CLAP_DESCRIPTION: Option<&'static str> = Some("A high quality open source parametric EQ");
@@ -226,6 +303,42 @@ impl ClapPlugin for MeadowEq {
 
     // Don't forget to change these features
     const CLAP_FEATURES: &'static [ClapFeature] = &[ClapFeature::AudioEffect, ClapFeature::Stereo];
+
+    fn remote_controls(&self, context: &mut impl RemoteControlsContext) {
+        let params = &self.params;
+
+        context.add_section("Cuts", |section| {
+            section.add_page("Cuts", |page| {
+                page.add_param(&params.eq.hp_enabled);
+                page.add_param(&params.eq.hp_cutoff_hz);
+                page.add_param(&params.eq.hp_q);
+                page.add_param(&params.eq.hp_order);
+                page.add_param(&params.eq.lp_enabled);
+                page.add_param(&params.eq.lp_cutoff_hz);
+                page.add_param(&params.eq.lp_q);
+                page.add_param(&params.eq.lp_order);
+            });
+        });
+
+        let bands = params.eq.bands();
+        for (name, bands) in [("Bands 1–4", &bands[..4]), ("Bands 5–8", &bands[4..])] {
+            context.add_section(name, |section| {
+                // Two knobs per band on each page, in the order the bands appear in the editor.
+                section.add_page(format!("{name} Cutoff/Gain"), |page| {
+                    for band in bands {
+                        page.add_param(&band.cutoff_hz);
+                        page.add_param(&band.gain_db);
+                    }
+                });
+                section.add_page(format!("{name} Q/Type"), |page| {
+                    for band in bands {
+                        page.add_param(&band.q);
+                        page.add_param(&band.band_type);
+                    }
+                });
+            });
+        }
+    }
 }
 
 impl Vst3Plugin for MeadowEq {
@@ -236,5 +349,7 @@ impl Vst3Plugin for MeadowEq {
         &[Vst3SubCategory::Fx, Vst3SubCategory::Dynamics];
 }
 
+#[cfg(not(feature = "bundled"))]
 nih_export_clap!(MeadowEq);
+#[cfg(not(feature = "bundled"))]
 nih_export_vst3!(MeadowEq);
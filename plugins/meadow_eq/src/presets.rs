@@ -0,0 +1,149 @@
+use meadow_eq_dsp::EqParams;
+use meadow_eq_dsp::preset::{EqPreset, PRESET_FILE_EXTENSION};
+use meadow_eq_params::NUM_BANDS;
+use nih_plug::prelude::*;
+use std::path::PathBuf;
+
+use crate::MeadowEqParams;
+
+/// The directory user presets are saved to and loaded from, or `None` if it can't be
+/// determined on this system.
+pub fn user_preset_dir() -> Option<PathBuf> {
+    #[cfg(target_os = "windows")]
+    let base = std::env::var_os("APPDATA").map(PathBuf::from);
+    #[cfg(target_os = "macos")]
+    let base =
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Audio/Presets"));
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let base = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")));
+
+    base.map(|base| base.join("Meadowlark").join("Meadow Eq"))
+}
+
+/// Loads every readable preset in the user preset directory, sorted by name.
+pub fn load_user_presets() -> Vec<EqPreset> {
+    let Some(dir) = user_preset_dir() else {
+        return Vec::new();
+    };
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+        return Vec::new();
+    };
+
+    let mut presets: Vec<EqPreset> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| ext == PRESET_FILE_EXTENSION)
+        })
+        .filter_map(|path| match EqPreset::load(&path) {
+            Ok(preset) => Some(preset),
+            Err(e) => {
+                nih_log!("Skipping preset {}: {e}", path.display());
+                None
+            }
+        })
+        .collect();
+    presets.sort_by(|a, b| a.name.cmp(&b.name));
+
+    presets
+}
+
+/// Saves the current parameters as a user preset and returns the path it was written to.
+pub fn save_user_preset(name: &str, params: &MeadowEqParams) -> Result<PathBuf, String> {
+    let dir = user_preset_dir().ok_or_else(|| String::from("no user preset directory"))?;
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+    let file_name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' || c == ' ' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let path = dir.join(format!("{}.{PRESET_FILE_EXTENSION}", file_name.trim()));
+
+    EqPreset::from_params(name, &params.eq.eq_params())
+        .save(&path)
+        .map_err(|e| e.to_string())?;
+
+    Ok(path)
+}
+
+/// Sets every parameter to the value stored in the preset, the same way a user would.
+pub fn apply_preset(
+    preset: &EqPreset,
+    params: &MeadowEqParams,
+    setter: &ParamSetter,
+) -> Result<(), String> {
+    let eq_params: EqParams<NUM_BANDS> = preset
+        .to_params(&params.eq.default_eq_params())
+        .map_err(|e| e.to_string())?;
+    apply_params(&eq_params, params, setter);
+
+    Ok(())
+}
+
+/// Sets every parameter to the value in `eq_params`, the same way a user would.
+pub fn apply_params(
+    eq_params: &EqParams<NUM_BANDS>,
+    params: &MeadowEqParams,
+    setter: &ParamSetter,
+) {
+    write_params(eq_params, params, setter, true);
+}
+
+/// Sets every parameter to the value in `eq_params` without any gestures, for when the audio
+/// thread already plays these settings and the host only needs to know the new values.
+pub fn load_params(eq_params: &EqParams<NUM_BANDS>, params: &MeadowEqParams, setter: &ParamSetter) {
+    write_params(eq_params, params, setter, false);
+}
+
+fn write_params(
+    eq_params: &EqParams<NUM_BANDS>,
+    params: &MeadowEqParams,
+    setter: &ParamSetter,
+    gestures: bool,
+) {
+    let writer = Writer { setter, gestures };
+
+    writer.set(&params.eq.lp_enabled, eq_params.lp_enabled);
+    writer.set(&params.eq.lp_cutoff_hz, eq_params.lp_cutoff_hz);
+    writer.set(&params.eq.lp_q, eq_params.lp_q);
+    writer.set(&params.eq.lp_order, eq_params.lp_order as i32);
+
+    writer.set(&params.eq.hp_enabled, eq_params.hp_enabled);
+    writer.set(&params.eq.hp_cutoff_hz, eq_params.hp_cutoff_hz);
+    writer.set(&params.eq.hp_q, eq_params.hp_q);
+    writer.set(&params.eq.hp_order, eq_params.hp_order as i32);
+
+    for (band, band_params) in params.eq.bands().into_iter().zip(eq_params.bands.iter()) {
+        writer.set(&band.enabled, band_params.enabled);
+        writer.set(&band.band_type, band_params.band_type as i32);
+        writer.set(&band.cutoff_hz, band_params.cutoff_hz);
+        writer.set(&band.q, band_params.q);
+        writer.set(&band.gain_db, band_params.gain_db);
+    }
+}
+
+struct Writer<'a> {
+    setter: &'a ParamSetter<'a>,
+    gestures: bool,
+}
+
+impl Writer<'_> {
+    fn set<P: Param>(&self, param: &P, value: P::Plain) {
+        if self.gestures {
+            self.setter.begin_set_parameter(param);
+        }
+        self.setter.set_parameter(param, value);
+        if self.gestures {
+            self.setter.end_set_parameter(param);
+        }
+    }
+}
@@ -0,0 +1,92 @@
+//! A/B/C/D snapshots of the EQ settings, for comparing them.
+//!
+//! The parameters are the live copy of the active snapshot, and the others are stored with
+//! the plugin state. Switching stores the parameters in the active snapshot and changes the
+//! `snapshot` parameter, which is the only edit the host sees as a gesture. The audio thread
+//! then crossfades to the settings stored for the new snapshot in one go, and the parameters
+//! are set to them without gestures so the host and the editor show the new values.
+
+use meadow_eq_dsp::preset::EqPreset;
+use nih_plug::prelude::*;
+
+use crate::MeadowEqParams;
+use crate::presets;
+
+pub const NUM_SNAPSHOTS: usize = 4;
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Snapshot {
+    #[id = "a"]
+    A,
+    #[id = "b"]
+    B,
+    #[id = "c"]
+    C,
+    #[id = "d"]
+    D,
+}
+
+impl Snapshot {
+    pub const ALL: [Self; NUM_SNAPSHOTS] = [Self::A, Self::B, Self::C, Self::D];
+}
+
+/// The stored settings of every snapshot, or `None` for one that hasn't been used yet. The
+/// active snapshot's slot is only updated when switching away from it.
+pub type SnapshotSlots = [Option<EqPreset>; NUM_SNAPSHOTS];
+
+/// Stores the current settings in the active snapshot, then makes `to` the active one. A
+/// snapshot that hasn't been used yet starts out with the current settings.
+pub fn switch(to: Snapshot, params: &MeadowEqParams, setter: &ParamSetter) {
+    let from = params.snapshot.value();
+    if from == to {
+        return;
+    }
+    let Ok(mut slots) = params.snapshots.lock() else {
+        return;
+    };
+
+    let current = current_settings(params);
+    let stored = slots[to as usize]
+        .get_or_insert_with(|| current.clone())
+        .clone();
+    slots[from as usize] = Some(current);
+    // The audio thread reads the new snapshot's settings from the slots.
+    drop(slots);
+
+    setter.begin_set_parameter(&params.snapshot);
+    setter.set_parameter(&params.snapshot, to);
+    setter.end_set_parameter(&params.snapshot);
+
+    if let Ok(eq_params) = stored.to_params(&params.eq.default_eq_params()) {
+        presets::load_params(&eq_params, params, setter);
+    }
+}
+
+/// Copies the settings of `from` to `to`. Copying to the active snapshot loads the settings
+/// into the parameters.
+pub fn copy(from: Snapshot, to: Snapshot, params: &MeadowEqParams, setter: &ParamSetter) {
+    let active = params.snapshot.value();
+    if from == to {
+        return;
+    }
+    let Ok(mut slots) = params.snapshots.lock() else {
+        return;
+    };
+
+    let settings = match &slots[from as usize] {
+        Some(stored) if from != active => stored.clone(),
+        _ => current_settings(params),
+    };
+
+    if to == active {
+        if let Ok(eq_params) = settings.to_params(&params.eq.default_eq_params()) {
+            presets::apply_params(&eq_params, params, setter);
+        }
+    } else {
+        slots[to as usize] = Some(settings);
+    }
+}
+
+fn current_settings(params: &MeadowEqParams) -> EqPreset {
+    EqPreset::from_params("", &params.eq.eq_params())
+}
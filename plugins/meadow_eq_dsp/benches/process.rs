@@ -0,0 +1,200 @@
+//! Benchmarks for `MeadowEqDsp`.
+//!
+//! Run the scalar build with:
+//!
+//! ```sh
+//! cargo bench -p meadow_eq_dsp
+//! ```
+//!
+//! and the SIMD build with:
+//!
+//! ```sh
+//! cargo bench -p meadow_eq_dsp --features portable-simd
+//! ```
+//!
+//! Criterion compares each run against the previous one. To compare the two builds
+//! directly, save the scalar run as a baseline with `-- --save-baseline scalar` and run the
+//! SIMD build with `-- --baseline scalar`.
+
+use criterion::{BatchSize, BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use meadow_eq_dsp::{BandParams, BandType, EqParams, FilterOrder, MeadowEqDsp};
+use std::hint::black_box;
+
+const NUM_BANDS: usize = 8;
+const SAMPLE_RATE: f64 = 48_000.0;
+const DEFAULT_BLOCK_SIZE: usize = 512;
+const BLOCK_SIZES: [usize; 9] = [16, 32, 64, 128, 256, 512, 1024, 2048, 4096];
+const ORDERS: [FilterOrder; 5] = [
+    FilterOrder::X1,
+    FilterOrder::X2,
+    FilterOrder::X4,
+    FilterOrder::X6,
+    FilterOrder::X8,
+];
+
+/// Parameters with the first `num_bands` bands enabled as bells spread over the spectrum.
+fn params_with_bands(num_bands: usize) -> EqParams<NUM_BANDS> {
+    let mut params = EqParams::default();
+
+    for (i, band) in params.bands.iter_mut().enumerate().take(num_bands) {
+        *band = BandParams {
+            enabled: true,
+            band_type: BandType::Bell,
+            cutoff_hz: 60.0 * 2.0f32.powi(i as i32),
+            q: 1.0,
+            gain_db: if i % 2 == 0 { 3.0 } else { -3.0 },
+        };
+    }
+
+    params
+}
+
+/// A typical mixing setting: a highpass and four bands.
+fn typical_params() -> EqParams<NUM_BANDS> {
+    EqParams {
+        hp_enabled: true,
+        hp_cutoff_hz: 40.0,
+        hp_order: FilterOrder::X4,
+        ..params_with_bands(4)
+    }
+}
+
+/// A block of a 76 Hz sine.
+fn buffers(block_size: usize) -> (Vec<f32>, Vec<f32>) {
+    let signal: Vec<f32> = (0..block_size)
+        .map(|i| (i as f32 * 0.01).sin() * 0.5)
+        .collect();
+
+    (signal.clone(), signal)
+}
+
+fn bench_process(
+    c: &mut Criterion,
+    group_name: &str,
+    cases: impl IntoIterator<Item = (String, EqParams<NUM_BANDS>, usize)>,
+) {
+    let mut group = c.benchmark_group(group_name);
+
+    for (id, params, block_size) in cases {
+        let mut dsp = MeadowEqDsp::<NUM_BANDS>::new(SAMPLE_RATE);
+        dsp.set_params(params);
+        let input = buffers(block_size);
+
+        // Every iteration gets a fresh copy of the input. Processing the same buffer over and
+        // over would filter it down to denormals, which are much slower than normal samples.
+        group.throughput(Throughput::Elements(block_size as u64));
+        group.bench_function(BenchmarkId::from_parameter(id), |b| {
+            b.iter_batched_ref(
+                || input.clone(),
+                |(buf_l, buf_r)| dsp.process(black_box(buf_l), black_box(buf_r)),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+fn process_bands(c: &mut Criterion) {
+    bench_process(
+        c,
+        "process/bands",
+        (0..=NUM_BANDS).map(|n| (n.to_string(), params_with_bands(n), DEFAULT_BLOCK_SIZE)),
+    );
+}
+
+fn process_lp_order(c: &mut Criterion) {
+    bench_process(
+        c,
+        "process/lp_order",
+        ORDERS.map(|order| {
+            let params = EqParams {
+                lp_enabled: true,
+                lp_cutoff_hz: 10_000.0,
+                lp_order: order,
+                ..EqParams::default()
+            };
+
+            (format!("{order:?}"), params, DEFAULT_BLOCK_SIZE)
+        }),
+    );
+}
+
+fn process_hp_order(c: &mut Criterion) {
+    bench_process(
+        c,
+        "process/hp_order",
+        ORDERS.map(|order| {
+            let params = EqParams {
+                hp_enabled: true,
+                hp_cutoff_hz: 80.0,
+                hp_order: order,
+                ..EqParams::default()
+            };
+
+            (format!("{order:?}"), params, DEFAULT_BLOCK_SIZE)
+        }),
+    );
+}
+
+fn process_block_size(c: &mut Criterion) {
+    bench_process(
+        c,
+        "process/block_size",
+        BLOCK_SIZES.map(|block_size| (block_size.to_string(), typical_params(), block_size)),
+    );
+}
+
+/// The cost of recomputing every coefficient. `set_params` returns early if nothing
+/// changed, so this alternates between two settings.
+fn set_params(c: &mut Criterion) {
+    let mut group = c.benchmark_group("set_params");
+
+    let everything = |gain_db: f32| {
+        let mut params = EqParams {
+            lp_enabled: true,
+            lp_cutoff_hz: 12_000.0,
+            lp_order: FilterOrder::X8,
+            hp_enabled: true,
+            hp_cutoff_hz: 40.0,
+            hp_order: FilterOrder::X8,
+            ..params_with_bands(NUM_BANDS)
+        };
+        for band in params.bands.iter_mut() {
+            band.gain_db = gain_db;
+        }
+
+        params
+    };
+
+    for (id, a, b) in [
+        ("typical", typical_params(), {
+            let mut params = typical_params();
+            params.bands[0].gain_db += 1.0;
+            params
+        }),
+        ("everything", everything(3.0), everything(-3.0)),
+    ] {
+        let mut dsp = MeadowEqDsp::<NUM_BANDS>::new(SAMPLE_RATE);
+        let mut flip = false;
+
+        group.bench_function(id, |bencher| {
+            bencher.iter(|| {
+                flip = !flip;
+                dsp.set_params(black_box(if flip { a } else { b }));
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    process_bands,
+    process_lp_order,
+    process_hp_order,
+    process_block_size,
+    set_params
+);
+criterion_main!(benches);
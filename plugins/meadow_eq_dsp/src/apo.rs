@@ -0,0 +1,640 @@
+//! Import and export of Equalizer APO parametric EQ configurations.
+//!
+//! This is the plain-text format used by AutoEQ headphone corrections and by REW's
+//! "Equalizer APO" filter export:
+//!
+//! ```text
+//! Preamp: -6.2 dB
+//! Filter 1: ON PK Fc 105 Hz Gain -2.8 dB Q 0.70
+//! Filter 2: ON LSC Fc 105 Hz Gain 6.0 dB Q 0.70
+//! ```
+//!
+//! Only `Preamp` and `Filter` commands are supported. Other Equalizer APO commands (such as
+//! `Channel` or `Convolution`) change how the filters are applied, so they are rejected
+//! rather than silently dropped. Any other line is treated as a comment, which lets REW's
+//! free-form header text through.
+//!
+//! Lowpass and highpass filters steeper than 12 dB/oct don't exist in the format, so they
+//! are written as a run of second-order sections at the same frequency. On import, such a
+//! run becomes a single filter of the matching order again.
+//!
+//! The shelves are the RBJ cookbook shelves. `LS` and `HS` give the centre of the
+//! transition, where the gain is half way, while `LSC` and `HSC` give its corner, where the
+//! shelf's full gain begins. The corner is `10^(|gain|/80)` times below the centre for a low
+//! shelf and above it for a high shelf. The DSP's low shelf is set by its centre, and its
+//! high shelf by a frequency `10^(gain/40)` times above the centre.
+
+use std::fmt::Write;
+
+use crate::{
+    BandParams, BandType, DEFAULT_Q, EqParams, FilterOrder, MAX_CUT_SECTIONS, ORD4_Q_SCALE,
+    ORD6_Q_SCALE, ORD8_Q_SCALE, Q_BUTTERWORTH_ORD2, Q_BUTTERWORTH_ORD4, Q_BUTTERWORTH_ORD6,
+    Q_BUTTERWORTH_ORD8, q_norm, scale_q_norm_for_order,
+};
+
+/// Equalizer APO commands that exist but cannot be represented by [`EqParams`].
+const UNSUPPORTED_COMMANDS: &[&str] = &[
+    "Channel",
+    "Convolution",
+    "Copy",
+    "Delay",
+    "Device",
+    "Eval",
+    "GraphicEQ",
+    "If",
+    "ElseIf",
+    "Else",
+    "EndIf",
+    "Include",
+    "Stage",
+    "LoudnessCorrection",
+];
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum ApoError {
+    #[error("line {line}: unsupported filter type \"{filter_type}\"")]
+    UnsupportedFilterType { line: usize, filter_type: String },
+    #[error("line {line}: unsupported command \"{command}\"")]
+    UnsupportedCommand { line: usize, command: String },
+    #[error("line {line}: missing {field}")]
+    MissingField { line: usize, field: &'static str },
+    #[error("line {line}: invalid {field} \"{value}\"")]
+    InvalidValue {
+        line: usize,
+        field: &'static str,
+        value: String,
+    },
+    #[error("line {line}: unexpected \"{token}\"")]
+    UnexpectedToken { line: usize, token: String },
+    #[error("line {line}: more than {max} bands")]
+    TooManyBands { line: usize, max: usize },
+    #[error("line {line}: only one {filter} filter is supported")]
+    DuplicateCutFilter { line: usize, filter: &'static str },
+    #[error("line {line}: a {filter} filter can have at most {max} sections")]
+    TooManyCutSections {
+        line: usize,
+        filter: &'static str,
+        max: usize,
+    },
+    #[error("a first-order {0} filter cannot be represented in Equalizer APO")]
+    Unrepresentable(&'static str),
+}
+
+/// An Equalizer APO configuration.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ApoConfig<const NUM_BANDS: usize> {
+    /// The sum of all `Preamp` gains.
+    pub preamp_db: f32,
+    pub params: EqParams<NUM_BANDS>,
+}
+
+impl<const NUM_BANDS: usize> Default for ApoConfig<NUM_BANDS> {
+    fn default() -> Self {
+        Self {
+            preamp_db: 0.0,
+            params: EqParams::default(),
+        }
+    }
+}
+
+impl<const NUM_BANDS: usize> ApoConfig<NUM_BANDS> {
+    pub fn parse(text: &str) -> Result<Self, ApoError> {
+        let mut config = Self::default();
+        let mut filters = Vec::new();
+
+        for (line_i, raw_line) in text.lines().enumerate() {
+            let line = line_i + 1;
+
+            let content = raw_line.split('#').next().unwrap_or_default().trim();
+            let Some((command, args)) = content.split_once(':') else {
+                continue;
+            };
+            let command = command.trim();
+
+            if command == "Preamp" {
+                config.preamp_db += parse_preamp(line, args)?;
+                continue;
+            }
+
+            if UNSUPPORTED_COMMANDS.contains(&command) {
+                return Err(ApoError::UnsupportedCommand {
+                    line,
+                    command: command.to_string(),
+                });
+            }
+
+            // "Filter", "Filter 1", "Filter  12", ...
+            let is_filter = command
+                .strip_prefix("Filter")
+                .is_some_and(|n| n.trim().chars().all(|c| c.is_ascii_digit()));
+            if !is_filter {
+                continue;
+            }
+
+            if let Some(filter) = parse_filter(line, args)? {
+                filters.push((line, filter));
+            }
+        }
+
+        let mut num_bands = 0;
+        let mut has_lp = false;
+        let mut has_hp = false;
+        let mut filters = filters.into_iter().peekable();
+
+        while let Some((line, filter)) = filters.next() {
+            match filter {
+                Filter::Band(band) => {
+                    if num_bands == NUM_BANDS {
+                        return Err(ApoError::TooManyBands {
+                            line,
+                            max: NUM_BANDS,
+                        });
+                    }
+                    config.params.bands[num_bands] = band;
+                    num_bands += 1;
+                }
+                Filter::Cut(kind, section) => {
+                    // The sections of a steeper filter follow each other.
+                    let mut section_qs = vec![section.q];
+                    while let Some((_, Filter::Cut(next_kind, next))) = filters.peek()
+                        && *next_kind == kind
+                        && next.enabled == section.enabled
+                        && next.cutoff_hz == section.cutoff_hz
+                    {
+                        section_qs.push(next.q);
+                        filters.next();
+                    }
+
+                    let (order, q) =
+                        cut_from_section_qs(&section_qs).ok_or(ApoError::TooManyCutSections {
+                            line,
+                            filter: kind.name(),
+                            max: MAX_CUT_SECTIONS,
+                        })?;
+
+                    let has_filter = match kind {
+                        CutKind::Lowpass => &mut has_lp,
+                        CutKind::Highpass => &mut has_hp,
+                    };
+                    if *has_filter {
+                        return Err(ApoError::DuplicateCutFilter {
+                            line,
+                            filter: kind.name(),
+                        });
+                    }
+                    *has_filter = true;
+
+                    let params = &mut config.params;
+                    match kind {
+                        CutKind::Lowpass => {
+                            params.lp_enabled = section.enabled;
+                            params.lp_cutoff_hz = section.cutoff_hz;
+                            params.lp_q = q;
+                            params.lp_order = order;
+                        }
+                        CutKind::Highpass => {
+                            params.hp_enabled = section.enabled;
+                            params.hp_cutoff_hz = section.cutoff_hz;
+                            params.hp_q = q;
+                            params.hp_order = order;
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Writes the configuration in Equalizer APO's format.
+    ///
+    /// Lowpass and highpass filters steeper than 12 dB/oct are written as a cascade of
+    /// `LPQ`/`HPQ` sections with the same Q values the DSP uses, so the exported response
+    /// matches, and [`Self::parse()`] reads them back as one filter.
+    pub fn write(&self) -> Result<String, ApoError> {
+        let params = &self.params;
+        let mut out = String::new();
+        let mut filter_n = 1;
+
+        let mut push_filter = |out: &mut String, args: String| {
+            let _ = writeln!(out, "Filter {filter_n}: {args}");
+            filter_n += 1;
+        };
+
+        let _ = writeln!(out, "Preamp: {} dB", format_number(self.preamp_db));
+
+        if params.hp_enabled {
+            for q in cut_section_qs(params.hp_order, params.hp_q)
+                .ok_or(ApoError::Unrepresentable("highpass"))?
+            {
+                push_filter(
+                    &mut out,
+                    format!(
+                        "ON HPQ Fc {} Hz Q {}",
+                        format_number(params.hp_cutoff_hz),
+                        format_number(q)
+                    ),
+                );
+            }
+        }
+
+        for band in params.bands.iter().filter(|b| b.enabled) {
+            let args = match band.band_type {
+                BandType::Bell => format!(
+                    "ON PK Fc {} Hz Gain {} dB Q {}",
+                    format_number(band.cutoff_hz),
+                    format_number(band.gain_db),
+                    format_number(band.q)
+                ),
+                BandType::LowShelf => format!(
+                    "ON LSC Fc {} Hz Gain {} dB Q {}",
+                    format_number(shelf_corner_hz(band)),
+                    format_number(band.gain_db),
+                    format_number(band.q)
+                ),
+                BandType::HighShelf => format!(
+                    "ON HSC Fc {} Hz Gain {} dB Q {}",
+                    format_number(shelf_corner_hz(band)),
+                    format_number(band.gain_db),
+                    format_number(band.q)
+                ),
+                BandType::Notch => {
+                    format!(
+                        "ON NO Fc {} Hz Q {}",
+                        format_number(band.cutoff_hz),
+                        format_number(band.q)
+                    )
+                }
+                BandType::Allpass => {
+                    format!(
+                        "ON AP Fc {} Hz Q {}",
+                        format_number(band.cutoff_hz),
+                        format_number(band.q)
+                    )
+                }
+            };
+            push_filter(&mut out, args);
+        }
+
+        if params.lp_enabled {
+            for q in cut_section_qs(params.lp_order, params.lp_q)
+                .ok_or(ApoError::Unrepresentable("lowpass"))?
+            {
+                push_filter(
+                    &mut out,
+                    format!(
+                        "ON LPQ Fc {} Hz Q {}",
+                        format_number(params.lp_cutoff_hz),
+                        format_number(q)
+                    ),
+                );
+            }
+        }
+
+        Ok(out)
+    }
+}
+
+enum Filter {
+    Band(BandParams),
+    /// One second-order section of a lowpass or highpass filter.
+    Cut(CutKind, CutSection),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum CutKind {
+    Lowpass,
+    Highpass,
+}
+
+impl CutKind {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Lowpass => "lowpass",
+            Self::Highpass => "highpass",
+        }
+    }
+}
+
+struct CutSection {
+    enabled: bool,
+    cutoff_hz: f32,
+    q: f32,
+}
+
+/// Which point of the transition an Equalizer APO shelf's frequency is.
+#[derive(Clone, Copy)]
+enum ShelfFreq {
+    Centre,
+    Corner,
+}
+
+fn parse_preamp(line: usize, args: &str) -> Result<f32, ApoError> {
+    let mut tokens = args.split_whitespace();
+
+    let value = tokens.next().ok_or(ApoError::MissingField {
+        line,
+        field: "preamp gain",
+    })?;
+    let gain_db = parse_number(line, "preamp gain", value)?;
+
+    match tokens.next() {
+        None | Some("dB") => {}
+        Some(token) => {
+            return Err(ApoError::UnexpectedToken {
+                line,
+                token: token.to_string(),
+            });
+        }
+    }
+
+    Ok(gain_db)
+}
+
+/// Returns `None` for filters that do nothing, such as REW's `None` entries.
+fn parse_filter(line: usize, args: &str) -> Result<Option<Filter>, ApoError> {
+    let mut tokens = args.split_whitespace().peekable();
+
+    let enabled = match tokens.next() {
+        Some("ON") => true,
+        Some("OFF") => false,
+        Some(token) => {
+            return Err(ApoError::InvalidValue {
+                line,
+                field: "filter state",
+                value: token.to_string(),
+            });
+        }
+        None => {
+            return Err(ApoError::MissingField {
+                line,
+                field: "filter state",
+            });
+        }
+    };
+
+    let mut filter_type = tokens
+        .next()
+        .ok_or(ApoError::MissingField {
+            line,
+            field: "filter type",
+        })?
+        .to_string();
+    // Shelf slopes are written as a separate token, e.g. "LS 12dB".
+    if let Some(slope) = tokens.next_if(|t| t.ends_with("dB") && t.len() > 2) {
+        filter_type = format!("{filter_type} {slope}");
+    }
+
+    let mut cutoff_hz = None;
+    let mut gain_db = None;
+    let mut q = None;
+
+    while let Some(key) = tokens.next() {
+        match key {
+            "Fc" => {
+                let value = tokens.next().ok_or(ApoError::MissingField {
+                    line,
+                    field: "frequency",
+                })?;
+                cutoff_hz = Some(parse_positive(line, "frequency", value)?);
+                tokens.next_if_eq(&"Hz");
+            }
+            "Gain" => {
+                let value = tokens.next().ok_or(ApoError::MissingField {
+                    line,
+                    field: "gain",
+                })?;
+                gain_db = Some(parse_number(line, "gain", value)?);
+                tokens.next_if_eq(&"dB");
+            }
+            "Q" => {
+                let value = tokens
+                    .next()
+                    .ok_or(ApoError::MissingField { line, field: "Q" })?;
+                q = Some(parse_positive(line, "Q", value)?);
+            }
+            "BW" => {
+                tokens.next_if_eq(&"Oct");
+                let value = tokens.next().ok_or(ApoError::MissingField {
+                    line,
+                    field: "bandwidth",
+                })?;
+                let octaves = parse_positive(line, "bandwidth", value)?;
+                q = Some(bandwidth_to_q(octaves));
+            }
+            token => {
+                return Err(ApoError::UnexpectedToken {
+                    line,
+                    token: token.to_string(),
+                });
+            }
+        }
+    }
+
+    let require_cutoff = || {
+        cutoff_hz.ok_or(ApoError::MissingField {
+            line,
+            field: "frequency",
+        })
+    };
+    let require_gain = || {
+        gain_db.ok_or(ApoError::MissingField {
+            line,
+            field: "gain",
+        })
+    };
+    let require_q = || q.ok_or(ApoError::MissingField { line, field: "Q" });
+
+    let band = |band_type: BandType,
+                cutoff_hz: f32,
+                q: f32,
+                gain_db: f32|
+     -> Result<Option<Filter>, ApoError> {
+        Ok(Some(Filter::Band(BandParams {
+            enabled,
+            band_type,
+            cutoff_hz,
+            q,
+            gain_db,
+        })))
+    };
+
+    match filter_type.as_str() {
+        "None" => Ok(None),
+        "PK" | "Modal" | "PEQ" => band(
+            BandType::Bell,
+            require_cutoff()?,
+            require_q()?,
+            require_gain()?,
+        ),
+        "LS" | "LSC" | "LS 12dB" | "HS" | "HSC" | "HS 12dB" => {
+            let (band_type, freq) = match filter_type.as_str() {
+                "LS" => (BandType::LowShelf, ShelfFreq::Centre),
+                "LSC" | "LS 12dB" => (BandType::LowShelf, ShelfFreq::Corner),
+                "HS" => (BandType::HighShelf, ShelfFreq::Centre),
+                _ => (BandType::HighShelf, ShelfFreq::Corner),
+            };
+            let gain_db = require_gain()?;
+
+            band(
+                band_type,
+                shelf_cutoff_hz(band_type, freq, require_cutoff()?, gain_db),
+                q.unwrap_or(DEFAULT_Q),
+                gain_db,
+            )
+        }
+        "NO" => band(BandType::Notch, require_cutoff()?, require_q()?, 0.0),
+        "AP" => band(BandType::Allpass, require_cutoff()?, require_q()?, 0.0),
+        "LP" | "LPQ" | "HP" | "HPQ" => {
+            let kind = if filter_type.starts_with("LP") {
+                CutKind::Lowpass
+            } else {
+                CutKind::Highpass
+            };
+
+            Ok(Some(Filter::Cut(
+                kind,
+                CutSection {
+                    enabled,
+                    cutoff_hz: require_cutoff()?,
+                    q: q.unwrap_or(DEFAULT_Q),
+                },
+            )))
+        }
+        _ => Err(ApoError::UnsupportedFilterType { line, filter_type }),
+    }
+}
+
+fn parse_number(line: usize, field: &'static str, value: &str) -> Result<f32, ApoError> {
+    value
+        .parse::<f32>()
+        .ok()
+        .filter(|v| v.is_finite())
+        .ok_or_else(|| ApoError::InvalidValue {
+            line,
+            field,
+            value: value.to_string(),
+        })
+}
+
+fn parse_positive(line: usize, field: &'static str, value: &str) -> Result<f32, ApoError> {
+    let number = parse_number(line, field, value)?;
+    if number <= 0.0 {
+        return Err(ApoError::InvalidValue {
+            line,
+            field,
+            value: value.to_string(),
+        });
+    }
+
+    Ok(number)
+}
+
+/// Converts the frequency of an Equalizer APO shelf to the cutoff the DSP uses for it. See
+/// the module docs.
+fn shelf_cutoff_hz(band_type: BandType, freq: ShelfFreq, freq_hz: f32, gain_db: f32) -> f32 {
+    let corner_ratio = shelf_corner_ratio(gain_db);
+    let is_high = band_type == BandType::HighShelf;
+
+    let centre_hz = match (freq, is_high) {
+        (ShelfFreq::Centre, _) => freq_hz,
+        (ShelfFreq::Corner, false) => freq_hz * corner_ratio,
+        (ShelfFreq::Corner, true) => freq_hz / corner_ratio,
+    };
+
+    if is_high {
+        centre_hz * high_shelf_offset(gain_db)
+    } else {
+        centre_hz
+    }
+}
+
+/// The corner frequency of one of the DSP's shelves, as written for `LSC` and `HSC`.
+fn shelf_corner_hz(band: &BandParams) -> f32 {
+    let corner_ratio = shelf_corner_ratio(band.gain_db);
+
+    if band.band_type == BandType::HighShelf {
+        band.cutoff_hz / high_shelf_offset(band.gain_db) * corner_ratio
+    } else {
+        band.cutoff_hz / corner_ratio
+    }
+}
+
+/// How far a shelf's corner is from its centre. A cut is the mirror image of a boost, so
+/// only the size of the gain matters.
+fn shelf_corner_ratio(gain_db: f32) -> f32 {
+    10.0f32.powf(gain_db.abs().min(30.0) / 80.0)
+}
+
+/// How far the DSP's high shelf cutoff is above the shelf's centre. The gain is clamped like
+/// the DSP does.
+fn high_shelf_offset(gain_db: f32) -> f32 {
+    10.0f32.powf(gain_db.clamp(-30.0, 30.0) / 40.0)
+}
+
+/// Converts a bandwidth in octaves to Q.
+fn bandwidth_to_q(octaves: f32) -> f32 {
+    let n = 2.0f32.powf(octaves);
+    n.sqrt() / (n - 1.0)
+}
+
+/// The Q of each second-order section the DSP uses for a lowpass or highpass band, or `None`
+/// for a first-order filter.
+fn cut_section_qs(order: FilterOrder, q: f32) -> Option<Vec<f32>> {
+    let q = q as f64;
+    let sections = |butterworth_qs: &[f64], scale: f64| {
+        let q_norm = scale_q_norm_for_order(q_norm(q), scale);
+        butterworth_qs
+            .iter()
+            .map(|bq| (q_norm * bq) as f32)
+            .collect()
+    };
+
+    match order {
+        FilterOrder::X1 => None,
+        FilterOrder::X2 => Some(vec![q as f32]),
+        FilterOrder::X4 => Some(sections(&Q_BUTTERWORTH_ORD4, ORD4_Q_SCALE)),
+        FilterOrder::X6 => Some(sections(&Q_BUTTERWORTH_ORD6, ORD6_Q_SCALE)),
+        FilterOrder::X8 => Some(sections(&Q_BUTTERWORTH_ORD8, ORD8_Q_SCALE)),
+    }
+}
+
+/// The order and Q of the cut filter whose sections' Qs are closest to `section_qs` on a
+/// logarithmic scale, or `None` if there are too many sections. For the sections written by
+/// [`cut_section_qs()`], this gets the original order and Q back.
+fn cut_from_section_qs(section_qs: &[f32]) -> Option<(FilterOrder, f32)> {
+    let (order, butterworth_qs, scale): (_, &[f64], _) = match section_qs.len() {
+        1 => return Some((FilterOrder::X2, section_qs[0])),
+        2 => (FilterOrder::X4, &Q_BUTTERWORTH_ORD4, ORD4_Q_SCALE),
+        3 => (FilterOrder::X6, &Q_BUTTERWORTH_ORD6, ORD6_Q_SCALE),
+        4 => (FilterOrder::X8, &Q_BUTTERWORTH_ORD8, ORD8_Q_SCALE),
+        _ => return None,
+    };
+
+    // The Butterworth Qs are in ascending order, and scaling keeps them that way.
+    let mut section_qs: Vec<f64> = section_qs.iter().map(|&q| q as f64).collect();
+    section_qs.sort_by(f64::total_cmp);
+
+    let mean_log_ratio = section_qs
+        .iter()
+        .zip(butterworth_qs)
+        .map(|(q, butterworth_q)| (q / butterworth_q).ln())
+        .sum::<f64>()
+        / section_qs.len() as f64;
+    let scaled_q_norm = mean_log_ratio.exp();
+
+    // Undoes `scale_q_norm_for_order()`.
+    let q_norm = if scaled_q_norm > 1.0 {
+        1.0 + (scaled_q_norm - 1.0) / scale
+    } else {
+        scaled_q_norm
+    };
+
+    Some((order, (q_norm * Q_BUTTERWORTH_ORD2) as f32))
+}
+
+/// Formats a number without trailing zeros, e.g. `100`, `-2.8`, `0.7071`.
+fn format_number(value: f32) -> String {
+    let s = format!("{value:.4}");
+    s.trim_end_matches('0').trim_end_matches('.').to_string()
+}
@@ -0,0 +1,268 @@
+//! Uniformly partitioned FFT convolution of a stereo signal with a long FIR kernel.
+
+use num_complex::Complex32;
+use realfft::{ComplexToReal, RealFftPlanner, RealToComplex};
+use std::sync::Arc;
+
+/// Convolves a stereo signal with a kernel of up to `max_kernel_len` samples.
+///
+/// The kernel is split into partitions of `partition_len` samples that are applied in the
+/// frequency domain with overlap-save, which delays the output by `partition_len` samples
+/// on top of the kernel's own delay. Everything is allocated up front, so
+/// [`Convolver::set_kernel`] and [`Convolver::process`] are realtime-safe.
+///
+/// A new kernel takes effect at the next partition boundary, where the output of the old
+/// and the new kernel is crossfaded over one partition.
+pub struct Convolver {
+    partition_len: usize,
+    num_partitions: usize,
+
+    forward: Arc<dyn RealToComplex<f32>>,
+    inverse: Arc<dyn ComplexToReal<f32>>,
+
+    /// Two sets of kernel spectra, one per partition. One is in use, and the other receives
+    /// the next kernel.
+    kernels: [Vec<Vec<Complex32>>; 2],
+    active_kernel: usize,
+    kernel_pending: bool,
+
+    channels: [ChannelState; 2],
+    /// The position within the current partition, shared by both channels.
+    pos: usize,
+
+    fft_input: Vec<f32>,
+    fft_output: Vec<f32>,
+    spectrum: Vec<Complex32>,
+    scratch: Vec<Complex32>,
+}
+
+struct ChannelState {
+    /// The previous and the current partition of the input.
+    input: Vec<f32>,
+    /// The output for the current partition, computed at the end of the previous one.
+    output: Vec<f32>,
+    /// The spectra of the most recent input partitions, used as a ring buffer.
+    history: Vec<Vec<Complex32>>,
+    history_pos: usize,
+}
+
+impl Convolver {
+    pub fn new(max_kernel_len: usize, partition_len: usize) -> Self {
+        assert!(partition_len > 0, "the partition length must be positive");
+
+        let num_partitions = max_kernel_len.div_ceil(partition_len).max(1);
+        let fft_len = 2 * partition_len;
+        let num_bins = partition_len + 1;
+
+        let mut planner = RealFftPlanner::<f32>::new();
+        let forward = planner.plan_fft_forward(fft_len);
+        let inverse = planner.plan_fft_inverse(fft_len);
+        let scratch_len = forward.get_scratch_len().max(inverse.get_scratch_len());
+
+        let spectra = || vec![vec![Complex32::default(); num_bins]; num_partitions];
+        let channel = || ChannelState {
+            input: vec![0.0; fft_len],
+            output: vec![0.0; partition_len],
+            history: spectra(),
+            history_pos: 0,
+        };
+
+        let mut new_self = Self {
+            partition_len,
+            num_partitions,
+            forward,
+            inverse,
+            kernels: [spectra(), spectra()],
+            active_kernel: 0,
+            kernel_pending: false,
+            channels: [channel(), channel()],
+            pos: 0,
+            fft_input: vec![0.0; fft_len],
+            fft_output: vec![0.0; fft_len],
+            spectrum: vec![Complex32::default(); num_bins],
+            scratch: vec![Complex32::default(); scratch_len],
+        };
+
+        // Start out passing the signal through, delayed by the latency.
+        new_self.set_kernel(&[1.0]);
+        new_self.active_kernel = 1;
+        new_self.kernel_pending = false;
+
+        new_self
+    }
+
+    /// The delay added on top of the kernel's own delay.
+    pub fn latency_samples(&self) -> usize {
+        self.partition_len
+    }
+
+    /// The number of samples [`Convolver::process`] takes until the next partition boundary,
+    /// where a new kernel would take effect.
+    pub fn samples_until_partition(&self) -> usize {
+        self.partition_len - self.pos
+    }
+
+    /// The longest kernel that can be used.
+    pub fn max_kernel_len(&self) -> usize {
+        self.num_partitions * self.partition_len
+    }
+
+    /// Replaces the kernel from the next partition boundary on. Samples past
+    /// [`Convolver::max_kernel_len`] are ignored.
+    pub fn set_kernel(&mut self, kernel: &[f32]) {
+        // The inverse transform is not normalized, so that is folded into the kernel.
+        let scale = (2 * self.partition_len) as f32;
+
+        let next_kernel = &mut self.kernels[1 - self.active_kernel];
+        for (i, spectrum) in next_kernel.iter_mut().enumerate() {
+            let start = (i * self.partition_len).min(kernel.len());
+            let end = (start + self.partition_len).min(kernel.len());
+
+            self.fft_input.fill(0.0);
+            for (x, &k) in self.fft_input.iter_mut().zip(&kernel[start..end]) {
+                *x = k / scale;
+            }
+
+            // This can only fail for mismatched buffer lengths.
+            let _ =
+                self.forward
+                    .process_with_scratch(&mut self.fft_input, spectrum, &mut self.scratch);
+        }
+
+        self.kernel_pending = true;
+    }
+
+    /// Clears the signal history, keeping the kernel.
+    pub fn reset(&mut self) {
+        for channel in self.channels.iter_mut() {
+            channel.input.fill(0.0);
+            channel.output.fill(0.0);
+            for spectrum in channel.history.iter_mut() {
+                spectrum.fill(Complex32::default());
+            }
+        }
+        self.pos = 0;
+
+        if self.kernel_pending {
+            self.active_kernel = 1 - self.active_kernel;
+            self.kernel_pending = false;
+        }
+    }
+
+    pub fn process(&mut self, buf_l: &mut [f32], buf_r: &mut [f32]) {
+        let num_samples = buf_l.len().min(buf_r.len());
+        let mut done = 0;
+
+        while done < num_samples {
+            let len = (self.partition_len - self.pos).min(num_samples - done);
+            let range = self.pos..self.pos + len;
+            let input_range = self.partition_len + self.pos..self.partition_len + self.pos + len;
+
+            for (channel, buf) in self
+                .channels
+                .iter_mut()
+                .zip([&mut buf_l[done..done + len], &mut buf_r[done..done + len]])
+            {
+                channel.input[input_range.clone()].copy_from_slice(buf);
+                buf.copy_from_slice(&channel.output[range.clone()]);
+            }
+
+            self.pos += len;
+            done += len;
+
+            if self.pos == self.partition_len {
+                self.pos = 0;
+                self.process_partition();
+            }
+        }
+    }
+
+    /// Computes the output for the next partition from the input that just completed.
+    fn process_partition(&mut self) {
+        let fade = self.kernel_pending;
+        let next_kernel = 1 - self.active_kernel;
+
+        for channel in self.channels.iter_mut() {
+            channel.history_pos = (channel.history_pos + 1) % self.num_partitions;
+
+            self.fft_input.copy_from_slice(&channel.input);
+            let _ = self.forward.process_with_scratch(
+                &mut self.fft_input,
+                &mut channel.history[channel.history_pos],
+                &mut self.scratch,
+            );
+
+            // Overlap-save keeps the second half, so the current input becomes the first.
+            channel.input.copy_within(self.partition_len.., 0);
+
+            Self::convolve(
+                &channel.history,
+                channel.history_pos,
+                &self.kernels[self.active_kernel],
+                &mut self.spectrum,
+            );
+            let _ = self.inverse.process_with_scratch(
+                &mut self.spectrum,
+                &mut self.fft_output,
+                &mut self.scratch,
+            );
+            channel
+                .output
+                .copy_from_slice(&self.fft_output[self.partition_len..]);
+
+            if fade {
+                Self::convolve(
+                    &channel.history,
+                    channel.history_pos,
+                    &self.kernels[next_kernel],
+                    &mut self.spectrum,
+                );
+                let _ = self.inverse.process_with_scratch(
+                    &mut self.spectrum,
+                    &mut self.fft_output,
+                    &mut self.scratch,
+                );
+
+                let fade_step = (self.partition_len as f32).recip();
+                for (i, (out, &new)) in channel
+                    .output
+                    .iter_mut()
+                    .zip(&self.fft_output[self.partition_len..])
+                    .enumerate()
+                {
+                    let t = (i as f32 + 0.5) * fade_step;
+                    *out += (new - *out) * t;
+                }
+            }
+        }
+
+        if fade {
+            self.active_kernel = next_kernel;
+            self.kernel_pending = false;
+        }
+    }
+
+    /// Sums the products of the most recent input spectra with the kernel's partitions.
+    fn convolve(
+        history: &[Vec<Complex32>],
+        history_pos: usize,
+        kernel: &[Vec<Complex32>],
+        spectrum: &mut [Complex32],
+    ) {
+        spectrum.fill(Complex32::default());
+
+        for (i, kernel_partition) in kernel.iter().enumerate() {
+            let input = &history[(history_pos + history.len() - i) % history.len()];
+            for ((y, &x), &h) in spectrum.iter_mut().zip(input).zip(kernel_partition) {
+                *y += x * h;
+            }
+        }
+
+        // The imaginary parts at DC and Nyquist are rounding noise, and the inverse
+        // transform rejects anything that isn't zero there.
+        spectrum[0].im = 0.0;
+        if let Some(last) = spectrum.last_mut() {
+            last.im = 0.0;
+        }
+    }
+}
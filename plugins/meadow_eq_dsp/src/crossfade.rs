@@ -0,0 +1,115 @@
+//! Switching between two sets of parameters without a jump in the output.
+
+use crate::{EqParams, MeadowEqDsp};
+
+/// How long a crossfade takes.
+const CROSSFADE_MS: f64 = 20.0;
+
+/// Crossfades are processed in chunks of this many samples, so the old filters' output fits
+/// on the stack.
+const CHUNK_LEN: usize = 64;
+
+/// [`MeadowEqDsp`] that can crossfade from its current parameters to new ones, for switches
+/// that are too large to make in place, like jumping between A/B snapshots.
+///
+/// A crossfade starts from a copy of the filters including their state, so the old and the
+/// new coefficients run on the same history and neither side starts with a transient.
+/// Copying the filters does not allocate.
+#[derive(Clone)]
+pub struct CrossfadeEqDsp<const NUM_BANDS: usize> {
+    eq: MeadowEqDsp<NUM_BANDS>,
+    /// The filters with the old parameters while a crossfade is running.
+    outgoing: MeadowEqDsp<NUM_BANDS>,
+
+    fade_len: usize,
+    /// The position within the crossfade, `fade_len` when there is none.
+    fade_pos: usize,
+}
+
+impl<const NUM_BANDS: usize> CrossfadeEqDsp<NUM_BANDS> {
+    pub fn new(sample_rate: f64) -> Self {
+        let fade_len = ((CROSSFADE_MS * 0.001 * sample_rate).round() as usize).max(1);
+
+        Self {
+            eq: MeadowEqDsp::new(sample_rate),
+            outgoing: MeadowEqDsp::new(sample_rate),
+            fade_len,
+            fade_pos: fade_len,
+        }
+    }
+
+    pub fn sample_rate(&self) -> f64 {
+        self.eq.sample_rate()
+    }
+
+    /// The filters with the latest parameters.
+    pub fn eq(&self) -> &MeadowEqDsp<NUM_BANDS> {
+        &self.eq
+    }
+
+    pub fn fade_len(&self) -> usize {
+        self.fade_len
+    }
+
+    pub fn is_crossfading(&self) -> bool {
+        self.fade_pos < self.fade_len
+    }
+
+    /// Changes the parameters in place, like [`MeadowEqDsp::set_params`]. A running
+    /// crossfade continues towards the new parameters.
+    pub fn set_params(&mut self, params: EqParams<NUM_BANDS>) {
+        self.eq.set_params(params);
+    }
+
+    /// Fades from the current parameters to `params`. A crossfade that is still running is
+    /// cut short, so only the newest switch is faded.
+    pub fn crossfade_to(&mut self, params: EqParams<NUM_BANDS>) {
+        self.outgoing.clone_from(&self.eq);
+        self.eq.set_params(params);
+        self.fade_pos = 0;
+    }
+
+    /// Clears the filter states and ends any crossfade.
+    pub fn reset(&mut self) {
+        self.eq.reset();
+        self.fade_pos = self.fade_len;
+    }
+
+    pub fn process(&mut self, buf_l: &mut [f32], buf_r: &mut [f32]) {
+        let num_samples = buf_l.len().min(buf_r.len());
+        let fade_samples = (self.fade_len - self.fade_pos).min(num_samples);
+
+        let (fade_l, rest_l) = buf_l[..num_samples].split_at_mut(fade_samples);
+        let (fade_r, rest_r) = buf_r[..num_samples].split_at_mut(fade_samples);
+
+        let fade_step = (self.fade_len as f32).recip();
+        for (l, r) in fade_l
+            .chunks_mut(CHUNK_LEN)
+            .zip(fade_r.chunks_mut(CHUNK_LEN))
+        {
+            let mut old_l = [0.0; CHUNK_LEN];
+            let mut old_r = [0.0; CHUNK_LEN];
+            let (old_l, old_r) = (&mut old_l[..l.len()], &mut old_r[..r.len()]);
+            old_l.copy_from_slice(l);
+            old_r.copy_from_slice(r);
+
+            self.outgoing.process(old_l, old_r);
+            self.eq.process(l, r);
+
+            for ((l, r), (&old_l, &old_r)) in l
+                .iter_mut()
+                .zip(r.iter_mut())
+                .zip(old_l.iter().zip(old_r.iter()))
+            {
+                let t = (self.fade_pos as f32 + 0.5) * fade_step;
+                *l = old_l + (*l - old_l) * t;
+                *r = old_r + (*r - old_r) * t;
+                self.fade_pos += 1;
+            }
+        }
+
+        if !rest_l.is_empty() {
+            self.eq.process(rest_l, rest_r);
+        }
+    }
+}
@@ -1,6 +1,24 @@
 use arrayvec::ArrayVec;
+use num_complex::Complex64;
 use std::f64::consts::PI;
 
+pub mod apo;
+#[cfg(any(feature = "natural-phase", feature = "linear-phase"))]
+pub mod convolver;
+pub mod crossfade;
+#[cfg(feature = "linear-phase")]
+pub mod linear_phase;
+#[cfg(feature = "match-eq")]
+pub mod match_eq;
+#[cfg(feature = "natural-phase")]
+pub mod natural_phase;
+#[cfg(feature = "presets")]
+pub mod preset;
+pub mod sidechain_filter;
+
+#[cfg(test)]
+mod tests;
+
 pub const DEFAULT_Q: f32 = Q_BUTTERWORTH_ORD2 as f32;
 
 const Q_BUTTERWORTH_ORD2: f64 = 0.70710678118654752440;
@@ -22,9 +40,21 @@ const ORD6_Q_SCALE: f64 = 0.2;
 const ORD8_Q_SCALE: f64 = 0.14;
 
 const MAX_ONE_POLE_FILTERS: usize = 2;
-const MAX_SVF_FILTERS: usize = 8;
+/// The number of SVF sections in an 8th order cut filter.
+const MAX_CUT_SECTIONS: usize = 4;
+const MAX_SVF_FILTERS: usize = 16;
+
+/// The highest cutoff as a fraction of the sample rate. The cutoff range goes up to
+/// 21.48 kHz, which is above Nyquist at lower sample rates. Prewarping a cutoff past Nyquist
+/// flips the sign of `g` and makes the filter unstable.
+const MAX_CUTOFF_RATIO: f64 = 0.49;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "presets",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
 pub enum FilterOrder {
     X1 = 0,
     X2,
@@ -46,6 +76,11 @@ impl FilterOrder {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "presets",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
 pub enum BandType {
     Bell = 0,
     LowShelf,
@@ -67,8 +102,14 @@ impl BandType {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "presets",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
 pub struct BandParams {
     pub enabled: bool,
+    #[cfg_attr(feature = "presets", serde(rename = "type"))]
     pub band_type: BandType,
     pub cutoff_hz: f32,
     pub q: f32,
@@ -120,6 +161,7 @@ impl<const NUM_BANDS: usize> Default for EqParams<NUM_BANDS> {
     }
 }
 
+#[derive(Clone)]
 pub struct MeadowEqDsp<const NUM_BANDS: usize> {
     params: EqParams<NUM_BANDS>,
 
@@ -130,11 +172,21 @@ pub struct MeadowEqDsp<const NUM_BANDS: usize> {
 
     has_first_order_filter: bool,
 
+    sample_rate: f64,
     sample_rate_recip: f64,
 }
 
 impl<const NUM_BANDS: usize> MeadowEqDsp<NUM_BANDS> {
+    /// Both cut filters at 8th order plus every band need to fit in the fixed-size filter
+    /// list that `process` builds, so that it never has to allocate or panic.
+    const FITS_IN_FILTER_LIST: () = assert!(
+        2 * MAX_CUT_SECTIONS + NUM_BANDS <= MAX_SVF_FILTERS,
+        "too many bands for the SVF filter list"
+    );
+
     pub fn new(sample_rate: f64) -> Self {
+        let () = Self::FITS_IN_FILTER_LIST;
+
         let sample_rate_recip = sample_rate.recip();
 
         let params = EqParams::default();
@@ -145,10 +197,112 @@ impl<const NUM_BANDS: usize> MeadowEqDsp<NUM_BANDS> {
             hp_band: MultiOrderBand::new(FilterOrder::X2),
             bands: [SecondOrderBand::new(); NUM_BANDS],
             has_first_order_filter: false,
+            sample_rate,
             sample_rate_recip,
         }
     }
 
+    pub fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+
+    /// The complex frequency response of the whole EQ at the given frequency.
+    ///
+    /// This is evaluated from the exact coefficients that [`MeadowEqDsp::process`] uses, so
+    /// the result matches what is heard (including any warping near Nyquist).
+    pub fn response(&self, freq_hz: f64) -> Complex64 {
+        let z = self.z(freq_hz);
+
+        let mut h = Complex64::new(1.0, 0.0);
+
+        if self.lp_band.enabled {
+            h *= self.lp_band.response(z);
+        }
+        if self.hp_band.enabled {
+            h *= self.hp_band.response(z);
+        }
+        for band in self.bands.iter().filter(|b| b.enabled) {
+            h *= band.response(z);
+        }
+
+        h
+    }
+
+    /// The complex frequency response of the analog prototypes the filters are derived
+    /// from, at the given frequency.
+    ///
+    /// This is what the EQ would sound like without the frequency warping and phase
+    /// distortion of the digital filters near Nyquist.
+    pub fn analog_response(&self, freq_hz: f64) -> Complex64 {
+        let mut h = Complex64::new(1.0, 0.0);
+
+        if self.lp_band.enabled {
+            h *= self.lp_band.analog_response(
+                self.params.lp_cutoff_hz as f64,
+                freq_hz,
+                self.sample_rate_recip,
+            );
+        }
+        if self.hp_band.enabled {
+            h *= self.hp_band.analog_response(
+                self.params.hp_cutoff_hz as f64,
+                freq_hz,
+                self.sample_rate_recip,
+            );
+        }
+        for (band, band_params) in self.bands.iter().zip(self.params.bands.iter()) {
+            if band.enabled {
+                h *= band.response(analog_z(
+                    band_params.cutoff_hz as f64,
+                    freq_hz,
+                    self.sample_rate_recip,
+                ));
+            }
+        }
+
+        h
+    }
+
+    /// The magnitude response of the whole EQ at the given frequency in decibels.
+    pub fn magnitude_db(&self, freq_hz: f64) -> f64 {
+        amp_to_db(self.response(freq_hz).norm())
+    }
+
+    /// The complex frequency response of a single band at the given frequency.
+    ///
+    /// A disabled band has a flat response.
+    pub fn band_response(&self, band_index: usize, freq_hz: f64) -> Complex64 {
+        let band = &self.bands[band_index];
+
+        if band.enabled {
+            band.response(self.z(freq_hz))
+        } else {
+            Complex64::new(1.0, 0.0)
+        }
+    }
+
+    /// The complex frequency response of the lowpass band at the given frequency.
+    pub fn lp_response(&self, freq_hz: f64) -> Complex64 {
+        if self.lp_band.enabled {
+            self.lp_band.response(self.z(freq_hz))
+        } else {
+            Complex64::new(1.0, 0.0)
+        }
+    }
+
+    /// The complex frequency response of the highpass band at the given frequency.
+    pub fn hp_response(&self, freq_hz: f64) -> Complex64 {
+        if self.hp_band.enabled {
+            self.hp_band.response(self.z(freq_hz))
+        } else {
+            Complex64::new(1.0, 0.0)
+        }
+    }
+
+    fn z(&self, freq_hz: f64) -> Complex64 {
+        Complex64::from_polar(1.0, 2.0 * PI * freq_hz * self.sample_rate_recip)
+    }
+
     pub fn set_params(&mut self, params: EqParams<NUM_BANDS>) {
         if self.params != params {
             self.params = params;
@@ -252,6 +406,20 @@ impl<const NUM_BANDS: usize> MeadowEqDsp<NUM_BANDS> {
         }
     }
 
+    /// Clears the filter states, keeping the parameters.
+    pub fn reset(&mut self) {
+        for band in [&mut self.lp_band, &mut self.hp_band] {
+            band.one_pole_state = Default::default();
+            band.state_0 = Default::default();
+            band.state_1 = Default::default();
+            band.state_2 = Default::default();
+            band.state_3 = Default::default();
+        }
+        for band in self.bands.iter_mut() {
+            band.state = Default::default();
+        }
+    }
+
     pub fn process(&mut self, buf_l: &mut [f32], buf_r: &mut [f32]) {
         let mut one_pole_filters: ArrayVec<
             (OnePoleCoeff, [OnePoleState; 2]),
@@ -275,21 +443,15 @@ impl<const NUM_BANDS: usize> MeadowEqDsp<NUM_BANDS> {
             return;
         }
 
-        match one_pole_filters.len() {
-            0 => {}
-            1 => {
-                let (coeff, state) = &mut one_pole_filters[0];
-
+        match one_pole_filters.as_mut_slice() {
+            [] => {}
+            [(coeff, state)] => {
                 for (buf_l, buf_r) in buf_l.iter_mut().zip(buf_r.iter_mut()) {
                     *buf_l = state[0].tick(*buf_l, coeff);
                     *buf_r = state[1].tick(*buf_r, coeff);
                 }
             }
-            2 => {
-                let (f0, f1) = one_pole_filters.split_first_mut().unwrap();
-                let (coeff_0, state_0) = f0;
-                let (coeff_1, state_1) = &mut f1[0];
-
+            [(coeff_0, state_0), (coeff_1, state_1), ..] => {
                 for (buf_l, buf_r) in buf_l.iter_mut().zip(buf_r.iter_mut()) {
                     let l = state_0[0].tick(*buf_l, coeff_0);
                     let r = state_0[1].tick(*buf_r, coeff_0);
@@ -298,7 +460,6 @@ impl<const NUM_BANDS: usize> MeadowEqDsp<NUM_BANDS> {
                     *buf_r = state_1[1].tick(r, coeff_1);
                 }
             }
-            _ => unreachable!(),
         }
 
         if !svf_filters.is_empty() {
@@ -341,8 +502,13 @@ impl<const NUM_BANDS: usize> MeadowEqDsp<NUM_BANDS> {
     }
 }
 
+/// The coefficients of a second order state variable filter, for use with [`SvfState`].
+///
+/// The structure stays stable when the coefficients change every sample, so bands can be
+/// modulated by recomputing them. The `*_prewarped` constructors take the result of
+/// [`prewarp`], which saves a `tan()` when only the gain changes.
 #[derive(Default, Clone, Copy)]
-struct SvfCoeff {
+pub struct SvfCoeff {
     a1: f32,
     a2: f32,
     a3: f32,
@@ -353,7 +519,7 @@ struct SvfCoeff {
 }
 
 impl SvfCoeff {
-    fn lowpass_ord2(cutoff_hz: f64, sample_rate_recip: f64, q: f64) -> Self {
+    pub fn lowpass_ord2(cutoff_hz: f64, sample_rate_recip: f64, q: f64) -> Self {
         let g = g(cutoff_hz, sample_rate_recip);
         let k = 1.0 / q;
 
@@ -396,7 +562,7 @@ impl SvfCoeff {
         })
     }
 
-    fn highpass_ord2(cutoff_hz: f64, sample_rate_recip: f64, q: f64) -> Self {
+    pub fn highpass_ord2(cutoff_hz: f64, sample_rate_recip: f64, q: f64) -> Self {
         let g = g(cutoff_hz, sample_rate_recip);
         let k = 1.0 / q;
 
@@ -439,47 +605,92 @@ impl SvfCoeff {
         })
     }
 
-    fn notch(cutoff_hz: f64, sample_rate_recip: f64, q: f64) -> Self {
+    /// A bandpass with unity gain at the cutoff.
+    pub fn bandpass(cutoff_hz: f64, sample_rate_recip: f64, q: f64) -> Self {
+        let g = g(cutoff_hz, sample_rate_recip);
+        let k = 1.0 / q;
+
+        Self::from_g_and_k(g, k, 0.0, k, 0.0)
+    }
+
+    pub fn notch(cutoff_hz: f64, sample_rate_recip: f64, q: f64) -> Self {
         let g = g(cutoff_hz, sample_rate_recip);
         let k = 1.0 / q;
 
         Self::from_g_and_k(g, k, 1.0, -k, 0.0)
     }
 
-    fn bell(cutoff_hz: f64, sample_rate_recip: f64, q: f64, gain_db: f64) -> Self {
-        let a = gain_db_to_a(gain_db);
+    pub fn bell(cutoff_hz: f64, sample_rate_recip: f64, q: f64, gain_db: f64) -> Self {
+        Self::bell_prewarped(g(cutoff_hz, sample_rate_recip), q, gain_db)
+    }
 
-        let g = g(cutoff_hz, sample_rate_recip);
+    pub fn bell_prewarped(g: f64, q: f64, gain_db: f64) -> Self {
+        let a = gain_db_to_a(gain_db);
         let k = 1.0 / (q * a);
 
         Self::from_g_and_k(g, k, 1.0, k * (a * a - 1.0), 0.0)
     }
 
-    fn low_shelf(cutoff_hz: f64, sample_rate_recip: f64, q: f64, gain_db: f64) -> Self {
+    pub fn low_shelf(cutoff_hz: f64, sample_rate_recip: f64, q: f64, gain_db: f64) -> Self {
+        Self::low_shelf_prewarped(g(cutoff_hz, sample_rate_recip), q, gain_db)
+    }
+
+    pub fn low_shelf_prewarped(g: f64, q: f64, gain_db: f64) -> Self {
         let a = gain_db_to_a(gain_db);
 
-        let g = (PI * cutoff_hz * sample_rate_recip).tan() / a.sqrt();
+        let g = g / a.sqrt();
         let k = 1.0 / q;
 
         Self::from_g_and_k(g, k, 1.0, k * (a - 1.0), a * a - 1.0)
     }
 
-    fn high_shelf(cutoff_hz: f64, sample_rate_recip: f64, q: f64, gain_db: f64) -> Self {
+    pub fn high_shelf(cutoff_hz: f64, sample_rate_recip: f64, q: f64, gain_db: f64) -> Self {
+        Self::high_shelf_prewarped(g(cutoff_hz, sample_rate_recip), q, gain_db)
+    }
+
+    pub fn high_shelf_prewarped(g: f64, q: f64, gain_db: f64) -> Self {
         let a = gain_db_to_a(gain_db);
 
-        let g = (PI * cutoff_hz * sample_rate_recip).tan() / a.sqrt();
+        let g = g / a.sqrt();
         let k = 1.0 / q;
 
         Self::from_g_and_k(g, k, a * a, k * (1.0 - a) * a, 1.0 - a * a)
     }
 
-    fn allpass(cutoff_hz: f64, sample_rate_recip: f64, q: f64) -> Self {
+    pub fn allpass(cutoff_hz: f64, sample_rate_recip: f64, q: f64) -> Self {
         let g = g(cutoff_hz, sample_rate_recip);
         let k = 1.0 / q;
 
         Self::from_g_and_k(g, k, 1.0, -2.0 * k, 0.0)
     }
 
+    /// Evaluates the transfer function of the discretized filter at the point `z` on the
+    /// unit circle.
+    ///
+    /// This is derived from the state-space form of [`SvfState::tick`] using the quantized
+    /// coefficients, so it describes exactly the filter that runs.
+    fn response(&self, z: Complex64) -> Complex64 {
+        let a1 = self.a1 as f64;
+        let a2 = self.a2 as f64;
+        let a3 = self.a3 as f64;
+        let m0 = self.m0 as f64;
+        let m1 = self.m1 as f64;
+        let m2 = self.m2 as f64;
+
+        // State transition, input, output, and feedthrough matrices for the state vector
+        // `[ic1eq, ic2eq]`.
+        let (a11, a12, a21, a22) = (2.0 * a1 - 1.0, -2.0 * a2, 2.0 * a2, 1.0 - 2.0 * a3);
+        let (b1, b2) = (2.0 * a2, 2.0 * a3);
+        let (c1, c2) = (m1 * a1 + m2 * a2, -m1 * a2 + m2 * (1.0 - a3));
+        let d = m0 + m1 * a2 + m2 * a3;
+
+        let det = (z - a11) * (z - a22) - a12 * a21;
+        let x1 = ((z - a22) * b1 + a12 * b2) / det;
+        let x2 = (a21 * b1 + (z - a11) * b2) / det;
+
+        x1 * c1 + x2 * c2 + d
+    }
+
     fn from_g_and_k(g: f64, k: f64, m0: f64, m1: f64, m2: f64) -> Self {
         let a1 = 1.0 / (1.0 + g * (g + k));
         let a2 = g * a1;
@@ -497,13 +708,40 @@ impl SvfCoeff {
 }
 
 fn g(cutoff_hz: f64, sample_rate_recip: f64) -> f64 {
-    (PI * cutoff_hz * sample_rate_recip).tan()
+    (PI * (cutoff_hz * sample_rate_recip).min(MAX_CUTOFF_RATIO)).tan()
+}
+
+/// The prewarped cutoff taken by the `SvfCoeff::*_prewarped` constructors.
+pub fn prewarp(cutoff_hz: f64, sample_rate_recip: f64) -> f64 {
+    g(cutoff_hz, sample_rate_recip)
+}
+
+/// The point on the unit circle where a filter prewarped at `cutoff_hz` has the response
+/// its analog prototype has at `freq_hz`.
+///
+/// The bilinear transform maps the analog frequency `tan(pi f / fs) / tan(pi fc / fs)`
+/// (normalized to the cutoff) to `f`, so undoing that mapping gives the prototype's response
+/// from the exact digital coefficients.
+fn analog_z(cutoff_hz: f64, freq_hz: f64, sample_rate_recip: f64) -> Complex64 {
+    let cutoff_ratio = (cutoff_hz * sample_rate_recip).min(MAX_CUTOFF_RATIO);
+    let normalized_freq = freq_hz * sample_rate_recip / cutoff_ratio;
+    let warped_ratio = ((PI * cutoff_ratio).tan() * normalized_freq).atan() / PI;
+
+    Complex64::from_polar(1.0, 2.0 * PI * warped_ratio)
 }
 
 fn q_norm(q: f64) -> f64 {
     q * (1.0 / Q_BUTTERWORTH_ORD2)
 }
 
+fn amp_to_db(amp: f64) -> f64 {
+    if amp > 1e-12 {
+        20.0 * amp.log10()
+    } else {
+        -240.0
+    }
+}
+
 fn gain_db_to_a(gain_db: f64) -> f64 {
     10.0f64.powf(gain_db.clamp(-30.0, 30.0) / 40.0)
 }
@@ -516,15 +754,16 @@ fn scale_q_norm_for_order(q_norm: f64, scale: f64) -> f64 {
     }
 }
 
+/// The state of one channel of a state variable filter.
 #[derive(Default, Clone, Copy)]
-struct SvfState {
+pub struct SvfState {
     ic1eq: f32,
     ic2eq: f32,
 }
 
 impl SvfState {
     #[inline(always)]
-    fn tick(&mut self, input: f32, coeff: &SvfCoeff) -> f32 {
+    pub fn tick(&mut self, input: f32, coeff: &SvfCoeff) -> f32 {
         let v3 = input - self.ic2eq;
         let v1 = coeff.a1 * self.ic1eq + coeff.a2 * v3;
         let v2 = self.ic2eq + coeff.a2 * self.ic1eq + coeff.a3 * v3;
@@ -568,6 +807,16 @@ impl OnePoleCoeff {
             m1: -1.0,
         }
     }
+
+    fn response(&self, z: Complex64) -> Complex64 {
+        let lp = self.a0 as f64 / (1.0 - self.b1 as f64 / z);
+        lp * self.m1 as f64 + self.m0 as f64
+    }
+
+    fn analog_response(&self, cutoff_hz: f64, freq_hz: f64) -> Complex64 {
+        let lp = 1.0 / Complex64::new(1.0, freq_hz / cutoff_hz);
+        lp * self.m1 as f64 + self.m0 as f64
+    }
 }
 
 #[derive(Default, Clone, Copy)]
@@ -610,6 +859,10 @@ impl<const NUM_CHANNELS: usize> SecondOrderBand<NUM_CHANNELS> {
         self.state = svf_filters[*svf_filter_i].1;
         *svf_filter_i += 1;
     }
+
+    fn response(&self, z: Complex64) -> Complex64 {
+        self.coeff.response(z)
+    }
 }
 
 impl<const NUM_CHANNELS: usize> SecondOrderBand<NUM_CHANNELS> {
@@ -622,6 +875,7 @@ impl<const NUM_CHANNELS: usize> SecondOrderBand<NUM_CHANNELS> {
     }
 }
 
+#[derive(Clone)]
 struct MultiOrderBand<const NUM_CHANNELS: usize> {
     enabled: bool,
     order: FilterOrder,
@@ -731,6 +985,30 @@ impl<const NUM_CHANNELS: usize> MultiOrderBand<NUM_CHANNELS> {
         }
     }
 
+    fn response(&self, z: Complex64) -> Complex64 {
+        match self.order {
+            FilterOrder::X1 => self.one_pole_coeff.response(z),
+            FilterOrder::X2 => self.coeff_0.response(z),
+            FilterOrder::X4 => self.coeff_0.response(z) * self.coeff_1.response(z),
+            FilterOrder::X6 => {
+                self.coeff_0.response(z) * self.coeff_1.response(z) * self.coeff_2.response(z)
+            }
+            FilterOrder::X8 => {
+                self.coeff_0.response(z)
+                    * self.coeff_1.response(z)
+                    * self.coeff_2.response(z)
+                    * self.coeff_3.response(z)
+            }
+        }
+    }
+
+    fn analog_response(&self, cutoff_hz: f64, freq_hz: f64, sample_rate_recip: f64) -> Complex64 {
+        match self.order {
+            FilterOrder::X1 => self.one_pole_coeff.analog_response(cutoff_hz, freq_hz),
+            _ => self.response(analog_z(cutoff_hz, freq_hz, sample_rate_recip)),
+        }
+    }
+
     fn set_ord1(&mut self, coeff: OnePoleCoeff) {
         self.order = FilterOrder::X1;
         self.one_pole_coeff = coeff;
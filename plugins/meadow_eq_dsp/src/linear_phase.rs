@@ -0,0 +1,154 @@
+//! A linear phase EQ, which applies the magnitude response of the analog prototypes with a
+//! long symmetric FIR kernel.
+//!
+//! Designing a kernel is too expensive for the audio thread, so this is split in two. A
+//! [`KernelDesigner`] turns a set of parameters into a kernel wherever there is time for
+//! that, and [`LinearPhaseEqDsp`] convolves the signal with the latest kernel it was given.
+
+use num_complex::Complex64;
+use realfft::{ComplexToReal, RealFftPlanner};
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::convolver::Convolver;
+use crate::{EqParams, MeadowEqDsp};
+
+/// The kernel length at 48 kHz, which gives a frequency resolution of about 6 Hz. It is
+/// scaled with the sample rate, so the resolution and the latency in seconds stay about the
+/// same.
+const KERNEL_LEN_48K: usize = 8192;
+
+/// The convolution's partitions are a sixteenth of the kernel.
+const PARTITIONS_PER_KERNEL: usize = 16;
+
+/// The length of the kernels used at `sample_rate`.
+pub fn kernel_len(sample_rate: f64) -> usize {
+    ((KERNEL_LEN_48K as f64 * sample_rate / 48_000.0).round() as usize)
+        .next_power_of_two()
+        .max(PARTITIONS_PER_KERNEL)
+}
+
+/// Designs linear phase kernels by frequency sampling.
+///
+/// The kernel's magnitude response is that of [`MeadowEqDsp::analog_response`], so there is
+/// no cramping near Nyquist. The phase is a pure delay of half the kernel length, which
+/// makes allpass bands do nothing. Designing allocates nothing.
+pub struct KernelDesigner<const NUM_BANDS: usize> {
+    eq: MeadowEqDsp<NUM_BANDS>,
+    kernel_len: usize,
+
+    inverse_fft: Arc<dyn ComplexToReal<f64>>,
+    spectrum: Vec<Complex64>,
+    impulse: Vec<f64>,
+    scratch: Vec<Complex64>,
+    window: Vec<f64>,
+}
+
+impl<const NUM_BANDS: usize> KernelDesigner<NUM_BANDS> {
+    pub fn new(sample_rate: f64) -> Self {
+        let kernel_len = kernel_len(sample_rate);
+
+        let inverse_fft = RealFftPlanner::<f64>::new().plan_fft_inverse(kernel_len);
+        let spectrum = inverse_fft.make_input_vec();
+        let impulse = inverse_fft.make_output_vec();
+        let scratch = inverse_fft.make_scratch_vec();
+
+        // A periodic Hann window, which is symmetric around the kernel's center.
+        let window = (0..kernel_len)
+            .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f64 / kernel_len as f64).cos())
+            .collect();
+
+        Self {
+            eq: MeadowEqDsp::new(sample_rate),
+            kernel_len,
+            inverse_fft,
+            spectrum,
+            impulse,
+            scratch,
+            window,
+        }
+    }
+
+    pub fn kernel_len(&self) -> usize {
+        self.kernel_len
+    }
+
+    /// Writes the kernel for `params` to the first [`KernelDesigner::kernel_len`] samples of
+    /// `kernel`.
+    pub fn design(&mut self, params: EqParams<NUM_BANDS>, kernel: &mut [f32]) {
+        self.eq.set_params(params);
+
+        let freq_step = self.eq.sample_rate() / self.kernel_len as f64;
+        for (i, bin) in self.spectrum.iter_mut().enumerate() {
+            let magnitude = self.eq.analog_response(i as f64 * freq_step).norm();
+
+            // A delay of half the kernel length flips the sign of every other bin.
+            *bin = Complex64::new(if i % 2 == 0 { magnitude } else { -magnitude }, 0.0);
+        }
+
+        // This can only fail for mismatched buffer lengths.
+        let _ = self.inverse_fft.process_with_scratch(
+            &mut self.spectrum,
+            &mut self.impulse,
+            &mut self.scratch,
+        );
+
+        let scale = (self.kernel_len as f64).recip();
+        for ((k, &x), &w) in kernel
+            .iter_mut()
+            .zip(self.impulse.iter())
+            .zip(self.window.iter())
+        {
+            *k = (x * scale * w) as f32;
+        }
+    }
+}
+
+/// Convolves a stereo signal with kernels from a [`KernelDesigner`] for the same sample
+/// rate. A new kernel is crossfaded in over a few milliseconds. Everything is allocated up
+/// front, so this is realtime-safe.
+pub struct LinearPhaseEqDsp {
+    convolver: Convolver,
+    kernel_len: usize,
+}
+
+impl LinearPhaseEqDsp {
+    /// Starts out with a kernel that only delays the signal.
+    pub fn new(sample_rate: f64) -> Self {
+        let kernel_len = kernel_len(sample_rate);
+
+        let mut new_self = Self {
+            convolver: Convolver::new(kernel_len, kernel_len / PARTITIONS_PER_KERNEL),
+            kernel_len,
+        };
+
+        let mut delay = vec![0.0; kernel_len / 2 + 1];
+        delay[kernel_len / 2] = 1.0;
+        new_self.set_kernel(&delay);
+        new_self.reset();
+
+        new_self
+    }
+
+    pub fn kernel_len(&self) -> usize {
+        self.kernel_len
+    }
+
+    /// The delay of the output in samples. This does not depend on the kernel.
+    pub fn latency_samples(&self) -> u32 {
+        (self.kernel_len / 2 + self.convolver.latency_samples()) as u32
+    }
+
+    pub fn set_kernel(&mut self, kernel: &[f32]) {
+        self.convolver.set_kernel(kernel);
+    }
+
+    /// Clears the delay line, keeping the kernel.
+    pub fn reset(&mut self) {
+        self.convolver.reset();
+    }
+
+    pub fn process(&mut self, buf_l: &mut [f32], buf_r: &mut [f32]) {
+        self.convolver.process(buf_l, buf_r);
+    }
+}
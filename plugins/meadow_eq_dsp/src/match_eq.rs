@@ -0,0 +1,403 @@
+//! An offline "match EQ" solver that fits [`EqParams`] so that one signal's spectrum matches
+//! another's.
+//!
+//! The fit is scored with [`MeadowEqDsp::magnitude_db`], so the resulting curve is exactly
+//! what the plugin plays, including any warping near Nyquist.
+
+use realfft::RealFftPlanner;
+use std::f64::consts::PI;
+
+use crate::{BandParams, BandType, DEFAULT_Q, EqParams, MeadowEqDsp};
+
+const ANALYSIS_FFT_SIZE: usize = 8192;
+const MIN_ANALYSIS_FFT_SIZE: usize = 256;
+
+/// The number of points on the logarithmic frequency grid the fit is evaluated on.
+const NUM_GRID_POINTS: usize = 240;
+
+const MIN_FIT_Q: f32 = 0.1;
+const MAX_FIT_Q: f32 = 10.0;
+
+/// The number of times the step sizes of the pattern search are halved.
+const NUM_REFINEMENTS: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum MatchEqError {
+    #[error("cannot analyze an empty signal")]
+    EmptySignal,
+    #[error("{requested} bands were requested, but the EQ only has {max}")]
+    TooManyBands { requested: usize, max: usize },
+    #[error("the frequency range {min_hz} Hz to {max_hz} Hz is empty or beyond Nyquist")]
+    InvalidFrequencyRange { min_hz: f64, max_hz: f64 },
+    #[error("the maximum gain must be zero or more, not {0} dB")]
+    InvalidMaxGain(f32),
+    #[error("the smoothing width must be zero or more, not {0} octaves")]
+    InvalidSmoothing(f64),
+}
+
+/// A long-term average magnitude spectrum.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spectrum {
+    /// Frequencies in ascending order.
+    pub freqs_hz: Vec<f64>,
+    /// The magnitude at each frequency in decibels.
+    pub magnitudes_db: Vec<f64>,
+}
+
+impl Spectrum {
+    /// Computes the average power spectrum of the given channels with Welch's method.
+    pub fn analyze(channels: &[&[f32]], sample_rate: f64) -> Result<Self, MatchEqError> {
+        let len = channels.iter().map(|c| c.len()).max().unwrap_or(0);
+        if len == 0 {
+            return Err(MatchEqError::EmptySignal);
+        }
+
+        let fft_size = if len >= ANALYSIS_FFT_SIZE {
+            ANALYSIS_FFT_SIZE
+        } else {
+            (len.next_power_of_two() / 2).max(MIN_ANALYSIS_FFT_SIZE)
+        };
+        let hop_size = fft_size / 2;
+
+        let fft = RealFftPlanner::<f64>::new().plan_fft_forward(fft_size);
+        let mut input = fft.make_input_vec();
+        let mut output = fft.make_output_vec();
+        let mut scratch = fft.make_scratch_vec();
+
+        let window: Vec<f64> = (0..fft_size)
+            .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f64 / fft_size as f64).cos())
+            .collect();
+
+        let mut power = vec![0.0f64; output.len()];
+        let mut num_frames = 0usize;
+
+        for channel in channels.iter().filter(|c| !c.is_empty()) {
+            let mut start = 0;
+            loop {
+                for (i, (x, w)) in input.iter_mut().zip(window.iter()).enumerate() {
+                    *x = channel.get(start + i).copied().unwrap_or(0.0) as f64 * w;
+                }
+
+                // The input length always matches the plan.
+                fft.process_with_scratch(&mut input, &mut output, &mut scratch)
+                    .unwrap();
+
+                for (p, bin) in power.iter_mut().zip(output.iter()) {
+                    *p += bin.norm_sqr();
+                }
+                num_frames += 1;
+
+                start += hop_size;
+                if start + fft_size > channel.len() {
+                    break;
+                }
+            }
+        }
+
+        let norm = 1.0 / num_frames as f64;
+        let bin_hz = sample_rate / fft_size as f64;
+
+        // Skip DC, which says nothing about the tonal balance.
+        let (freqs_hz, magnitudes_db) = power
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(i, p)| (i as f64 * bin_hz, 10.0 * (p * norm).max(1e-30).log10()))
+            .unzip();
+
+        Ok(Self {
+            freqs_hz,
+            magnitudes_db,
+        })
+    }
+
+    /// The magnitude at the given frequency, interpolated linearly on a logarithmic
+    /// frequency axis.
+    pub fn magnitude_db_at(&self, freq_hz: f64) -> f64 {
+        let i = self.freqs_hz.partition_point(|&f| f < freq_hz);
+
+        if i == 0 {
+            return self.magnitudes_db.first().copied().unwrap_or(0.0);
+        }
+        if i >= self.freqs_hz.len() {
+            return self.magnitudes_db.last().copied().unwrap_or(0.0);
+        }
+
+        let (f0, f1) = (self.freqs_hz[i - 1].ln(), self.freqs_hz[i].ln());
+        let t = (freq_hz.ln() - f0) / (f1 - f0);
+
+        self.magnitudes_db[i - 1] + (self.magnitudes_db[i] - self.magnitudes_db[i - 1]) * t
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MatchEqSettings {
+    /// The number of bands to fit. This must not exceed the number of bands of the EQ.
+    pub num_bands: usize,
+    /// Whether the lowest and highest band may be shelves rather than bells.
+    pub use_shelves: bool,
+    /// The width of the fractional-octave smoothing applied to the difference curve, in
+    /// octaves. `0.0` disables smoothing, and it must not be negative.
+    pub smoothing_octaves: f64,
+    /// The largest boost or cut any band may apply. This must not be negative.
+    pub max_gain_db: f32,
+    /// The range of frequencies the fit considers.
+    pub min_freq_hz: f64,
+    pub max_freq_hz: f64,
+}
+
+impl Default for MatchEqSettings {
+    fn default() -> Self {
+        Self {
+            num_bands: 6,
+            use_shelves: true,
+            smoothing_octaves: 1.0 / 3.0,
+            max_gain_db: 12.0,
+            min_freq_hz: 30.0,
+            max_freq_hz: 16_000.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchEqResult<const NUM_BANDS: usize> {
+    pub params: EqParams<NUM_BANDS>,
+    /// The smoothed, level-matched difference curve the EQ was fitted to, as
+    /// `(frequency in Hz, gain in dB)` pairs.
+    pub target: Vec<(f64, f64)>,
+    /// The root-mean-square deviation between the target and the fitted response in decibels.
+    pub rms_error_db: f64,
+}
+
+/// Fits an EQ that makes `source` sound like `reference`.
+///
+/// The overall level difference between the two spectra is removed before fitting, since
+/// that is the job of a gain control and not of an EQ.
+pub fn match_eq<const NUM_BANDS: usize>(
+    source: &Spectrum,
+    reference: &Spectrum,
+    settings: &MatchEqSettings,
+    sample_rate: f64,
+) -> Result<MatchEqResult<NUM_BANDS>, MatchEqError> {
+    if settings.num_bands > NUM_BANDS {
+        return Err(MatchEqError::TooManyBands {
+            requested: settings.num_bands,
+            max: NUM_BANDS,
+        });
+    }
+    if source.freqs_hz.is_empty() || reference.freqs_hz.is_empty() {
+        return Err(MatchEqError::EmptySignal);
+    }
+    if !(settings.min_freq_hz > 0.0
+        && settings.min_freq_hz < settings.max_freq_hz
+        && settings.max_freq_hz < sample_rate * 0.5)
+    {
+        return Err(MatchEqError::InvalidFrequencyRange {
+            min_hz: settings.min_freq_hz,
+            max_hz: settings.max_freq_hz,
+        });
+    }
+    if !(settings.max_gain_db >= 0.0 && settings.max_gain_db.is_finite()) {
+        return Err(MatchEqError::InvalidMaxGain(settings.max_gain_db));
+    }
+    if !(settings.smoothing_octaves >= 0.0 && settings.smoothing_octaves.is_finite()) {
+        return Err(MatchEqError::InvalidSmoothing(settings.smoothing_octaves));
+    }
+
+    let grid = log_grid(settings.min_freq_hz, settings.max_freq_hz);
+
+    let difference: Vec<f64> = grid
+        .iter()
+        .map(|&f| reference.magnitude_db_at(f) - source.magnitude_db_at(f))
+        .collect();
+    let mut target = smooth(&grid, &difference, settings.smoothing_octaves);
+    let mean = target.iter().sum::<f64>() / target.len() as f64;
+    for t in target.iter_mut() {
+        *t -= mean;
+    }
+
+    let mut fitter = Fitter {
+        dsp: MeadowEqDsp::new(sample_rate),
+        grid: &grid,
+        target: &target,
+        settings,
+    };
+
+    let mut params = EqParams::<NUM_BANDS>::default();
+
+    // Place the bands one by one where the remaining error is largest, then refine them all
+    // together so they can share the work.
+    for band_i in 0..settings.num_bands {
+        let residual = fitter.residual(&params);
+        let (peak_i, &peak_db) = residual
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.abs().total_cmp(&b.abs()))
+            .unwrap();
+
+        let is_last = band_i + 1 == settings.num_bands;
+        let band_type = if settings.use_shelves && settings.num_bands >= 3 && band_i == 0 {
+            BandType::LowShelf
+        } else if settings.use_shelves && settings.num_bands >= 3 && is_last {
+            BandType::HighShelf
+        } else {
+            BandType::Bell
+        };
+
+        let cutoff_hz = match band_type {
+            BandType::LowShelf => settings.min_freq_hz * 4.0,
+            BandType::HighShelf => settings.max_freq_hz / 4.0,
+            _ => grid[peak_i],
+        };
+        let gain_db = match band_type {
+            BandType::LowShelf => target[0],
+            BandType::HighShelf => target[target.len() - 1],
+            _ => peak_db,
+        };
+
+        params.bands[band_i] = BandParams {
+            enabled: true,
+            band_type,
+            cutoff_hz: cutoff_hz as f32,
+            q: if band_type == BandType::Bell {
+                1.0
+            } else {
+                DEFAULT_Q
+            },
+            gain_db: (gain_db as f32).clamp(-settings.max_gain_db, settings.max_gain_db),
+        };
+
+        fitter.refine(&mut params, &[band_i]);
+    }
+
+    let all_bands: Vec<usize> = (0..settings.num_bands).collect();
+    fitter.refine(&mut params, &all_bands);
+
+    let rms_error_db = (fitter.cost(&params) / grid.len() as f64).sqrt();
+
+    Ok(MatchEqResult {
+        params,
+        target: grid.iter().copied().zip(target.iter().copied()).collect(),
+        rms_error_db,
+    })
+}
+
+struct Fitter<'a, const NUM_BANDS: usize> {
+    dsp: MeadowEqDsp<NUM_BANDS>,
+    grid: &'a [f64],
+    target: &'a [f64],
+    settings: &'a MatchEqSettings,
+}
+
+impl<const NUM_BANDS: usize> Fitter<'_, NUM_BANDS> {
+    fn residual(&mut self, params: &EqParams<NUM_BANDS>) -> Vec<f64> {
+        self.dsp.set_params(*params);
+
+        self.grid
+            .iter()
+            .zip(self.target.iter())
+            .map(|(&f, &t)| t - self.dsp.magnitude_db(f))
+            .collect()
+    }
+
+    /// The sum of squared errors between the target and the response of `params`.
+    fn cost(&mut self, params: &EqParams<NUM_BANDS>) -> f64 {
+        self.dsp.set_params(*params);
+
+        self.grid
+            .iter()
+            .zip(self.target.iter())
+            .map(|(&f, &t)| (t - self.dsp.magnitude_db(f)).powi(2))
+            .sum()
+    }
+
+    /// Refines the frequency, gain, and Q of the given bands with a pattern search.
+    fn refine(&mut self, params: &mut EqParams<NUM_BANDS>, band_indices: &[usize]) {
+        let mut best_cost = self.cost(params);
+
+        // Frequency steps are in octaves, Q steps are in octaves of Q.
+        let mut freq_step = 0.5f32;
+        let mut gain_step = 2.0f32;
+        let mut q_step = 0.5f32;
+
+        for _ in 0..NUM_REFINEMENTS {
+            let mut improved = true;
+            while improved {
+                improved = false;
+
+                for &band_i in band_indices {
+                    for param in 0..3 {
+                        for direction in [1.0f32, -1.0] {
+                            let mut candidate = *params;
+                            let band = &mut candidate.bands[band_i];
+
+                            match param {
+                                0 => band.cutoff_hz *= (freq_step * direction).exp2(),
+                                1 => band.gain_db += gain_step * direction,
+                                _ => {
+                                    // Shelves keep a fixed slope.
+                                    if band.band_type != BandType::Bell {
+                                        continue;
+                                    }
+                                    band.q *= (q_step * direction).exp2();
+                                }
+                            }
+                            self.clamp_band(band);
+
+                            let cost = self.cost(&candidate);
+                            if cost < best_cost {
+                                best_cost = cost;
+                                *params = candidate;
+                                improved = true;
+                            }
+                        }
+                    }
+                }
+            }
+
+            freq_step *= 0.5;
+            gain_step *= 0.5;
+            q_step *= 0.5;
+        }
+    }
+
+    fn clamp_band(&self, band: &mut BandParams) {
+        band.cutoff_hz = band.cutoff_hz.clamp(
+            self.settings.min_freq_hz as f32,
+            self.settings.max_freq_hz as f32,
+        );
+        band.gain_db = band
+            .gain_db
+            .clamp(-self.settings.max_gain_db, self.settings.max_gain_db);
+        band.q = band.q.clamp(MIN_FIT_Q, MAX_FIT_Q);
+    }
+}
+
+fn log_grid(min_freq_hz: f64, max_freq_hz: f64) -> Vec<f64> {
+    let ratio = max_freq_hz / min_freq_hz;
+
+    (0..NUM_GRID_POINTS)
+        .map(|i| min_freq_hz * ratio.powf(i as f64 / (NUM_GRID_POINTS - 1) as f64))
+        .collect()
+}
+
+/// Fractional-octave smoothing of a curve sampled on a logarithmic grid.
+fn smooth(grid: &[f64], values: &[f64], width_octaves: f64) -> Vec<f64> {
+    if width_octaves <= 0.0 {
+        return values.to_vec();
+    }
+
+    let half_width = width_octaves * 0.5;
+
+    grid.iter()
+        .map(|&center| {
+            let (sum, count) = grid
+                .iter()
+                .zip(values.iter())
+                .filter(|(f, _)| (*f / center).log2().abs() <= half_width)
+                .fold((0.0, 0usize), |(sum, count), (_, v)| (sum + v, count + 1));
+
+            sum / count as f64
+        })
+        .collect()
+}
@@ -0,0 +1,221 @@
+//! A natural phase mode, in which the EQ follows the magnitude and phase of the analog
+//! prototypes up to 20 kHz.
+//!
+//! The SVF filters are bilinear transforms, so their response is squeezed towards Nyquist
+//! and their phase differs from the analog filters in the upper octaves. This runs the
+//! regular filters, followed by a short FIR kernel that corrects the difference. The FIR
+//! kernel is centered in its window, so the corrected EQ is delayed by a fixed number of
+//! samples, which must be reported to the host.
+
+use num_complex::Complex64;
+use realfft::{ComplexToReal, RealFftPlanner};
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::convolver::Convolver;
+use crate::crossfade::CrossfadeEqDsp;
+use crate::{EqParams, MeadowEqDsp};
+
+/// The highest frequency that is corrected. Above that the correction is held constant.
+pub const MAX_CORRECTION_HZ: f64 = 20_000.0;
+
+/// The limit for [`MAX_CORRECTION_HZ`] at low sample rates, which keeps the correction
+/// away from Nyquist where the digital lowpass filters have their zeros.
+const MAX_CORRECTION_RATIO: f64 = 0.46;
+
+/// The largest boost the correction applies. The digital cut filters reach far lower
+/// levels near Nyquist than the analog ones, and correcting that completely would only
+/// amplify noise.
+const MAX_CORRECTION_DB: f64 = 24.0;
+
+/// The kernel length at 48 kHz. It is scaled with the sample rate, so the frequency
+/// resolution and the latency in seconds stay about the same.
+const KERNEL_LEN_48K: usize = 256;
+
+/// The convolution's partitions are a quarter of the kernel.
+const PARTITIONS_PER_KERNEL: usize = 4;
+
+/// [`MeadowEqDsp`] with a correction stage that matches its response to the analog
+/// prototypes. See the [module documentation](self) for how this works.
+///
+/// The correction kernel is redesigned whenever the parameters have changed, but at most
+/// once per convolution partition since that is how often the convolver can switch kernels.
+/// That keeps changing the parameters every sample cheap. All buffers are allocated up
+/// front, so this is realtime-safe.
+pub struct NaturalPhaseEqDsp<const NUM_BANDS: usize> {
+    eq: CrossfadeEqDsp<NUM_BANDS>,
+    params: EqParams<NUM_BANDS>,
+
+    convolver: Convolver,
+    kernel_len: usize,
+    /// Whether the parameters changed since the kernel was designed.
+    kernel_outdated: bool,
+    max_correction_hz: f64,
+
+    inverse_fft: Arc<dyn ComplexToReal<f64>>,
+    spectrum: Vec<Complex64>,
+    impulse: Vec<f64>,
+    scratch: Vec<Complex64>,
+    window: Vec<f64>,
+    kernel: Vec<f32>,
+}
+
+impl<const NUM_BANDS: usize> NaturalPhaseEqDsp<NUM_BANDS> {
+    pub fn new(sample_rate: f64) -> Self {
+        let kernel_len = ((KERNEL_LEN_48K as f64 * sample_rate / 48_000.0).round() as usize)
+            .next_power_of_two()
+            .max(PARTITIONS_PER_KERNEL);
+
+        let inverse_fft = RealFftPlanner::<f64>::new().plan_fft_inverse(kernel_len);
+        let spectrum = inverse_fft.make_input_vec();
+        let impulse = inverse_fft.make_output_vec();
+        let scratch = inverse_fft.make_scratch_vec();
+
+        // A periodic Hann window, which peaks at the kernel's center.
+        let window = (0..kernel_len)
+            .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f64 / kernel_len as f64).cos())
+            .collect();
+
+        let mut new_self = Self {
+            eq: CrossfadeEqDsp::new(sample_rate),
+            params: EqParams::default(),
+            convolver: Convolver::new(kernel_len, kernel_len / PARTITIONS_PER_KERNEL),
+            kernel_len,
+            kernel_outdated: false,
+            max_correction_hz: MAX_CORRECTION_HZ.min(MAX_CORRECTION_RATIO * sample_rate),
+            inverse_fft,
+            spectrum,
+            impulse,
+            scratch,
+            window,
+            kernel: vec![0.0; kernel_len],
+        };
+        new_self.update_kernel();
+        new_self.convolver.reset();
+
+        new_self
+    }
+
+    pub fn sample_rate(&self) -> f64 {
+        self.eq.sample_rate()
+    }
+
+    /// The delay of the corrected output in samples. This does not depend on the
+    /// parameters.
+    pub fn latency_samples(&self) -> u32 {
+        (self.kernel_len / 2 + self.convolver.latency_samples()) as u32
+    }
+
+    /// The filters without the correction stage, for computing their responses.
+    pub fn eq(&self) -> &MeadowEqDsp<NUM_BANDS> {
+        self.eq.eq()
+    }
+
+    pub fn set_params(&mut self, params: EqParams<NUM_BANDS>) {
+        if self.params != params {
+            self.params = params;
+            self.eq.set_params(params);
+            self.kernel_outdated = true;
+        }
+    }
+
+    /// Like [`NaturalPhaseEqDsp::set_params`], but crossfades the filters the way
+    /// [`CrossfadeEqDsp::crossfade_to`] does. The correction kernel is faded in over one
+    /// convolution partition as usual.
+    pub fn crossfade_to(&mut self, params: EqParams<NUM_BANDS>) {
+        if self.params != params {
+            self.params = params;
+            self.eq.crossfade_to(params);
+            self.kernel_outdated = true;
+        }
+    }
+
+    pub fn is_crossfading(&self) -> bool {
+        self.eq.is_crossfading()
+    }
+
+    /// Clears the filter and delay line states, keeping the parameters.
+    pub fn reset(&mut self) {
+        if self.kernel_outdated {
+            self.update_kernel();
+        }
+
+        self.eq.reset();
+        self.convolver.reset();
+    }
+
+    pub fn process(&mut self, buf_l: &mut [f32], buf_r: &mut [f32]) {
+        // The kernel only has to be ready by the time the convolver reaches the next
+        // partition.
+        let num_samples = buf_l.len().min(buf_r.len());
+        if self.kernel_outdated && num_samples >= self.convolver.samples_until_partition() {
+            self.update_kernel();
+        }
+
+        self.eq.process(buf_l, buf_r);
+        self.convolver.process(buf_l, buf_r);
+    }
+
+    /// Designs the correction kernel by frequency sampling the ratio of the analog and the
+    /// digital response, delayed by half the kernel length.
+    fn update_kernel(&mut self) {
+        self.kernel_outdated = false;
+
+        let freq_step = self.eq.sample_rate() / self.kernel_len as f64;
+        let max_correction = correction(self.eq.eq(), self.max_correction_hz);
+
+        for (i, bin) in self.spectrum.iter_mut().enumerate() {
+            let freq_hz = i as f64 * freq_step;
+            let correction = if freq_hz < self.max_correction_hz {
+                correction(self.eq.eq(), freq_hz)
+            } else {
+                max_correction
+            };
+
+            // A delay of half the kernel length flips the sign of every other bin.
+            *bin = if i % 2 == 0 { correction } else { -correction };
+        }
+        // The response of a real kernel is real at DC and Nyquist.
+        self.spectrum[0].im = 0.0;
+        if let Some(last) = self.spectrum.last_mut() {
+            last.im = 0.0;
+        }
+
+        // This can only fail for mismatched buffer lengths.
+        let _ = self.inverse_fft.process_with_scratch(
+            &mut self.spectrum,
+            &mut self.impulse,
+            &mut self.scratch,
+        );
+
+        let scale = (self.kernel_len as f64).recip();
+        for ((k, &x), &w) in self
+            .kernel
+            .iter_mut()
+            .zip(self.impulse.iter())
+            .zip(self.window.iter())
+        {
+            *k = (x * scale * w) as f32;
+        }
+
+        self.convolver.set_kernel(&self.kernel);
+    }
+}
+
+/// The response that turns the digital EQ into its analog prototype at `freq_hz`, with the
+/// boost limited to [`MAX_CORRECTION_DB`].
+fn correction<const NUM_BANDS: usize>(eq: &MeadowEqDsp<NUM_BANDS>, freq_hz: f64) -> Complex64 {
+    let digital = eq.response(freq_hz);
+    // Both responses are zero at the center of a notch.
+    if digital.norm() < 1e-12 {
+        return Complex64::new(1.0, 0.0);
+    }
+
+    let ratio = eq.analog_response(freq_hz) / digital;
+    let max_gain = 10.0f64.powf(MAX_CORRECTION_DB / 20.0);
+    if ratio.norm() > max_gain {
+        ratio * (max_gain / ratio.norm())
+    } else {
+        ratio
+    }
+}
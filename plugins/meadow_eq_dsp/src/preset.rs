@@ -0,0 +1,203 @@
+//! A versioned, human-readable preset format for [`EqParams`].
+//!
+//! Presets are stored as JSON. The number of bands in a preset does not have to match the
+//! number of bands of the EQ it is loaded into, as long as every enabled band fits.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::{BandParams, EqParams, FilterOrder};
+
+/// The version written by [`EqPreset::to_json`]. Bump this whenever the format changes in a
+/// way older versions of the plugin cannot read.
+pub const PRESET_FORMAT_VERSION: u32 = 1;
+
+/// The file extension used for preset files.
+pub const PRESET_FILE_EXTENSION: &str = "json";
+
+#[derive(Debug, thiserror::Error)]
+pub enum PresetError {
+    #[error(
+        "preset format version {0} is newer than the supported version {PRESET_FORMAT_VERSION}"
+    )]
+    UnsupportedVersion(u32),
+    #[error("preset has {enabled} enabled bands, but only {max} are available")]
+    TooManyBands { enabled: usize, max: usize },
+    #[error("invalid preset: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("could not access preset file: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EqPreset {
+    pub format_version: u32,
+    pub name: String,
+
+    #[serde(default)]
+    pub lp: CutFilterPreset,
+    #[serde(default = "CutFilterPreset::default_hp")]
+    pub hp: CutFilterPreset,
+
+    #[serde(default)]
+    pub bands: Vec<BandParams>,
+}
+
+/// The settings of the lowpass or highpass band.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CutFilterPreset {
+    pub enabled: bool,
+    pub cutoff_hz: f32,
+    pub q: f32,
+    pub order: FilterOrder,
+}
+
+impl Default for CutFilterPreset {
+    fn default() -> Self {
+        let params = EqParams::<0>::default();
+
+        Self {
+            enabled: params.lp_enabled,
+            cutoff_hz: params.lp_cutoff_hz,
+            q: params.lp_q,
+            order: params.lp_order,
+        }
+    }
+}
+
+impl CutFilterPreset {
+    fn default_hp() -> Self {
+        let params = EqParams::<0>::default();
+
+        Self {
+            enabled: params.hp_enabled,
+            cutoff_hz: params.hp_cutoff_hz,
+            q: params.hp_q,
+            order: params.hp_order,
+        }
+    }
+}
+
+impl EqPreset {
+    pub fn from_params<const NUM_BANDS: usize>(
+        name: impl Into<String>,
+        params: &EqParams<NUM_BANDS>,
+    ) -> Self {
+        Self {
+            format_version: PRESET_FORMAT_VERSION,
+            name: name.into(),
+            lp: CutFilterPreset {
+                enabled: params.lp_enabled,
+                cutoff_hz: params.lp_cutoff_hz,
+                q: params.lp_q,
+                order: params.lp_order,
+            },
+            hp: CutFilterPreset {
+                enabled: params.hp_enabled,
+                cutoff_hz: params.hp_cutoff_hz,
+                q: params.hp_q,
+                order: params.hp_order,
+            },
+            bands: params.bands.to_vec(),
+        }
+    }
+
+    /// Converts this preset into parameters for an EQ with `NUM_BANDS` bands.
+    ///
+    /// If the preset has no more bands than the EQ, every band keeps its slot. Otherwise only
+    /// the enabled bands are kept, packed into the first slots in the order they appear. The
+    /// slots left over get their band from `defaults`, which should be the EQ's own defaults.
+    pub fn to_params<const NUM_BANDS: usize>(
+        &self,
+        defaults: &EqParams<NUM_BANDS>,
+    ) -> Result<EqParams<NUM_BANDS>, PresetError> {
+        if self.format_version > PRESET_FORMAT_VERSION {
+            return Err(PresetError::UnsupportedVersion(self.format_version));
+        }
+
+        let enabled = self.bands.iter().filter(|b| b.enabled).count();
+        if enabled > NUM_BANDS {
+            return Err(PresetError::TooManyBands {
+                enabled,
+                max: NUM_BANDS,
+            });
+        }
+
+        let mut params = EqParams::<NUM_BANDS> {
+            lp_enabled: self.lp.enabled,
+            lp_cutoff_hz: self.lp.cutoff_hz,
+            lp_q: self.lp.q,
+            lp_order: self.lp.order,
+
+            hp_enabled: self.hp.enabled,
+            hp_cutoff_hz: self.hp.cutoff_hz,
+            hp_q: self.hp.q,
+            hp_order: self.hp.order,
+
+            bands: defaults.bands,
+        };
+
+        // Keep disabled bands around if there is room for them so a round trip through a
+        // preset doesn't lose their settings.
+        if self.bands.len() <= NUM_BANDS {
+            params.bands[..self.bands.len()].copy_from_slice(&self.bands);
+        } else {
+            for (slot, band) in params
+                .bands
+                .iter_mut()
+                .zip(self.bands.iter().filter(|b| b.enabled))
+            {
+                *slot = *band;
+            }
+        }
+
+        Ok(params)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, PresetError> {
+        let preset: Self = serde_json::from_str(json)?;
+
+        if preset.format_version > PRESET_FORMAT_VERSION {
+            return Err(PresetError::UnsupportedVersion(preset.format_version));
+        }
+
+        Ok(preset)
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut preset = self.clone();
+        preset.format_version = PRESET_FORMAT_VERSION;
+
+        // Serializing plain data to a string cannot fail.
+        serde_json::to_string_pretty(&preset).unwrap()
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, PresetError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), PresetError> {
+        std::fs::write(path, self.to_json())?;
+        Ok(())
+    }
+}
+
+const FACTORY_PRESETS_JSON: &[&str] = &[
+    include_str!("../presets/vocal_cleanup.json"),
+    include_str!("../presets/kick_bass_separation.json"),
+    include_str!("../presets/telephone.json"),
+    include_str!("../presets/air_shelf.json"),
+    include_str!("../presets/mud_cut.json"),
+    include_str!("../presets/de_harsh.json"),
+    include_str!("../presets/rumble_filter.json"),
+];
+
+/// The presets that ship with the EQ. Hosts can't list these yet, see "Presets" under the
+/// parametric EQ in `DESIGN_DOC.md`.
+pub fn factory_presets() -> Vec<EqPreset> {
+    FACTORY_PRESETS_JSON
+        .iter()
+        .map(|json| EqPreset::from_json(json).expect("factory presets are valid"))
+        .collect()
+}
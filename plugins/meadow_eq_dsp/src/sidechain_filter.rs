@@ -0,0 +1,78 @@
+//! Filters on a dynamics processor's sidechain, which the compressor and the gate share.
+
+use crate::{DEFAULT_Q, SvfCoeff, SvfState};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SidechainFilterParams {
+    pub hp_enabled: bool,
+    pub hp_cutoff_hz: f32,
+    pub lp_enabled: bool,
+    pub lp_cutoff_hz: f32,
+}
+
+impl Default for SidechainFilterParams {
+    fn default() -> Self {
+        Self {
+            hp_enabled: false,
+            hp_cutoff_hz: 20.0,
+            lp_enabled: false,
+            lp_cutoff_hz: 20_000.0,
+        }
+    }
+}
+
+/// Second order Butterworth filters on the sidechain, so the level detection can ignore parts of
+/// the spectrum.
+pub struct SidechainFilter {
+    hp: Option<SvfCoeff>,
+    lp: Option<SvfCoeff>,
+    hp_state: [SvfState; 2],
+    lp_state: [SvfState; 2],
+}
+
+impl SidechainFilter {
+    pub fn new(params: &SidechainFilterParams, sample_rate: f64) -> Self {
+        let mut new_self = Self {
+            hp: None,
+            lp: None,
+            hp_state: [SvfState::default(); 2],
+            lp_state: [SvfState::default(); 2],
+        };
+        new_self.set(params, sample_rate);
+
+        new_self
+    }
+
+    pub fn set(&mut self, params: &SidechainFilterParams, sample_rate: f64) {
+        let sample_rate_recip = sample_rate.recip();
+        let q = DEFAULT_Q as f64;
+
+        self.hp = params
+            .hp_enabled
+            .then(|| SvfCoeff::highpass_ord2(params.hp_cutoff_hz as f64, sample_rate_recip, q));
+        self.lp = params
+            .lp_enabled
+            .then(|| SvfCoeff::lowpass_ord2(params.lp_cutoff_hz as f64, sample_rate_recip, q));
+    }
+
+    pub fn reset(&mut self) {
+        self.hp_state = [SvfState::default(); 2];
+        self.lp_state = [SvfState::default(); 2];
+    }
+
+    /// Filters one stereo sample.
+    #[inline(always)]
+    pub fn tick(&mut self, input: [f32; 2]) -> [f32; 2] {
+        let mut output = input;
+        for (channel, x) in output.iter_mut().enumerate() {
+            if let Some(hp) = &self.hp {
+                *x = self.hp_state[channel].tick(*x, hp);
+            }
+            if let Some(lp) = &self.lp {
+                *x = self.lp_state[channel].tick(*x, lp);
+            }
+        }
+
+        output
+    }
+}
@@ -0,0 +1,396 @@
+//! Compares every filter against the analog prototype it is derived from.
+//!
+//! The SVF filters are bilinear transforms prewarped at their cutoff. The digital response
+//! at `f` therefore equals the analog prototype at the normalized frequency
+//! `tan(pi f / fs) / tan(pi fc / fs)` exactly, and any difference beyond rounding is a bug.
+//! The one-pole filters are impulse invariant instead, so they only follow their prototype
+//! well below Nyquist.
+
+use num_complex::Complex64;
+use std::f64::consts::PI;
+
+use super::{Chain, SAMPLE_RATES, Tolerance, assert_no_failures, check_chain, test_freqs};
+use crate::{
+    DEFAULT_Q, ORD4_Q_SCALE, ORD6_Q_SCALE, ORD8_Q_SCALE, OnePoleCoeff, SvfCoeff,
+    scale_q_norm_for_order,
+};
+
+/// What is left after quantizing the coefficients and the state to `f32`.
+const SVF_TOLERANCE: Tolerance = Tolerance {
+    magnitude_db: 0.01,
+    phase_deg: 0.1,
+};
+
+/// How far the impulse-invariant one-pole lowpass may stray from its analog prototype up
+/// to [`ONE_POLE_MAX_FREQ_RATIO`] times the sample rate. Impulse invariance keeps the
+/// magnitude close, but not the phase.
+const ONE_POLE_TOLERANCE: Tolerance = Tolerance {
+    magnitude_db: 0.1,
+    phase_deg: 15.0,
+};
+const ONE_POLE_MAX_FREQ_RATIO: f64 = 1.0 / 16.0;
+
+const CUTOFFS_HZ: [f64; 3] = [20.0, 1_000.0, 15_000.0];
+/// The extremes of the plugin's Q range. These are only tested at cutoffs where the
+/// impulse response decays in a reasonable time.
+const EXTREME_QS: [f64; 2] = [0.02, 40.0];
+const GAINS_DB: [f64; 4] = [-30.0, -6.0, 6.0, 30.0];
+
+/// Every combination of sample rate, cutoff, and Q that is tested.
+fn cases() -> Vec<(f64, f64, f64)> {
+    let mut cases = Vec::new();
+
+    for sample_rate in SAMPLE_RATES {
+        for cutoff_hz in CUTOFFS_HZ {
+            cases.push((sample_rate, cutoff_hz, DEFAULT_Q as f64));
+
+            if cutoff_hz >= 1_000.0 {
+                for q in EXTREME_QS {
+                    cases.push((sample_rate, cutoff_hz, q));
+                }
+            }
+        }
+    }
+
+    cases
+}
+
+/// The prewarped, normalized analog frequency `s` that `freq_hz` maps to.
+fn s(freq_hz: f64, cutoff_hz: f64, sample_rate: f64) -> Complex64 {
+    Complex64::new(
+        0.0,
+        (PI * freq_hz / sample_rate).tan() / (PI * cutoff_hz / sample_rate).tan(),
+    )
+}
+
+fn lowpass_ord2_prototype(s: Complex64, q: f64) -> Complex64 {
+    1.0 / (s * s + s / q + 1.0)
+}
+
+fn highpass_ord2_prototype(s: Complex64, q: f64) -> Complex64 {
+    s * s / (s * s + s / q + 1.0)
+}
+
+/// The Qs of the second-order sections of an `order`th-order Butterworth filter, from the
+/// angles of its poles.
+fn butterworth_qs(order: usize) -> Vec<f64> {
+    (1..=order / 2)
+        .map(|i| 1.0 / (2.0 * ((2 * i - 1) as f64 * PI / (2 * order) as f64).cos()))
+        .collect()
+}
+
+/// The Qs of the sections of a higher-order cut filter. At the default Q this is a
+/// Butterworth filter, and larger Qs raise every section's Q by a per-order fraction of the
+/// extra resonance.
+fn section_qs(order: usize, q: f64) -> Vec<f64> {
+    let scale = match order {
+        2 => 1.0,
+        4 => ORD4_Q_SCALE,
+        6 => ORD6_Q_SCALE,
+        _ => ORD8_Q_SCALE,
+    };
+    let q_norm = scale_q_norm_for_order(q / (DEFAULT_Q as f64), scale);
+
+    butterworth_qs(order)
+        .into_iter()
+        .map(|section_q| section_q * q_norm)
+        .collect()
+}
+
+fn lowpass(order: usize, cutoff_hz: f64, sample_rate_recip: f64, q: f64) -> Vec<SvfCoeff> {
+    match order {
+        2 => vec![SvfCoeff::lowpass_ord2(cutoff_hz, sample_rate_recip, q)],
+        4 => SvfCoeff::lowpass_ord4(cutoff_hz, sample_rate_recip, q).to_vec(),
+        6 => SvfCoeff::lowpass_ord6(cutoff_hz, sample_rate_recip, q).to_vec(),
+        _ => SvfCoeff::lowpass_ord8(cutoff_hz, sample_rate_recip, q).to_vec(),
+    }
+}
+
+fn highpass(order: usize, cutoff_hz: f64, sample_rate_recip: f64, q: f64) -> Vec<SvfCoeff> {
+    match order {
+        2 => vec![SvfCoeff::highpass_ord2(cutoff_hz, sample_rate_recip, q)],
+        4 => SvfCoeff::highpass_ord4(cutoff_hz, sample_rate_recip, q).to_vec(),
+        6 => SvfCoeff::highpass_ord6(cutoff_hz, sample_rate_recip, q).to_vec(),
+        _ => SvfCoeff::highpass_ord8(cutoff_hz, sample_rate_recip, q).to_vec(),
+    }
+}
+
+#[test]
+fn butterworth_sections_match_the_crate_constants() {
+    for (order, qs) in [
+        (4, &crate::Q_BUTTERWORTH_ORD4[..]),
+        (6, &crate::Q_BUTTERWORTH_ORD6[..]),
+        (8, &crate::Q_BUTTERWORTH_ORD8[..]),
+    ] {
+        for (expected, actual) in butterworth_qs(order).iter().zip(qs) {
+            assert!(
+                (expected - actual).abs() < 1e-12,
+                "order {order}: expected Q {expected}, found {actual}"
+            );
+        }
+    }
+}
+
+#[test]
+fn svf_lowpass_matches_analog_prototype() {
+    let mut failures = Vec::new();
+
+    for order in [2, 4, 6, 8] {
+        for (sample_rate, cutoff_hz, q) in cases() {
+            let chain = Chain::svf(&lowpass(order, cutoff_hz, sample_rate.recip(), q));
+            let qs = section_qs(order, q);
+
+            check_chain(
+                &format!("lowpass x{order} {cutoff_hz} Hz Q {q} at {sample_rate} Hz"),
+                &chain,
+                sample_rate,
+                &test_freqs(sample_rate, cutoff_hz),
+                SVF_TOLERANCE,
+                |f| {
+                    let s = s(f, cutoff_hz, sample_rate);
+                    qs.iter().map(|&q| lowpass_ord2_prototype(s, q)).product()
+                },
+                &mut failures,
+            );
+        }
+    }
+
+    assert_no_failures(&failures);
+}
+
+#[test]
+fn svf_highpass_matches_analog_prototype() {
+    let mut failures = Vec::new();
+
+    for order in [2, 4, 6, 8] {
+        for (sample_rate, cutoff_hz, q) in cases() {
+            let chain = Chain::svf(&highpass(order, cutoff_hz, sample_rate.recip(), q));
+            let qs = section_qs(order, q);
+
+            check_chain(
+                &format!("highpass x{order} {cutoff_hz} Hz Q {q} at {sample_rate} Hz"),
+                &chain,
+                sample_rate,
+                &test_freqs(sample_rate, cutoff_hz),
+                SVF_TOLERANCE,
+                |f| {
+                    let s = s(f, cutoff_hz, sample_rate);
+                    qs.iter().map(|&q| highpass_ord2_prototype(s, q)).product()
+                },
+                &mut failures,
+            );
+        }
+    }
+
+    assert_no_failures(&failures);
+}
+
+#[test]
+fn svf_bell_matches_analog_prototype() {
+    let mut failures = Vec::new();
+
+    for gain_db in GAINS_DB {
+        for (sample_rate, cutoff_hz, q) in cases() {
+            let chain = Chain::svf(&[SvfCoeff::bell(cutoff_hz, sample_rate.recip(), q, gain_db)]);
+            let a = 10.0f64.powf(gain_db / 40.0);
+
+            check_chain(
+                &format!("bell {cutoff_hz} Hz Q {q} {gain_db} dB at {sample_rate} Hz"),
+                &chain,
+                sample_rate,
+                &test_freqs(sample_rate, cutoff_hz),
+                SVF_TOLERANCE,
+                |f| {
+                    // The gain at the center is exactly `gain_db`.
+                    let s = s(f, cutoff_hz, sample_rate);
+                    (s * s + s * (a / q) + 1.0) / (s * s + s / (a * q) + 1.0)
+                },
+                &mut failures,
+            );
+        }
+    }
+
+    assert_no_failures(&failures);
+}
+
+#[test]
+fn svf_low_shelf_matches_analog_prototype() {
+    let mut failures = Vec::new();
+
+    for gain_db in GAINS_DB {
+        for (sample_rate, cutoff_hz, q) in cases() {
+            let chain = Chain::svf(&[SvfCoeff::low_shelf(
+                cutoff_hz,
+                sample_rate.recip(),
+                q,
+                gain_db,
+            )]);
+            let a = 10.0f64.powf(gain_db / 40.0);
+
+            check_chain(
+                &format!("low shelf {cutoff_hz} Hz Q {q} {gain_db} dB at {sample_rate} Hz"),
+                &chain,
+                sample_rate,
+                &test_freqs(sample_rate, cutoff_hz),
+                SVF_TOLERANCE,
+                |f| {
+                    // The cutoff sits at the geometric middle of the transition, so the
+                    // prototype is scaled by `sqrt(A)`. The gain is `A^2` at DC and 1 above.
+                    let s = s(f, cutoff_hz, sample_rate) * a.sqrt();
+                    (s * s + s * (a / q) + a * a) / (s * s + s / q + 1.0)
+                },
+                &mut failures,
+            );
+        }
+    }
+
+    assert_no_failures(&failures);
+}
+
+#[test]
+fn svf_high_shelf_matches_analog_prototype() {
+    let mut failures = Vec::new();
+
+    for gain_db in GAINS_DB {
+        for (sample_rate, cutoff_hz, q) in cases() {
+            let chain = Chain::svf(&[SvfCoeff::high_shelf(
+                cutoff_hz,
+                sample_rate.recip(),
+                q,
+                gain_db,
+            )]);
+            let a = 10.0f64.powf(gain_db / 40.0);
+
+            check_chain(
+                &format!("high shelf {cutoff_hz} Hz Q {q} {gain_db} dB at {sample_rate} Hz"),
+                &chain,
+                sample_rate,
+                &test_freqs(sample_rate, cutoff_hz),
+                SVF_TOLERANCE,
+                |f| {
+                    // The gain is 1 at DC and `A^2` towards Nyquist.
+                    let s = s(f, cutoff_hz, sample_rate) * a.sqrt();
+                    (s * s * (a * a) + s * (a / q) + 1.0) / (s * s + s / q + 1.0)
+                },
+                &mut failures,
+            );
+        }
+    }
+
+    assert_no_failures(&failures);
+}
+
+#[test]
+fn svf_notch_matches_analog_prototype() {
+    let mut failures = Vec::new();
+
+    for (sample_rate, cutoff_hz, q) in cases() {
+        let chain = Chain::svf(&[SvfCoeff::notch(cutoff_hz, sample_rate.recip(), q)]);
+
+        check_chain(
+            &format!("notch {cutoff_hz} Hz Q {q} at {sample_rate} Hz"),
+            &chain,
+            sample_rate,
+            &test_freqs(sample_rate, cutoff_hz),
+            SVF_TOLERANCE,
+            |f| {
+                let s = s(f, cutoff_hz, sample_rate);
+                (s * s + 1.0) / (s * s + s / q + 1.0)
+            },
+            &mut failures,
+        );
+    }
+
+    assert_no_failures(&failures);
+}
+
+#[test]
+fn svf_allpass_matches_analog_prototype() {
+    let mut failures = Vec::new();
+
+    for (sample_rate, cutoff_hz, q) in cases() {
+        let chain = Chain::svf(&[SvfCoeff::allpass(cutoff_hz, sample_rate.recip(), q)]);
+
+        check_chain(
+            &format!("allpass {cutoff_hz} Hz Q {q} at {sample_rate} Hz"),
+            &chain,
+            sample_rate,
+            &test_freqs(sample_rate, cutoff_hz),
+            SVF_TOLERANCE,
+            |f| {
+                let s = s(f, cutoff_hz, sample_rate);
+                (s * s - s / q + 1.0) / (s * s + s / q + 1.0)
+            },
+            &mut failures,
+        );
+    }
+
+    assert_no_failures(&failures);
+}
+
+#[test]
+fn one_pole_matches_its_difference_equation() {
+    let mut failures = Vec::new();
+
+    for sample_rate in SAMPLE_RATES {
+        for cutoff_hz in CUTOFFS_HZ {
+            // `y[n] = (1 - b) x[n] + b y[n - 1]` with the pole `b` placed by impulse
+            // invariance, and the highpass is its complement.
+            let b = (-2.0 * PI * cutoff_hz / sample_rate).exp();
+            let lowpass = |f: f64| {
+                (1.0 - b) / (1.0 - b * Complex64::from_polar(1.0, -2.0 * PI * f / sample_rate))
+            };
+
+            check_chain(
+                &format!("one-pole lowpass {cutoff_hz} Hz at {sample_rate} Hz"),
+                &Chain::one_pole(OnePoleCoeff::lowpass(cutoff_hz, sample_rate.recip())),
+                sample_rate,
+                &test_freqs(sample_rate, cutoff_hz),
+                SVF_TOLERANCE,
+                lowpass,
+                &mut failures,
+            );
+            check_chain(
+                &format!("one-pole highpass {cutoff_hz} Hz at {sample_rate} Hz"),
+                &Chain::one_pole(OnePoleCoeff::highpass(cutoff_hz, sample_rate.recip())),
+                sample_rate,
+                &test_freqs(sample_rate, cutoff_hz),
+                SVF_TOLERANCE,
+                |f| 1.0 - lowpass(f),
+                &mut failures,
+            );
+        }
+    }
+
+    assert_no_failures(&failures);
+}
+
+#[test]
+fn one_pole_lowpass_follows_analog_prototype_below_nyquist() {
+    // Only the lowpass is compared. The highpass is `1 - lowpass`, which leaves its passband
+    // `exp(-2 pi fc / fs)` below unity (about -0.6 dB for 1 kHz at 44.1 kHz) instead of
+    // following `s / (s + 1)`.
+    let mut failures = Vec::new();
+
+    for sample_rate in SAMPLE_RATES {
+        for cutoff_hz in [20.0, 1_000.0] {
+            let freqs: Vec<f64> = test_freqs(sample_rate, cutoff_hz)
+                .into_iter()
+                .filter(|&f| f <= sample_rate * ONE_POLE_MAX_FREQ_RATIO)
+                .collect();
+
+            check_chain(
+                &format!("one-pole lowpass {cutoff_hz} Hz at {sample_rate} Hz"),
+                &Chain::one_pole(OnePoleCoeff::lowpass(cutoff_hz, sample_rate.recip())),
+                sample_rate,
+                &freqs,
+                ONE_POLE_TOLERANCE,
+                // Impulse invariance maps the analog pole exactly, without prewarping.
+                |f| 1.0 / Complex64::new(1.0, f / cutoff_hz),
+                &mut failures,
+            );
+        }
+    }
+
+    assert_no_failures(&failures);
+}
@@ -0,0 +1,332 @@
+//! Checks that Equalizer APO configurations survive a round trip and that shelf frequencies
+//! mean what they mean in Equalizer APO.
+
+use crate::apo::{ApoConfig, ApoError};
+use crate::{BandParams, BandType, DEFAULT_Q, EqParams, FilterOrder, MeadowEqDsp, amp_to_db};
+
+const NUM_BANDS: usize = 4;
+const ORDERS: [FilterOrder; 4] = [
+    FilterOrder::X2,
+    FilterOrder::X4,
+    FilterOrder::X6,
+    FilterOrder::X8,
+];
+const QS: [f32; 5] = [0.3, 0.5, DEFAULT_Q, 2.0, 8.0];
+
+fn parse(text: &str) -> Result<ApoConfig<NUM_BANDS>, ApoError> {
+    ApoConfig::parse(text)
+}
+
+fn round_trip(config: &ApoConfig<NUM_BANDS>) -> ApoConfig<NUM_BANDS> {
+    parse(&config.write().unwrap()).unwrap()
+}
+
+fn band(band_type: BandType, cutoff_hz: f32, q: f32, gain_db: f32) -> BandParams {
+    BandParams {
+        enabled: true,
+        band_type,
+        cutoff_hz,
+        q,
+        gain_db,
+    }
+}
+
+/// Values are written with four decimals.
+fn assert_close(label: &str, value: f32, expected: f32) {
+    assert!(
+        (value - expected).abs() <= 1e-3 * expected.abs().max(1.0),
+        "{label}: {value} instead of {expected}"
+    );
+}
+
+/// The gain of the analog prototype of `params` at `freq_hz`, without any warping.
+fn analog_gain_db(params: EqParams<NUM_BANDS>, freq_hz: f64) -> f64 {
+    let mut dsp = MeadowEqDsp::<NUM_BANDS>::new(48_000.0);
+    dsp.set_params(params);
+
+    amp_to_db(dsp.analog_response(freq_hz).norm())
+}
+
+#[test]
+fn cut_filters_of_every_order_survive_a_round_trip() {
+    for order in ORDERS {
+        for q in QS {
+            let config = ApoConfig::<NUM_BANDS> {
+                preamp_db: -3.5,
+                params: EqParams {
+                    lp_enabled: true,
+                    lp_cutoff_hz: 9_000.0,
+                    lp_q: q,
+                    lp_order: order,
+                    hp_enabled: true,
+                    hp_cutoff_hz: 45.0,
+                    hp_q: q,
+                    hp_order: order,
+                    ..EqParams::default()
+                },
+            };
+            let text = config.write().unwrap();
+            let loaded = parse(&text).unwrap();
+
+            let sections = order as usize;
+            assert_eq!(text.matches("HPQ").count(), sections, "{text}");
+            assert_eq!(text.matches("LPQ").count(), sections, "{text}");
+
+            let label = format!("{order:?}, Q {q}");
+            assert_eq!(loaded.params.lp_order, order, "{label}");
+            assert_eq!(loaded.params.hp_order, order, "{label}");
+            assert!(loaded.params.lp_enabled && loaded.params.hp_enabled);
+            assert_close(&label, loaded.params.lp_cutoff_hz, 9_000.0);
+            assert_close(&label, loaded.params.hp_cutoff_hz, 45.0);
+            assert_close(&label, loaded.params.lp_q, q);
+            assert_close(&label, loaded.params.hp_q, q);
+            assert_close(&label, loaded.preamp_db, -3.5);
+        }
+    }
+}
+
+#[test]
+fn first_order_cut_filters_cannot_be_written() {
+    let mut config = ApoConfig::<NUM_BANDS>::default();
+    config.params.hp_enabled = true;
+    config.params.hp_order = FilterOrder::X1;
+
+    assert_eq!(config.write(), Err(ApoError::Unrepresentable("highpass")));
+
+    config.params.hp_enabled = false;
+    config.params.lp_enabled = true;
+    config.params.lp_order = FilterOrder::X1;
+
+    assert_eq!(config.write(), Err(ApoError::Unrepresentable("lowpass")));
+}
+
+#[test]
+fn bands_survive_a_round_trip() {
+    for gain_db in [-18.0, -4.5, 0.0, 6.0, 24.0] {
+        for q in QS {
+            let mut config = ApoConfig::<NUM_BANDS>::default();
+            config.params.bands = [
+                band(BandType::Bell, 1_234.5, q, gain_db),
+                band(BandType::LowShelf, 150.0, q, gain_db),
+                band(BandType::HighShelf, 6_000.0, q, gain_db),
+                band(BandType::Notch, 3_000.0, q, 0.0),
+            ];
+            let loaded = round_trip(&config);
+
+            for (band, expected) in loaded.params.bands.iter().zip(&config.params.bands) {
+                let label = format!("{expected:?}");
+                assert!(band.enabled, "{label}");
+                assert_eq!(band.band_type, expected.band_type, "{label}");
+                assert_close(&label, band.cutoff_hz, expected.cutoff_hz);
+                assert_close(&label, band.q, expected.q);
+                assert_close(&label, band.gain_db, expected.gain_db);
+            }
+        }
+    }
+
+    let mut config = ApoConfig::<NUM_BANDS>::default();
+    config.params.bands[0] = band(BandType::Allpass, 700.0, 0.9, 0.0);
+    let loaded = round_trip(&config);
+    assert_eq!(loaded.params.bands[0].band_type, BandType::Allpass);
+    assert_close("allpass", loaded.params.bands[0].cutoff_hz, 700.0);
+}
+
+#[test]
+fn disabled_bands_are_left_out() {
+    let mut config = ApoConfig::<NUM_BANDS>::default();
+    config.params.bands[0] = BandParams {
+        enabled: false,
+        ..band(BandType::Bell, 100.0, 1.0, 3.0)
+    };
+    config.params.bands[1] = band(BandType::Bell, 2_000.0, 1.0, -3.0);
+
+    let text = config.write().unwrap();
+    assert_eq!(text.matches("Filter").count(), 1, "{text}");
+    assert_close(
+        "bell",
+        round_trip(&config).params.bands[0].cutoff_hz,
+        2_000.0,
+    );
+}
+
+#[test]
+fn centre_shelves_are_half_way_at_their_frequency() {
+    for gain_db in [-12.0, -3.0, 3.0, 12.0] {
+        let config = parse(&format!(
+            "Filter 1: ON LS Fc 200 Hz Gain {gain_db} dB Q 0.7071\n\
+             Filter 2: ON HS Fc 4000 Hz Gain {gain_db} dB Q 0.7071"
+        ))
+        .unwrap();
+
+        for (i, freq_hz) in [(0, 200.0), (1, 4_000.0)] {
+            let mut params = config.params;
+            params.bands[1 - i].enabled = false;
+
+            let measured_db = analog_gain_db(params, freq_hz);
+            assert!(
+                (measured_db - gain_db / 2.0).abs() < 1e-3,
+                "{:?} at {gain_db} dB: {measured_db} dB",
+                params.bands[i].band_type
+            );
+        }
+    }
+}
+
+#[test]
+fn corner_shelves_are_centre_shelves_moved_by_an_eighth_of_the_gain() {
+    for gain_db in [-12.0f32, 12.0] {
+        let ratio = 10.0f32.powf(gain_db.abs() / 80.0);
+
+        for (centre, corner, centre_hz) in [
+            ("LS", "LSC", 200.0 * ratio),
+            ("LS", "LS 12dB", 200.0 * ratio),
+            ("HS", "HSC", 4_000.0 / ratio),
+            ("HS", "HS 12dB", 4_000.0 / ratio),
+        ] {
+            let corner_hz = if centre == "LS" { 200.0 } else { 4_000.0 };
+            let from_corner = parse(&format!(
+                "Filter: ON {corner} Fc {corner_hz} Hz Gain {gain_db} dB Q 0.7071"
+            ))
+            .unwrap();
+            let from_centre = parse(&format!(
+                "Filter: ON {centre} Fc {centre_hz} Hz Gain {gain_db} dB Q 0.7071"
+            ))
+            .unwrap();
+
+            let label = format!("{corner} at {gain_db} dB");
+            let (a, b) = (from_corner.params.bands[0], from_centre.params.bands[0]);
+            assert_eq!(a.band_type, b.band_type, "{label}");
+            assert_close(&label, a.cutoff_hz, b.cutoff_hz);
+        }
+    }
+}
+
+#[test]
+fn shelves_are_written_with_their_corner() {
+    let mut config = ApoConfig::<NUM_BANDS>::default();
+    config.params.bands[0] = band(BandType::LowShelf, 1_000.0, DEFAULT_Q, 8.0);
+
+    let text = config.write().unwrap();
+    assert!(text.contains("ON LSC Fc 794.3282 Hz Gain 8 dB"), "{text}");
+
+    // The same shelf written as a centre shelf reads back the same.
+    let loaded = parse("Filter: ON LS Fc 1000 Hz Gain 8 dB Q 0.7071").unwrap();
+    assert_close("LS", loaded.params.bands[0].cutoff_hz, 1_000.0);
+}
+
+#[test]
+fn identical_cut_sections_become_one_filter() {
+    let config = parse(
+        "Filter 1: ON HPQ Fc 30 Hz Q 0.7071\n\
+         Filter 2: ON HPQ Fc 30 Hz Q 0.7071\n\
+         Filter 3: ON LP Fc 15000 Hz\n\
+         Filter 4: ON LP Fc 15000 Hz\n\
+         Filter 5: ON LP Fc 15000 Hz",
+    )
+    .unwrap();
+
+    assert_eq!(config.params.hp_order, FilterOrder::X4);
+    assert_eq!(config.params.lp_order, FilterOrder::X6);
+    assert_close("highpass", config.params.hp_cutoff_hz, 30.0);
+    assert_close("lowpass", config.params.lp_cutoff_hz, 15_000.0);
+}
+
+#[test]
+fn separate_cut_filters_are_rejected() {
+    let err = parse(
+        "Filter 1: ON HP Fc 30 Hz\n\
+         Filter 2: ON PK Fc 1000 Hz Gain 2 dB Q 1\n\
+         Filter 3: ON HP Fc 30 Hz",
+    );
+    assert_eq!(
+        err,
+        Err(ApoError::DuplicateCutFilter {
+            line: 3,
+            filter: "highpass"
+        })
+    );
+
+    let err = parse(
+        "Filter 1: ON LP Fc 10000 Hz\n\
+         Filter 2: ON LP Fc 12000 Hz",
+    );
+    assert_eq!(
+        err,
+        Err(ApoError::DuplicateCutFilter {
+            line: 2,
+            filter: "lowpass"
+        })
+    );
+
+    let too_steep = "Filter: ON HP Fc 30 Hz\n".repeat(5);
+    assert_eq!(
+        parse(&too_steep),
+        Err(ApoError::TooManyCutSections {
+            line: 1,
+            filter: "highpass",
+            max: 4
+        })
+    );
+}
+
+#[test]
+fn parses_an_autoeq_file() {
+    let config = parse(
+        "# AutoEQ\n\
+         Preamp: -6.2 dB\n\
+         Preamp: 1 dB\n\
+         Filter 1: ON LSC Fc 105 Hz Gain 6.0 dB Q 0.70\n\
+         Filter 2: ON PK Fc 2000 Hz Gain -2.8 dB BW Oct 1\n\
+         Filter 3: OFF PK Fc 5000 Hz Gain 1 dB Q 2 # A comment\n\
+         Filter 4: ON None\n\
+         Filter 5: ON NO Fc 60 Hz Q 30",
+    )
+    .unwrap();
+
+    assert_close("preamp", config.preamp_db, -5.2);
+    let bands = config.params.bands;
+    assert_eq!(bands[0].band_type, BandType::LowShelf);
+    assert_close("bandwidth", bands[1].q, std::f32::consts::SQRT_2);
+    assert!(!bands[2].enabled);
+    assert_eq!(bands[3].band_type, BandType::Notch);
+}
+
+#[test]
+fn rejects_invalid_files() {
+    assert!(matches!(
+        parse("Channel: L"),
+        Err(ApoError::UnsupportedCommand { line: 1, .. })
+    ));
+    assert!(matches!(
+        parse("Filter: ON XX Fc 100 Hz"),
+        Err(ApoError::UnsupportedFilterType { line: 1, .. })
+    ));
+    assert!(matches!(
+        parse("Filter: ON PK Fc 100 Hz Gain 1 dB"),
+        Err(ApoError::MissingField {
+            line: 1,
+            field: "Q"
+        })
+    ));
+    for text in [
+        "Filter: ON PK Fc 100 Hz Gain 1 dB Q -1",
+        "Filter: ON PK Fc 100 Hz Gain 1 dB Q 0",
+        "Filter: ON PK Fc 0 Hz Gain 1 dB Q 1",
+        "Filter: ON PK Fc 100 Hz Gain 1 dB BW Oct -1",
+        "Filter: ON PK Fc NaN Hz Gain 1 dB Q 1",
+    ] {
+        assert!(
+            matches!(parse(text), Err(ApoError::InvalidValue { line: 1, .. })),
+            "{text}"
+        );
+    }
+
+    let too_many = "Filter: ON PK Fc 100 Hz Gain 1 dB Q 1\n".repeat(NUM_BANDS + 1);
+    assert_eq!(
+        parse(&too_many),
+        Err(ApoError::TooManyBands {
+            line: NUM_BANDS + 1,
+            max: NUM_BANDS
+        })
+    );
+}
@@ -0,0 +1,127 @@
+//! Checks that a crossfade is exactly a linear mix of the old and the new filters.
+
+use crate::crossfade::CrossfadeEqDsp;
+use crate::{BandParams, BandType, EqParams, FilterOrder, MeadowEqDsp};
+
+const NUM_BANDS: usize = 8;
+const SAMPLE_RATE: f64 = 48_000.0;
+const SIGNAL_LEN: usize = 8192;
+const SWITCH_AT: usize = 3000;
+
+fn params(gain_db: f32, hp_cutoff_hz: f32) -> EqParams<NUM_BANDS> {
+    let mut params = EqParams {
+        hp_enabled: true,
+        hp_cutoff_hz,
+        hp_order: FilterOrder::X4,
+        ..EqParams::default()
+    };
+    params.bands[0] = BandParams {
+        enabled: true,
+        band_type: BandType::Bell,
+        cutoff_hz: 1_000.0,
+        q: 2.0,
+        gain_db,
+    };
+    params
+}
+
+/// Runs `signal` through `dsp` in blocks of `block_size`, calling `switch` once before the
+/// block that contains [`SWITCH_AT`].
+fn run<D>(
+    dsp: &mut D,
+    signal: &[f32],
+    block_size: usize,
+    mut process: impl FnMut(&mut D, &mut [f32], &mut [f32]),
+    mut switch: impl FnMut(&mut D),
+) -> Vec<f32> {
+    let mut buf_l = signal.to_vec();
+    let mut buf_r: Vec<f32> = signal.iter().map(|x| -x).collect();
+
+    let mut pos = 0;
+    for (l, r) in buf_l
+        .chunks_mut(block_size)
+        .zip(buf_r.chunks_mut(block_size))
+    {
+        // The switch lands on a block boundary, as it would in a plugin.
+        if pos <= SWITCH_AT && SWITCH_AT < pos + l.len() {
+            switch(dsp);
+        }
+        process(dsp, l, r);
+        pos += l.len();
+
+        for (&l, &r) in l.iter().zip(r.iter()) {
+            assert_eq!(l, -r, "the channels should be processed identically");
+        }
+    }
+
+    buf_l
+}
+
+#[test]
+fn crossfade_mixes_the_old_and_the_new_filters() {
+    let (from, to) = (params(12.0, 40.0), params(-12.0, 200.0));
+
+    let mut rng_state = 1u32;
+    let signal: Vec<f32> = (0..SIGNAL_LEN)
+        .map(|_| {
+            rng_state ^= rng_state << 13;
+            rng_state ^= rng_state >> 17;
+            rng_state ^= rng_state << 5;
+            (rng_state as f32 / u32::MAX as f32) * 2.0 - 1.0
+        })
+        .collect();
+
+    for block_size in [1, 63, 64, 100, 512] {
+        let mut dsp = CrossfadeEqDsp::<NUM_BANDS>::new(SAMPLE_RATE);
+        dsp.set_params(from);
+        let fade_len = dsp.fade_len();
+        let mut switched_at = None;
+        let faded = run(
+            &mut dsp,
+            &signal,
+            block_size,
+            |dsp, l, r| dsp.process(l, r),
+            |dsp| dsp.crossfade_to(to),
+        );
+        assert!(!dsp.is_crossfading());
+
+        // The old filters keep running, and the new ones jump.
+        let mut old = MeadowEqDsp::<NUM_BANDS>::new(SAMPLE_RATE);
+        old.set_params(from);
+        let old = run(
+            &mut old,
+            &signal,
+            block_size,
+            |dsp, l, r| dsp.process(l, r),
+            |_| (),
+        );
+
+        let mut new = MeadowEqDsp::<NUM_BANDS>::new(SAMPLE_RATE);
+        new.set_params(from);
+        let new = run(
+            &mut new,
+            &signal,
+            block_size,
+            |dsp, l, r| dsp.process(l, r),
+            |dsp| {
+                dsp.set_params(to);
+            },
+        );
+
+        for n in 0..SIGNAL_LEN {
+            if switched_at.is_none() && old[n] != new[n] {
+                switched_at = Some(n);
+            }
+            let t = switched_at.map_or(0.0, |start| {
+                ((n - start) as f32 + 0.5).min(fade_len as f32) / fade_len as f32
+            });
+            let expected = old[n] + (new[n] - old[n]) * t;
+
+            assert!(
+                (faded[n] - expected).abs() < 1e-5,
+                "block size {block_size}, sample {n}: {}, expected {expected}",
+                faded[n]
+            );
+        }
+    }
+}
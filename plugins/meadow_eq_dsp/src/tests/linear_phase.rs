@@ -0,0 +1,178 @@
+//! Checks that the linear phase kernels have the analog prototypes' magnitude response and a
+//! pure delay as their phase.
+
+use num_complex::Complex64;
+use std::f64::consts::PI;
+
+use super::{SAMPLE_RATES, dtft};
+use crate::linear_phase::{KernelDesigner, LinearPhaseEqDsp, kernel_len};
+use crate::{BandParams, BandType, EqParams, FilterOrder, MeadowEqDsp, amp_to_db};
+
+const NUM_BANDS: usize = 8;
+
+/// The kernels are too short to follow the response much below this.
+const MIN_FREQ_HZ: f64 = 100.0;
+const MAX_FREQ_HZ: f64 = 20_000.0;
+
+const MAGNITUDE_TOLERANCE_DB: f64 = 0.5;
+const PHASE_TOLERANCE_DEG: f64 = 1.0;
+/// The errors are only checked where the response is above this. Below it the response only
+/// has to stay below it, as the kernels smooth over a few hertz, which fills in the bottom
+/// of notches.
+const STOPBAND_DB: f64 = -24.0;
+
+fn band(band_type: BandType, cutoff_hz: f32, q: f32, gain_db: f32) -> BandParams {
+    BandParams {
+        enabled: true,
+        band_type,
+        cutoff_hz,
+        q,
+        gain_db,
+    }
+}
+
+fn with_bands(bands: &[BandParams]) -> EqParams<NUM_BANDS> {
+    let mut params = EqParams::default();
+    params.bands[..bands.len()].copy_from_slice(bands);
+    params
+}
+
+fn settings() -> Vec<(&'static str, EqParams<NUM_BANDS>)> {
+    vec![
+        ("flat", EqParams::default()),
+        (
+            "mastering",
+            EqParams {
+                hp_enabled: true,
+                hp_cutoff_hz: 30.0,
+                hp_order: FilterOrder::X4,
+                ..with_bands(&[
+                    band(BandType::LowShelf, 120.0, 0.7, -2.0),
+                    band(BandType::Bell, 400.0, 1.0, -1.5),
+                    band(BandType::HighShelf, 12_000.0, 0.7, 3.0),
+                ])
+            },
+        ),
+        (
+            "notch and bells",
+            with_bands(&[
+                band(BandType::Notch, 1_000.0, 4.0, 0.0),
+                band(BandType::Bell, 3_000.0, 2.0, 6.0),
+                band(BandType::Bell, 15_000.0, 1.0, -6.0),
+            ]),
+        ),
+        (
+            "steep cuts",
+            EqParams {
+                hp_enabled: true,
+                hp_cutoff_hz: 200.0,
+                hp_order: FilterOrder::X8,
+                lp_enabled: true,
+                lp_cutoff_hz: 8_000.0,
+                lp_order: FilterOrder::X8,
+                ..EqParams::default()
+            },
+        ),
+        (
+            "allpass",
+            with_bands(&[band(BandType::Allpass, 2_000.0, 1.0, 0.0)]),
+        ),
+    ]
+}
+
+fn test_freqs() -> impl Iterator<Item = f64> {
+    const NUM_FREQS: usize = 200;
+
+    (0..=NUM_FREQS)
+        .map(|i| MIN_FREQ_HZ * (MAX_FREQ_HZ / MIN_FREQ_HZ).powf(i as f64 / NUM_FREQS as f64))
+}
+
+fn design(sample_rate: f64, params: EqParams<NUM_BANDS>) -> Vec<f32> {
+    let mut designer = KernelDesigner::<NUM_BANDS>::new(sample_rate);
+    let mut kernel = vec![0.0; designer.kernel_len()];
+    designer.design(params, &mut kernel);
+    kernel
+}
+
+#[test]
+fn kernels_are_symmetric() {
+    for sample_rate in SAMPLE_RATES {
+        for (label, params) in settings() {
+            let kernel = design(sample_rate, params);
+            let center = kernel.len() / 2;
+
+            let peak = kernel.iter().fold(0.0f32, |peak, x| peak.max(x.abs()));
+            for i in 1..center {
+                assert!(
+                    (kernel[center + i] - kernel[center - i]).abs() <= 1e-6 * peak,
+                    "{sample_rate} Hz, {label}: sample {i} from the center differs, {} and {}",
+                    kernel[center + i],
+                    kernel[center - i],
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn linear_phase_follows_the_analog_magnitudes() {
+    let mut failures = Vec::new();
+
+    for sample_rate in SAMPLE_RATES {
+        for (label, params) in settings() {
+            let mut dsp = LinearPhaseEqDsp::new(sample_rate);
+            dsp.set_kernel(&design(sample_rate, params));
+            dsp.reset();
+
+            let latency = dsp.latency_samples() as f64;
+            let len = latency as usize + kernel_len(sample_rate);
+            let mut buf_l = vec![0.0; len];
+            let mut buf_r = vec![0.0; len];
+            buf_l[0] = 1.0;
+            // Odd block sizes exercise the partition boundaries.
+            for (l, r) in buf_l.chunks_mut(97).zip(buf_r.chunks_mut(97)) {
+                dsp.process(l, r);
+            }
+
+            let mut eq = MeadowEqDsp::<NUM_BANDS>::new(sample_rate);
+            eq.set_params(params);
+
+            let (mut max_db, mut max_deg) = (0.0f64, 0.0f64);
+            for freq_hz in test_freqs() {
+                let measured = dtft(&buf_l, freq_hz, sample_rate)
+                    * Complex64::from_polar(1.0, 2.0 * PI * freq_hz * latency / sample_rate);
+                let measured_db = amp_to_db(measured.norm());
+                let expected_db = amp_to_db(eq.analog_response(freq_hz).norm());
+
+                if expected_db > STOPBAND_DB {
+                    max_db = max_db.max((measured_db - expected_db).abs());
+                    max_deg = max_deg.max(measured.arg().to_degrees().abs());
+                } else {
+                    max_db = max_db.max(measured_db - STOPBAND_DB);
+                }
+            }
+
+            if max_db > MAGNITUDE_TOLERANCE_DB || max_deg > PHASE_TOLERANCE_DEG {
+                failures.push(format!(
+                    "{sample_rate} Hz, {label}: {max_db:.3} dB, {max_deg:.2}°"
+                ));
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn latency_is_half_the_kernel_plus_one_partition() {
+    for sample_rate in SAMPLE_RATES {
+        let dsp = LinearPhaseEqDsp::new(sample_rate);
+        let kernel_len = kernel_len(sample_rate);
+
+        assert_eq!(dsp.kernel_len(), kernel_len);
+        assert_eq!(
+            dsp.latency_samples() as usize,
+            kernel_len / 2 + kernel_len / 16
+        );
+    }
+}
@@ -0,0 +1,247 @@
+//! Checks that the match EQ finds a known EQ curve and refuses settings it can't work with.
+
+use crate::match_eq::{MatchEqError, MatchEqSettings, Spectrum, match_eq};
+use crate::{BandParams, BandType, DEFAULT_Q, EqParams, MeadowEqDsp};
+
+const NUM_BANDS: usize = 8;
+const SAMPLE_RATE: f64 = 48_000.0;
+
+/// Deterministic white noise.
+fn noise(len: usize) -> Vec<f32> {
+    let mut state = 0x1234_5678u32;
+
+    (0..len)
+        .map(|_| {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (state >> 8) as f32 / (1 << 23) as f32 - 1.0
+        })
+        .collect()
+}
+
+fn band(band_type: BandType, cutoff_hz: f32, q: f32, gain_db: f32) -> BandParams {
+    BandParams {
+        enabled: true,
+        band_type,
+        cutoff_hz,
+        q,
+        gain_db,
+    }
+}
+
+fn eq(bands: &[BandParams]) -> EqParams<NUM_BANDS> {
+    let mut params = EqParams::default();
+    params.bands[..bands.len()].copy_from_slice(bands);
+    params
+}
+
+/// The spectrum of `signal` as it sounds through `params`.
+fn spectrum_through(params: EqParams<NUM_BANDS>, signal: &[f32]) -> Spectrum {
+    let mut dsp = MeadowEqDsp::<NUM_BANDS>::new(SAMPLE_RATE);
+    dsp.set_params(params);
+
+    let mut buf_l = signal.to_vec();
+    let mut buf_r = signal.to_vec();
+    dsp.process(&mut buf_l, &mut buf_r);
+
+    Spectrum::analyze(&[&buf_l, &buf_r], SAMPLE_RATE).unwrap()
+}
+
+/// The response of `params` at `freqs_hz`, without its average level.
+fn level_matched_response(params: EqParams<NUM_BANDS>, freqs_hz: &[f64]) -> Vec<f64> {
+    let mut dsp = MeadowEqDsp::<NUM_BANDS>::new(SAMPLE_RATE);
+    dsp.set_params(params);
+
+    let response: Vec<f64> = freqs_hz.iter().map(|&f| dsp.magnitude_db(f)).collect();
+    let mean = response.iter().sum::<f64>() / response.len() as f64;
+    response.iter().map(|r| r - mean).collect()
+}
+
+fn flat_spectrum() -> Spectrum {
+    let freqs_hz: Vec<f64> = (1..1000).map(|i| i as f64 * 24.0).collect();
+    Spectrum {
+        magnitudes_db: vec![0.0; freqs_hz.len()],
+        freqs_hz,
+    }
+}
+
+#[test]
+fn finds_a_known_eq_curve() {
+    let known = eq(&[
+        band(BandType::LowShelf, 120.0, DEFAULT_Q, 4.0),
+        band(BandType::Bell, 2_000.0, 1.5, -6.0),
+        band(BandType::HighShelf, 8_000.0, DEFAULT_Q, 3.0),
+    ]);
+    let signal = noise(2 * SAMPLE_RATE as usize);
+    let source = spectrum_through(EqParams::default(), &signal);
+    let reference = spectrum_through(known, &signal);
+
+    let settings = MatchEqSettings {
+        num_bands: 3,
+        smoothing_octaves: 1.0 / 6.0,
+        ..Default::default()
+    };
+    let result = match_eq::<NUM_BANDS>(&source, &reference, &settings, SAMPLE_RATE).unwrap();
+    assert!(result.rms_error_db < 0.5, "{}", result.rms_error_db);
+
+    let freqs_hz: Vec<f64> = result.target.iter().map(|(f, _)| *f).collect();
+    let expected = level_matched_response(known, &freqs_hz);
+    let fitted = level_matched_response(result.params, &freqs_hz);
+    for ((freq_hz, expected_db), fitted_db) in freqs_hz.iter().zip(&expected).zip(&fitted) {
+        assert!(
+            (fitted_db - expected_db).abs() < 1.0,
+            "{freq_hz:.1} Hz: {fitted_db:.2} dB instead of {expected_db:.2} dB"
+        );
+    }
+
+    // Only the requested bands are used.
+    assert!(result.params.bands[3..].iter().all(|b| !b.enabled));
+}
+
+#[test]
+fn gains_stay_within_the_limit() {
+    let source = flat_spectrum();
+    let mut reference = flat_spectrum();
+    let mut dsp = MeadowEqDsp::<NUM_BANDS>::new(SAMPLE_RATE);
+    dsp.set_params(eq(&[band(BandType::Bell, 1_000.0, 2.0, 24.0)]));
+    for (freq_hz, magnitude_db) in reference.freqs_hz.iter().zip(&mut reference.magnitudes_db) {
+        *magnitude_db = dsp.magnitude_db(*freq_hz);
+    }
+
+    for max_gain_db in [0.0, 6.0] {
+        let settings = MatchEqSettings {
+            max_gain_db,
+            ..Default::default()
+        };
+        let result = match_eq::<NUM_BANDS>(&source, &reference, &settings, SAMPLE_RATE).unwrap();
+
+        for band in result.params.bands.iter().filter(|b| b.enabled) {
+            assert!(band.gain_db.abs() <= max_gain_db, "{band:?}");
+        }
+    }
+}
+
+#[test]
+fn matching_a_spectrum_to_itself_is_flat() {
+    let spectrum = flat_spectrum();
+    let result =
+        match_eq::<NUM_BANDS>(&spectrum, &spectrum, &Default::default(), SAMPLE_RATE).unwrap();
+
+    assert!(result.rms_error_db < 1e-3, "{}", result.rms_error_db);
+    for band in result.params.bands.iter().filter(|b| b.enabled) {
+        assert!(band.gain_db.abs() < 1e-3, "{band:?}");
+    }
+
+    let no_bands = MatchEqSettings {
+        num_bands: 0,
+        ..Default::default()
+    };
+    let result = match_eq::<NUM_BANDS>(&spectrum, &spectrum, &no_bands, SAMPLE_RATE).unwrap();
+    assert_eq!(result.params, EqParams::default());
+}
+
+#[test]
+fn analysis_finds_a_sine() {
+    let sine: Vec<f32> = (0..SAMPLE_RATE as usize)
+        .map(|n| (2.0 * std::f64::consts::PI * 1_000.0 * n as f64 / SAMPLE_RATE).sin() as f32)
+        .collect();
+
+    // A long signal and one shorter than an analysis frame.
+    for len in [sine.len(), 300] {
+        let spectrum = Spectrum::analyze(&[&sine[..len]], SAMPLE_RATE).unwrap();
+        let (peak_i, _) = spectrum
+            .magnitudes_db
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .unwrap();
+        let bin_hz = spectrum.freqs_hz[0];
+
+        assert!(
+            (spectrum.freqs_hz[peak_i] - 1_000.0).abs() <= bin_hz,
+            "{len} samples: {} Hz",
+            spectrum.freqs_hz[peak_i]
+        );
+    }
+}
+
+#[test]
+fn rejects_invalid_input() {
+    let spectrum = flat_spectrum();
+    let empty = Spectrum {
+        freqs_hz: Vec::new(),
+        magnitudes_db: Vec::new(),
+    };
+    let run = |source: &Spectrum, settings: MatchEqSettings| {
+        match_eq::<NUM_BANDS>(source, &spectrum, &settings, SAMPLE_RATE)
+    };
+
+    assert_eq!(
+        Spectrum::analyze(&[&[]], SAMPLE_RATE),
+        Err(MatchEqError::EmptySignal)
+    );
+    assert_eq!(
+        run(&empty, Default::default()),
+        Err(MatchEqError::EmptySignal)
+    );
+    assert_eq!(
+        run(
+            &spectrum,
+            MatchEqSettings {
+                num_bands: NUM_BANDS + 1,
+                ..Default::default()
+            }
+        ),
+        Err(MatchEqError::TooManyBands {
+            requested: NUM_BANDS + 1,
+            max: NUM_BANDS
+        })
+    );
+
+    for (min_freq_hz, max_freq_hz) in [
+        (1_000.0, 100.0),
+        (0.0, 100.0),
+        (100.0, SAMPLE_RATE),
+        (f64::NAN, 100.0),
+    ] {
+        let settings = MatchEqSettings {
+            min_freq_hz,
+            max_freq_hz,
+            ..Default::default()
+        };
+        assert!(
+            matches!(
+                run(&spectrum, settings),
+                Err(MatchEqError::InvalidFrequencyRange { .. })
+            ),
+            "{min_freq_hz} Hz to {max_freq_hz} Hz"
+        );
+    }
+
+    for max_gain_db in [-1.0, f32::NAN, f32::INFINITY] {
+        let settings = MatchEqSettings {
+            max_gain_db,
+            ..Default::default()
+        };
+        assert!(
+            matches!(
+                run(&spectrum, settings),
+                Err(MatchEqError::InvalidMaxGain(_))
+            ),
+            "{max_gain_db} dB"
+        );
+    }
+
+    for smoothing_octaves in [-0.5, f64::NAN] {
+        let settings = MatchEqSettings {
+            smoothing_octaves,
+            ..Default::default()
+        };
+        assert!(
+            matches!(
+                run(&spectrum, settings),
+                Err(MatchEqError::InvalidSmoothing(_))
+            ),
+            "{smoothing_octaves} octaves"
+        );
+    }
+}
@@ -0,0 +1,213 @@
+//! Measurement helpers shared by the test suites.
+
+mod analytic;
+mod apo;
+mod crossfade;
+#[cfg(feature = "linear-phase")]
+mod linear_phase;
+#[cfg(feature = "match-eq")]
+mod match_eq;
+#[cfg(feature = "natural-phase")]
+mod natural_phase;
+#[cfg(feature = "presets")]
+mod preset;
+mod sidechain_filter;
+mod stability;
+
+use num_complex::Complex64;
+use std::f64::consts::PI;
+
+use crate::{OnePoleCoeff, OnePoleState, SvfCoeff, SvfState, amp_to_db};
+
+const SAMPLE_RATES: [f64; 4] = [44_100.0, 48_000.0, 96_000.0, 192_000.0];
+
+/// An impulse response counts as finished once this many samples in a row are below
+/// [`IMPULSE_SILENCE`].
+const IMPULSE_SILENCE_LEN: usize = 1024;
+const IMPULSE_SILENCE: f32 = 1e-10;
+const MAX_IMPULSE_LEN: usize = 1 << 20;
+
+/// The largest absolute error that is put down to rounding, -60 dB. A cascade of high-Q
+/// sections amplifies the rounding noise of its `f32` state considerably, which shows up in
+/// the stopband of the impulse measurements. The sine measurements average it out.
+const NOISE_FLOOR: f64 = 1e-3;
+
+/// A chain of filters that is run one sample at a time.
+#[derive(Clone)]
+enum Chain {
+    Svf(Vec<(SvfCoeff, SvfState)>),
+    OnePole(OnePoleCoeff, OnePoleState),
+}
+
+impl Chain {
+    fn svf(coeffs: &[SvfCoeff]) -> Self {
+        Self::Svf(coeffs.iter().map(|c| (*c, SvfState::default())).collect())
+    }
+
+    fn one_pole(coeff: OnePoleCoeff) -> Self {
+        Self::OnePole(coeff, OnePoleState::default())
+    }
+
+    fn tick(&mut self, input: f32) -> f32 {
+        match self {
+            Self::Svf(filters) => filters
+                .iter_mut()
+                .fold(input, |x, (coeff, state)| state.tick(x, coeff)),
+            Self::OnePole(coeff, state) => state.tick(input, coeff),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Tolerance {
+    magnitude_db: f64,
+    phase_deg: f64,
+}
+
+/// Feeds a unit impulse through a fresh copy of the chain until the output has died down.
+fn impulse_response(chain: &Chain) -> Vec<f32> {
+    let mut chain = chain.clone();
+    let mut response = Vec::new();
+    let mut silent_len = 0;
+
+    let mut input = 1.0;
+    while silent_len < IMPULSE_SILENCE_LEN && response.len() < MAX_IMPULSE_LEN {
+        let y = chain.tick(input);
+        input = 0.0;
+
+        if y.abs() < IMPULSE_SILENCE {
+            silent_len += 1;
+        } else {
+            silent_len = 0;
+        }
+        response.push(y);
+    }
+
+    response
+}
+
+/// The discrete-time Fourier transform of `signal` at the given frequency.
+fn dtft(signal: &[f32], freq_hz: f64, sample_rate: f64) -> Complex64 {
+    let rotation = Complex64::from_polar(1.0, -2.0 * PI * freq_hz / sample_rate);
+    let mut phasor = Complex64::new(1.0, 0.0);
+
+    let mut sum = Complex64::new(0.0, 0.0);
+    for &x in signal {
+        sum += phasor * x as f64;
+        phasor *= rotation;
+    }
+
+    sum
+}
+
+/// Measures the response at one frequency by running a sine through a fresh copy of the
+/// chain, skipping the first `settle_len` samples, and correlating the rest with the input.
+fn sine_response(chain: &Chain, freq_hz: f64, sample_rate: f64, settle_len: usize) -> Complex64 {
+    let mut chain = chain.clone();
+
+    let w = 2.0 * PI * freq_hz / sample_rate;
+    let measure_len = ((8.0 * sample_rate / freq_hz) as usize).max(8192);
+
+    let mut input_sum = Complex64::new(0.0, 0.0);
+    let mut output_sum = Complex64::new(0.0, 0.0);
+    for n in 0..settle_len + measure_len {
+        let x = (w * n as f64).sin() as f32;
+        let y = chain.tick(x);
+
+        if let Some(i) = n.checked_sub(settle_len) {
+            // A Hann window keeps leakage from the negative frequency image out of the
+            // estimate.
+            let window = 0.5 - 0.5 * (2.0 * PI * i as f64 / measure_len as f64).cos();
+            let phasor = Complex64::from_polar(window, -w * n as f64);
+
+            input_sum += phasor * x as f64;
+            output_sum += phasor * y as f64;
+        }
+    }
+
+    output_sum / input_sum
+}
+
+/// Logarithmically spaced frequencies from 10 Hz to just below Nyquist, plus `extra_hz`.
+fn test_freqs(sample_rate: f64, extra_hz: f64) -> Vec<f64> {
+    const NUM_FREQS: usize = 24;
+    let (min_hz, max_hz) = (10.0, 0.45 * sample_rate);
+
+    let mut freqs: Vec<f64> = (0..NUM_FREQS)
+        .map(|i| min_hz * (max_hz / min_hz).powf(i as f64 / (NUM_FREQS - 1) as f64))
+        .collect();
+    freqs.push(extra_hz);
+
+    freqs
+}
+
+/// Measures `chain` with an impulse at every test frequency and with a stepped sine sweep at
+/// every fourth one, and records every mismatch with `expected` in `failures`.
+fn check_chain(
+    label: &str,
+    chain: &Chain,
+    sample_rate: f64,
+    freqs: &[f64],
+    tolerance: Tolerance,
+    expected: impl Fn(f64) -> Complex64,
+    failures: &mut Vec<String>,
+) {
+    let impulse = impulse_response(chain);
+
+    for &freq_hz in freqs {
+        let measured = dtft(&impulse, freq_hz, sample_rate);
+        compare(
+            &format!("{label}, impulse at {freq_hz:.1} Hz"),
+            measured,
+            expected(freq_hz),
+            tolerance,
+            failures,
+        );
+    }
+
+    for &freq_hz in freqs.iter().step_by(4) {
+        let measured = sine_response(chain, freq_hz, sample_rate, impulse.len());
+        compare(
+            &format!("{label}, sine at {freq_hz:.1} Hz"),
+            measured,
+            expected(freq_hz),
+            tolerance,
+            failures,
+        );
+    }
+}
+
+fn compare(
+    label: &str,
+    measured: Complex64,
+    expected: Complex64,
+    tolerance: Tolerance,
+    failures: &mut Vec<String>,
+) {
+    if (measured - expected).norm() < NOISE_FLOOR {
+        return;
+    }
+
+    let measured_db = amp_to_db(measured.norm());
+    let expected_db = amp_to_db(expected.norm());
+    let phase_error = (measured / expected).arg().to_degrees().abs();
+
+    if (measured_db - expected_db).abs() > tolerance.magnitude_db
+        || phase_error > tolerance.phase_deg
+    {
+        failures.push(format!(
+            "{label}: measured {measured_db:.3} dB / {:.2}°, expected {expected_db:.3} dB / {:.2}°",
+            measured.arg().to_degrees(),
+            expected.arg().to_degrees(),
+        ));
+    }
+}
+
+fn assert_no_failures(failures: &[String]) {
+    assert!(
+        failures.is_empty(),
+        "{} mismatches:\n{}",
+        failures.len(),
+        failures.join("\n")
+    );
+}
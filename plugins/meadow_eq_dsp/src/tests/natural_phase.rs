@@ -0,0 +1,343 @@
+//! Checks the convolver and that the natural phase mode follows the analog prototypes.
+
+use num_complex::Complex64;
+use std::f64::consts::PI;
+
+use super::{SAMPLE_RATES, dtft};
+use crate::convolver::Convolver;
+use crate::natural_phase::{MAX_CORRECTION_HZ, NaturalPhaseEqDsp};
+use crate::{BandParams, BandType, EqParams, FilterOrder, MeadowEqDsp, amp_to_db};
+
+const NUM_BANDS: usize = 8;
+
+const NATURAL_PHASE_TOLERANCE_DB: f64 = 0.5;
+const NATURAL_PHASE_TOLERANCE_DEG: f64 = 2.0;
+
+fn band(band_type: BandType, cutoff_hz: f32, q: f32, gain_db: f32) -> BandParams {
+    BandParams {
+        enabled: true,
+        band_type,
+        cutoff_hz,
+        q,
+        gain_db,
+    }
+}
+
+fn with_bands(bands: &[BandParams]) -> EqParams<NUM_BANDS> {
+    let mut params = EqParams::default();
+    params.bands[..bands.len()].copy_from_slice(bands);
+    params
+}
+
+/// Typical settings, several of which have their features in the top octave where the
+/// digital filters are warped the most.
+fn settings() -> Vec<(&'static str, EqParams<NUM_BANDS>)> {
+    vec![
+        ("flat", EqParams::default()),
+        (
+            "air shelf",
+            with_bands(&[band(BandType::HighShelf, 12_000.0, 0.7, 9.0)]),
+        ),
+        (
+            "presence bells",
+            with_bands(&[
+                band(BandType::Bell, 3_000.0, 1.0, 4.0),
+                band(BandType::Bell, 9_000.0, 2.0, -6.0),
+                band(BandType::Bell, 16_000.0, 0.7, 6.0),
+            ]),
+        ),
+        (
+            "low shelf and bell",
+            with_bands(&[
+                band(BandType::LowShelf, 100.0, 0.7, 6.0),
+                band(BandType::Bell, 1_000.0, 1.0, -3.0),
+            ]),
+        ),
+        (
+            "console",
+            EqParams {
+                hp_enabled: true,
+                hp_cutoff_hz: 40.0,
+                hp_order: FilterOrder::X2,
+                lp_enabled: true,
+                lp_cutoff_hz: 18_000.0,
+                lp_order: FilterOrder::X2,
+                ..with_bands(&[
+                    band(BandType::LowShelf, 80.0, 0.7, 3.0),
+                    band(BandType::Bell, 2_500.0, 1.5, 3.0),
+                    band(BandType::HighShelf, 10_000.0, 0.7, 4.0),
+                ])
+            },
+        ),
+    ]
+}
+
+/// Runs an impulse through a fresh copy of the EQ.
+fn impulse_response(dsp: &mut NaturalPhaseEqDsp<NUM_BANDS>, len: usize) -> Vec<f32> {
+    let mut buf_l = vec![0.0; len];
+    let mut buf_r = vec![0.0; len];
+    buf_l[0] = 1.0;
+    buf_r[0] = 1.0;
+
+    // Odd block sizes exercise the partition boundaries.
+    for (l, r) in buf_l.chunks_mut(97).zip(buf_r.chunks_mut(97)) {
+        dsp.process(l, r);
+    }
+
+    buf_l
+}
+
+/// The largest deviation between `measured` and `expected` up to [`MAX_CORRECTION_HZ`],
+/// in decibels and degrees.
+fn max_error(
+    measured: impl Fn(f64) -> Complex64,
+    expected: impl Fn(f64) -> Complex64,
+) -> (f64, f64) {
+    const NUM_FREQS: usize = 200;
+
+    (0..=NUM_FREQS)
+        .map(|i| 20.0 * (MAX_CORRECTION_HZ / 20.0).powf(i as f64 / NUM_FREQS as f64))
+        .map(|freq_hz| {
+            let (measured, expected) = (measured(freq_hz), expected(freq_hz));
+            (
+                (amp_to_db(measured.norm()) - amp_to_db(expected.norm())).abs(),
+                (measured / expected).arg().to_degrees().abs(),
+            )
+        })
+        .fold((0.0, 0.0), |(db, deg), (e_db, e_deg)| {
+            (f64::max(db, e_db), f64::max(deg, e_deg))
+        })
+}
+
+#[test]
+fn analog_response_matches_the_prototypes() {
+    type Prototype = Box<dyn Fn(f64) -> Complex64>;
+
+    let s = |freq_hz: f64, cutoff_hz: f64| Complex64::new(0.0, freq_hz / cutoff_hz);
+    let a = |gain_db: f64| 10.0f64.powf(gain_db / 40.0);
+
+    let cases: [(&str, EqParams<NUM_BANDS>, Prototype); 4] = [
+        (
+            "lowpass",
+            EqParams {
+                lp_enabled: true,
+                lp_cutoff_hz: 10_000.0,
+                lp_q: 0.5,
+                ..EqParams::default()
+            },
+            Box::new(move |f| {
+                let s = s(f, 10_000.0);
+                1.0 / (s * s + s / 0.5 + 1.0)
+            }),
+        ),
+        (
+            "one-pole highpass",
+            EqParams {
+                hp_enabled: true,
+                hp_cutoff_hz: 5_000.0,
+                hp_order: FilterOrder::X1,
+                ..EqParams::default()
+            },
+            Box::new(move |f| {
+                let s = s(f, 5_000.0);
+                s / (s + 1.0)
+            }),
+        ),
+        (
+            "stacked bells",
+            EqParams {
+                bands: [band(BandType::Bell, 12_000.0, 2.0, 12.0); NUM_BANDS],
+                ..EqParams::default()
+            },
+            Box::new(move |f| {
+                let (s, a) = (s(f, 12_000.0), a(12.0));
+                ((s * s + s * a / 2.0 + 1.0) / (s * s + s / (2.0 * a) + 1.0)).powi(NUM_BANDS as i32)
+            }),
+        ),
+        (
+            "high shelf",
+            with_bands(&[band(BandType::HighShelf, 8_000.0, 0.7, 6.0)]),
+            Box::new(move |f| {
+                let a = a(6.0);
+                let s = s(f, 8_000.0) * a.sqrt();
+                (a * a * s * s + s * a / 0.7 + 1.0) / (s * s + s / 0.7 + 1.0)
+            }),
+        ),
+    ];
+
+    for (label, params, expected) in cases {
+        let mut dsp = MeadowEqDsp::<NUM_BANDS>::new(48_000.0);
+        dsp.set_params(params);
+
+        let (db, deg) = max_error(|f| dsp.analog_response(f), expected);
+        assert!(db < 0.01 && deg < 0.1, "{label}: {db:.4} dB, {deg:.3}°");
+    }
+}
+
+#[test]
+fn natural_phase_follows_the_analog_prototypes() {
+    let mut failures = Vec::new();
+
+    for sample_rate in SAMPLE_RATES {
+        for (label, params) in settings() {
+            let mut dsp = NaturalPhaseEqDsp::<NUM_BANDS>::new(sample_rate);
+            dsp.set_params(params);
+            dsp.reset();
+
+            let latency = dsp.latency_samples() as f64;
+            let response = impulse_response(&mut dsp, 1 << 17);
+
+            let (db, deg) = max_error(
+                |f| dtft(&response, f, sample_rate),
+                |f| {
+                    dsp.eq().analog_response(f)
+                        * Complex64::from_polar(1.0, -2.0 * PI * f * latency / sample_rate)
+                },
+            );
+            if db > NATURAL_PHASE_TOLERANCE_DB || deg > NATURAL_PHASE_TOLERANCE_DEG {
+                failures.push(format!("{sample_rate} Hz, {label}: {db:.3} dB, {deg:.2}°"));
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+/// Makes sure the test above tests something: without the correction, the console setting
+/// is well outside the tolerance at 44.1 kHz.
+#[test]
+fn minimum_phase_mode_differs_from_the_analog_prototypes() {
+    let (_, params) = settings().pop().unwrap();
+    let mut dsp = MeadowEqDsp::<NUM_BANDS>::new(44_100.0);
+    dsp.set_params(params);
+
+    let (db, deg) = max_error(|f| dsp.response(f), |f| dsp.analog_response(f));
+    assert!(
+        db > 4.0 * NATURAL_PHASE_TOLERANCE_DB && deg > 4.0 * NATURAL_PHASE_TOLERANCE_DEG,
+        "{db:.3} dB, {deg:.2}°"
+    );
+}
+
+#[test]
+fn latency_does_not_depend_on_the_parameters() {
+    for sample_rate in SAMPLE_RATES {
+        let mut dsp = NaturalPhaseEqDsp::<NUM_BANDS>::new(sample_rate);
+        let latency = dsp.latency_samples();
+
+        for (_, params) in settings() {
+            dsp.set_params(params);
+            assert_eq!(dsp.latency_samples(), latency);
+        }
+    }
+}
+
+/// Setting the parameters before every sample only redesigns the kernel at the partition
+/// boundaries, after which the output is the same as when they were set up front.
+#[test]
+fn kernel_follows_parameters_set_every_sample() {
+    const SIGNAL_LEN: usize = 4096;
+
+    let (_, params) = settings().pop().unwrap();
+    let mut up_front = NaturalPhaseEqDsp::<NUM_BANDS>::new(48_000.0);
+    up_front.set_params(params);
+    up_front.reset();
+    let mut every_sample = NaturalPhaseEqDsp::<NUM_BANDS>::new(48_000.0);
+
+    let mut expected = impulse_response(&mut up_front, SIGNAL_LEN);
+    let mut buf_l = vec![0.0; SIGNAL_LEN];
+    let mut buf_r = vec![0.0; SIGNAL_LEN];
+    buf_l[0] = 1.0;
+    buf_r[0] = 1.0;
+    for (l, r) in buf_l.chunks_mut(1).zip(buf_r.chunks_mut(1)) {
+        every_sample.set_params(params);
+        every_sample.process(l, r);
+    }
+
+    // The first kernel takes effect after one partition and is faded in over the next.
+    let settled = 3 * every_sample.latency_samples() as usize;
+    expected.drain(..settled);
+    for (n, (&x, &expected)) in buf_l[settled..].iter().zip(expected.iter()).enumerate() {
+        assert!(
+            (x - expected).abs() < 1e-6,
+            "sample {}: {x}, expected {expected}",
+            n + settled
+        );
+    }
+}
+
+/// Direct convolution of `signal` with `kernel`, delayed by `delay` samples.
+fn convolve(signal: &[f32], kernel: &[f32], delay: usize) -> Vec<f64> {
+    (0..signal.len())
+        .map(|n| {
+            (0..kernel.len())
+                .filter(|&k| n >= delay + k)
+                .map(|k| kernel[k] as f64 * signal[n - delay - k] as f64)
+                .sum()
+        })
+        .collect()
+}
+
+#[test]
+fn convolver_matches_direct_convolution() {
+    const PARTITION_LEN: usize = 64;
+    const KERNEL_LEN: usize = 300;
+    const SIGNAL_LEN: usize = 4096;
+    const SWITCH_AT: usize = 2048;
+
+    let mut rng_state = 1u32;
+    let mut noise = || {
+        rng_state ^= rng_state << 13;
+        rng_state ^= rng_state >> 17;
+        rng_state ^= rng_state << 5;
+        (rng_state as f32 / u32::MAX as f32) * 2.0 - 1.0
+    };
+    let kernel_a: Vec<f32> = (0..KERNEL_LEN).map(|_| noise() * 0.1).collect();
+    let kernel_b: Vec<f32> = (0..KERNEL_LEN - 100).map(|_| noise() * 0.1).collect();
+    let signal: Vec<f32> = (0..SIGNAL_LEN).map(|_| noise()).collect();
+
+    for block_size in [1, 13, 64, 100, 1024] {
+        let mut convolver = Convolver::new(KERNEL_LEN, PARTITION_LEN);
+        convolver.set_kernel(&kernel_a);
+        convolver.reset();
+        assert_eq!(convolver.latency_samples(), PARTITION_LEN);
+
+        let mut buf_l = signal.clone();
+        let mut buf_r: Vec<f32> = signal.iter().map(|x| -x).collect();
+        let mut pos = 0;
+        for (l, r) in buf_l
+            .chunks_mut(block_size)
+            .zip(buf_r.chunks_mut(block_size))
+        {
+            if pos <= SWITCH_AT && SWITCH_AT < pos + l.len() {
+                convolver.set_kernel(&kernel_b);
+            }
+            convolver.process(l, r);
+            pos += l.len();
+        }
+
+        let expected_a = convolve(&signal, &kernel_a, PARTITION_LEN);
+        let expected_b = convolve(&signal, &kernel_b, PARTITION_LEN);
+        // The new kernel is faded in over the partition that starts after it was set.
+        let fade_end = SWITCH_AT.next_multiple_of(PARTITION_LEN) + 2 * PARTITION_LEN;
+
+        for n in 0..SIGNAL_LEN {
+            let expected = if n < SWITCH_AT {
+                Some(expected_a[n])
+            } else if n >= fade_end {
+                Some(expected_b[n])
+            } else {
+                None
+            };
+
+            if let Some(expected) = expected {
+                assert!(
+                    (buf_l[n] as f64 - expected).abs() < 1e-4
+                        && (buf_r[n] as f64 + expected).abs() < 1e-4,
+                    "block size {block_size}, sample {n}: {} and {}, expected {expected}",
+                    buf_l[n],
+                    buf_r[n]
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,227 @@
+//! Checks that presets survive a round trip and load into EQs of any size.
+
+use crate::preset::{EqPreset, PRESET_FORMAT_VERSION, PresetError, factory_presets};
+use crate::{BandParams, BandType, EqParams, FilterOrder};
+
+const NUM_BANDS: usize = 4;
+
+fn band(band_type: BandType, cutoff_hz: f32, gain_db: f32) -> BandParams {
+    BandParams {
+        enabled: true,
+        band_type,
+        cutoff_hz,
+        q: 1.3,
+        gain_db,
+    }
+}
+
+/// Defaults that differ from [`BandParams::default()`] in every band, like a plugin's would.
+fn plugin_defaults() -> EqParams<NUM_BANDS> {
+    EqParams {
+        bands: std::array::from_fn(|i| BandParams {
+            enabled: false,
+            band_type: BandType::Bell,
+            cutoff_hz: 100.0 * (i + 1) as f32,
+            ..BandParams::default()
+        }),
+        ..EqParams::default()
+    }
+}
+
+fn example_params() -> EqParams<NUM_BANDS> {
+    let mut params = EqParams {
+        lp_enabled: true,
+        lp_cutoff_hz: 12_345.0,
+        lp_q: 0.9,
+        lp_order: FilterOrder::X6,
+        hp_enabled: true,
+        hp_cutoff_hz: 35.5,
+        hp_q: 1.1,
+        hp_order: FilterOrder::X1,
+        ..plugin_defaults()
+    };
+    params.bands[0] = band(BandType::LowShelf, 120.0, -4.5);
+    params.bands[1] = BandParams {
+        enabled: false,
+        ..band(BandType::Notch, 3_000.0, 0.0)
+    };
+    params.bands[3] = band(BandType::HighShelf, 9_000.0, 2.25);
+    params
+}
+
+#[test]
+fn json_round_trip_keeps_every_value() {
+    let params = example_params();
+    let preset = EqPreset::from_params("Round trip", &params);
+    let loaded = EqPreset::from_json(&preset.to_json()).unwrap();
+
+    assert_eq!(loaded, preset);
+    assert_eq!(loaded.to_params(&plugin_defaults()).unwrap(), params);
+}
+
+#[test]
+fn file_round_trip_keeps_every_value() {
+    let params = example_params();
+    let path = std::env::temp_dir().join(format!(
+        "meadow_eq_dsp_preset_test_{}.json",
+        std::process::id()
+    ));
+
+    EqPreset::from_params("File", &params).save(&path).unwrap();
+    let loaded = EqPreset::load(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(
+        loaded.unwrap().to_params(&plugin_defaults()).unwrap(),
+        params
+    );
+}
+
+#[test]
+fn unused_slots_get_the_plugins_defaults() {
+    let defaults = plugin_defaults();
+    let preset = EqPreset {
+        bands: vec![band(BandType::Bell, 1_000.0, 3.0)],
+        ..EqPreset::from_params("One band", &EqParams::<0>::default())
+    };
+
+    let params = preset.to_params(&defaults).unwrap();
+    assert_eq!(params.bands[0], preset.bands[0]);
+    assert_eq!(params.bands[1..], defaults.bands[1..]);
+}
+
+#[test]
+fn enabled_bands_are_packed_when_there_are_too_many_slots() {
+    let defaults = plugin_defaults();
+    let disabled = BandParams {
+        enabled: false,
+        ..band(BandType::Bell, 50.0, 0.0)
+    };
+    let enabled: Vec<BandParams> = (0..3)
+        .map(|i| band(BandType::Bell, 1_000.0 * (i + 1) as f32, 1.0))
+        .collect();
+    let preset = EqPreset {
+        bands: vec![
+            disabled, enabled[0], disabled, disabled, enabled[1], disabled, enabled[2],
+        ],
+        ..EqPreset::from_params("Sparse", &EqParams::<0>::default())
+    };
+
+    let params = preset.to_params(&defaults).unwrap();
+    assert_eq!(params.bands[..3], enabled[..]);
+    assert_eq!(params.bands[3], defaults.bands[3]);
+
+    let too_many = EqPreset {
+        bands: vec![enabled[0]; NUM_BANDS + 1],
+        ..preset
+    };
+    assert!(matches!(
+        too_many.to_params(&defaults),
+        Err(PresetError::TooManyBands {
+            enabled: 5,
+            max: NUM_BANDS
+        })
+    ));
+}
+
+#[test]
+fn format_version_is_checked() {
+    let preset = EqPreset::from_params("Versions", &example_params());
+
+    // Whatever the version of the preset, it is written as the current one.
+    let older = EqPreset {
+        format_version: 0,
+        ..preset.clone()
+    };
+    assert!(
+        older
+            .to_json()
+            .contains(&format!("\"format_version\": {PRESET_FORMAT_VERSION}"))
+    );
+
+    // Older versions still load.
+    let json = preset.to_json().replace(
+        &format!("\"format_version\": {PRESET_FORMAT_VERSION}"),
+        "\"format_version\": 0",
+    );
+    assert_eq!(EqPreset::from_json(&json).unwrap().format_version, 0);
+
+    // Newer ones are refused, whether they come from JSON or not.
+    let newer = PRESET_FORMAT_VERSION + 1;
+    let json = preset.to_json().replace(
+        &format!("\"format_version\": {PRESET_FORMAT_VERSION}"),
+        &format!("\"format_version\": {newer}"),
+    );
+    assert!(matches!(
+        EqPreset::from_json(&json),
+        Err(PresetError::UnsupportedVersion(v)) if v == newer
+    ));
+    let newer_preset = EqPreset {
+        format_version: newer,
+        ..preset
+    };
+    assert!(matches!(
+        newer_preset.to_params(&plugin_defaults()),
+        Err(PresetError::UnsupportedVersion(v)) if v == newer
+    ));
+
+    // The version can't be left out.
+    assert!(matches!(
+        EqPreset::from_json(r#"{ "name": "No version" }"#),
+        Err(PresetError::Json(_))
+    ));
+}
+
+#[test]
+fn missing_sections_get_the_default_settings() {
+    let preset = EqPreset::from_json(r#"{ "format_version": 1, "name": "Empty" }"#).unwrap();
+    let params = preset.to_params(&plugin_defaults()).unwrap();
+
+    assert_eq!(params, plugin_defaults());
+}
+
+#[test]
+fn factory_presets_are_valid() {
+    let presets = factory_presets();
+    assert!(!presets.is_empty());
+
+    for (i, preset) in presets.iter().enumerate() {
+        assert_eq!(
+            preset.format_version, PRESET_FORMAT_VERSION,
+            "{}",
+            preset.name
+        );
+        assert!(!preset.name.is_empty());
+        assert!(
+            presets[..i].iter().all(|other| other.name != preset.name),
+            "{} is there twice",
+            preset.name
+        );
+
+        // They fit in MeadowEq and survive a round trip.
+        let params = preset.to_params(&EqParams::<8>::default()).unwrap();
+        let reloaded = EqPreset::from_json(&preset.to_json()).unwrap();
+        assert_eq!(
+            reloaded.to_params(&EqParams::<8>::default()).unwrap(),
+            params
+        );
+
+        // Every value is inside MeadowEq's parameter ranges.
+        for (cutoff_hz, q) in [
+            (params.lp_cutoff_hz, params.lp_q),
+            (params.hp_cutoff_hz, params.hp_q),
+        ] {
+            assert!((20.0..=21_480.0).contains(&cutoff_hz), "{}", preset.name);
+            assert!((0.3..=8.0).contains(&q), "{}", preset.name);
+        }
+        for band in &preset.bands {
+            assert!(
+                (20.0..=21_480.0).contains(&band.cutoff_hz),
+                "{}",
+                preset.name
+            );
+            assert!((0.02..=40.0).contains(&band.q), "{}", preset.name);
+            assert!((-30.0..=30.0).contains(&band.gain_db), "{}", preset.name);
+        }
+    }
+}
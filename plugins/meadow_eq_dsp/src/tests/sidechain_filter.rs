@@ -0,0 +1,50 @@
+//! Checks the sidechain filter's bypass and its slopes.
+
+use std::f64::consts::PI;
+
+use crate::amp_to_db;
+use crate::sidechain_filter::{SidechainFilter, SidechainFilterParams};
+
+const SAMPLE_RATE: f64 = 48_000.0;
+const SETTLE_LEN: usize = 48_000;
+const MEASURE_LEN: usize = 4_800;
+
+/// The level of the filtered left channel of a unit sine at `freq_hz`, in dB.
+fn level_db(params: &SidechainFilterParams, freq_hz: f64) -> f64 {
+    let mut filter = SidechainFilter::new(params, SAMPLE_RATE);
+
+    let mut sum = 0.0;
+    for n in 0..SETTLE_LEN + MEASURE_LEN {
+        let x = (2.0 * PI * freq_hz * n as f64 / SAMPLE_RATE).sin() as f32;
+        let [l, _] = filter.tick([x, x]);
+        if n >= SETTLE_LEN {
+            sum += (l as f64).powi(2);
+        }
+    }
+
+    // A unit sine's RMS is -3 dB.
+    amp_to_db((2.0 * sum / MEASURE_LEN as f64).sqrt())
+}
+
+#[test]
+fn disabled_filters_pass_through() {
+    let mut filter = SidechainFilter::new(&SidechainFilterParams::default(), SAMPLE_RATE);
+    for x in [1.0, -0.5, 0.25, 0.0] {
+        assert_eq!(filter.tick([x, -x]), [x, -x]);
+    }
+}
+
+#[test]
+fn filters_cut_outside_their_cutoffs() {
+    let params = SidechainFilterParams {
+        hp_enabled: true,
+        hp_cutoff_hz: 200.0,
+        lp_enabled: true,
+        lp_cutoff_hz: 5_000.0,
+    };
+
+    assert!(level_db(&params, 1_000.0).abs() < 0.5);
+    // Two octaves out, a second order filter is 24 dB down.
+    assert!(level_db(&params, 50.0) < -22.0);
+    assert!(level_db(&params, 20_000.0) < -22.0);
+}
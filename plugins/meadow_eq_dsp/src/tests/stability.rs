@@ -0,0 +1,256 @@
+//! Drives the EQ with random automation and checks that nothing ever blows up.
+
+use proptest::prelude::*;
+
+use crate::{BandParams, BandType, EqParams, FilterOrder, MeadowEqDsp, SvfCoeff};
+
+const NUM_BANDS: usize = 8;
+
+const SAMPLE_RATES: [f64; 7] = [
+    22_050.0, 32_000.0, 44_100.0, 48_000.0, 88_200.0, 96_000.0, 192_000.0,
+];
+
+/// Headroom on top of the summed band boosts for resonant cut filters and the transients
+/// caused by parameter jumps.
+const OUTPUT_HEADROOM_DB: f32 = 60.0;
+
+/// The parameter ranges exposed by the plugin.
+const MIN_CUTOFF_HZ: f32 = 20.0;
+const MAX_CUTOFF_HZ: f32 = 21_480.0;
+const MIN_CUT_Q: f32 = 0.3;
+const MAX_CUT_Q: f32 = 8.0;
+const MIN_BAND_Q: f32 = 0.02;
+const MAX_BAND_Q: f32 = 40.0;
+const MAX_GAIN_DB: f32 = 30.0;
+
+/// A value from a logarithmic range, with the ends of the range showing up often.
+fn log_range(min: f32, max: f32) -> impl Strategy<Value = f32> {
+    prop_oneof![
+        1 => Just(min),
+        1 => Just(max),
+        8 => (0.0f32..=1.0).prop_map(move |t| min * (max / min).powf(t)),
+    ]
+}
+
+fn gain_db() -> impl Strategy<Value = f32> {
+    prop_oneof![
+        1 => Just(-MAX_GAIN_DB),
+        1 => Just(MAX_GAIN_DB),
+        8 => -MAX_GAIN_DB..=MAX_GAIN_DB,
+    ]
+}
+
+fn band_params() -> impl Strategy<Value = BandParams> {
+    (
+        any::<bool>(),
+        0u32..5,
+        log_range(MIN_CUTOFF_HZ, MAX_CUTOFF_HZ),
+        log_range(MIN_BAND_Q, MAX_BAND_Q),
+        gain_db(),
+    )
+        .prop_map(|(enabled, band_type, cutoff_hz, q, gain_db)| BandParams {
+            enabled,
+            band_type: BandType::from_u32(band_type),
+            cutoff_hz,
+            q,
+            gain_db,
+        })
+}
+
+fn eq_params() -> impl Strategy<Value = EqParams<NUM_BANDS>> {
+    let cut = || {
+        (
+            any::<bool>(),
+            log_range(MIN_CUTOFF_HZ, MAX_CUTOFF_HZ),
+            log_range(MIN_CUT_Q, MAX_CUT_Q),
+            0u32..5,
+        )
+    };
+
+    (cut(), cut(), prop::array::uniform8(band_params())).prop_map(|(lp, hp, bands)| EqParams {
+        lp_enabled: lp.0,
+        lp_cutoff_hz: lp.1,
+        lp_q: lp.2,
+        lp_order: FilterOrder::from_u32(lp.3),
+
+        hp_enabled: hp.0,
+        hp_cutoff_hz: hp.1,
+        hp_q: hp.2,
+        hp_order: FilterOrder::from_u32(hp.3),
+
+        bands,
+    })
+}
+
+fn block_size() -> impl Strategy<Value = usize> {
+    prop_oneof![Just(1usize), 1usize..64, 64usize..=4096]
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Signal {
+    Noise,
+    Dc,
+    Nyquist,
+}
+
+fn signal() -> impl Strategy<Value = Signal> {
+    prop_oneof![Just(Signal::Noise), Just(Signal::Dc), Just(Signal::Nyquist)]
+}
+
+/// Fills the buffer with a full-scale test signal. The noise comes from a xorshift
+/// generator so that it doesn't need to be shrunk by proptest.
+fn fill(buffer: &mut [f32], signal: Signal, rng_state: &mut u32) {
+    for (i, sample) in buffer.iter_mut().enumerate() {
+        *sample = match signal {
+            Signal::Noise => {
+                *rng_state ^= *rng_state << 13;
+                *rng_state ^= *rng_state >> 17;
+                *rng_state ^= *rng_state << 5;
+                (*rng_state as f32 / u32::MAX as f32) * 2.0 - 1.0
+            }
+            Signal::Dc => 1.0,
+            Signal::Nyquist => {
+                if i % 2 == 0 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+        };
+    }
+}
+
+/// The summed boost of all enabled bands.
+fn boost_db(params: &EqParams<NUM_BANDS>) -> f32 {
+    params
+        .bands
+        .iter()
+        .filter(|b| b.enabled)
+        .map(|b| b.gain_db.max(0.0))
+        .sum()
+}
+
+impl<const N: usize> MeadowEqDsp<N> {
+    fn states_are_finite(&self) -> bool {
+        let cut_bands = [&self.lp_band, &self.hp_band].into_iter().all(|band| {
+            band.one_pole_state.iter().all(|s| s.z1.is_finite())
+                && [band.state_0, band.state_1, band.state_2, band.state_3]
+                    .iter()
+                    .flatten()
+                    .all(|s| s.ic1eq.is_finite() && s.ic2eq.is_finite())
+        });
+        let bands = self
+            .bands
+            .iter()
+            .flat_map(|b| b.state.iter())
+            .all(|s| s.ic1eq.is_finite() && s.ic2eq.is_finite());
+
+        cut_bands && bands
+    }
+
+    /// Every set of SVF coefficients that is currently in use.
+    fn svf_coeffs(&self) -> Vec<SvfCoeff> {
+        let mut coeffs = Vec::new();
+
+        for band in [&self.lp_band, &self.hp_band] {
+            if band.enabled {
+                let num_sections = match band.order {
+                    FilterOrder::X1 => 0,
+                    FilterOrder::X2 => 1,
+                    FilterOrder::X4 => 2,
+                    FilterOrder::X6 => 3,
+                    FilterOrder::X8 => 4,
+                };
+                coeffs.extend(
+                    [band.coeff_0, band.coeff_1, band.coeff_2, band.coeff_3]
+                        .into_iter()
+                        .take(num_sections),
+                );
+            }
+        }
+        coeffs.extend(self.bands.iter().filter(|b| b.enabled).map(|b| b.coeff));
+
+        coeffs
+    }
+}
+
+/// The largest magnitude of the eigenvalues of the SVF's state transition matrix, which must
+/// be below 1 for the filter to be stable. This uses the same state-space form as
+/// [`SvfCoeff::response`].
+fn spectral_radius(coeff: &SvfCoeff) -> f64 {
+    let (a1, a2, a3) = (coeff.a1 as f64, coeff.a2 as f64, coeff.a3 as f64);
+    let (a11, a12, a21, a22) = (2.0 * a1 - 1.0, -2.0 * a2, 2.0 * a2, 1.0 - 2.0 * a3);
+
+    let trace = a11 + a22;
+    let det = a11 * a22 - a12 * a21;
+    let discriminant = trace * trace - 4.0 * det;
+
+    if discriminant >= 0.0 {
+        let root = discriminant.sqrt();
+        ((trace + root) * 0.5)
+            .abs()
+            .max(((trace - root) * 0.5).abs())
+    } else {
+        // Complex conjugate poles.
+        det.sqrt()
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn output_stays_finite_and_bounded_under_automation(
+        sample_rate in prop::sample::select(&SAMPLE_RATES[..]),
+        steps in prop::collection::vec((eq_params(), block_size(), signal()), 1..12),
+        seed in 1u32..,
+    ) {
+        let mut dsp = MeadowEqDsp::<NUM_BANDS>::new(sample_rate);
+        let mut rng_state = seed;
+        let mut buf_l = Vec::new();
+        let mut buf_r = Vec::new();
+        // Energy stored in the filters during one step can be boosted again after the next
+        // parameter change, so the bound for a full-scale input grows with every step's
+        // boost. An unstable filter grows exponentially and still crosses it quickly.
+        let mut total_boost_db = 0.0f32;
+
+        for (step, (params, block_size, signal)) in steps.iter().enumerate() {
+            dsp.set_params(*params);
+
+            buf_l.resize(*block_size, 0.0);
+            buf_r.resize(*block_size, 0.0);
+            fill(&mut buf_l, *signal, &mut rng_state);
+            fill(&mut buf_r, *signal, &mut rng_state);
+
+            dsp.process(&mut buf_l, &mut buf_r);
+
+            total_boost_db += boost_db(params);
+            let bound = 10.0f32.powf((total_boost_db + OUTPUT_HEADROOM_DB) / 20.0);
+            for &y in buf_l.iter().chain(buf_r.iter()) {
+                prop_assert!(y.is_finite(), "step {step}: non-finite output {y}");
+                prop_assert!(y.abs() <= bound, "step {step}: output {y} exceeds {bound}");
+            }
+            prop_assert!(dsp.states_are_finite(), "step {step}: non-finite filter state");
+        }
+    }
+
+    #[test]
+    fn every_filter_is_stable(
+        sample_rate in prop::sample::select(&SAMPLE_RATES[..]),
+        params in eq_params(),
+    ) {
+        let mut dsp = MeadowEqDsp::<NUM_BANDS>::new(sample_rate);
+        dsp.set_params(params);
+
+        for coeff in dsp.svf_coeffs() {
+            let radius = spectral_radius(&coeff);
+            prop_assert!(radius < 1.0, "pole radius {radius} for {params:?}");
+        }
+        for band in [&dsp.lp_band, &dsp.hp_band] {
+            if band.enabled && band.order == FilterOrder::X1 {
+                let b1 = band.one_pole_coeff.b1;
+                prop_assert!(b1.abs() < 1.0, "one-pole pole {b1} for {params:?}");
+            }
+        }
+    }
+}
@@ -0,0 +1,211 @@
+//! Checks that the audio thread API of `MeadowEqDsp` never allocates.
+//!
+//! `set_params` and `process` are run inside `assert_no_alloc` with its allocator installed
+//! for this test binary. A panic also allocates its payload, so it shows up here as well as
+//! failing the test directly.
+
+use assert_no_alloc::{AllocDisabler, assert_no_alloc, reset_violation_count, violation_count};
+use meadow_eq_dsp::{BandParams, BandType, EqParams, FilterOrder, MeadowEqDsp};
+
+#[global_allocator]
+static ALLOCATOR: AllocDisabler = AllocDisabler;
+
+const NUM_BANDS: usize = 8;
+const SAMPLE_RATES: [f64; 4] = [22_050.0, 44_100.0, 48_000.0, 192_000.0];
+const BLOCK_SIZES: [usize; 7] = [0, 1, 2, 63, 64, 512, MAX_BLOCK_SIZE];
+const MAX_BLOCK_SIZE: usize = 4096;
+const ORDERS: [FilterOrder; 5] = [
+    FilterOrder::X1,
+    FilterOrder::X2,
+    FilterOrder::X4,
+    FilterOrder::X6,
+    FilterOrder::X8,
+];
+const BAND_TYPES: [BandType; 5] = [
+    BandType::Bell,
+    BandType::LowShelf,
+    BandType::HighShelf,
+    BandType::Notch,
+    BandType::Allpass,
+];
+
+/// Runs `f` and fails the test if it allocated.
+fn assert_realtime_safe(label: &str, f: impl FnOnce()) {
+    reset_violation_count();
+    assert_no_alloc(f);
+
+    let violations = violation_count();
+    assert_eq!(violations, 0, "{label}: {violations} allocations");
+}
+
+/// Every combination of cut filter orders, with all bands enabled and cycling through the
+/// band types. This covers the largest filter list `process` can build.
+fn all_settings() -> Vec<EqParams<NUM_BANDS>> {
+    let mut settings = vec![EqParams::default()];
+
+    for (i, &lp_order) in ORDERS.iter().enumerate() {
+        for (j, &hp_order) in ORDERS.iter().enumerate() {
+            settings.push(EqParams {
+                lp_enabled: true,
+                lp_cutoff_hz: 21_480.0,
+                lp_q: 8.0,
+                lp_order,
+                hp_enabled: true,
+                hp_cutoff_hz: 20.0,
+                hp_q: 0.3,
+                hp_order,
+                bands: std::array::from_fn(|band| BandParams {
+                    enabled: true,
+                    band_type: BAND_TYPES[(band + i + j) % BAND_TYPES.len()],
+                    cutoff_hz: 40.0 * 2.0f32.powi(band as i32),
+                    q: if band % 2 == 0 { 0.02 } else { 40.0 },
+                    gain_db: if band % 2 == 0 { 30.0 } else { -30.0 },
+                }),
+            });
+        }
+    }
+
+    settings
+}
+
+#[test]
+fn process_does_not_allocate() {
+    let settings = all_settings();
+    let mut buf_l = vec![0.0f32; MAX_BLOCK_SIZE];
+    let mut buf_r = vec![0.0f32; MAX_BLOCK_SIZE];
+
+    for sample_rate in SAMPLE_RATES {
+        let mut dsp = MeadowEqDsp::<NUM_BANDS>::new(sample_rate);
+
+        for (i, params) in settings.iter().enumerate() {
+            for block_size in BLOCK_SIZES {
+                let (buf_l, buf_r) = (&mut buf_l[..block_size], &mut buf_r[..block_size]);
+                buf_l.fill(0.5);
+                buf_r.fill(-0.5);
+
+                assert_realtime_safe(
+                    &format!("{sample_rate} Hz, setting {i}, {block_size} samples"),
+                    || {
+                        dsp.set_params(*params);
+                        dsp.process(buf_l, buf_r);
+                    },
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn crossfade_does_not_allocate() {
+    use meadow_eq_dsp::crossfade::CrossfadeEqDsp;
+
+    let settings = all_settings();
+    let mut buf_l = vec![0.0f32; MAX_BLOCK_SIZE];
+    let mut buf_r = vec![0.0f32; MAX_BLOCK_SIZE];
+
+    for sample_rate in SAMPLE_RATES {
+        let mut dsp = CrossfadeEqDsp::<NUM_BANDS>::new(sample_rate);
+
+        for (i, params) in settings.iter().enumerate() {
+            for block_size in BLOCK_SIZES {
+                let (buf_l, buf_r) = (&mut buf_l[..block_size], &mut buf_r[..block_size]);
+                buf_l.fill(0.5);
+                buf_r.fill(-0.5);
+
+                assert_realtime_safe(
+                    &format!("crossfade, {sample_rate} Hz, setting {i}, {block_size} samples"),
+                    || {
+                        dsp.crossfade_to(*params);
+                        dsp.process(buf_l, buf_r);
+                    },
+                );
+            }
+        }
+    }
+}
+
+#[cfg(feature = "natural-phase")]
+#[test]
+fn natural_phase_does_not_allocate() {
+    use meadow_eq_dsp::natural_phase::NaturalPhaseEqDsp;
+
+    let settings = all_settings();
+    let mut buf_l = vec![0.0f32; MAX_BLOCK_SIZE];
+    let mut buf_r = vec![0.0f32; MAX_BLOCK_SIZE];
+
+    for sample_rate in SAMPLE_RATES {
+        let mut dsp = NaturalPhaseEqDsp::<NUM_BANDS>::new(sample_rate);
+
+        for (i, params) in settings.iter().enumerate() {
+            for block_size in BLOCK_SIZES {
+                let (buf_l, buf_r) = (&mut buf_l[..block_size], &mut buf_r[..block_size]);
+                buf_l.fill(0.5);
+                buf_r.fill(-0.5);
+
+                assert_realtime_safe(
+                    &format!("natural phase, {sample_rate} Hz, setting {i}, {block_size} samples"),
+                    || {
+                        if i % 2 == 0 {
+                            dsp.set_params(*params);
+                        } else {
+                            dsp.crossfade_to(*params);
+                        }
+                        dsp.process(buf_l, buf_r);
+                        dsp.reset();
+                    },
+                );
+            }
+        }
+    }
+}
+
+#[cfg(feature = "linear-phase")]
+#[test]
+fn linear_phase_does_not_allocate() {
+    use meadow_eq_dsp::linear_phase::{KernelDesigner, LinearPhaseEqDsp};
+
+    let settings = all_settings();
+    let mut buf_l = vec![0.0f32; MAX_BLOCK_SIZE];
+    let mut buf_r = vec![0.0f32; MAX_BLOCK_SIZE];
+
+    for sample_rate in SAMPLE_RATES {
+        let mut designer = KernelDesigner::<NUM_BANDS>::new(sample_rate);
+        let mut kernel = vec![0.0f32; designer.kernel_len()];
+        let mut dsp = LinearPhaseEqDsp::new(sample_rate);
+
+        for (i, params) in settings.iter().enumerate() {
+            assert_realtime_safe(
+                &format!("kernel design, {sample_rate} Hz, setting {i}"),
+                || designer.design(*params, &mut kernel),
+            );
+
+            for block_size in BLOCK_SIZES {
+                let (buf_l, buf_r) = (&mut buf_l[..block_size], &mut buf_r[..block_size]);
+                buf_l.fill(0.5);
+                buf_r.fill(-0.5);
+
+                assert_realtime_safe(
+                    &format!("linear phase, {sample_rate} Hz, setting {i}, {block_size} samples"),
+                    || {
+                        dsp.set_kernel(&kernel);
+                        dsp.process(buf_l, buf_r);
+                        dsp.reset();
+                    },
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn process_handles_mismatched_buffers() {
+    let mut dsp = MeadowEqDsp::<NUM_BANDS>::new(48_000.0);
+    dsp.set_params(all_settings()[1]);
+    let mut buf_l = vec![0.5f32; 256];
+    let mut buf_r = vec![0.5f32; 128];
+
+    assert_realtime_safe("mismatched buffers", || {
+        dsp.process(&mut buf_l, &mut buf_r);
+        dsp.process(&mut buf_r, &mut buf_l);
+    });
+}
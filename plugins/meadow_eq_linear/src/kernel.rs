@@ -0,0 +1,59 @@
+use meadow_eq_dsp::EqParams;
+use meadow_eq_dsp::linear_phase::KernelDesigner;
+
+use crate::NUM_BANDS;
+
+pub type ParamsInput = triple_buffer::Input<EqParams<NUM_BANDS>>;
+pub type KernelOutput = triple_buffer::Output<Vec<f32>>;
+
+/// Designs kernels on the background thread.
+///
+/// The audio thread sends the latest parameters through a [`ParamsInput`] and then asks for
+/// a rebuild with a background task. The finished kernel comes back through a
+/// [`KernelOutput`]. As only the latest parameters are kept, tasks that pile up while the
+/// parameters are being automated do nothing but return.
+pub struct KernelBuilder {
+    designer: KernelDesigner<NUM_BANDS>,
+    /// The parameters of the last kernel that was designed.
+    designed_params: EqParams<NUM_BANDS>,
+
+    params_output: triple_buffer::Output<EqParams<NUM_BANDS>>,
+    kernel_input: triple_buffer::Input<Vec<f32>>,
+}
+
+impl KernelBuilder {
+    /// Also designs the kernel for `params`, which is ready to be read from the returned
+    /// [`KernelOutput`].
+    pub fn new(sample_rate: f64, params: EqParams<NUM_BANDS>) -> (Self, ParamsInput, KernelOutput) {
+        let designer = KernelDesigner::new(sample_rate);
+
+        let (params_input, params_output) = triple_buffer::TripleBuffer::new(&params).split();
+        let (kernel_input, kernel_output) =
+            triple_buffer::TripleBuffer::new(&vec![0.0; designer.kernel_len()]).split();
+
+        let mut new_self = Self {
+            designer,
+            designed_params: params,
+            params_output,
+            kernel_input,
+        };
+        new_self.design(params);
+
+        (new_self, params_input, kernel_output)
+    }
+
+    /// Designs a kernel for the latest parameters, unless that has already been done.
+    pub fn rebuild(&mut self) {
+        let params = *self.params_output.read();
+        if params != self.designed_params {
+            self.design(params);
+        }
+    }
+
+    fn design(&mut self, params: EqParams<NUM_BANDS>) {
+        self.designed_params = params;
+        self.designer
+            .design(params, self.kernel_input.input_buffer());
+        self.kernel_input.publish();
+    }
+}
@@ -0,0 +1,189 @@
+use meadow_eq_dsp::EqParams;
+use meadow_eq_dsp::linear_phase::LinearPhaseEqDsp;
+use meadow_eq_params::{EqParamsGroup, NUM_BANDS};
+use nih_plug::prelude::*;
+use std::sync::{Arc, Mutex};
+
+mod kernel;
+
+use kernel::{KernelBuilder, KernelOutput, ParamsInput};
+
+/// A linear phase version of Meadow Eq. It has the same filters, but only their magnitude
+/// response is applied, at the cost of latency.
+pub struct MeadowEqLinear {
+    params: Arc<MeadowEqLinearParams>,
+    dsp: LinearPhaseEqDsp,
+
+    /// The parameters the most recent kernel was requested for.
+    requested_params: EqParams<NUM_BANDS>,
+    params_input: ParamsInput,
+    kernel_output: KernelOutput,
+    /// Shared with the background task. The audio thread never locks this.
+    kernel_builder: Arc<Mutex<KernelBuilder>>,
+}
+
+/// Asks the background thread to design a kernel for the latest parameters.
+pub struct RebuildKernel;
+
+#[derive(Params)]
+struct MeadowEqLinearParams {
+    #[nested]
+    pub eq: EqParamsGroup,
+}
+
+impl Default for MeadowEqLinear {
+    fn default() -> Self {
+        let params = Arc::new(MeadowEqLinearParams::default());
+        let eq_params = params.eq.eq_params();
+
+        let (kernel_builder, params_input, mut kernel_output) =
+            KernelBuilder::new(44_100.0, eq_params);
+        let mut dsp = LinearPhaseEqDsp::new(44_100.0);
+        dsp.set_kernel(kernel_output.read());
+
+        Self {
+            params,
+            dsp,
+            requested_params: eq_params,
+            params_input,
+            kernel_output,
+            kernel_builder: Arc::new(Mutex::new(kernel_builder)),
+        }
+    }
+}
+
+impl Default for MeadowEqLinearParams {
+    fn default() -> Self {
+        Self {
+            eq: EqParamsGroup::without_allpass(),
+        }
+    }
+}
+
+impl Plugin for MeadowEqLinear {
+    const NAME: &'static str = "Meadow Eq Linear";
+    const VENDOR: &'static str = "Billy Messenger";
+    const URL: &'static str = env!("CARGO_PKG_HOMEPAGE");
+    const EMAIL: &'static str = "60663878+BillyDM@users.noreply.github.com";
+
+    const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+
+    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[AudioIOLayout {
+        main_input_channels: NonZeroU32::new(2),
+        main_output_channels: NonZeroU32::new(2),
+
+        aux_input_ports: &[],
+        aux_output_ports: &[],
+
+        names: PortNames::const_default(),
+    }];
+
+    const MIDI_INPUT: MidiConfig = MidiConfig::None;
+    const MIDI_OUTPUT: MidiConfig = MidiConfig::None;
+
+    // A new kernel is requested at most once per block, so splitting the blocks at parameter
+    // changes would gain nothing.
+    const SAMPLE_ACCURATE_AUTOMATION: bool = false;
+
+    type SysExMessage = ();
+    type BackgroundTask = RebuildKernel;
+
+    fn params(&self) -> Arc<dyn Params> {
+        self.params.clone()
+    }
+
+    fn task_executor(&mut self) -> TaskExecutor<Self> {
+        let kernel_builder = self.kernel_builder.clone();
+
+        Box::new(move |RebuildKernel| {
+            if let Ok(mut kernel_builder) = kernel_builder.lock() {
+                kernel_builder.rebuild();
+            }
+        })
+    }
+
+    fn initialize(
+        &mut self,
+        _audio_io_layout: &AudioIOLayout,
+        config: &BufferConfig,
+        context: &mut impl InitContext<Self>,
+    ) -> bool {
+        let sample_rate = config.sample_rate as f64;
+        let eq_params = self.params.eq.eq_params();
+
+        // The kernel for the current parameters is designed right here, so the plugin does
+        // not start out flat.
+        let (kernel_builder, params_input, mut kernel_output) =
+            KernelBuilder::new(sample_rate, eq_params);
+        self.dsp = LinearPhaseEqDsp::new(sample_rate);
+        self.dsp.set_kernel(kernel_output.read());
+
+        self.requested_params = eq_params;
+        self.params_input = params_input;
+        self.kernel_output = kernel_output;
+        // This waits for a kernel that is still being designed for the old sample rate.
+        if let Ok(mut old_kernel_builder) = self.kernel_builder.lock() {
+            *old_kernel_builder = kernel_builder;
+        }
+
+        context.set_latency_samples(self.dsp.latency_samples());
+
+        true
+    }
+
+    fn reset(&mut self) {
+        self.dsp.reset();
+    }
+
+    fn process(
+        &mut self,
+        buffer: &mut Buffer,
+        _aux: &mut AuxiliaryBuffers,
+        context: &mut impl ProcessContext<Self>,
+    ) -> ProcessStatus {
+        let params = self.params.eq.eq_params();
+        if params != self.requested_params {
+            self.requested_params = params;
+            self.params_input.write(params);
+            context.execute_background(RebuildKernel);
+        }
+
+        // The convolver crossfades to the new kernel.
+        if self.kernel_output.update() {
+            self.dsp.set_kernel(self.kernel_output.output_buffer());
+        }
+
+        let [out_l, out_r, ..] = buffer.as_slice() else {
+            return ProcessStatus::Normal;
+        };
+
+        self.dsp.process(out_l, out_r);
+
+        ProcessStatus::Normal
+    }
+}
+
+impl ClapPlugin for MeadowEqLinear {
+    const CLAP_ID: &'static str = "app.meadowlark.meadow-eq-linear";
+    const CLAP_DESCRIPTION: Option<&'static str> = Some("A linear phase parametric EQ");
+    const CLAP_MANUAL_URL: Option<&'static str> = Some(Self::URL);
+    const CLAP_SUPPORT_URL: Option<&'static str> = None;
+
+    const CLAP_FEATURES: &'static [ClapFeature] = &[
+        ClapFeature::AudioEffect,
+        ClapFeature::Stereo,
+        ClapFeature::Equalizer,
+    ];
+}
+
+impl Vst3Plugin for MeadowEqLinear {
+    const VST3_CLASS_ID: [u8; 16] = *b"Meadowlark.LinEQ";
+
+    const VST3_SUBCATEGORIES: &'static [Vst3SubCategory] =
+        &[Vst3SubCategory::Fx, Vst3SubCategory::Eq];
+}
+
+#[cfg(not(feature = "bundled"))]
+nih_export_clap!(MeadowEqLinear);
+#[cfg(not(feature = "bundled"))]
+nih_export_vst3!(MeadowEqLinear);
@@ -0,0 +1,321 @@
+//! The parameters Meadow Eq and Meadow Eq Linear share, so both plugins have the same ranges,
+//! defaults and IDs, and a session or preset made with one sounds the same in the other.
+//!
+//! The plugins include [`EqParamsGroup`] with a flat `#[nested]` attribute, which keeps the
+//! IDs the parameters had when every plugin declared them itself.
+
+use meadow_eq_dsp::{BandParams, BandType, DEFAULT_Q, EqParams, FilterOrder};
+use nih_plug::prelude::*;
+use std::sync::Arc;
+
+pub const NUM_BANDS: usize = 8;
+
+/// How long the float parameters take to reach a new value. Modulation from the host goes
+/// through the same smoothers, so it is applied per sample without zipper noise.
+pub const PARAM_SMOOTHING_MS: f32 = 10.0;
+
+/// Band 1 keeps the 1000 Hz it had when it was the only band, so sessions that rely on its
+/// default still load the same.
+pub const DEFAULT_BAND_CUTOFFS_HZ: [f32; NUM_BANDS] =
+    [1000.0, 80.0, 200.0, 500.0, 2000.0, 4000.0, 8000.0, 12000.0];
+
+/// The cut filters and bands.
+#[derive(Params)]
+pub struct EqParamsGroup {
+    #[id = "lp_enabled"]
+    pub lp_enabled: BoolParam,
+    #[id = "lp_cutoff_hz"]
+    pub lp_cutoff_hz: FloatParam,
+    #[id = "lp_q"]
+    pub lp_q: FloatParam,
+    #[id = "lp_order"]
+    pub lp_order: IntParam,
+
+    #[id = "hp_enabled"]
+    pub hp_enabled: BoolParam,
+    #[id = "hp_cutoff_hz"]
+    pub hp_cutoff_hz: FloatParam,
+    #[id = "hp_q"]
+    pub hp_q: FloatParam,
+    #[id = "hp_order"]
+    pub hp_order: IntParam,
+
+    #[nested(id_prefix = "band_1", group = "Band 1")]
+    pub band_1: BandParamsGroup,
+    #[nested(id_prefix = "band_2", group = "Band 2")]
+    pub band_2: BandParamsGroup,
+    #[nested(id_prefix = "band_3", group = "Band 3")]
+    pub band_3: BandParamsGroup,
+    #[nested(id_prefix = "band_4", group = "Band 4")]
+    pub band_4: BandParamsGroup,
+    #[nested(id_prefix = "band_5", group = "Band 5")]
+    pub band_5: BandParamsGroup,
+    #[nested(id_prefix = "band_6", group = "Band 6")]
+    pub band_6: BandParamsGroup,
+    #[nested(id_prefix = "band_7", group = "Band 7")]
+    pub band_7: BandParamsGroup,
+    #[nested(id_prefix = "band_8", group = "Band 8")]
+    pub band_8: BandParamsGroup,
+}
+
+#[derive(Params)]
+pub struct BandParamsGroup {
+    #[id = "enabled"]
+    pub enabled: BoolParam,
+    #[id = "type"]
+    pub band_type: IntParam,
+    #[id = "cutoff_hz"]
+    pub cutoff_hz: FloatParam,
+    #[id = "q"]
+    pub q: FloatParam,
+    #[id = "gain_db"]
+    pub gain_db: FloatParam,
+}
+
+fn cutoff_range() -> FloatRange {
+    FloatRange::Skewed {
+        min: 20.0,
+        max: 21_480.0,
+        factor: FloatRange::skew_factor(-2.0),
+    }
+}
+
+fn order_to_string() -> Arc<dyn Fn(i32) -> String + Send + Sync> {
+    Arc::new(|v| match v {
+        0 => String::from("x1"),
+        1 => String::from("x2"),
+        2 => String::from("x4"),
+        3 => String::from("x6"),
+        _ => String::from("x8"),
+    })
+}
+
+impl Default for EqParamsGroup {
+    /// Bands of every type.
+    fn default() -> Self {
+        Self::new(BandType::Allpass)
+    }
+}
+
+impl EqParamsGroup {
+    /// Bands without the allpass type, which would do nothing without its phase response.
+    pub fn without_allpass() -> Self {
+        Self::new(BandType::Notch)
+    }
+
+    /// `last_band_type` is the last of the [`BandType`]s the band type parameters go up to.
+    fn new(last_band_type: BandType) -> Self {
+        let q_range_1 = FloatRange::SymmetricalSkewed {
+            min: 0.3,
+            max: 8.0,
+            factor: 0.85,
+            center: 1.5,
+        };
+
+        Self {
+            lp_enabled: BoolParam::new("LP enabled", false),
+            lp_cutoff_hz: FloatParam::new("LP cutoff", 21_480.0, cutoff_range())
+                .with_smoother(SmoothingStyle::Logarithmic(PARAM_SMOOTHING_MS)),
+            lp_q: FloatParam::new("LP Q", DEFAULT_Q, q_range_1.clone())
+                .with_smoother(SmoothingStyle::Logarithmic(PARAM_SMOOTHING_MS)),
+            lp_order: IntParam::new("LP order", 1, IntRange::Linear { min: 0, max: 4 })
+                .with_value_to_string(order_to_string()),
+
+            hp_enabled: BoolParam::new("HP enabled", false),
+            hp_cutoff_hz: FloatParam::new("HP cutoff", 20.0, cutoff_range())
+                .with_smoother(SmoothingStyle::Logarithmic(PARAM_SMOOTHING_MS)),
+            hp_q: FloatParam::new("HP Q", DEFAULT_Q, q_range_1)
+                .with_smoother(SmoothingStyle::Logarithmic(PARAM_SMOOTHING_MS)),
+            hp_order: IntParam::new("HP order", 1, IntRange::Linear { min: 0, max: 4 })
+                .with_value_to_string(order_to_string()),
+
+            band_1: BandParamsGroup::new(0, last_band_type),
+            band_2: BandParamsGroup::new(1, last_band_type),
+            band_3: BandParamsGroup::new(2, last_band_type),
+            band_4: BandParamsGroup::new(3, last_band_type),
+            band_5: BandParamsGroup::new(4, last_band_type),
+            band_6: BandParamsGroup::new(5, last_band_type),
+            band_7: BandParamsGroup::new(6, last_band_type),
+            band_8: BandParamsGroup::new(7, last_band_type),
+        }
+    }
+
+    pub fn bands(&self) -> [&BandParamsGroup; NUM_BANDS] {
+        [
+            &self.band_1,
+            &self.band_2,
+            &self.band_3,
+            &self.band_4,
+            &self.band_5,
+            &self.band_6,
+            &self.band_7,
+            &self.band_8,
+        ]
+    }
+
+    /// Collects the current parameter values into the form used by the DSP.
+    pub fn eq_params(&self) -> EqParams<NUM_BANDS> {
+        let bands = self.bands();
+
+        EqParams {
+            lp_enabled: self.lp_enabled.value(),
+            lp_cutoff_hz: self.lp_cutoff_hz.value(),
+            lp_q: self.lp_q.value(),
+            lp_order: FilterOrder::from_u32(self.lp_order.value() as u32),
+
+            hp_enabled: self.hp_enabled.value(),
+            hp_cutoff_hz: self.hp_cutoff_hz.value(),
+            hp_q: self.hp_q.value(),
+            hp_order: FilterOrder::from_u32(self.hp_order.value() as u32),
+
+            bands: std::array::from_fn(|i| bands[i].band_params()),
+        }
+    }
+
+    /// The parameters' default values in the form used by the DSP. Presets and snapshots
+    /// fill the bands they don't have with these.
+    pub fn default_eq_params(&self) -> EqParams<NUM_BANDS> {
+        let bands = self.bands();
+
+        EqParams {
+            lp_enabled: self.lp_enabled.default_plain_value(),
+            lp_cutoff_hz: self.lp_cutoff_hz.default_plain_value(),
+            lp_q: self.lp_q.default_plain_value(),
+            lp_order: FilterOrder::from_u32(self.lp_order.default_plain_value() as u32),
+
+            hp_enabled: self.hp_enabled.default_plain_value(),
+            hp_cutoff_hz: self.hp_cutoff_hz.default_plain_value(),
+            hp_q: self.hp_q.default_plain_value(),
+            hp_order: FilterOrder::from_u32(self.hp_order.default_plain_value() as u32),
+
+            bands: std::array::from_fn(|i| bands[i].default_band_params()),
+        }
+    }
+
+    /// Like [`Self::eq_params()`], but advances the smoothed float parameters by `steps`
+    /// samples. Their values include any modulation offsets from the host.
+    pub fn next_smoothed_eq_params(&self, steps: u32) -> EqParams<NUM_BANDS> {
+        let bands = self.bands();
+
+        EqParams {
+            lp_cutoff_hz: self.lp_cutoff_hz.smoothed.next_step(steps),
+            lp_q: self.lp_q.smoothed.next_step(steps),
+            hp_cutoff_hz: self.hp_cutoff_hz.smoothed.next_step(steps),
+            hp_q: self.hp_q.smoothed.next_step(steps),
+            bands: std::array::from_fn(|i| bands[i].next_smoothed_band_params(steps)),
+            ..self.eq_params()
+        }
+    }
+
+    fn float_params(&self) -> impl Iterator<Item = &FloatParam> {
+        [
+            &self.lp_cutoff_hz,
+            &self.lp_q,
+            &self.hp_cutoff_hz,
+            &self.hp_q,
+        ]
+        .into_iter()
+        .chain(
+            self.bands()
+                .into_iter()
+                .flat_map(|band| band.float_params()),
+        )
+    }
+
+    pub fn is_smoothing(&self) -> bool {
+        self.float_params()
+            .any(|param| param.smoothed.is_smoothing())
+    }
+
+    /// Snaps the smoothed float parameters to their current values.
+    pub fn reset_smoothers(&self) {
+        for param in self.float_params() {
+            param.smoothed.reset(param.modulated_plain_value());
+        }
+    }
+}
+
+impl BandParamsGroup {
+    fn new(band_index: usize, last_band_type: BandType) -> Self {
+        let n = band_index + 1;
+
+        let q_range_2 = FloatRange::SymmetricalSkewed {
+            min: 0.02,
+            max: 40.0,
+            factor: 0.85,
+            center: 2.5,
+        };
+
+        Self {
+            enabled: BoolParam::new(format!("Band {n} enabled"), false),
+            band_type: IntParam::new(
+                format!("Band {n} type"),
+                0,
+                IntRange::Linear {
+                    min: 0,
+                    max: last_band_type as i32,
+                },
+            )
+            .with_value_to_string(Arc::new(|v| match v {
+                0 => String::from("bell"),
+                1 => String::from("low shelf"),
+                2 => String::from("high shelf"),
+                3 => String::from("notch"),
+                _ => String::from("allpass"),
+            })),
+            cutoff_hz: FloatParam::new(
+                format!("Band {n} cutoff"),
+                DEFAULT_BAND_CUTOFFS_HZ[band_index],
+                cutoff_range(),
+            )
+            .with_smoother(SmoothingStyle::Logarithmic(PARAM_SMOOTHING_MS)),
+            q: FloatParam::new(format!("Band {n} Q"), DEFAULT_Q, q_range_2)
+                .with_smoother(SmoothingStyle::Logarithmic(PARAM_SMOOTHING_MS)),
+            gain_db: FloatParam::new(
+                format!("Band {n} Gain"),
+                0.0,
+                FloatRange::SymmetricalSkewed {
+                    min: -30.0,
+                    max: 30.0,
+                    factor: 0.4,
+                    center: 0.0,
+                },
+            )
+            .with_smoother(SmoothingStyle::Linear(PARAM_SMOOTHING_MS))
+            .with_unit(" dB"),
+        }
+    }
+
+    fn float_params(&self) -> [&FloatParam; 3] {
+        [&self.cutoff_hz, &self.q, &self.gain_db]
+    }
+
+    pub fn band_params(&self) -> BandParams {
+        BandParams {
+            enabled: self.enabled.value(),
+            band_type: BandType::from_u32(self.band_type.value() as u32),
+            cutoff_hz: self.cutoff_hz.value(),
+            q: self.q.value(),
+            gain_db: self.gain_db.value(),
+        }
+    }
+
+    pub fn default_band_params(&self) -> BandParams {
+        BandParams {
+            enabled: self.enabled.default_plain_value(),
+            band_type: BandType::from_u32(self.band_type.default_plain_value() as u32),
+            cutoff_hz: self.cutoff_hz.default_plain_value(),
+            q: self.q.default_plain_value(),
+            gain_db: self.gain_db.default_plain_value(),
+        }
+    }
+
+    fn next_smoothed_band_params(&self, steps: u32) -> BandParams {
+        BandParams {
+            cutoff_hz: self.cutoff_hz.smoothed.next_step(steps),
+            q: self.q.smoothed.next_step(steps),
+            gain_db: self.gain_db.smoothed.next_step(steps),
+            ..self.band_params()
+        }
+    }
+}
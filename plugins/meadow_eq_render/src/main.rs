@@ -0,0 +1,406 @@
+//! Runs `MeadowEqDsp` over audio files without a DAW, for scripts and regression tests.
+
+use clap::{Args, Parser, Subcommand};
+use hound::{SampleFormat, WavSpec};
+use meadow_eq_dsp::apo::ApoConfig;
+use meadow_eq_dsp::preset::EqPreset;
+use meadow_eq_dsp::{BandParams, BandType, EqParams, FilterOrder, MeadowEqDsp};
+use std::error::Error;
+use std::fmt::Write as _;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+#[cfg(test)]
+mod tests;
+mod wav;
+
+use wav::Audio;
+
+/// The same number of bands as the plugin, so presets behave identically.
+const NUM_BANDS: usize = 8;
+
+// The plugin's parameter ranges, so anything that is rendered can be dialled in there too.
+const CUTOFF_RANGE_HZ: RangeInclusive<f32> = 20.0..=21_480.0;
+const CUT_Q_RANGE: RangeInclusive<f32> = 0.3..=8.0;
+const BAND_Q_RANGE: RangeInclusive<f32> = 0.02..=40.0;
+const GAIN_RANGE_DB: RangeInclusive<f32> = -30.0..=30.0;
+
+/// Renders audio, impulse responses, and frequency responses with Meadow Eq.
+#[derive(Parser)]
+#[command(name = "meadow-eq-render", version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Processes a WAV file with any sample rate and number of channels.
+    Render {
+        input: PathBuf,
+        output: PathBuf,
+        /// Write 32-bit float samples instead of the input's sample format.
+        #[arg(long)]
+        float: bool,
+        #[command(flatten)]
+        eq: EqArgs,
+    },
+    /// Writes the impulse response as a mono 32-bit float WAV file, or as CSV if the output
+    /// ends in `.csv`.
+    Impulse {
+        output: PathBuf,
+        #[arg(long, default_value_t = 48_000, value_parser = clap::value_parser!(u32).range(1..))]
+        sample_rate: u32,
+        /// The length of the impulse response in samples.
+        #[arg(long, default_value_t = 8192)]
+        length: usize,
+        #[command(flatten)]
+        eq: EqArgs,
+    },
+    /// Writes the magnitude and phase response as CSV.
+    Response {
+        output: PathBuf,
+        #[arg(long, default_value_t = 48_000, value_parser = clap::value_parser!(u32).range(1..))]
+        sample_rate: u32,
+        /// The number of logarithmically spaced frequencies.
+        #[arg(long, default_value_t = 512)]
+        points: usize,
+        #[arg(long, default_value_t = 20.0)]
+        min_hz: f64,
+        #[arg(long, default_value_t = 20_000.0)]
+        max_hz: f64,
+        #[command(flatten)]
+        eq: EqArgs,
+    },
+}
+
+/// The EQ settings. Flags are applied on top of the preset, if any. The result has to be
+/// within the plugin's parameter ranges.
+#[derive(Args)]
+struct EqArgs {
+    /// A Meadow Eq preset, or an Equalizer APO configuration if the file ends in `.txt`.
+    #[arg(long)]
+    preset: Option<PathBuf>,
+
+    /// Enables the lowpass filter at this cutoff.
+    #[arg(long, value_name = "HZ")]
+    lp: Option<f32>,
+    #[arg(long, value_name = "Q")]
+    lp_q: Option<f32>,
+    /// One of 1, 2, 4, 6, or 8.
+    #[arg(long, value_name = "ORDER", value_parser = parse_order)]
+    lp_order: Option<FilterOrder>,
+
+    /// Enables the highpass filter at this cutoff.
+    #[arg(long, value_name = "HZ")]
+    hp: Option<f32>,
+    #[arg(long, value_name = "Q")]
+    hp_q: Option<f32>,
+    /// One of 1, 2, 4, 6, or 8.
+    #[arg(long, value_name = "ORDER", value_parser = parse_order)]
+    hp_order: Option<FilterOrder>,
+
+    /// Adds a band as `TYPE:FREQ_HZ:Q[:GAIN_DB]`, for example `bell:1000:1.4:-3`. The type is
+    /// one of bell, low_shelf, high_shelf, notch, or allpass. Can be repeated.
+    #[arg(long = "band", value_name = "BAND", value_parser = parse_band, allow_hyphen_values = true)]
+    bands: Vec<BandParams>,
+
+    /// A gain in decibels applied after the EQ, added to the preamp of an APO configuration.
+    #[arg(
+        long,
+        value_name = "DB",
+        default_value_t = 0.0,
+        allow_hyphen_values = true
+    )]
+    gain: f32,
+}
+
+impl EqArgs {
+    /// Returns the parameters and the output gain in decibels.
+    fn resolve(&self) -> Result<(EqParams<NUM_BANDS>, f32), Box<dyn Error>> {
+        let (mut params, mut gain_db) = match &self.preset {
+            Some(path) if path.extension().is_some_and(|ext| ext == "txt") => {
+                let config = ApoConfig::<NUM_BANDS>::parse(&std::fs::read_to_string(path)?)?;
+                (config.params, config.preamp_db)
+            }
+            Some(path) => (EqPreset::load(path)?.to_params(&EqParams::default())?, 0.0),
+            None => (EqParams::default(), 0.0),
+        };
+        gain_db += self.gain;
+
+        if let Some(cutoff_hz) = self.lp {
+            params.lp_enabled = true;
+            params.lp_cutoff_hz = cutoff_hz;
+        }
+        if let Some(q) = self.lp_q {
+            params.lp_q = q;
+        }
+        if let Some(order) = self.lp_order {
+            params.lp_order = order;
+        }
+
+        if let Some(cutoff_hz) = self.hp {
+            params.hp_enabled = true;
+            params.hp_cutoff_hz = cutoff_hz;
+        }
+        if let Some(q) = self.hp_q {
+            params.hp_q = q;
+        }
+        if let Some(order) = self.hp_order {
+            params.hp_order = order;
+        }
+
+        for band in self.bands.iter() {
+            let slot = params
+                .bands
+                .iter_mut()
+                .find(|b| !b.enabled)
+                .ok_or_else(|| format!("more than {NUM_BANDS} bands are enabled"))?;
+            *slot = *band;
+        }
+
+        validate(&params)?;
+        if !gain_db.is_finite() {
+            return Err(format!("invalid gain {gain_db} dB").into());
+        }
+
+        Ok((params, gain_db))
+    }
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse().command) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(command: Command) -> Result<(), Box<dyn Error>> {
+    match command {
+        Command::Render {
+            input,
+            output,
+            float,
+            eq,
+        } => render(&input, &output, float, &eq),
+        Command::Impulse {
+            output,
+            sample_rate,
+            length,
+            eq,
+        } => impulse(&output, sample_rate, length, &eq),
+        Command::Response {
+            output,
+            sample_rate,
+            points,
+            min_hz,
+            max_hz,
+            eq,
+        } => response(&output, sample_rate, points, min_hz, max_hz, &eq),
+    }
+}
+
+/// Checks that every enabled filter is within the plugin's parameter ranges.
+fn validate(params: &EqParams<NUM_BANDS>) -> Result<(), String> {
+    let check = |name: &str, what: &str, value: f32, range: &RangeInclusive<f32>| {
+        if range.contains(&value) {
+            Ok(())
+        } else {
+            Err(format!(
+                "the {name}'s {what} {value} is outside of {} to {}",
+                range.start(),
+                range.end()
+            ))
+        }
+    };
+
+    for (name, enabled, cutoff_hz, q) in [
+        (
+            "lowpass",
+            params.lp_enabled,
+            params.lp_cutoff_hz,
+            params.lp_q,
+        ),
+        (
+            "highpass",
+            params.hp_enabled,
+            params.hp_cutoff_hz,
+            params.hp_q,
+        ),
+    ] {
+        if enabled {
+            check(name, "cutoff", cutoff_hz, &CUTOFF_RANGE_HZ)?;
+            check(name, "Q", q, &CUT_Q_RANGE)?;
+        }
+    }
+
+    for (i, band) in params.bands.iter().enumerate().filter(|(_, b)| b.enabled) {
+        let name = format!("band {}", i + 1);
+        check(&name, "cutoff", band.cutoff_hz, &CUTOFF_RANGE_HZ)?;
+        check(&name, "Q", band.q, &BAND_Q_RANGE)?;
+        check(&name, "gain", band.gain_db, &GAIN_RANGE_DB)?;
+    }
+
+    Ok(())
+}
+
+fn render(input: &Path, output: &Path, float: bool, eq: &EqArgs) -> Result<(), Box<dyn Error>> {
+    let (params, gain_db) = eq.resolve()?;
+    let mut audio = wav::read(input)?;
+
+    process(&mut audio.channels, audio.spec.sample_rate, params, gain_db);
+
+    if float {
+        audio.spec.sample_format = SampleFormat::Float;
+        audio.spec.bits_per_sample = 32;
+    }
+
+    let num_clipped = wav::write(output, &audio)?;
+    if num_clipped > 0 {
+        eprintln!("warning: {num_clipped} samples were clipped, consider using --float");
+    }
+
+    Ok(())
+}
+
+fn impulse(
+    output: &Path,
+    sample_rate: u32,
+    length: usize,
+    eq: &EqArgs,
+) -> Result<(), Box<dyn Error>> {
+    let (params, gain_db) = eq.resolve()?;
+
+    let mut channels = vec![vec![0.0; length]];
+    if let Some(first) = channels[0].first_mut() {
+        *first = 1.0;
+    }
+    process(&mut channels, sample_rate, params, gain_db);
+
+    if output.extension().is_some_and(|ext| ext == "csv") {
+        let mut csv = String::from("sample,value\n");
+        for (i, value) in channels[0].iter().enumerate() {
+            writeln!(csv, "{i},{value}").unwrap();
+        }
+        std::fs::write(output, csv)?;
+    } else {
+        let audio = Audio {
+            spec: WavSpec {
+                channels: 1,
+                sample_rate,
+                bits_per_sample: 32,
+                sample_format: SampleFormat::Float,
+            },
+            channels,
+        };
+        wav::write(output, &audio)?;
+    }
+
+    Ok(())
+}
+
+fn response(
+    output: &Path,
+    sample_rate: u32,
+    points: usize,
+    min_hz: f64,
+    max_hz: f64,
+    eq: &EqArgs,
+) -> Result<(), Box<dyn Error>> {
+    if !(min_hz > 0.0 && min_hz < max_hz && points >= 2) {
+        return Err("the frequency range must be positive and contain at least two points".into());
+    }
+
+    let (params, gain_db) = eq.resolve()?;
+    let mut dsp = MeadowEqDsp::<NUM_BANDS>::new(sample_rate as f64);
+    dsp.set_params(params);
+
+    let mut csv = String::from("frequency_hz,magnitude_db,phase_deg\n");
+    for i in 0..points {
+        let freq_hz = min_hz * (max_hz / min_hz).powf(i as f64 / (points - 1) as f64);
+        let response = dsp.response(freq_hz);
+        let magnitude_db = 20.0 * response.norm().max(1e-12).log10() + gain_db as f64;
+
+        writeln!(
+            csv,
+            "{freq_hz},{magnitude_db},{}",
+            response.arg().to_degrees()
+        )
+        .unwrap();
+    }
+    std::fs::write(output, csv)?;
+
+    Ok(())
+}
+
+/// Runs the EQ over the channels in stereo pairs. A trailing odd channel is processed on
+/// its own.
+fn process(channels: &mut [Vec<f32>], sample_rate: u32, params: EqParams<NUM_BANDS>, gain_db: f32) {
+    let gain = 10.0f32.powf(gain_db / 20.0);
+
+    for pair in channels.chunks_mut(2) {
+        let mut dsp = MeadowEqDsp::<NUM_BANDS>::new(sample_rate as f64);
+        dsp.set_params(params);
+
+        match pair {
+            [l, r] => dsp.process(l, r),
+            [mono] => {
+                let mut scratch = vec![0.0; mono.len()];
+                dsp.process(mono, &mut scratch);
+            }
+            _ => unreachable!(),
+        }
+
+        if gain != 1.0 {
+            for sample in pair.iter_mut().flatten() {
+                *sample *= gain;
+            }
+        }
+    }
+}
+
+fn parse_order(s: &str) -> Result<FilterOrder, String> {
+    match s.trim_start_matches(['x', 'X']) {
+        "1" => Ok(FilterOrder::X1),
+        "2" => Ok(FilterOrder::X2),
+        "4" => Ok(FilterOrder::X4),
+        "6" => Ok(FilterOrder::X6),
+        "8" => Ok(FilterOrder::X8),
+        _ => Err(format!("\"{s}\" is not one of 1, 2, 4, 6, or 8")),
+    }
+}
+
+fn parse_band(s: &str) -> Result<BandParams, String> {
+    let fields: Vec<&str> = s.split(':').collect();
+    let (type_str, freq_str, q_str, gain_str) = match fields[..] {
+        [t, f, q] => (t, f, q, "0"),
+        [t, f, q, g] => (t, f, q, g),
+        _ => return Err(String::from("expected TYPE:FREQ_HZ:Q[:GAIN_DB]")),
+    };
+
+    let band_type = match type_str.to_ascii_lowercase().replace('-', "_").as_str() {
+        "bell" | "peak" => BandType::Bell,
+        "low_shelf" | "lowshelf" | "ls" => BandType::LowShelf,
+        "high_shelf" | "highshelf" | "hs" => BandType::HighShelf,
+        "notch" => BandType::Notch,
+        "allpass" => BandType::Allpass,
+        _ => return Err(format!("unknown band type \"{type_str}\"")),
+    };
+    let number = |field: &str, value: &str| {
+        value
+            .parse::<f32>()
+            .map_err(|_| format!("invalid {field} \"{value}\""))
+    };
+
+    Ok(BandParams {
+        enabled: true,
+        band_type,
+        cutoff_hz: number("frequency", freq_str)?,
+        q: number("Q", q_str)?,
+        gain_db: number("gain", gain_str)?,
+    })
+}
@@ -0,0 +1,201 @@
+use clap::Parser;
+use hound::{SampleFormat, WavSpec};
+use meadow_eq_dsp::preset::EqPreset;
+use meadow_eq_dsp::{BandParams, BandType, EqParams};
+use std::f32::consts::PI;
+use std::path::PathBuf;
+
+use crate::wav::{self, Audio};
+use crate::{Cli, NUM_BANDS, run};
+
+const SAMPLE_RATE: u32 = 48_000;
+
+/// A path in the temporary directory that is removed again when dropped.
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn new(name: &str) -> Self {
+        Self(std::env::temp_dir().join(format!(
+            "meadow_eq_render_test_{}_{name}",
+            std::process::id()
+        )))
+    }
+
+    fn arg(&self) -> &str {
+        self.0.to_str().unwrap()
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+fn run_args(args: &[&str]) -> Result<(), String> {
+    let cli =
+        Cli::try_parse_from(["meadow-eq-render"].iter().chain(args)).map_err(|e| e.to_string())?;
+
+    run(cli.command).map_err(|e| e.to_string())
+}
+
+fn rms_db(signal: &[f32]) -> f32 {
+    let power = signal.iter().map(|x| x * x).sum::<f32>() / signal.len() as f32;
+    10.0 * power.log10()
+}
+
+#[test]
+fn flat_impulse_is_an_impulse() {
+    let output = TempFile::new("flat_impulse.wav");
+    run_args(&["impulse", output.arg(), "--length", "64", "--gain", "-6"]).unwrap();
+
+    let audio = wav::read(&output.0).unwrap();
+    assert_eq!(audio.spec.sample_rate, SAMPLE_RATE);
+    assert_eq!(audio.channels.len(), 1);
+    assert_eq!(audio.channels[0].len(), 64);
+    assert!((audio.channels[0][0] - 10.0f32.powf(-6.0 / 20.0)).abs() < 1e-6);
+    assert!(audio.channels[0][1..].iter().all(|&x| x == 0.0));
+}
+
+#[test]
+fn impulse_csv_has_the_eqs_response() {
+    let output = TempFile::new("bell_impulse.csv");
+    run_args(&["impulse", output.arg(), "--band", "bell:1000:1:6"]).unwrap();
+
+    let csv = std::fs::read_to_string(&output.0).unwrap();
+    let mut lines = csv.lines();
+    assert_eq!(lines.next(), Some("sample,value"));
+    let impulse: Vec<f64> = lines
+        .map(|line| line.split_once(',').unwrap().1.parse().unwrap())
+        .collect();
+    assert_eq!(impulse.len(), 8192);
+
+    // The impulse response's spectrum at the bell's centre.
+    let w = 2.0 * std::f64::consts::PI * 1_000.0 / SAMPLE_RATE as f64;
+    let (re, im) = impulse
+        .iter()
+        .enumerate()
+        .fold((0.0, 0.0), |(re, im), (n, x)| {
+            (re + x * (w * n as f64).cos(), im - x * (w * n as f64).sin())
+        });
+    let gain_db = 10.0 * (re * re + im * im).log10();
+    assert!((gain_db - 6.0).abs() < 0.01, "{gain_db} dB");
+}
+
+#[test]
+fn render_keeps_the_format_and_applies_the_eq() {
+    let input = TempFile::new("render_in.wav");
+    let output = TempFile::new("render_out.wav");
+
+    // Three channels, so the odd one is processed on its own.
+    let sine: Vec<f32> = (0..SAMPLE_RATE / 2)
+        .map(|n| 0.25 * (2.0 * PI * 1_000.0 * n as f32 / SAMPLE_RATE as f32).sin())
+        .collect();
+    let spec = WavSpec {
+        channels: 3,
+        sample_rate: SAMPLE_RATE,
+        bits_per_sample: 24,
+        sample_format: SampleFormat::Int,
+    };
+    let audio = Audio {
+        spec,
+        channels: vec![sine.clone(); 3],
+    };
+    wav::write(&input.0, &audio).unwrap();
+
+    run_args(&[
+        "render",
+        input.arg(),
+        output.arg(),
+        "--band",
+        "bell:1000:1:-6",
+    ])
+    .unwrap();
+
+    let rendered = wav::read(&output.0).unwrap();
+    assert_eq!(rendered.spec, spec);
+    assert_eq!(rendered.channels.len(), 3);
+
+    let settled = SAMPLE_RATE as usize / 10;
+    for channel in &rendered.channels {
+        assert_eq!(channel.len(), sine.len());
+        let gain_db = rms_db(&channel[settled..]) - rms_db(&sine[settled..]);
+        assert!((gain_db + 6.0).abs() < 0.05, "{gain_db} dB");
+    }
+}
+
+#[test]
+fn response_csv_covers_the_range() {
+    let output = TempFile::new("response.csv");
+    run_args(&[
+        "response",
+        output.arg(),
+        "--points",
+        "3",
+        "--min-hz",
+        "100",
+        "--max-hz",
+        "10000",
+        "--gain",
+        "3",
+    ])
+    .unwrap();
+
+    let csv = std::fs::read_to_string(&output.0).unwrap();
+    let rows: Vec<Vec<f64>> = csv
+        .lines()
+        .skip(1)
+        .map(|line| line.split(',').map(|v| v.parse().unwrap()).collect())
+        .collect();
+
+    assert_eq!(rows.len(), 3);
+    for (row, freq_hz) in rows.iter().zip([100.0, 1_000.0, 10_000.0]) {
+        assert!((row[0] - freq_hz).abs() < 1e-6 * freq_hz);
+        assert!((row[1] - 3.0).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn rejects_values_outside_the_plugins_ranges() {
+    let output = TempFile::new("rejected.wav");
+
+    for band in [
+        "bell:1000:-1:3",
+        "bell:1000:0:3",
+        "bell:5:1:3",
+        "bell:30000:1:3",
+        "bell:1000:1:40",
+        "bell:NaN:1:3",
+    ] {
+        let err = run_args(&["impulse", output.arg(), "--band", band]).unwrap_err();
+        assert!(err.contains("band 1"), "{band}: {err}");
+    }
+
+    for flags in [
+        ["--hp", "10"],
+        ["--lp-q", "0"],
+        ["--gain", "inf"],
+        ["--sample-rate", "0"],
+    ] {
+        let args = [&["impulse", output.arg(), "--lp", "10000"], &flags[..]].concat();
+        assert!(run_args(&args).is_err(), "{flags:?}");
+    }
+
+    // Presets are checked the same way.
+    let preset = TempFile::new("rejected_preset.json");
+    let mut params = EqParams::<NUM_BANDS>::default();
+    params.bands[2] = BandParams {
+        enabled: true,
+        band_type: BandType::LowShelf,
+        cutoff_hz: 100.0,
+        q: 100.0,
+        gain_db: 3.0,
+    };
+    EqPreset::from_params("Out of range", &params)
+        .save(&preset.0)
+        .unwrap();
+    let err = run_args(&["impulse", output.arg(), "--preset", preset.arg()]).unwrap_err();
+    assert!(err.contains("band 3's Q"), "{err}");
+
+    assert!(!output.0.exists());
+}
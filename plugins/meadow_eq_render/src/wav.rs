@@ -0,0 +1,72 @@
+use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
+use std::path::Path;
+
+/// A WAV file's contents, one buffer per channel.
+pub struct Audio {
+    pub spec: WavSpec,
+    pub channels: Vec<Vec<f32>>,
+}
+
+pub fn read(path: &Path) -> Result<Audio, hound::Error> {
+    let mut reader = WavReader::open(path)?;
+    let spec = reader.spec();
+    let num_channels = spec.channels as usize;
+
+    let samples: Vec<f32> = match spec.sample_format {
+        SampleFormat::Float => reader.samples::<f32>().collect::<Result<_, _>>()?,
+        SampleFormat::Int => {
+            let scale = 1.0 / (1u64 << (spec.bits_per_sample - 1)) as f32;
+
+            reader
+                .samples::<i32>()
+                .map(|s| s.map(|s| s as f32 * scale))
+                .collect::<Result<_, _>>()?
+        }
+    };
+
+    let mut channels = vec![Vec::with_capacity(samples.len() / num_channels); num_channels];
+    for frame in samples.chunks_exact(num_channels) {
+        for (channel, &sample) in channels.iter_mut().zip(frame.iter()) {
+            channel.push(sample);
+        }
+    }
+
+    Ok(Audio { spec, channels })
+}
+
+/// Writes the audio, and returns the number of samples that had to be clipped to fit an
+/// integer sample format.
+pub fn write(path: &Path, audio: &Audio) -> Result<usize, hound::Error> {
+    let mut writer = WavWriter::create(path, audio.spec)?;
+    let num_frames = audio.channels.first().map_or(0, |c| c.len());
+    let mut num_clipped = 0;
+
+    match audio.spec.sample_format {
+        SampleFormat::Float => {
+            for i in 0..num_frames {
+                for channel in audio.channels.iter() {
+                    writer.write_sample(channel[i])?;
+                }
+            }
+        }
+        SampleFormat::Int => {
+            let scale = (1u64 << (audio.spec.bits_per_sample - 1)) as f32;
+            let (min, max) = (-scale, scale - 1.0);
+
+            for i in 0..num_frames {
+                for channel in audio.channels.iter() {
+                    let sample = (channel[i] * scale).round();
+                    if sample < min || sample > max {
+                        num_clipped += 1;
+                    }
+
+                    writer.write_sample(sample.clamp(min, max) as i32)?;
+                }
+            }
+        }
+    }
+
+    writer.finalize()?;
+
+    Ok(num_clipped)
+}
@@ -0,0 +1,376 @@
+use atomic_float::AtomicF32;
+use meadow_gate_dsp::{
+    Algorithm, GateDsp, GateParams, MAX_LOOKAHEAD_MS, Sidechain, SidechainFilterParams,
+};
+use meadow_meter_editor::EguiState;
+use nih_plug::prelude::*;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+
+#[cfg(test)]
+mod tests;
+
+/// Where the editor's meter ends, the most the range parameter allows.
+const MAX_METER_DB: f32 = 80.0;
+
+pub struct MeadowGate {
+    params: Arc<MeadowGateParams>,
+    dsp: GateDsp,
+    /// The latency last reported to the host.
+    latency: u32,
+
+    /// The gain reduction during the last processed block in decibels. Shared with the
+    /// editor's meter.
+    gain_reduction_db: Arc<AtomicF32>,
+}
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+enum AlgorithmParam {
+    #[id = "zam_gate_x2"]
+    #[name = "ZamGateX2"]
+    ZamGateX2,
+}
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+enum SidechainSource {
+    #[id = "internal"]
+    Internal,
+    #[id = "external"]
+    External,
+}
+
+#[derive(Params)]
+struct MeadowGateParams {
+    #[persist = "editor-state"]
+    editor_state: Arc<EguiState>,
+
+    #[id = "algorithm"]
+    pub algorithm: EnumParam<AlgorithmParam>,
+
+    #[id = "input_gain_db"]
+    pub input_gain_db: FloatParam,
+    #[id = "threshold_db"]
+    pub threshold_db: FloatParam,
+    #[id = "hysteresis_db"]
+    pub hysteresis_db: FloatParam,
+    #[id = "attack_ms"]
+    pub attack_ms: FloatParam,
+    #[id = "hold_ms"]
+    pub hold_ms: FloatParam,
+    #[id = "release_ms"]
+    pub release_ms: FloatParam,
+    #[id = "range_db"]
+    pub range_db: FloatParam,
+    #[id = "lookahead_ms"]
+    pub lookahead_ms: FloatParam,
+    #[id = "output_gain_db"]
+    pub output_gain_db: FloatParam,
+
+    #[id = "sidechain"]
+    pub sidechain: EnumParam<SidechainSource>,
+    #[id = "sc_hp_enabled"]
+    pub sc_hp_enabled: BoolParam,
+    #[id = "sc_hp_cutoff_hz"]
+    pub sc_hp_cutoff_hz: FloatParam,
+    #[id = "sc_lp_enabled"]
+    pub sc_lp_enabled: BoolParam,
+    #[id = "sc_lp_cutoff_hz"]
+    pub sc_lp_cutoff_hz: FloatParam,
+}
+
+impl Default for MeadowGate {
+    fn default() -> Self {
+        let dsp = GateDsp::new(44_100.0);
+
+        Self {
+            params: Arc::new(MeadowGateParams::default()),
+            latency: dsp.latency_samples(),
+            dsp,
+            gain_reduction_db: Arc::new(AtomicF32::new(0.0)),
+        }
+    }
+}
+
+impl MeadowGate {
+    /// The gain reduction during the last processed block as a positive number of decibels.
+    /// Meant for a gain reduction meter.
+    pub fn gain_reduction_db(&self) -> Arc<AtomicF32> {
+        self.gain_reduction_db.clone()
+    }
+
+    /// Gates one block and updates the meter.
+    fn process_block(
+        &mut self,
+        out_l: &mut [f32],
+        out_r: &mut [f32],
+        sidechain: Option<(&[f32], &[f32])>,
+    ) {
+        self.dsp.process(out_l, out_r, sidechain);
+        self.gain_reduction_db
+            .store(self.dsp.gain_reduction_db(), Ordering::Relaxed);
+    }
+}
+
+fn gain_range() -> FloatRange {
+    FloatRange::Linear {
+        min: -24.0,
+        max: 24.0,
+    }
+}
+
+fn cutoff_range() -> FloatRange {
+    FloatRange::Skewed {
+        min: 20.0,
+        max: 21_480.0,
+        factor: FloatRange::skew_factor(-2.0),
+    }
+}
+
+impl Default for MeadowGateParams {
+    fn default() -> Self {
+        let defaults = GateParams::default();
+        let filter_defaults = SidechainFilterParams::default();
+
+        Self {
+            editor_state: meadow_meter_editor::default_state(),
+
+            algorithm: EnumParam::new("Algorithm", AlgorithmParam::ZamGateX2),
+
+            input_gain_db: FloatParam::new("Input gain", defaults.input_gain_db, gain_range())
+                .with_unit(" dB"),
+            threshold_db: FloatParam::new(
+                "Threshold",
+                defaults.threshold_db,
+                FloatRange::Linear {
+                    min: -80.0,
+                    max: 0.0,
+                },
+            )
+            .with_unit(" dB"),
+            hysteresis_db: FloatParam::new(
+                "Hysteresis",
+                defaults.hysteresis_db,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 24.0,
+                },
+            )
+            .with_unit(" dB"),
+            attack_ms: FloatParam::new(
+                "Attack",
+                defaults.attack_ms,
+                FloatRange::Skewed {
+                    min: 0.01,
+                    max: 100.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" ms"),
+            hold_ms: FloatParam::new(
+                "Hold",
+                defaults.hold_ms,
+                FloatRange::Skewed {
+                    min: 0.0,
+                    max: 1000.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" ms"),
+            release_ms: FloatParam::new(
+                "Release",
+                defaults.release_ms,
+                FloatRange::Skewed {
+                    min: 1.0,
+                    max: 2000.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" ms"),
+            range_db: FloatParam::new(
+                "Range",
+                defaults.range_db,
+                FloatRange::Linear {
+                    min: -80.0,
+                    max: 0.0,
+                },
+            )
+            .with_unit(" dB"),
+            // Changing this changes the latency and clears the DSP, so hosts can't automate it.
+            lookahead_ms: FloatParam::new(
+                "Look-ahead",
+                defaults.lookahead_ms,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: MAX_LOOKAHEAD_MS as f32,
+                },
+            )
+            .with_unit(" ms")
+            .with_step_size(0.1)
+            .non_automatable(),
+            output_gain_db: FloatParam::new("Output gain", defaults.output_gain_db, gain_range())
+                .with_unit(" dB"),
+
+            sidechain: EnumParam::new("Sidechain", SidechainSource::Internal),
+            sc_hp_enabled: BoolParam::new("Sidechain HP enabled", filter_defaults.hp_enabled),
+            sc_hp_cutoff_hz: FloatParam::new(
+                "Sidechain HP cutoff",
+                filter_defaults.hp_cutoff_hz,
+                cutoff_range(),
+            )
+            .with_unit(" Hz"),
+            sc_lp_enabled: BoolParam::new("Sidechain LP enabled", filter_defaults.lp_enabled),
+            sc_lp_cutoff_hz: FloatParam::new(
+                "Sidechain LP cutoff",
+                filter_defaults.lp_cutoff_hz,
+                cutoff_range(),
+            )
+            .with_unit(" Hz"),
+        }
+    }
+}
+
+impl MeadowGateParams {
+    /// Collects the current parameter values into the form used by the DSP.
+    pub fn gate_params(&self) -> GateParams {
+        GateParams {
+            algorithm: match self.algorithm.value() {
+                AlgorithmParam::ZamGateX2 => Algorithm::ZamGateX2,
+            },
+            input_gain_db: self.input_gain_db.value(),
+            threshold_db: self.threshold_db.value(),
+            hysteresis_db: self.hysteresis_db.value(),
+            attack_ms: self.attack_ms.value(),
+            hold_ms: self.hold_ms.value(),
+            release_ms: self.release_ms.value(),
+            range_db: self.range_db.value(),
+            lookahead_ms: self.lookahead_ms.value(),
+            output_gain_db: self.output_gain_db.value(),
+            sidechain: match self.sidechain.value() {
+                SidechainSource::Internal => Sidechain::Internal,
+                SidechainSource::External => Sidechain::External,
+            },
+            sidechain_filter: SidechainFilterParams {
+                hp_enabled: self.sc_hp_enabled.value(),
+                hp_cutoff_hz: self.sc_hp_cutoff_hz.value(),
+                lp_enabled: self.sc_lp_enabled.value(),
+                lp_cutoff_hz: self.sc_lp_cutoff_hz.value(),
+            },
+        }
+    }
+}
+
+impl Plugin for MeadowGate {
+    const NAME: &'static str = "Meadow Gate";
+    const VENDOR: &'static str = "Billy Messenger";
+    const URL: &'static str = env!("CARGO_PKG_HOMEPAGE");
+    const EMAIL: &'static str = "60663878+BillyDM@users.noreply.github.com";
+
+    const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+
+    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[AudioIOLayout {
+        main_input_channels: NonZeroU32::new(2),
+        main_output_channels: NonZeroU32::new(2),
+
+        aux_input_ports: &[new_nonzero_u32(2)],
+        aux_output_ports: &[],
+
+        names: PortNames {
+            aux_inputs: &["Sidechain"],
+            ..PortNames::const_default()
+        },
+    }];
+
+    const MIDI_INPUT: MidiConfig = MidiConfig::None;
+    const MIDI_OUTPUT: MidiConfig = MidiConfig::None;
+
+    const SAMPLE_ACCURATE_AUTOMATION: bool = true;
+
+    type SysExMessage = ();
+    type BackgroundTask = ();
+
+    fn params(&self) -> Arc<dyn Params> {
+        self.params.clone()
+    }
+
+    fn editor(&mut self, _async_executor: AsyncExecutor<Self>) -> Option<Box<dyn Editor>> {
+        meadow_meter_editor::create(
+            self.params.clone(),
+            self.params.editor_state.clone(),
+            self.gain_reduction_db.clone(),
+            MAX_METER_DB,
+        )
+    }
+
+    fn initialize(
+        &mut self,
+        _audio_io_layout: &AudioIOLayout,
+        config: &BufferConfig,
+        context: &mut impl InitContext<Self>,
+    ) -> bool {
+        self.dsp = GateDsp::new(config.sample_rate as f64);
+        self.dsp.set_params(self.params.gate_params());
+        self.dsp.reset();
+        self.latency = self.dsp.latency_samples();
+        context.set_latency_samples(self.latency);
+
+        true
+    }
+
+    fn reset(&mut self) {
+        self.dsp.reset();
+        self.gain_reduction_db.store(0.0, Ordering::Relaxed);
+    }
+
+    fn process(
+        &mut self,
+        buffer: &mut Buffer,
+        aux: &mut AuxiliaryBuffers,
+        context: &mut impl ProcessContext<Self>,
+    ) -> ProcessStatus {
+        self.dsp.set_params(self.params.gate_params());
+
+        let latency = self.dsp.latency_samples();
+        if latency != self.latency {
+            self.latency = latency;
+            context.set_latency_samples(latency);
+        }
+
+        let [out_l, out_r, ..] = buffer.as_slice() else {
+            return ProcessStatus::Normal;
+        };
+        let sidechain = match aux.inputs.first_mut().map(|sidechain| sidechain.as_slice()) {
+            Some([sc_l, sc_r, ..]) => Some((&**sc_l, &**sc_r)),
+            _ => None,
+        };
+
+        self.process_block(out_l, out_r, sidechain);
+
+        ProcessStatus::Normal
+    }
+}
+
+impl ClapPlugin for MeadowGate {
+    const CLAP_ID: &'static str = "app.meadowlark.meadow-gate";
+    const CLAP_DESCRIPTION: Option<&'static str> =
+        Some("A noise gate with a filtered sidechain and look-ahead");
+    const CLAP_MANUAL_URL: Option<&'static str> = Some(Self::URL);
+    const CLAP_SUPPORT_URL: Option<&'static str> = None;
+
+    const CLAP_FEATURES: &'static [ClapFeature] = &[
+        ClapFeature::AudioEffect,
+        ClapFeature::Stereo,
+        ClapFeature::Gate,
+    ];
+}
+
+impl Vst3Plugin for MeadowGate {
+    const VST3_CLASS_ID: [u8; 16] = *b"Meadowlark.Gate_";
+
+    const VST3_SUBCATEGORIES: &'static [Vst3SubCategory] =
+        &[Vst3SubCategory::Fx, Vst3SubCategory::Dynamics];
+}
+
+#[cfg(not(feature = "bundled"))]
+nih_export_clap!(MeadowGate);
+#[cfg(not(feature = "bundled"))]
+nih_export_vst3!(MeadowGate);
@@ -0,0 +1,16 @@
+use meadow_meter_editor::testing::check_meter;
+
+use crate::MeadowGate;
+
+#[test]
+fn meter_shows_the_gain_reduction() {
+    let mut plugin = MeadowGate::default();
+    let meter = plugin.gain_reduction_db();
+
+    // Silence keeps the gate closed, which reduces the gain by the whole range.
+    let gain_reduction_db = check_meter(&mut plugin, &meter, 0.0, |plugin, l, r| {
+        plugin.process_block(l, r, None);
+        plugin.dsp.gain_reduction_db()
+    });
+    assert!(gain_reduction_db > 60.0, "{gain_reduction_db} dB");
+}
@@ -0,0 +1,24 @@
+//! The algorithms that decide when the gate opens and closes.
+//!
+//! A new algorithm implements [`GateAlgorithm`], gets a variant in
+//! [`Algorithm`](crate::Algorithm) and a field in [`GateDsp`](crate::GateDsp).
+//! Every algorithm is kept alive so switching between them never allocates.
+
+use crate::GateParams;
+
+mod zam_gate;
+
+pub use zam_gate::ZamGate;
+
+pub trait GateAlgorithm {
+    /// Called whenever the parameters change. The algorithm is free to ignore the parameters
+    /// it has no use for.
+    fn set_params(&mut self, params: &GateParams, sample_rate: f64);
+
+    /// Forgets about the signal so far and closes the gate.
+    fn reset(&mut self);
+
+    /// Takes the level of one frame of the sidechain as a linear amplitude and returns the
+    /// gain for that frame, which is at most `1.0`.
+    fn tick(&mut self, level: f32) -> f32;
+}
@@ -0,0 +1,80 @@
+use meadow_common_dsp::{db_to_gain, time_constant};
+
+use super::GateAlgorithm;
+use crate::GateParams;
+
+/// After ZamGateX2: the gate opens when the level goes over the threshold and closes once it
+/// has stayed below the threshold minus the hysteresis for the hold time. The gain fades
+/// towards fully open or the range with the attack and release times.
+#[derive(Debug, Clone, Copy)]
+pub struct ZamGate {
+    open_threshold: f32,
+    close_threshold: f32,
+    range: f32,
+    attack_coeff: f32,
+    release_coeff: f32,
+    hold_samples: usize,
+
+    open: bool,
+    hold_remaining: usize,
+    gain: f32,
+}
+
+impl Default for ZamGate {
+    fn default() -> Self {
+        Self {
+            open_threshold: 0.0,
+            close_threshold: 0.0,
+            range: 0.0,
+            attack_coeff: 0.0,
+            release_coeff: 0.0,
+            hold_samples: 0,
+            open: false,
+            hold_remaining: 0,
+            gain: 0.0,
+        }
+    }
+}
+
+impl GateAlgorithm for ZamGate {
+    fn set_params(&mut self, params: &GateParams, sample_rate: f64) {
+        self.open_threshold = db_to_gain(params.threshold_db);
+        self.close_threshold = db_to_gain(params.threshold_db - params.hysteresis_db.max(0.0));
+        self.range = db_to_gain(params.range_db.min(0.0));
+        self.attack_coeff = time_constant(params.attack_ms, sample_rate);
+        self.release_coeff = time_constant(params.release_ms, sample_rate);
+        self.hold_samples =
+            (params.hold_ms.max(0.0) as f64 / 1000.0 * sample_rate).round() as usize;
+    }
+
+    fn reset(&mut self) {
+        self.open = false;
+        self.hold_remaining = 0;
+        self.gain = self.range;
+    }
+
+    #[inline(always)]
+    fn tick(&mut self, level: f32) -> f32 {
+        if level > self.open_threshold {
+            self.open = true;
+        }
+        if self.open {
+            if level >= self.close_threshold {
+                self.hold_remaining = self.hold_samples;
+            } else if self.hold_remaining > 0 {
+                self.hold_remaining -= 1;
+            } else {
+                self.open = false;
+            }
+        }
+
+        let (target, coeff) = if self.open {
+            (1.0, self.attack_coeff)
+        } else {
+            (self.range, self.release_coeff)
+        };
+        self.gain = target + coeff * (self.gain - target);
+
+        self.gain
+    }
+}
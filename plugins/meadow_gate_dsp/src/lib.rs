@@ -0,0 +1,259 @@
+//! A noise gate with a filtered sidechain and switchable algorithms.
+//!
+//! The gate takes care of everything around the algorithm: the input and output gains, the
+//! sidechain and its filters, detecting the sidechain's level and the look-ahead. The
+//! algorithm turns that level into a gain, see [`GateAlgorithm`].
+//!
+//! With look-ahead, the main signal is delayed but the sidechain isn't, so the gate can open
+//! before a transient arrives.
+
+use meadow_common_dsp::{Ramp, db_to_gain, gain_to_db, time_constant};
+use meadow_eq_dsp::sidechain_filter::SidechainFilter;
+
+pub mod algorithm;
+#[cfg(test)]
+mod tests;
+
+pub use algorithm::GateAlgorithm;
+use algorithm::ZamGate;
+
+pub const MAX_LOOKAHEAD_MS: f64 = 20.0;
+
+/// How long it takes to move to new input and output gains.
+pub const SMOOTHING_MS: f64 = 20.0;
+
+/// How fast the detected level falls after a peak. This bridges the gaps between the peaks
+/// of low notes, so the gate doesn't chatter on them.
+pub const DETECTOR_RELEASE_MS: f64 = 20.0;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Algorithm {
+    /// After ZamGateX2. See [`algorithm::ZamGate`].
+    #[default]
+    ZamGateX2,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Sidechain {
+    /// Gate based on the main input, after the input gain.
+    #[default]
+    Internal,
+    /// Gate based on the external sidechain input, or the main input if there is none.
+    External,
+}
+
+/// The same sidechain filter as Meadow Compressor's.
+pub use meadow_eq_dsp::sidechain_filter::SidechainFilterParams;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GateParams {
+    pub algorithm: Algorithm,
+
+    pub input_gain_db: f32,
+    /// The level the gate opens at.
+    pub threshold_db: f32,
+    /// How far below the threshold the level has to fall for the gate to close.
+    pub hysteresis_db: f32,
+    pub attack_ms: f32,
+    /// How long the gate stays open after the level falls below the closing threshold.
+    pub hold_ms: f32,
+    pub release_ms: f32,
+    /// The gain of the closed gate. `0.0` turns the gate off.
+    pub range_db: f32,
+    /// From `0.0` to [`MAX_LOOKAHEAD_MS`].
+    pub lookahead_ms: f32,
+    pub output_gain_db: f32,
+
+    pub sidechain: Sidechain,
+    pub sidechain_filter: SidechainFilterParams,
+}
+
+impl Default for GateParams {
+    fn default() -> Self {
+        Self {
+            algorithm: Algorithm::default(),
+            input_gain_db: 0.0,
+            threshold_db: -40.0,
+            hysteresis_db: 6.0,
+            attack_ms: 1.0,
+            hold_ms: 20.0,
+            release_ms: 100.0,
+            range_db: -80.0,
+            lookahead_ms: 0.0,
+            output_gain_db: 0.0,
+            sidechain: Sidechain::default(),
+            sidechain_filter: SidechainFilterParams::default(),
+        }
+    }
+}
+
+pub struct GateDsp {
+    params: GateParams,
+    sample_rate: f64,
+    ramp_len: usize,
+    lookahead_samples: usize,
+
+    zam_gate: ZamGate,
+
+    input_gain: Ramp,
+    output_gain: Ramp,
+    sidechain_filter: SidechainFilter,
+    detector_coeff: f32,
+    level: f32,
+    delay: [Vec<f32>; 2],
+    mask: usize,
+    pos: usize,
+
+    /// The most gain reduction during the last call to `process()`.
+    gain_reduction_db: f32,
+}
+
+impl GateDsp {
+    pub fn new(sample_rate: f64) -> Self {
+        assert!(sample_rate > 0.0);
+
+        let params = GateParams::default();
+        let max_lookahead = (MAX_LOOKAHEAD_MS / 1000.0 * sample_rate).ceil() as usize;
+        let capacity = (max_lookahead + 1).next_power_of_two();
+
+        let mut new_self = Self {
+            params,
+            sample_rate,
+            ramp_len: ((SMOOTHING_MS / 1000.0 * sample_rate).round() as usize).max(1),
+            lookahead_samples: 0,
+            zam_gate: ZamGate::default(),
+            input_gain: Ramp::new(db_to_gain(params.input_gain_db)),
+            output_gain: Ramp::new(db_to_gain(params.output_gain_db)),
+            sidechain_filter: SidechainFilter::new(&params.sidechain_filter, sample_rate),
+            detector_coeff: time_constant(DETECTOR_RELEASE_MS as f32, sample_rate),
+            level: 0.0,
+            delay: [vec![0.0; capacity], vec![0.0; capacity]],
+            mask: capacity - 1,
+            pos: 0,
+            gain_reduction_db: 0.0,
+        };
+        new_self.lookahead_samples = new_self.lookahead_samples_for(params.lookahead_ms);
+        let algorithm = new_self.algorithm_mut();
+        algorithm.set_params(&params, sample_rate);
+        algorithm.reset();
+
+        new_self
+    }
+
+    pub fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+
+    fn lookahead_samples_for(&self, lookahead_ms: f32) -> usize {
+        let max = (MAX_LOOKAHEAD_MS / 1000.0 * self.sample_rate).ceil() as usize;
+        ((lookahead_ms.max(0.0) as f64 / 1000.0 * self.sample_rate).round() as usize).min(max)
+    }
+
+    /// The delay of the output in samples, which is the look-ahead time.
+    pub fn latency_samples(&self) -> u32 {
+        self.lookahead_samples as u32
+    }
+
+    fn algorithm_mut(&mut self) -> &mut dyn GateAlgorithm {
+        match self.params.algorithm {
+            Algorithm::ZamGateX2 => &mut self.zam_gate,
+        }
+    }
+
+    /// Sets new parameters. A new look-ahead time changes the latency and clears the gate.
+    pub fn set_params(&mut self, params: GateParams) {
+        if self.params == params {
+            return;
+        }
+
+        if params.input_gain_db != self.params.input_gain_db {
+            self.input_gain
+                .set_target(db_to_gain(params.input_gain_db), self.ramp_len);
+        }
+        if params.output_gain_db != self.params.output_gain_db {
+            self.output_gain
+                .set_target(db_to_gain(params.output_gain_db), self.ramp_len);
+        }
+        if params.sidechain_filter != self.params.sidechain_filter {
+            self.sidechain_filter
+                .set(&params.sidechain_filter, self.sample_rate);
+        }
+
+        let switched_algorithm = params.algorithm != self.params.algorithm;
+        self.params = params;
+
+        let sample_rate = self.sample_rate;
+        let algorithm = self.algorithm_mut();
+        algorithm.set_params(&params, sample_rate);
+        if switched_algorithm {
+            // It hasn't seen the signal since it was last used.
+            algorithm.reset();
+        }
+
+        let lookahead_samples = self.lookahead_samples_for(params.lookahead_ms);
+        if lookahead_samples != self.lookahead_samples {
+            self.lookahead_samples = lookahead_samples;
+            self.reset();
+        }
+    }
+
+    /// The most gain reduction during the last call to [`Self::process()`], as a positive
+    /// number of decibels.
+    pub fn gain_reduction_db(&self) -> f32 {
+        self.gain_reduction_db
+    }
+
+    pub fn reset(&mut self) {
+        self.input_gain.snap();
+        self.output_gain.snap();
+        self.sidechain_filter.reset();
+        self.level = 0.0;
+        for delay in self.delay.iter_mut() {
+            delay.fill(0.0);
+        }
+        self.pos = 0;
+        self.gain_reduction_db = 0.0;
+        self.algorithm_mut().reset();
+    }
+
+    /// Gates the main signal in place. With [`Sidechain::External`], the level is taken from
+    /// `sidechain` where it's available and from the main input elsewhere.
+    pub fn process(
+        &mut self,
+        buf_l: &mut [f32],
+        buf_r: &mut [f32],
+        sidechain: Option<(&[f32], &[f32])>,
+    ) {
+        let sidechain = sidechain.filter(|_| self.params.sidechain == Sidechain::External);
+        let mask = self.mask;
+        let mut min_gain = 1.0f32;
+
+        for (i, (l, r)) in buf_l.iter_mut().zip(buf_r.iter_mut()).enumerate() {
+            let input_gain = self.input_gain.next();
+            let output_gain = self.output_gain.next();
+            let input = [*l * input_gain, *r * input_gain];
+
+            let detector_input = sidechain
+                .and_then(|(sc_l, sc_r)| Some([*sc_l.get(i)?, *sc_r.get(i)?]))
+                .unwrap_or(input);
+            let [sc_l, sc_r] = self.sidechain_filter.tick(detector_input);
+            let peak = sc_l.abs().max(sc_r.abs());
+            self.level = peak.max(self.detector_coeff * self.level);
+
+            let gain = match self.params.algorithm {
+                Algorithm::ZamGateX2 => self.zam_gate.tick(self.level),
+            };
+            min_gain = min_gain.min(gain);
+
+            let pos = self.pos;
+            self.delay[0][pos & mask] = input[0];
+            self.delay[1][pos & mask] = input[1];
+            let out_pos = pos.wrapping_sub(self.lookahead_samples);
+            *l = self.delay[0][out_pos & mask] * gain * output_gain;
+            *r = self.delay[1][out_pos & mask] * gain * output_gain;
+            self.pos = pos.wrapping_add(1);
+        }
+
+        self.gain_reduction_db = -gain_to_db(min_gain);
+    }
+}
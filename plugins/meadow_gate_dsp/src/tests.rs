@@ -0,0 +1,223 @@
+use meadow_common_dsp::{db_to_gain, gain_to_db};
+use meadow_test_signals::sine;
+use std::f32::consts::LOG10_E;
+
+use crate::{
+    DETECTOR_RELEASE_MS, GateDsp, GateParams, MAX_LOOKAHEAD_MS, Sidechain, SidechainFilterParams,
+};
+
+const SAMPLE_RATES: [f64; 3] = [44_100.0, 48_000.0, 192_000.0];
+
+/// Runs a constant signal at each of `levels_db` for `len` samples in turn, and returns the
+/// gain of the last sample of each in decibels. The release is exponential in linear gain, so
+/// it takes a while to settle on a deep range.
+fn gains_db(dsp: &mut GateDsp, levels_db: &[f32], len: usize) -> Vec<f32> {
+    levels_db
+        .iter()
+        .map(|&level_db| {
+            let mut buf_l = vec![db_to_gain(level_db); len];
+            let mut buf_r = buf_l.clone();
+            dsp.process(&mut buf_l, &mut buf_r, None);
+
+            gain_to_db(buf_l[len - 1]) - level_db
+        })
+        .collect()
+}
+
+#[test]
+fn opens_above_the_threshold_and_closes_to_the_range() {
+    for sample_rate in SAMPLE_RATES {
+        let mut dsp = GateDsp::new(sample_rate);
+        dsp.set_params(GateParams {
+            threshold_db: -30.0,
+            range_db: -40.0,
+            ..Default::default()
+        });
+
+        let gains = gains_db(&mut dsp, &[-50.0, -10.0, -50.0], 2 * sample_rate as usize);
+        assert!(
+            (gains[0] + 40.0).abs() < 0.01,
+            "{sample_rate} Hz: {gains:?}"
+        );
+        assert!(gains[1].abs() < 0.01, "{sample_rate} Hz: {gains:?}");
+        assert!(
+            (gains[2] + 40.0).abs() < 0.01,
+            "{sample_rate} Hz: {gains:?}"
+        );
+        assert!((dsp.gain_reduction_db() - 40.0).abs() < 0.01);
+    }
+}
+
+#[test]
+fn hysteresis_keeps_the_state_between_thresholds() {
+    let sample_rate = 48_000.0;
+    let len = 2 * sample_rate as usize;
+    let params = GateParams {
+        threshold_db: -30.0,
+        hysteresis_db: 10.0,
+        range_db: -40.0,
+        ..Default::default()
+    };
+
+    // Between -40 and -30 dB, a closed gate stays closed...
+    let mut dsp = GateDsp::new(sample_rate);
+    dsp.set_params(params);
+    dsp.reset();
+    let gains = gains_db(&mut dsp, &[-35.0], len);
+    assert!((gains[0] + 40.0).abs() < 0.01, "{gains:?}");
+
+    // ...and an open one stays open.
+    let gains = gains_db(&mut dsp, &[-20.0, -35.0, -45.0], len);
+    assert!(gains[0].abs() < 0.01, "{gains:?}");
+    assert!(gains[1].abs() < 0.01, "{gains:?}");
+    assert!((gains[2] + 40.0).abs() < 0.01, "{gains:?}");
+
+    // Without hysteresis it closes right away.
+    dsp.set_params(GateParams {
+        hysteresis_db: 0.0,
+        ..params
+    });
+    let gains = gains_db(&mut dsp, &[-20.0, -35.0], len);
+    assert!((gains[1] + 40.0).abs() < 0.01, "{gains:?}");
+}
+
+#[test]
+fn hold_delays_the_release() {
+    let sample_rate = 48_000.0;
+    let hold_ms = 50.0;
+    let mut dsp = GateDsp::new(sample_rate);
+    dsp.set_params(GateParams {
+        threshold_db: -30.0,
+        hold_ms,
+        release_ms: 1.0,
+        ..Default::default()
+    });
+
+    gains_db(&mut dsp, &[-10.0], sample_rate as usize);
+
+    // The detector takes a while to fall below the threshold, then the hold starts.
+    let len = sample_rate as usize / 2;
+    let mut buf_l = vec![db_to_gain(-50.0); len];
+    let mut buf_r = buf_l.clone();
+    dsp.process(&mut buf_l, &mut buf_r, None);
+
+    // The closing threshold is 26 dB below the level the detector falls from.
+    let closing = buf_l
+        .iter()
+        .position(|&x| x < 0.5 * db_to_gain(-50.0))
+        .unwrap();
+    let detector_ms = DETECTOR_RELEASE_MS as f32 * 26.0 / (20.0 * LOG10_E);
+    let closing_ms = closing as f32 / sample_rate as f32 * 1000.0;
+    assert!(
+        closing_ms > hold_ms + detector_ms && closing_ms < hold_ms + detector_ms + 2.0,
+        "closed after {closing_ms} ms"
+    );
+}
+
+#[test]
+fn lookahead_opens_before_the_transient() {
+    for sample_rate in SAMPLE_RATES {
+        let lookahead_ms = 2.0;
+        let params = GateParams {
+            threshold_db: -30.0,
+            attack_ms: 0.2,
+            lookahead_ms,
+            ..Default::default()
+        };
+        let mut dsp = GateDsp::new(sample_rate);
+        dsp.set_params(params);
+
+        let latency = dsp.latency_samples() as usize;
+        assert_eq!(
+            latency,
+            (lookahead_ms as f64 / 1000.0 * sample_rate).round() as usize
+        );
+
+        // Silence, then a burst.
+        let onset = 1000;
+        let len = onset + 2 * latency;
+        let mut buf_l: Vec<f32> = (0..len)
+            .map(|n| if n < onset { 0.0 } else { 0.5 })
+            .collect();
+        let mut buf_r = buf_l.clone();
+        dsp.process(&mut buf_l, &mut buf_r, None);
+
+        assert!(buf_l[..onset + latency].iter().all(|&x| x == 0.0));
+        assert!(
+            buf_l[onset + latency] > 0.49,
+            "{sample_rate} Hz: the burst starts at {}",
+            buf_l[onset + latency]
+        );
+
+        // Without look-ahead the start of the burst is cut.
+        dsp.set_params(GateParams {
+            lookahead_ms: 0.0,
+            ..params
+        });
+        assert_eq!(dsp.latency_samples(), 0);
+        let mut buf_l: Vec<f32> = (0..len)
+            .map(|n| if n < onset { 0.0 } else { 0.5 })
+            .collect();
+        let mut buf_r = buf_l.clone();
+        dsp.process(&mut buf_l, &mut buf_r, None);
+        assert!(buf_l[onset] < 0.25);
+    }
+}
+
+#[test]
+fn zero_range_is_a_pure_delay() {
+    let sample_rate = 48_000.0;
+    let input = sine(440.0, sample_rate, 8192);
+
+    for lookahead_ms in [0.0, 1.0, MAX_LOOKAHEAD_MS as f32] {
+        let mut dsp = GateDsp::new(sample_rate);
+        dsp.set_params(GateParams {
+            range_db: 0.0,
+            lookahead_ms,
+            ..Default::default()
+        });
+        dsp.reset();
+
+        let latency = dsp.latency_samples() as usize;
+        let mut buf_l = input.clone();
+        let mut buf_r = input.clone();
+        dsp.process(&mut buf_l, &mut buf_r, None);
+
+        assert_eq!(buf_l[latency..], input[..input.len() - latency]);
+        assert_eq!(dsp.gain_reduction_db(), 0.0);
+    }
+}
+
+#[test]
+fn sidechain_filters_pick_out_the_snare() {
+    let sample_rate = 48_000.0;
+    let len = sample_rate as usize;
+    let kick = sine(50.0, sample_rate, len);
+    let snare = sine(5_000.0, sample_rate, len);
+
+    for (key, hp_enabled, expect_open) in [
+        (&kick, false, true),
+        (&kick, true, false),
+        (&snare, true, true),
+    ] {
+        let mut dsp = GateDsp::new(sample_rate);
+        dsp.set_params(GateParams {
+            threshold_db: -20.0,
+            range_db: -40.0,
+            sidechain: Sidechain::External,
+            sidechain_filter: SidechainFilterParams {
+                hp_enabled,
+                hp_cutoff_hz: 1_000.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+
+        let mut buf_l = vec![0.1f32; len];
+        let mut buf_r = buf_l.clone();
+        dsp.process(&mut buf_l, &mut buf_r, Some((key, key)));
+
+        let is_open = (buf_l[len - 1] - 0.1).abs() < 1e-3;
+        assert_eq!(is_open, expect_open, "hp {hp_enabled}: {}", buf_l[len - 1]);
+    }
+}
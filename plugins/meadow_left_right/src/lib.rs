@@ -0,0 +1,57 @@
+//! L/R split and merge plugins, for processing the left and right channels of a stereo signal
+//! separately.
+
+use meadow_left_right_dsp::LeftRightParams;
+use nih_plug::prelude::*;
+
+mod merge;
+mod split;
+
+pub use merge::MeadowLeftRightMerge;
+pub use split::MeadowLeftRightSplit;
+
+#[derive(Params)]
+struct LeftRightGainParams {
+    #[id = "left_gain_db"]
+    pub left_gain_db: FloatParam,
+    #[id = "right_gain_db"]
+    pub right_gain_db: FloatParam,
+}
+
+impl Default for LeftRightGainParams {
+    fn default() -> Self {
+        Self {
+            left_gain_db: gain_param("Left gain"),
+            right_gain_db: gain_param("Right gain"),
+        }
+    }
+}
+
+impl LeftRightGainParams {
+    /// Collects the current parameter values into the form used by the DSP.
+    pub fn left_right_params(&self) -> LeftRightParams {
+        LeftRightParams {
+            left_gain_db: self.left_gain_db.value(),
+            right_gain_db: self.right_gain_db.value(),
+        }
+    }
+}
+
+fn gain_param(name: &str) -> FloatParam {
+    FloatParam::new(
+        name,
+        0.0,
+        FloatRange::SymmetricalSkewed {
+            min: -30.0,
+            max: 30.0,
+            factor: 0.4,
+            center: 0.0,
+        },
+    )
+    .with_unit(" dB")
+}
+
+#[cfg(not(feature = "bundled"))]
+nih_export_clap!(MeadowLeftRightSplit, MeadowLeftRightMerge);
+#[cfg(not(feature = "bundled"))]
+nih_export_vst3!(MeadowLeftRightSplit, MeadowLeftRightMerge);
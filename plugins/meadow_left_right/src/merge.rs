@@ -0,0 +1,115 @@
+use meadow_left_right_dsp::LeftRightDsp;
+use nih_plug::prelude::*;
+use std::sync::Arc;
+
+use crate::LeftRightGainParams;
+
+pub struct MeadowLeftRightMerge {
+    params: Arc<LeftRightGainParams>,
+    dsp: LeftRightDsp,
+}
+
+impl Default for MeadowLeftRightMerge {
+    fn default() -> Self {
+        Self {
+            params: Arc::new(LeftRightGainParams::default()),
+            dsp: LeftRightDsp::new(44_100.0),
+        }
+    }
+}
+
+impl Plugin for MeadowLeftRightMerge {
+    const NAME: &'static str = "Meadow L/R Merge";
+    const VENDOR: &'static str = "Billy Messenger";
+    const URL: &'static str = env!("CARGO_PKG_HOMEPAGE");
+    const EMAIL: &'static str = "60663878+BillyDM@users.noreply.github.com";
+
+    const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+
+    // The left and right channels on two mono inputs, and a stereo output.
+    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[AudioIOLayout {
+        main_input_channels: NonZeroU32::new(1),
+        main_output_channels: NonZeroU32::new(2),
+
+        aux_input_ports: &[new_nonzero_u32(1)],
+        aux_output_ports: &[],
+
+        names: PortNames {
+            main_input: Some("Left"),
+            aux_inputs: &["Right"],
+            ..PortNames::const_default()
+        },
+    }];
+
+    const MIDI_INPUT: MidiConfig = MidiConfig::None;
+    const MIDI_OUTPUT: MidiConfig = MidiConfig::None;
+
+    const SAMPLE_ACCURATE_AUTOMATION: bool = true;
+
+    type SysExMessage = ();
+    type BackgroundTask = ();
+
+    fn params(&self) -> Arc<dyn Params> {
+        self.params.clone()
+    }
+
+    fn initialize(
+        &mut self,
+        _audio_io_layout: &AudioIOLayout,
+        config: &BufferConfig,
+        _context: &mut impl InitContext<Self>,
+    ) -> bool {
+        self.dsp = LeftRightDsp::new(config.sample_rate as f64);
+        self.dsp.set_params(self.params.left_right_params());
+
+        true
+    }
+
+    fn reset(&mut self) {
+        self.dsp.reset();
+    }
+
+    fn process(
+        &mut self,
+        buffer: &mut Buffer,
+        aux: &mut AuxiliaryBuffers,
+        _context: &mut impl ProcessContext<Self>,
+    ) -> ProcessStatus {
+        self.dsp.set_params(self.params.left_right_params());
+
+        // The left input is already in the left output, and the right one comes from the
+        // second input.
+        let [l, r, ..] = buffer.as_slice() else {
+            return ProcessStatus::Normal;
+        };
+        let Some([r_in, ..]) = aux.inputs.first_mut().map(|buffer| buffer.as_slice()) else {
+            return ProcessStatus::Normal;
+        };
+
+        self.dsp.process(l, r_in, r);
+
+        ProcessStatus::Normal
+    }
+}
+
+impl ClapPlugin for MeadowLeftRightMerge {
+    const CLAP_ID: &'static str = "app.meadowlark.meadow-left-right-merge";
+    const CLAP_DESCRIPTION: Option<&'static str> =
+        Some("Merges two mono signals into the left and right channels");
+    const CLAP_MANUAL_URL: Option<&'static str> = Some(Self::URL);
+    const CLAP_SUPPORT_URL: Option<&'static str> = None;
+
+    const CLAP_FEATURES: &'static [ClapFeature] = &[
+        ClapFeature::AudioEffect,
+        ClapFeature::Stereo,
+        ClapFeature::Mono,
+        ClapFeature::Utility,
+    ];
+}
+
+impl Vst3Plugin for MeadowLeftRightMerge {
+    const VST3_CLASS_ID: [u8; 16] = *b"Meadowlark.LRMrg";
+
+    const VST3_SUBCATEGORIES: &'static [Vst3SubCategory] =
+        &[Vst3SubCategory::Fx, Vst3SubCategory::Tools];
+}
@@ -0,0 +1,118 @@
+use meadow_left_right_dsp::LeftRightDsp;
+use nih_plug::prelude::*;
+use std::sync::Arc;
+
+use crate::LeftRightGainParams;
+
+pub struct MeadowLeftRightSplit {
+    params: Arc<LeftRightGainParams>,
+    dsp: LeftRightDsp,
+}
+
+impl Default for MeadowLeftRightSplit {
+    fn default() -> Self {
+        Self {
+            params: Arc::new(LeftRightGainParams::default()),
+            dsp: LeftRightDsp::new(44_100.0),
+        }
+    }
+}
+
+impl Plugin for MeadowLeftRightSplit {
+    const NAME: &'static str = "Meadow L/R Split";
+    const VENDOR: &'static str = "Billy Messenger";
+    const URL: &'static str = env!("CARGO_PKG_HOMEPAGE");
+    const EMAIL: &'static str = "60663878+BillyDM@users.noreply.github.com";
+
+    const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+
+    // A stereo input, with the left channel on both channels of the main output and the right
+    // one on a mono output. nih-plug's buffer only holds the main output's channels, so a mono
+    // main output would leave nowhere to read the right input from.
+    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[AudioIOLayout {
+        main_input_channels: NonZeroU32::new(2),
+        main_output_channels: NonZeroU32::new(2),
+
+        aux_input_ports: &[],
+        aux_output_ports: &[new_nonzero_u32(1)],
+
+        names: PortNames {
+            main_output: Some("Left"),
+            aux_outputs: &["Right"],
+            ..PortNames::const_default()
+        },
+    }];
+
+    const MIDI_INPUT: MidiConfig = MidiConfig::None;
+    const MIDI_OUTPUT: MidiConfig = MidiConfig::None;
+
+    const SAMPLE_ACCURATE_AUTOMATION: bool = true;
+
+    type SysExMessage = ();
+    type BackgroundTask = ();
+
+    fn params(&self) -> Arc<dyn Params> {
+        self.params.clone()
+    }
+
+    fn initialize(
+        &mut self,
+        _audio_io_layout: &AudioIOLayout,
+        config: &BufferConfig,
+        _context: &mut impl InitContext<Self>,
+    ) -> bool {
+        self.dsp = LeftRightDsp::new(config.sample_rate as f64);
+        self.dsp.set_params(self.params.left_right_params());
+
+        true
+    }
+
+    fn reset(&mut self) {
+        self.dsp.reset();
+    }
+
+    fn process(
+        &mut self,
+        buffer: &mut Buffer,
+        aux: &mut AuxiliaryBuffers,
+        _context: &mut impl ProcessContext<Self>,
+    ) -> ProcessStatus {
+        self.dsp.set_params(self.params.left_right_params());
+
+        // The left channel stays where it is and is copied over the right one, which moves to
+        // the second output.
+        let [l, r, ..] = buffer.as_slice() else {
+            return ProcessStatus::Normal;
+        };
+        let Some([r_out, ..]) = aux.outputs.first_mut().map(|buffer| buffer.as_slice()) else {
+            return ProcessStatus::Normal;
+        };
+
+        self.dsp.process(l, r, r_out);
+        r.copy_from_slice(l);
+
+        ProcessStatus::Normal
+    }
+}
+
+impl ClapPlugin for MeadowLeftRightSplit {
+    const CLAP_ID: &'static str = "app.meadowlark.meadow-left-right-split";
+    const CLAP_DESCRIPTION: Option<&'static str> =
+        Some("Splits a stereo signal into its left and right channels");
+    const CLAP_MANUAL_URL: Option<&'static str> = Some(Self::URL);
+    const CLAP_SUPPORT_URL: Option<&'static str> = None;
+
+    const CLAP_FEATURES: &'static [ClapFeature] = &[
+        ClapFeature::AudioEffect,
+        ClapFeature::Stereo,
+        ClapFeature::Mono,
+        ClapFeature::Utility,
+    ];
+}
+
+impl Vst3Plugin for MeadowLeftRightSplit {
+    const VST3_CLASS_ID: [u8; 16] = *b"Meadowlark.LRSpl";
+
+    const VST3_SUBCATEGORIES: &'static [Vst3SubCategory] =
+        &[Vst3SubCategory::Fx, Vst3SubCategory::Tools];
+}
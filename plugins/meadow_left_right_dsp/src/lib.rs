@@ -0,0 +1,75 @@
+//! Gains for the left and right channels of the L/R split and merge plugins.
+//!
+//! Splitting and merging only move the right channel between a stereo port and a mono one,
+//! so both directions are the same processing. At unity gain the samples pass through
+//! untouched.
+
+use meadow_common_dsp::{Ramp, db_to_gain};
+
+#[cfg(test)]
+mod tests;
+
+/// How long it takes to move to new gains.
+pub const SMOOTHING_MS: f64 = 20.0;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct LeftRightParams {
+    pub left_gain_db: f32,
+    pub right_gain_db: f32,
+}
+
+pub struct LeftRightDsp {
+    params: LeftRightParams,
+    sample_rate: f64,
+    ramp_len: usize,
+
+    left: Ramp,
+    right: Ramp,
+}
+
+impl LeftRightDsp {
+    pub fn new(sample_rate: f64) -> Self {
+        assert!(sample_rate > 0.0);
+
+        Self {
+            params: LeftRightParams::default(),
+            sample_rate,
+            ramp_len: ((SMOOTHING_MS / 1000.0 * sample_rate).round() as usize).max(1),
+            left: Ramp::new(1.0),
+            right: Ramp::new(1.0),
+        }
+    }
+
+    pub fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+
+    /// Sets new gains, which are ramped to over [`SMOOTHING_MS`].
+    pub fn set_params(&mut self, params: LeftRightParams) {
+        if self.params == params {
+            return;
+        }
+
+        self.left
+            .set_target(db_to_gain(params.left_gain_db), self.ramp_len);
+        self.right
+            .set_target(db_to_gain(params.right_gain_db), self.ramp_len);
+
+        self.params = params;
+    }
+
+    /// Jumps to the current gains without ramping.
+    pub fn reset(&mut self) {
+        self.left.snap();
+        self.right.snap();
+    }
+
+    /// Applies the left gain to `buf_l` in place, and writes `buf_r_in` with the right gain
+    /// applied to `buf_r_out`.
+    pub fn process(&mut self, buf_l: &mut [f32], buf_r_in: &[f32], buf_r_out: &mut [f32]) {
+        for ((l, &r_in), r_out) in buf_l.iter_mut().zip(buf_r_in).zip(buf_r_out.iter_mut()) {
+            *l *= self.left.next();
+            *r_out = r_in * self.right.next();
+        }
+    }
+}
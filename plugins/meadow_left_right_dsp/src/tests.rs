@@ -0,0 +1,76 @@
+use meadow_test_signals::noise;
+
+use crate::{LeftRightDsp, LeftRightParams, SMOOTHING_MS};
+
+const SAMPLE_RATE: f64 = 48_000.0;
+const LEN: usize = 8192;
+
+/// Runs `l` and `r` through `dsp` in uneven blocks, the way both plugins do.
+fn run(dsp: &mut LeftRightDsp, l: &[f32], r: &[f32]) -> (Vec<f32>, Vec<f32>) {
+    let mut out_l = l.to_vec();
+    let mut out_r = vec![0.0; r.len()];
+    for ((l, r_in), r_out) in out_l
+        .chunks_mut(100)
+        .zip(r.chunks(100))
+        .zip(out_r.chunks_mut(100))
+    {
+        dsp.process(l, r_in, r_out);
+    }
+
+    (out_l, out_r)
+}
+
+#[test]
+fn split_and_merge_are_transparent() {
+    let l = noise(LEN, 1);
+    let r = noise(LEN, 2);
+
+    let (left, right) = run(&mut LeftRightDsp::new(SAMPLE_RATE), &l, &r);
+    let (out_l, out_r) = run(&mut LeftRightDsp::new(SAMPLE_RATE), &left, &right);
+
+    assert!(out_l == l && out_r == r);
+}
+
+#[test]
+fn gains_apply_per_channel() {
+    let l = noise(LEN, 3);
+    let r = noise(LEN, 4);
+    let mut dsp = LeftRightDsp::new(SAMPLE_RATE);
+    dsp.set_params(LeftRightParams {
+        left_gain_db: -6.0,
+        right_gain_db: 12.0,
+    });
+    dsp.reset();
+
+    let (out_l, out_r) = run(&mut dsp, &l, &r);
+    let (left_gain, right_gain) = (10.0f32.powf(-6.0 / 20.0), 10.0f32.powf(12.0 / 20.0));
+    for n in 0..LEN {
+        assert_eq!(out_l[n], left_gain * l[n]);
+        assert_eq!(out_r[n], right_gain * r[n]);
+    }
+}
+
+#[test]
+fn gain_changes_are_ramped() {
+    let ramp_len = (SMOOTHING_MS / 1000.0 * SAMPLE_RATE) as usize;
+    let mut dsp = LeftRightDsp::new(SAMPLE_RATE);
+    dsp.set_params(LeftRightParams {
+        left_gain_db: 0.0,
+        right_gain_db: -60.0,
+    });
+
+    let ones = vec![1.0; 2 * ramp_len];
+    let (out_l, out_r) = run(&mut dsp, &ones, &ones);
+    assert!(out_l.iter().all(|&x| x == 1.0));
+
+    let max_step = 1.0 / ramp_len as f32 + 1e-6;
+    let mut previous = 1.0;
+    for (n, &x) in out_r.iter().enumerate() {
+        assert!(
+            x <= previous && previous - x <= max_step,
+            "sample {n}: {x} after {previous}"
+        );
+        previous = x;
+    }
+    assert_eq!(out_r[ramp_len - 1], 0.001);
+}
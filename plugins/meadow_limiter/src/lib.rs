@@ -0,0 +1,259 @@
+use atomic_float::AtomicF32;
+use meadow_limiter_dsp::{Algorithm, LimiterDsp, LimiterParams, MAX_LOOKAHEAD_MS};
+use meadow_meter_editor::EguiState;
+use nih_plug::prelude::*;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+
+#[cfg(test)]
+mod tests;
+
+/// Where the editor's meter ends.
+const MAX_METER_DB: f32 = 24.0;
+
+pub struct MeadowLimiter {
+    params: Arc<MeadowLimiterParams>,
+    dsp: LimiterDsp,
+    /// The latency last reported to the host.
+    latency: u32,
+
+    /// The gain reduction during the last processed block in decibels. Shared with the
+    /// editor's meter.
+    gain_reduction_db: Arc<AtomicF32>,
+}
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+enum AlgorithmParam {
+    #[id = "dpl"]
+    #[name = "x42-dpl"]
+    Dpl,
+    #[id = "za_maxim_x2"]
+    #[name = "ZaMaximX2"]
+    ZaMaximX2,
+}
+
+#[derive(Params)]
+struct MeadowLimiterParams {
+    #[persist = "editor-state"]
+    editor_state: Arc<EguiState>,
+
+    #[id = "algorithm"]
+    pub algorithm: EnumParam<AlgorithmParam>,
+    #[id = "input_gain_db"]
+    pub input_gain_db: FloatParam,
+    #[id = "threshold_db"]
+    pub threshold_db: FloatParam,
+    #[id = "release_ms"]
+    pub release_ms: FloatParam,
+    #[id = "lookahead_ms"]
+    pub lookahead_ms: FloatParam,
+    #[id = "true_peak"]
+    pub true_peak: BoolParam,
+}
+
+impl Default for MeadowLimiter {
+    fn default() -> Self {
+        let dsp = LimiterDsp::new(44_100.0);
+
+        Self {
+            params: Arc::new(MeadowLimiterParams::default()),
+            latency: dsp.latency_samples(),
+            dsp,
+            gain_reduction_db: Arc::new(AtomicF32::new(0.0)),
+        }
+    }
+}
+
+impl MeadowLimiter {
+    /// The gain reduction during the last processed block as a positive number of decibels.
+    /// Meant for a gain reduction meter.
+    pub fn gain_reduction_db(&self) -> Arc<AtomicF32> {
+        self.gain_reduction_db.clone()
+    }
+
+    /// Limits one block and updates the meter.
+    fn process_block(&mut self, out_l: &mut [f32], out_r: &mut [f32]) {
+        self.dsp.process(out_l, out_r);
+        self.gain_reduction_db
+            .store(self.dsp.gain_reduction_db(), Ordering::Relaxed);
+    }
+}
+
+impl Default for MeadowLimiterParams {
+    fn default() -> Self {
+        let defaults = LimiterParams::default();
+
+        Self {
+            editor_state: meadow_meter_editor::default_state(),
+
+            algorithm: EnumParam::new("Algorithm", AlgorithmParam::Dpl),
+            input_gain_db: FloatParam::new(
+                "Input gain",
+                defaults.input_gain_db,
+                FloatRange::Linear {
+                    min: -24.0,
+                    max: 24.0,
+                },
+            )
+            .with_unit(" dB"),
+            threshold_db: FloatParam::new(
+                "Threshold",
+                defaults.threshold_db,
+                FloatRange::Linear {
+                    min: -30.0,
+                    max: 0.0,
+                },
+            )
+            .with_unit(" dB"),
+            release_ms: FloatParam::new(
+                "Release",
+                defaults.release_ms,
+                FloatRange::Skewed {
+                    min: 1.0,
+                    max: 1000.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_unit(" ms"),
+            // Changing this changes the latency and clears the DSP, so hosts can't automate it.
+            lookahead_ms: FloatParam::new(
+                "Look-ahead",
+                defaults.lookahead_ms,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: MAX_LOOKAHEAD_MS as f32,
+                },
+            )
+            .with_unit(" ms")
+            .with_step_size(0.1)
+            .non_automatable(),
+            true_peak: BoolParam::new("True peak", defaults.true_peak),
+        }
+    }
+}
+
+impl MeadowLimiterParams {
+    /// Collects the current parameter values into the form used by the DSP.
+    pub fn limiter_params(&self) -> LimiterParams {
+        LimiterParams {
+            algorithm: match self.algorithm.value() {
+                AlgorithmParam::Dpl => Algorithm::Dpl,
+                AlgorithmParam::ZaMaximX2 => Algorithm::ZaMaximX2,
+            },
+            input_gain_db: self.input_gain_db.value(),
+            threshold_db: self.threshold_db.value(),
+            release_ms: self.release_ms.value(),
+            lookahead_ms: self.lookahead_ms.value(),
+            true_peak: self.true_peak.value(),
+        }
+    }
+}
+
+impl Plugin for MeadowLimiter {
+    const NAME: &'static str = "Meadow Limiter";
+    const VENDOR: &'static str = "Billy Messenger";
+    const URL: &'static str = env!("CARGO_PKG_HOMEPAGE");
+    const EMAIL: &'static str = "60663878+BillyDM@users.noreply.github.com";
+
+    const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+
+    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[AudioIOLayout {
+        main_input_channels: NonZeroU32::new(2),
+        main_output_channels: NonZeroU32::new(2),
+
+        aux_input_ports: &[],
+        aux_output_ports: &[],
+
+        names: PortNames::const_default(),
+    }];
+
+    const MIDI_INPUT: MidiConfig = MidiConfig::None;
+    const MIDI_OUTPUT: MidiConfig = MidiConfig::None;
+
+    const SAMPLE_ACCURATE_AUTOMATION: bool = true;
+
+    type SysExMessage = ();
+    type BackgroundTask = ();
+
+    fn params(&self) -> Arc<dyn Params> {
+        self.params.clone()
+    }
+
+    fn editor(&mut self, _async_executor: AsyncExecutor<Self>) -> Option<Box<dyn Editor>> {
+        meadow_meter_editor::create(
+            self.params.clone(),
+            self.params.editor_state.clone(),
+            self.gain_reduction_db.clone(),
+            MAX_METER_DB,
+        )
+    }
+
+    fn initialize(
+        &mut self,
+        _audio_io_layout: &AudioIOLayout,
+        config: &BufferConfig,
+        context: &mut impl InitContext<Self>,
+    ) -> bool {
+        self.dsp = LimiterDsp::new(config.sample_rate as f64);
+        self.dsp.set_params(self.params.limiter_params());
+        self.dsp.reset();
+        self.latency = self.dsp.latency_samples();
+        context.set_latency_samples(self.latency);
+
+        true
+    }
+
+    fn reset(&mut self) {
+        self.dsp.reset();
+        self.gain_reduction_db.store(0.0, Ordering::Relaxed);
+    }
+
+    fn process(
+        &mut self,
+        buffer: &mut Buffer,
+        _aux: &mut AuxiliaryBuffers,
+        context: &mut impl ProcessContext<Self>,
+    ) -> ProcessStatus {
+        self.dsp.set_params(self.params.limiter_params());
+
+        let latency = self.dsp.latency_samples();
+        if latency != self.latency {
+            self.latency = latency;
+            context.set_latency_samples(latency);
+        }
+
+        let [out_l, out_r, ..] = buffer.as_slice() else {
+            return ProcessStatus::Normal;
+        };
+
+        self.process_block(out_l, out_r);
+
+        ProcessStatus::Normal
+    }
+}
+
+impl ClapPlugin for MeadowLimiter {
+    const CLAP_ID: &'static str = "app.meadowlark.meadow-limiter";
+    const CLAP_DESCRIPTION: Option<&'static str> =
+        Some("A look-ahead limiter with optional true peak detection");
+    const CLAP_MANUAL_URL: Option<&'static str> = Some(Self::URL);
+    const CLAP_SUPPORT_URL: Option<&'static str> = None;
+
+    const CLAP_FEATURES: &'static [ClapFeature] = &[
+        ClapFeature::AudioEffect,
+        ClapFeature::Stereo,
+        ClapFeature::Limiter,
+    ];
+}
+
+impl Vst3Plugin for MeadowLimiter {
+    const VST3_CLASS_ID: [u8; 16] = *b"Meadowlark.Limit";
+
+    const VST3_SUBCATEGORIES: &'static [Vst3SubCategory] =
+        &[Vst3SubCategory::Fx, Vst3SubCategory::Dynamics];
+}
+
+#[cfg(not(feature = "bundled"))]
+nih_export_clap!(MeadowLimiter);
+#[cfg(not(feature = "bundled"))]
+nih_export_vst3!(MeadowLimiter);
@@ -0,0 +1,16 @@
+use meadow_meter_editor::testing::check_meter;
+
+use crate::MeadowLimiter;
+
+#[test]
+fn meter_shows_the_gain_reduction() {
+    let mut plugin = MeadowLimiter::default();
+    let meter = plugin.gain_reduction_db();
+
+    // A sine at +12 dBFS is 13 dB over the default threshold.
+    let gain_reduction_db = check_meter(&mut plugin, &meter, 4.0, |plugin, l, r| {
+        plugin.process_block(l, r);
+        plugin.dsp.gain_reduction_db()
+    });
+    assert!(gain_reduction_db > 6.0, "{gain_reduction_db} dB");
+}
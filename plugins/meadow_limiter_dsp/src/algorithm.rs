@@ -0,0 +1,26 @@
+//! The ways the gain can recover after a peak.
+//!
+//! A new algorithm implements [`LimiterAlgorithm`], gets a variant in
+//! [`Algorithm`](crate::Algorithm) and a field in [`LimiterDsp`](crate::LimiterDsp).
+//! Whatever it returns, the limiter never lets the gain rise above what the peaks need, so
+//! an algorithm can't break the ceiling.
+
+use crate::LimiterParams;
+
+mod dpl;
+mod za_maxim;
+
+pub use dpl::Dpl;
+pub use za_maxim::ZaMaximX2;
+
+pub trait LimiterAlgorithm {
+    /// Called whenever the parameters change.
+    fn set_params(&mut self, params: &LimiterParams, sample_rate: f64);
+
+    /// Forgets about the signal so far.
+    fn reset(&mut self);
+
+    /// Takes the largest gain that keeps the coming peaks below the threshold, and returns
+    /// the gain to use. Anything above `held` is ignored.
+    fn tick(&mut self, held: f32) -> f32;
+}
@@ -0,0 +1,43 @@
+use meadow_common_dsp::time_constant;
+
+use super::LimiterAlgorithm;
+use crate::LimiterParams;
+
+/// After x42's dpl: the gain recovers exponentially as a linear gain, which is gentle on
+/// deep gain reduction.
+#[derive(Debug, Clone, Copy)]
+pub struct Dpl {
+    release_coeff: f32,
+
+    gain: f32,
+}
+
+impl Default for Dpl {
+    fn default() -> Self {
+        Self {
+            release_coeff: 0.0,
+            gain: 1.0,
+        }
+    }
+}
+
+impl LimiterAlgorithm for Dpl {
+    fn set_params(&mut self, params: &LimiterParams, sample_rate: f64) {
+        self.release_coeff = time_constant(params.release_ms, sample_rate);
+    }
+
+    fn reset(&mut self) {
+        self.gain = 1.0;
+    }
+
+    #[inline(always)]
+    fn tick(&mut self, held: f32) -> f32 {
+        self.gain = if held < self.gain {
+            held
+        } else {
+            held + self.release_coeff * (self.gain - held)
+        };
+
+        self.gain
+    }
+}
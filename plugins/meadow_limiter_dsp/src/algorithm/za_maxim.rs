@@ -0,0 +1,44 @@
+use meadow_common_dsp::{db_to_gain, gain_to_db, time_constant};
+
+use super::LimiterAlgorithm;
+use crate::LimiterParams;
+
+/// After ZaMaximX2: the gain recovers exponentially in decibels. Deep gain reduction comes
+/// back up faster than with [`Dpl`](super::Dpl), which makes it louder and more aggressive.
+#[derive(Debug, Clone, Copy)]
+pub struct ZaMaximX2 {
+    release_coeff: f32,
+
+    gain_db: f32,
+}
+
+impl Default for ZaMaximX2 {
+    fn default() -> Self {
+        Self {
+            release_coeff: 0.0,
+            gain_db: 0.0,
+        }
+    }
+}
+
+impl LimiterAlgorithm for ZaMaximX2 {
+    fn set_params(&mut self, params: &LimiterParams, sample_rate: f64) {
+        self.release_coeff = time_constant(params.release_ms, sample_rate);
+    }
+
+    fn reset(&mut self) {
+        self.gain_db = 0.0;
+    }
+
+    #[inline(always)]
+    fn tick(&mut self, held: f32) -> f32 {
+        let held_db = gain_to_db(held);
+        self.gain_db = if held_db < self.gain_db {
+            held_db
+        } else {
+            held_db + self.release_coeff * (self.gain_db - held_db)
+        };
+
+        db_to_gain(self.gain_db)
+    }
+}
@@ -0,0 +1,401 @@
+//! A look-ahead limiter with switchable release algorithms.
+//!
+//! The output is guaranteed to stay below the threshold. For every sample, the gain needed to
+//! bring its peak down to the threshold is held for the look-ahead time with a sliding minimum.
+//! The algorithm then decides how the gain recovers, but it can never raise the gain above
+//! the held one. Finally the gain is averaged over the look-ahead time, which turns the
+//! attack into a smooth fade. Every value in the average is at most the gain the peak needs,
+//! so the average is too.
+//!
+//! With true peak detection, the peaks between samples are estimated by oversampling the
+//! input, and count towards the peaks of the two samples on either side of them. This keeps
+//! the true peak as measured with 4 times oversampling below the threshold, give or take a
+//! hundredth of a decibel from the gain changing between samples. Without look-ahead the gain
+//! can jump, so the true peak can go further over.
+
+use meadow_common_dsp::{Ramp, db_to_gain, gain_to_db};
+
+pub mod algorithm;
+#[cfg(test)]
+mod tests;
+
+pub use algorithm::LimiterAlgorithm;
+use algorithm::{Dpl, ZaMaximX2};
+
+pub const MAX_LOOKAHEAD_MS: f64 = 20.0;
+
+/// How long it takes to move to a new input gain.
+pub const SMOOTHING_MS: f64 = 20.0;
+
+/// How many times the input is oversampled to look for peaks between samples.
+pub const TRUE_PEAK_OVERSAMPLING: usize = 4;
+
+/// How many future samples the peak interpolator needs. This is part of the latency whether
+/// or not true peak detection is enabled, so toggling it doesn't change the latency.
+pub const INTERPOLATOR_DELAY: usize = 8;
+
+const INTERPOLATOR_TAPS: usize = 2 * INTERPOLATOR_DELAY;
+
+/// How far below the threshold the peaks are brought, so rounding errors can't push them over.
+const THRESHOLD_MARGIN: f32 = 1.0 - 1e-5;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Algorithm {
+    /// After x42's dpl. See [`algorithm::Dpl`].
+    #[default]
+    Dpl,
+    /// After ZaMaximX2. See [`algorithm::ZaMaximX2`].
+    ZaMaximX2,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LimiterParams {
+    pub algorithm: Algorithm,
+    pub input_gain_db: f32,
+    /// The ceiling the output never goes above.
+    pub threshold_db: f32,
+    pub release_ms: f32,
+    /// From `0.0` to [`MAX_LOOKAHEAD_MS`].
+    pub lookahead_ms: f32,
+    /// Also keeps the peaks between samples below the threshold.
+    pub true_peak: bool,
+}
+
+impl Default for LimiterParams {
+    fn default() -> Self {
+        Self {
+            algorithm: Algorithm::default(),
+            input_gain_db: 0.0,
+            threshold_db: -1.0,
+            release_ms: 50.0,
+            lookahead_ms: 5.0,
+            true_peak: false,
+        }
+    }
+}
+
+pub struct LimiterDsp {
+    params: LimiterParams,
+    sample_rate: f64,
+    ramp_len: usize,
+    lookahead_samples: usize,
+
+    dpl: Dpl,
+    za_maxim: ZaMaximX2,
+
+    input_gain: Ramp,
+    delay: [Vec<f32>; 2],
+    mask: usize,
+    /// The number of samples processed so far.
+    pos: usize,
+    interpolator: [[f32; INTERPOLATOR_TAPS]; TRUE_PEAK_OVERSAMPLING - 1],
+    /// The largest estimated peak between the sample being detected and the one before it.
+    prev_intersample_peak: f32,
+    hold: SlidingMin,
+    average: MovingAverage,
+
+    /// The most gain reduction during the last call to `process()`.
+    gain_reduction_db: f32,
+}
+
+impl LimiterDsp {
+    pub fn new(sample_rate: f64) -> Self {
+        assert!(sample_rate > 0.0);
+
+        let params = LimiterParams::default();
+        let max_lookahead = (MAX_LOOKAHEAD_MS / 1000.0 * sample_rate).ceil() as usize;
+        let capacity = (max_lookahead + INTERPOLATOR_DELAY + 1).next_power_of_two();
+
+        let mut new_self = Self {
+            params,
+            sample_rate,
+            ramp_len: ((SMOOTHING_MS / 1000.0 * sample_rate).round() as usize).max(1),
+            lookahead_samples: 0,
+            dpl: Dpl::default(),
+            za_maxim: ZaMaximX2::default(),
+            input_gain: Ramp::new(db_to_gain(params.input_gain_db)),
+            delay: [vec![0.0; capacity], vec![0.0; capacity]],
+            mask: capacity - 1,
+            pos: 0,
+            interpolator: interpolator_taps(),
+            prev_intersample_peak: 0.0,
+            hold: SlidingMin::new(capacity),
+            average: MovingAverage::new(capacity),
+            gain_reduction_db: 0.0,
+        };
+        new_self.lookahead_samples = new_self.lookahead_samples_for(params.lookahead_ms);
+        new_self.apply_lookahead();
+        new_self.algorithm_mut().set_params(&params, sample_rate);
+
+        new_self
+    }
+
+    pub fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+
+    fn lookahead_samples_for(&self, lookahead_ms: f32) -> usize {
+        let max = (MAX_LOOKAHEAD_MS / 1000.0 * self.sample_rate).ceil() as usize;
+        ((lookahead_ms.max(0.0) as f64 / 1000.0 * self.sample_rate).round() as usize).min(max)
+    }
+
+    /// The delay of the output in samples. This only changes with the look-ahead time.
+    pub fn latency_samples(&self) -> u32 {
+        (self.lookahead_samples + INTERPOLATOR_DELAY) as u32
+    }
+
+    fn algorithm_mut(&mut self) -> &mut dyn LimiterAlgorithm {
+        match self.params.algorithm {
+            Algorithm::Dpl => &mut self.dpl,
+            Algorithm::ZaMaximX2 => &mut self.za_maxim,
+        }
+    }
+
+    /// Sets new parameters. A new look-ahead time changes the latency and clears the limiter.
+    pub fn set_params(&mut self, params: LimiterParams) {
+        if self.params == params {
+            return;
+        }
+
+        if params.input_gain_db != self.params.input_gain_db {
+            self.input_gain
+                .set_target(db_to_gain(params.input_gain_db), self.ramp_len);
+        }
+
+        let switched_algorithm = params.algorithm != self.params.algorithm;
+        self.params = params;
+
+        let lookahead_samples = self.lookahead_samples_for(params.lookahead_ms);
+        if lookahead_samples != self.lookahead_samples {
+            self.lookahead_samples = lookahead_samples;
+            self.reset();
+        }
+
+        let sample_rate = self.sample_rate;
+        let algorithm = self.algorithm_mut();
+        if switched_algorithm {
+            // It hasn't seen the signal since it was last used.
+            algorithm.reset();
+        }
+        algorithm.set_params(&params, sample_rate);
+    }
+
+    /// The most gain reduction during the last call to [`Self::process()`], as a positive
+    /// number of decibels.
+    pub fn gain_reduction_db(&self) -> f32 {
+        self.gain_reduction_db
+    }
+
+    pub fn reset(&mut self) {
+        self.input_gain.snap();
+        for delay in self.delay.iter_mut() {
+            delay.fill(0.0);
+        }
+        self.pos = 0;
+        self.prev_intersample_peak = 0.0;
+        self.apply_lookahead();
+        self.gain_reduction_db = 0.0;
+        self.algorithm_mut().reset();
+    }
+
+    fn apply_lookahead(&mut self) {
+        self.hold.reset(self.lookahead_samples + 1);
+        self.average.reset(self.lookahead_samples + 1);
+    }
+
+    /// The peak of the sample at `pos`, which needs the [`INTERPOLATOR_DELAY`] samples after
+    /// it.
+    #[inline(always)]
+    fn peak_at(&mut self, pos: usize) -> f32 {
+        let mask = self.mask;
+        let sample_peak = self.delay[0][pos & mask]
+            .abs()
+            .max(self.delay[1][pos & mask].abs());
+        if !self.params.true_peak {
+            return sample_peak;
+        }
+
+        // The peaks between this sample and the next.
+        let first = pos.wrapping_sub(INTERPOLATOR_DELAY - 1);
+        let mut intersample_peak = 0.0f32;
+        for delay in &self.delay {
+            for taps in &self.interpolator {
+                let y: f32 = taps
+                    .iter()
+                    .enumerate()
+                    .map(|(k, tap)| tap * delay[first.wrapping_add(k) & mask])
+                    .sum();
+                intersample_peak = intersample_peak.max(y.abs());
+            }
+        }
+
+        let peak = sample_peak
+            .max(intersample_peak)
+            .max(self.prev_intersample_peak);
+        self.prev_intersample_peak = intersample_peak;
+
+        peak
+    }
+
+    pub fn process(&mut self, buf_l: &mut [f32], buf_r: &mut [f32]) {
+        let threshold = db_to_gain(self.params.threshold_db) * THRESHOLD_MARGIN;
+        let mut min_gain = 1.0f32;
+
+        for (l, r) in buf_l.iter_mut().zip(buf_r.iter_mut()) {
+            let input_gain = self.input_gain.next();
+            let pos = self.pos;
+            let mask = self.mask;
+            self.delay[0][pos & mask] = *l * input_gain;
+            self.delay[1][pos & mask] = *r * input_gain;
+
+            let detect_pos = pos.wrapping_sub(INTERPOLATOR_DELAY);
+            let peak = self.peak_at(detect_pos);
+            let required = if peak > threshold {
+                threshold / peak
+            } else {
+                1.0
+            };
+
+            let held = self.hold.push(detect_pos, required);
+            let algorithm: &mut dyn LimiterAlgorithm = match self.params.algorithm {
+                Algorithm::Dpl => &mut self.dpl,
+                Algorithm::ZaMaximX2 => &mut self.za_maxim,
+            };
+            let released = algorithm.tick(held).min(held);
+            let gain = self.average.push(released);
+            min_gain = min_gain.min(gain);
+
+            let out_pos = detect_pos.wrapping_sub(self.lookahead_samples);
+            *l = self.delay[0][out_pos & mask] * gain;
+            *r = self.delay[1][out_pos & mask] * gain;
+
+            self.pos = pos.wrapping_add(1);
+        }
+
+        self.gain_reduction_db = -gain_to_db(min_gain);
+    }
+}
+
+/// Blackman windowed sinc interpolators for the points between a sample and the next, at
+/// each fraction of [`TRUE_PEAK_OVERSAMPLING`]. Tap `k` applies to the sample at offset
+/// `k + 1 - INTERPOLATOR_DELAY` from the first of the two.
+fn interpolator_taps() -> [[f32; INTERPOLATOR_TAPS]; TRUE_PEAK_OVERSAMPLING - 1] {
+    use std::f64::consts::PI;
+
+    std::array::from_fn(|phase| {
+        let t = (phase + 1) as f64 / TRUE_PEAK_OVERSAMPLING as f64;
+        let half_width = INTERPOLATOR_DELAY as f64;
+
+        let mut taps = [0.0f64; INTERPOLATOR_TAPS];
+        for (k, tap) in taps.iter_mut().enumerate() {
+            let x = t - (k as f64 - (INTERPOLATOR_DELAY as f64 - 1.0));
+            let sinc = if x == 0.0 {
+                1.0
+            } else {
+                (PI * x).sin() / (PI * x)
+            };
+            let w = (x / half_width + 1.0) * 0.5;
+            let window = 0.42 - 0.5 * (2.0 * PI * w).cos() + 0.08 * (4.0 * PI * w).cos();
+            *tap = sinc * window;
+        }
+
+        // Unity gain at DC.
+        let sum: f64 = taps.iter().sum();
+        taps.map(|tap| (tap / sum) as f32)
+    })
+}
+
+/// The minimum of the last `len` values, in constant time per value.
+struct SlidingMin {
+    /// An increasing run of values with the positions they were pushed at.
+    values: Vec<f32>,
+    positions: Vec<usize>,
+    mask: usize,
+    head: usize,
+    count: usize,
+    len: usize,
+}
+
+impl SlidingMin {
+    fn new(capacity: usize) -> Self {
+        assert!(capacity.is_power_of_two());
+
+        Self {
+            values: vec![0.0; capacity],
+            positions: vec![0; capacity],
+            mask: capacity - 1,
+            head: 0,
+            count: 0,
+            len: 1,
+        }
+    }
+
+    fn reset(&mut self, len: usize) {
+        assert!(len >= 1 && len <= self.values.len());
+
+        self.head = 0;
+        self.count = 0;
+        self.len = len;
+    }
+
+    #[inline(always)]
+    fn push(&mut self, pos: usize, value: f32) -> f32 {
+        // Values that are larger than the new one can't be the minimum anymore.
+        while self.count > 0 && self.values[(self.head + self.count - 1) & self.mask] >= value {
+            self.count -= 1;
+        }
+        let tail = (self.head + self.count) & self.mask;
+        self.values[tail] = value;
+        self.positions[tail] = pos;
+        self.count += 1;
+
+        while pos.wrapping_sub(self.positions[self.head]) >= self.len {
+            self.head = (self.head + 1) & self.mask;
+            self.count -= 1;
+        }
+
+        self.values[self.head]
+    }
+}
+
+/// The mean of the last `len` values, which start out as `1.0`.
+struct MovingAverage {
+    values: Vec<f32>,
+    mask: usize,
+    pos: usize,
+    len: usize,
+    sum: f64,
+}
+
+impl MovingAverage {
+    fn new(capacity: usize) -> Self {
+        assert!(capacity.is_power_of_two());
+
+        Self {
+            values: vec![1.0; capacity],
+            mask: capacity - 1,
+            pos: 0,
+            len: 1,
+            sum: 1.0,
+        }
+    }
+
+    fn reset(&mut self, len: usize) {
+        assert!(len >= 1 && len <= self.values.len());
+
+        self.values.fill(1.0);
+        self.len = len;
+        self.sum = len as f64;
+    }
+
+    #[inline(always)]
+    fn push(&mut self, value: f32) -> f32 {
+        let oldest = self.values[self.pos.wrapping_sub(self.len) & self.mask];
+        self.values[self.pos & self.mask] = value;
+        self.pos = self.pos.wrapping_add(1);
+        self.sum += value as f64 - oldest as f64;
+
+        // Rounding errors in the running sum must not raise the gain.
+        ((self.sum / self.len as f64) as f32).min(1.0)
+    }
+}
@@ -0,0 +1,339 @@
+use meadow_common_dsp::db_to_gain;
+use std::f64::consts::PI;
+
+use crate::{Algorithm, LimiterDsp, LimiterParams, MAX_LOOKAHEAD_MS};
+
+const SAMPLE_RATES: [f64; 3] = [44_100.0, 48_000.0, 192_000.0];
+const ALGORITHMS: [Algorithm; 2] = [Algorithm::Dpl, Algorithm::ZaMaximX2];
+const LOOKAHEADS_MS: [f32; 4] = [0.0, 0.1, 5.0, MAX_LOOKAHEAD_MS as f32];
+const LEN: usize = 1 << 15;
+
+struct Rng(u32);
+
+impl Rng {
+    fn next(&mut self) -> u32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0
+    }
+
+    /// From -1.0 to 1.0.
+    fn bipolar(&mut self) -> f32 {
+        (self.next() as f32 / u32::MAX as f32) * 2.0 - 1.0
+    }
+}
+
+/// Signals that are as hard as possible on a limiter.
+fn worst_cases(sample_rate: f64) -> Vec<(&'static str, Vec<f32>)> {
+    let mut rng = Rng(1);
+
+    // Single sample spikes far over full scale, after silence and in quick succession.
+    let mut spikes = vec![0.0f32; LEN];
+    let mut i = 1000;
+    while i < LEN {
+        spikes[i] = if rng.next() & 1 == 0 { 100.0 } else { -100.0 };
+        i += 1 + rng.next() as usize % 300;
+    }
+
+    let steps = (0..LEN)
+        .map(|n| if (n / 4096) % 2 == 0 { 0.0 } else { 10.0 })
+        .collect();
+    let square = (0..LEN)
+        .map(|n| if (n / 50) % 2 == 0 { 4.0 } else { -4.0 })
+        .collect();
+    let nyquist = (0..LEN)
+        .map(|n| if n % 2 == 0 { 2.0 } else { -2.0 })
+        .collect();
+    let noise = (0..LEN).map(|_| 16.0 * rng.bipolar()).collect();
+    // Gets louder the whole way, so the gain never gets to recover.
+    let swell = (0..LEN)
+        .map(|n| {
+            let t = n as f64 / sample_rate;
+            ((n as f64 / LEN as f64) * 30.0 * (2.0 * PI * 997.0 * t).sin()) as f32
+        })
+        .collect();
+
+    vec![
+        ("spikes", spikes),
+        ("steps", steps),
+        ("square", square),
+        ("nyquist", nyquist),
+        ("noise", noise),
+        ("swell", swell),
+    ]
+}
+
+/// Runs `input` through `dsp` in blocks of random sizes, changing the parameters now and
+/// then with `change`.
+fn process(
+    dsp: &mut LimiterDsp,
+    input: &[f32],
+    rng: &mut Rng,
+    mut change: impl FnMut(usize) -> Option<LimiterParams>,
+) -> Vec<f32> {
+    let mut buf_l = input.to_vec();
+    let mut buf_r: Vec<f32> = input.iter().map(|x| -0.5 * x).collect();
+
+    let mut start = 0;
+    while start < input.len() {
+        if let Some(params) = change(start) {
+            dsp.set_params(params);
+        }
+        let end = (start + 1 + rng.next() as usize % 700).min(input.len());
+        dsp.process(&mut buf_l[start..end], &mut buf_r[start..end]);
+        start = end;
+    }
+
+    buf_l
+}
+
+/// Measures the true peak the way true peak meters do, with 4 times oversampling. The
+/// interpolator is designed like the limiter's, but computed independently. The last few
+/// samples are left out, since the limiter hasn't seen what comes after them.
+fn measured_true_peak(signal: &[f32]) -> f32 {
+    const OVERSAMPLING: usize = 4;
+    const HALF_TAPS: usize = 8;
+
+    let mut peak = signal.iter().fold(0.0f32, |peak, x| peak.max(x.abs()));
+    for n in HALF_TAPS - 1..signal.len() - HALF_TAPS {
+        for phase in 1..OVERSAMPLING {
+            let t = phase as f64 / OVERSAMPLING as f64;
+            let (mut y, mut sum) = (0.0f64, 0.0f64);
+            let first = n + 1 - HALF_TAPS;
+            for (i, x) in signal[first..=n + HALF_TAPS].iter().enumerate() {
+                let d = t - (first as f64 + i as f64 - n as f64);
+                let sinc = (PI * d).sin() / (PI * d);
+                let w = (d / HALF_TAPS as f64 + 1.0) * 0.5;
+                let window = 0.42 - 0.5 * (2.0 * PI * w).cos() + 0.08 * (4.0 * PI * w).cos();
+                y += *x as f64 * sinc * window;
+                sum += sinc * window;
+            }
+            peak = peak.max((y / sum).abs() as f32);
+        }
+    }
+
+    peak
+}
+
+#[test]
+fn quiet_signals_pass_through_with_the_reported_latency() {
+    let mut rng = Rng(2);
+    let input: Vec<f32> = (0..LEN)
+        .map(|_| db_to_gain(-12.0) * rng.bipolar())
+        .collect();
+
+    for sample_rate in SAMPLE_RATES {
+        for algorithm in ALGORITHMS {
+            for lookahead_ms in LOOKAHEADS_MS {
+                for true_peak in [false, true] {
+                    let mut dsp = LimiterDsp::new(sample_rate);
+                    dsp.set_params(LimiterParams {
+                        algorithm,
+                        lookahead_ms,
+                        true_peak,
+                        ..Default::default()
+                    });
+
+                    let latency = dsp.latency_samples() as usize;
+                    let expected_latency =
+                        (lookahead_ms as f64 / 1000.0 * sample_rate).round() as usize + 8;
+                    assert_eq!(latency, expected_latency);
+
+                    let output = process(&mut dsp, &input, &mut rng, |_| None);
+                    assert!(output[..latency].iter().all(|&x| x == 0.0));
+                    assert_eq!(
+                        output[latency..],
+                        input[..LEN - latency],
+                        "{algorithm:?}, {lookahead_ms} ms, true peak {true_peak}, {sample_rate} Hz"
+                    );
+                    assert_eq!(dsp.gain_reduction_db(), 0.0);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn output_never_exceeds_the_threshold() {
+    let mut rng = Rng(3);
+
+    for sample_rate in SAMPLE_RATES {
+        for (name, input) in worst_cases(sample_rate) {
+            for algorithm in ALGORITHMS {
+                for lookahead_ms in LOOKAHEADS_MS {
+                    for true_peak in [false, true] {
+                        for threshold_db in [-0.1, -12.0] {
+                            let params = LimiterParams {
+                                algorithm,
+                                threshold_db,
+                                lookahead_ms,
+                                true_peak,
+                                release_ms: 5.0,
+                                ..Default::default()
+                            };
+                            let mut dsp = LimiterDsp::new(sample_rate);
+                            dsp.set_params(params);
+
+                            // Jumps the input gain, threshold and release around mid-stream.
+                            let output = process(&mut dsp, &input, &mut rng, |start| {
+                                (start > LEN / 2).then_some(LimiterParams {
+                                    input_gain_db: 24.0,
+                                    threshold_db: threshold_db - 6.0,
+                                    release_ms: 1.0,
+                                    ..params
+                                })
+                            });
+
+                            let ceiling = db_to_gain(threshold_db);
+                            let peak = output.iter().fold(0.0f32, |peak, x| peak.max(x.abs()));
+                            assert!(
+                                peak <= ceiling,
+                                "{name}, {algorithm:?}, {lookahead_ms} ms, true peak {true_peak}, {threshold_db} dB, {sample_rate} Hz: peaks at {peak}"
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn true_peak_stays_below_the_threshold() {
+    let sample_rate = 48_000.0;
+    let mut rng = Rng(4);
+
+    // Sines whose samples all miss the peaks, as far as they can.
+    let quarter = (0..LEN)
+        .map(|n| 2.0 * (PI * 0.5 * n as f64 + PI * 0.25).sin() as f32)
+        .collect();
+    let near_nyquist = (0..LEN)
+        .map(|n| 2.0 * (2.0 * PI * 0.45 * n as f64 + 0.3).sin() as f32)
+        .collect();
+    let mut signals = worst_cases(sample_rate);
+    signals.push(("quarter", quarter));
+    signals.push(("near nyquist", near_nyquist));
+
+    for (name, input) in signals {
+        for algorithm in ALGORITHMS {
+            // Without look-ahead the gain jumps between samples, so that isn't covered.
+            for lookahead_ms in [1.0, 5.0] {
+                let threshold_db = -1.0;
+                let mut dsp = LimiterDsp::new(sample_rate);
+                dsp.set_params(LimiterParams {
+                    algorithm,
+                    threshold_db,
+                    lookahead_ms,
+                    true_peak: true,
+                    ..Default::default()
+                });
+
+                let output = process(&mut dsp, &input, &mut rng, |_| None);
+                let true_peak_db = 20.0 * measured_true_peak(&output).log10();
+                // The gain changes from sample to sample, which moves the peaks in between
+                // a little.
+                assert!(
+                    true_peak_db < threshold_db + 0.02,
+                    "{name}, {algorithm:?}, {lookahead_ms} ms: true peak at {true_peak_db} dB"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn gain_reduction_is_metered() {
+    let sample_rate = 48_000.0;
+
+    for algorithm in ALGORITHMS {
+        let mut dsp = LimiterDsp::new(sample_rate);
+        dsp.set_params(LimiterParams {
+            algorithm,
+            threshold_db: -6.0,
+            ..Default::default()
+        });
+
+        let mut buf_l = vec![1.0f32; 4096];
+        let mut buf_r = buf_l.clone();
+        dsp.process(&mut buf_l, &mut buf_r);
+
+        let reduction_db = dsp.gain_reduction_db();
+        assert!(
+            (reduction_db - 6.0).abs() < 0.001,
+            "{algorithm:?}: {reduction_db} dB"
+        );
+        assert!((buf_l[4095] - db_to_gain(-6.0)).abs() < 1e-4);
+    }
+}
+
+#[test]
+fn gain_recovers_in_the_release_time() {
+    let sample_rate = 48_000.0;
+    let release_ms = 50.0f32;
+
+    for algorithm in ALGORITHMS {
+        let mut dsp = LimiterDsp::new(sample_rate);
+        dsp.set_params(LimiterParams {
+            algorithm,
+            threshold_db: 0.0,
+            release_ms,
+            lookahead_ms: 0.0,
+            ..Default::default()
+        });
+
+        // 20 dB of gain reduction for a while, then a quiet signal.
+        let len = sample_rate as usize;
+        let mut buf_l: Vec<f32> = (0..len)
+            .map(|n| if n < 1000 { 10.0 } else { 0.01 })
+            .collect();
+        let mut buf_r = buf_l.clone();
+        dsp.process(&mut buf_l, &mut buf_r);
+
+        let gain_at = |ms: f32| {
+            let n = 1000 + 8 + (ms as f64 / 1000.0 * sample_rate) as usize;
+            buf_l[n] / 0.01
+        };
+        let gain_after_release = gain_at(release_ms);
+        match algorithm {
+            // Covers all but 1/e of the way back from 0.1 in linear gain...
+            Algorithm::Dpl => {
+                let expected = 1.0 - 0.9 * (-1.0f32).exp();
+                assert!((gain_after_release - expected).abs() < 0.01);
+            }
+            // ...or in decibels.
+            Algorithm::ZaMaximX2 => {
+                let expected = db_to_gain(-20.0 * (-1.0f32).exp());
+                assert!((gain_after_release - expected).abs() < 0.01);
+            }
+        }
+        assert!(gain_at(500.0) > 0.999);
+    }
+}
+
+#[test]
+fn lookahead_changes_the_latency() {
+    let mut dsp = LimiterDsp::new(48_000.0);
+    let mut last_latency = dsp.latency_samples();
+
+    for lookahead_ms in [0.0, 1.0, 10.0, 100.0] {
+        dsp.set_params(LimiterParams {
+            lookahead_ms,
+            ..Default::default()
+        });
+        let latency = dsp.latency_samples();
+        assert_ne!(latency, last_latency);
+        last_latency = latency;
+
+        // Toggling true peak detection leaves it alone.
+        dsp.set_params(LimiterParams {
+            lookahead_ms,
+            true_peak: true,
+            ..Default::default()
+        });
+        assert_eq!(dsp.latency_samples(), latency);
+    }
+
+    // Clamped to the longest look-ahead time.
+    assert_eq!(last_latency, 960 + 8);
+}
@@ -0,0 +1,86 @@
+//! The editor the compressors, the limiter and the gate share: a gain reduction meter next to
+//! a slider for every parameter.
+
+use atomic_float::AtomicF32;
+use nih_plug::prelude::*;
+use nih_plug_egui::create_egui_editor;
+use nih_plug_egui::egui::{self, Align2, Color32, FontId, Rect, Sense, Vec2};
+use nih_plug_egui::widgets::generic::{self, GenericSlider};
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+
+pub use nih_plug_egui::EguiState;
+
+pub mod testing;
+
+const EDITOR_WIDTH: u32 = 420;
+const EDITOR_HEIGHT: u32 = 480;
+
+const METER_WIDTH: f32 = 32.0;
+
+const METER_BACKGROUND_COLOR: Color32 = Color32::from_rgb(0x16, 0x16, 0x18);
+const METER_COLOR: Color32 = Color32::from_rgb(0xe8, 0x9a, 0x3c);
+const METER_TEXT_COLOR: Color32 = Color32::from_rgb(0xf0, 0xf0, 0xf0);
+
+pub fn default_state() -> Arc<EguiState> {
+    EguiState::from_size(EDITOR_WIDTH, EDITOR_HEIGHT)
+}
+
+/// `gain_reduction_db` is the positive number of decibels the plugin stores after every
+/// block. The meter is full at `max_gain_reduction_db`.
+pub fn create<P: Params>(
+    params: Arc<P>,
+    editor_state: Arc<EguiState>,
+    gain_reduction_db: Arc<AtomicF32>,
+    max_gain_reduction_db: f32,
+) -> Option<Box<dyn Editor>> {
+    create_egui_editor(
+        editor_state,
+        (),
+        |_, _| {},
+        move |egui_ctx, setter, _| {
+            egui::CentralPanel::default().show(egui_ctx, |ui| {
+                ui.horizontal(|ui| {
+                    draw_meter(
+                        ui,
+                        gain_reduction_db.load(Ordering::Relaxed),
+                        max_gain_reduction_db,
+                    );
+
+                    ui.vertical(|ui| {
+                        generic::create(ui, params.clone(), setter, GenericSlider);
+                    });
+                });
+            });
+
+            // The meter should follow the audio even without user input.
+            egui_ctx.request_repaint();
+        },
+    )
+}
+
+/// The bar grows down from the top, like the gain reduction meters on hardware.
+fn draw_meter(ui: &mut egui::Ui, gain_reduction_db: f32, max_gain_reduction_db: f32) {
+    let (rect, _) = ui.allocate_exact_size(
+        Vec2::new(METER_WIDTH, ui.available_height()),
+        Sense::hover(),
+    );
+    let painter = ui.painter();
+
+    painter.rect_filled(rect, 0.0, METER_BACKGROUND_COLOR);
+
+    let fraction = (gain_reduction_db / max_gain_reduction_db).clamp(0.0, 1.0);
+    let bar = Rect::from_min_max(
+        rect.min,
+        egui::pos2(rect.max.x, rect.min.y + fraction * rect.height()),
+    );
+    painter.rect_filled(bar, 0.0, METER_COLOR);
+
+    painter.text(
+        rect.center_bottom() - Vec2::new(0.0, 4.0),
+        Align2::CENTER_BOTTOM,
+        format!("{gain_reduction_db:.1}"),
+        FontId::monospace(11.0),
+        METER_TEXT_COLOR,
+    );
+}
@@ -0,0 +1,42 @@
+//! The meter's side of the plugins' tests.
+
+use atomic_float::AtomicF32;
+use nih_plug::prelude::*;
+use std::f32::consts::PI;
+use std::sync::atomic::Ordering;
+
+/// One second at the sample rate a plugin starts out with.
+pub const BLOCK_LEN: usize = 44_100;
+
+/// Checks that `plugin` stores the gain reduction its DSP ends every block with in `meter`,
+/// and clears it on reset, which is all the editor reads. `process_block` processes a
+/// stereo block and returns the DSP's gain reduction after it.
+///
+/// The first block is a 1 kHz sine peaking at `level`, which has to make the plugin reduce
+/// its gain, and the second one is silence. Returns the gain reduction after the first.
+pub fn check_meter<P: Plugin>(
+    plugin: &mut P,
+    meter: &AtomicF32,
+    level: f32,
+    mut process_block: impl FnMut(&mut P, &mut [f32], &mut [f32]) -> f32,
+) -> f32 {
+    assert_eq!(meter.load(Ordering::Relaxed), 0.0);
+
+    let mut l: Vec<f32> = (0..BLOCK_LEN)
+        .map(|i| level * (2.0 * PI * 1000.0 * i as f32 / BLOCK_LEN as f32).sin())
+        .collect();
+    let mut r = l.clone();
+    let gain_reduction_db = process_block(plugin, &mut l, &mut r);
+    assert!(gain_reduction_db > 0.0, "{gain_reduction_db} dB");
+    assert_eq!(meter.load(Ordering::Relaxed), gain_reduction_db);
+
+    l.fill(0.0);
+    r.fill(0.0);
+    let quiet_db = process_block(plugin, &mut l, &mut r);
+    assert_eq!(meter.load(Ordering::Relaxed), quiet_db);
+
+    plugin.reset();
+    assert_eq!(meter.load(Ordering::Relaxed), 0.0);
+
+    gain_reduction_db
+}
@@ -0,0 +1,57 @@
+//! Mid/side split and merge plugins. MeadowEq has no mid/side mode of its own, so a mid/side
+//! EQ is a split, an EQ on each of the mid and side outputs, and a merge.
+
+use meadow_mid_side_dsp::MidSideParams;
+use nih_plug::prelude::*;
+
+mod merge;
+mod split;
+
+pub use merge::MeadowMidSideMerge;
+pub use split::MeadowMidSideSplit;
+
+#[derive(Params)]
+struct MidSideGainParams {
+    #[id = "mid_gain_db"]
+    pub mid_gain_db: FloatParam,
+    #[id = "side_gain_db"]
+    pub side_gain_db: FloatParam,
+}
+
+impl Default for MidSideGainParams {
+    fn default() -> Self {
+        Self {
+            mid_gain_db: gain_param("Mid gain"),
+            side_gain_db: gain_param("Side gain"),
+        }
+    }
+}
+
+impl MidSideGainParams {
+    /// Collects the current parameter values into the form used by the DSP.
+    pub fn mid_side_params(&self) -> MidSideParams {
+        MidSideParams {
+            mid_gain_db: self.mid_gain_db.value(),
+            side_gain_db: self.side_gain_db.value(),
+        }
+    }
+}
+
+fn gain_param(name: &str) -> FloatParam {
+    FloatParam::new(
+        name,
+        0.0,
+        FloatRange::SymmetricalSkewed {
+            min: -30.0,
+            max: 30.0,
+            factor: 0.4,
+            center: 0.0,
+        },
+    )
+    .with_unit(" dB")
+}
+
+#[cfg(not(feature = "bundled"))]
+nih_export_clap!(MeadowMidSideSplit, MeadowMidSideMerge);
+#[cfg(not(feature = "bundled"))]
+nih_export_vst3!(MeadowMidSideSplit, MeadowMidSideMerge);
@@ -0,0 +1,114 @@
+use meadow_mid_side_dsp::MidSideMergeDsp;
+use nih_plug::prelude::*;
+use std::sync::Arc;
+
+use crate::MidSideGainParams;
+
+pub struct MeadowMidSideMerge {
+    params: Arc<MidSideGainParams>,
+    dsp: MidSideMergeDsp,
+}
+
+impl Default for MeadowMidSideMerge {
+    fn default() -> Self {
+        Self {
+            params: Arc::new(MidSideGainParams::default()),
+            dsp: MidSideMergeDsp::new(44_100.0),
+        }
+    }
+}
+
+impl Plugin for MeadowMidSideMerge {
+    const NAME: &'static str = "Meadow Mid/Side Merge";
+    const VENDOR: &'static str = "Billy Messenger";
+    const URL: &'static str = env!("CARGO_PKG_HOMEPAGE");
+    const EMAIL: &'static str = "60663878+BillyDM@users.noreply.github.com";
+
+    const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+
+    // The mid and side signals on two mono inputs, and a stereo output.
+    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[AudioIOLayout {
+        main_input_channels: NonZeroU32::new(1),
+        main_output_channels: NonZeroU32::new(2),
+
+        aux_input_ports: &[new_nonzero_u32(1)],
+        aux_output_ports: &[],
+
+        names: PortNames {
+            main_input: Some("Mid"),
+            aux_inputs: &["Side"],
+            ..PortNames::const_default()
+        },
+    }];
+
+    const MIDI_INPUT: MidiConfig = MidiConfig::None;
+    const MIDI_OUTPUT: MidiConfig = MidiConfig::None;
+
+    const SAMPLE_ACCURATE_AUTOMATION: bool = true;
+
+    type SysExMessage = ();
+    type BackgroundTask = ();
+
+    fn params(&self) -> Arc<dyn Params> {
+        self.params.clone()
+    }
+
+    fn initialize(
+        &mut self,
+        _audio_io_layout: &AudioIOLayout,
+        config: &BufferConfig,
+        _context: &mut impl InitContext<Self>,
+    ) -> bool {
+        self.dsp = MidSideMergeDsp::new(config.sample_rate as f64);
+        self.dsp.set_params(self.params.mid_side_params());
+
+        true
+    }
+
+    fn reset(&mut self) {
+        self.dsp.reset();
+    }
+
+    fn process(
+        &mut self,
+        buffer: &mut Buffer,
+        aux: &mut AuxiliaryBuffers,
+        _context: &mut impl ProcessContext<Self>,
+    ) -> ProcessStatus {
+        self.dsp.set_params(self.params.mid_side_params());
+
+        // The mid input is in the left output, and becomes the left channel in place.
+        let [l, r, ..] = buffer.as_slice() else {
+            return ProcessStatus::Normal;
+        };
+        let Some([side, ..]) = aux.inputs.first_mut().map(|buffer| buffer.as_slice()) else {
+            return ProcessStatus::Normal;
+        };
+
+        self.dsp.process(l, side, r);
+
+        ProcessStatus::Normal
+    }
+}
+
+impl ClapPlugin for MeadowMidSideMerge {
+    const CLAP_ID: &'static str = "app.meadowlark.meadow-mid-side-merge";
+    const CLAP_DESCRIPTION: Option<&'static str> =
+        Some("Merges mid and side back into a stereo signal");
+    const CLAP_MANUAL_URL: Option<&'static str> = Some(Self::URL);
+    const CLAP_SUPPORT_URL: Option<&'static str> = None;
+
+    const CLAP_FEATURES: &'static [ClapFeature] = &[
+        ClapFeature::AudioEffect,
+        ClapFeature::Stereo,
+        ClapFeature::Mono,
+        ClapFeature::Utility,
+    ];
+}
+
+impl Vst3Plugin for MeadowMidSideMerge {
+    const VST3_CLASS_ID: [u8; 16] = *b"Meadowlark.MSMrg";
+
+    const VST3_SUBCATEGORIES: &'static [Vst3SubCategory] =
+        &[Vst3SubCategory::Fx, Vst3SubCategory::Tools];
+}
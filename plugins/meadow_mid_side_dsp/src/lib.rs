@@ -0,0 +1,146 @@
+//! Conversion between left/right and mid/side, split across two plugins so other plugins can
+//! be put in between.
+//!
+//! Mid is `(l + r) / 2` and side is `(l - r) / 2`, so merging is just `l = mid + side` and
+//! `r = mid - side`. At unity gain a split followed by a merge gives back the exact input
+//! samples whenever the sums and differences don't need more precision than an `f32` has,
+//! which holds for anything that came from a 24 bit or lower fixed point source. Otherwise
+//! the result can differ in the last bit.
+
+use meadow_common_dsp::{Ramp, db_to_gain};
+
+#[cfg(test)]
+mod tests;
+
+/// How long it takes to move to new gains.
+pub const SMOOTHING_MS: f64 = 20.0;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct MidSideParams {
+    pub mid_gain_db: f32,
+    pub side_gain_db: f32,
+}
+
+/// Turns left and right into mid and side.
+pub struct MidSideSplitDsp {
+    gains: Gains,
+}
+
+impl MidSideSplitDsp {
+    pub fn new(sample_rate: f64) -> Self {
+        Self {
+            gains: Gains::new(sample_rate),
+        }
+    }
+
+    pub fn sample_rate(&self) -> f64 {
+        self.gains.sample_rate
+    }
+
+    /// Sets new gains, which are ramped to over [`SMOOTHING_MS`].
+    pub fn set_params(&mut self, params: MidSideParams) {
+        self.gains.set_params(params);
+    }
+
+    /// Jumps to the current gains without ramping.
+    pub fn reset(&mut self) {
+        self.gains.reset();
+    }
+
+    /// Overwrites `buf_l` with the mid signal and writes the side signal to `buf_side`.
+    pub fn process(&mut self, buf_l: &mut [f32], buf_r: &[f32], buf_side: &mut [f32]) {
+        for ((l_mid, &r), side) in buf_l.iter_mut().zip(buf_r).zip(buf_side.iter_mut()) {
+            let (mid_gain, side_gain) = self.gains.next();
+            let l = *l_mid;
+
+            *l_mid = mid_gain * (0.5 * (l + r));
+            *side = side_gain * (0.5 * (l - r));
+        }
+    }
+}
+
+/// Turns mid and side back into left and right.
+pub struct MidSideMergeDsp {
+    gains: Gains,
+}
+
+impl MidSideMergeDsp {
+    pub fn new(sample_rate: f64) -> Self {
+        Self {
+            gains: Gains::new(sample_rate),
+        }
+    }
+
+    pub fn sample_rate(&self) -> f64 {
+        self.gains.sample_rate
+    }
+
+    /// Sets new gains, which are ramped to over [`SMOOTHING_MS`].
+    pub fn set_params(&mut self, params: MidSideParams) {
+        self.gains.set_params(params);
+    }
+
+    /// Jumps to the current gains without ramping.
+    pub fn reset(&mut self) {
+        self.gains.reset();
+    }
+
+    /// Overwrites `buf_mid` with the left signal and writes the right signal to `buf_r`.
+    pub fn process(&mut self, buf_mid: &mut [f32], buf_side: &[f32], buf_r: &mut [f32]) {
+        for ((mid_l, &side), r) in buf_mid.iter_mut().zip(buf_side).zip(buf_r.iter_mut()) {
+            let (mid_gain, side_gain) = self.gains.next();
+            let mid = mid_gain * *mid_l;
+            let side = side_gain * side;
+
+            *mid_l = mid + side;
+            *r = mid - side;
+        }
+    }
+}
+
+/// The mid and side gains shared by both directions.
+struct Gains {
+    params: MidSideParams,
+    sample_rate: f64,
+    ramp_len: usize,
+
+    mid: Ramp,
+    side: Ramp,
+}
+
+impl Gains {
+    fn new(sample_rate: f64) -> Self {
+        assert!(sample_rate > 0.0);
+
+        Self {
+            params: MidSideParams::default(),
+            sample_rate,
+            ramp_len: ((SMOOTHING_MS / 1000.0 * sample_rate).round() as usize).max(1),
+            mid: Ramp::new(1.0),
+            side: Ramp::new(1.0),
+        }
+    }
+
+    fn set_params(&mut self, params: MidSideParams) {
+        if self.params == params {
+            return;
+        }
+
+        self.mid
+            .set_target(db_to_gain(params.mid_gain_db), self.ramp_len);
+        self.side
+            .set_target(db_to_gain(params.side_gain_db), self.ramp_len);
+
+        self.params = params;
+    }
+
+    fn reset(&mut self) {
+        self.mid.snap();
+        self.side.snap();
+    }
+
+    #[inline(always)]
+    fn next(&mut self) -> (f32, f32) {
+        (self.mid.next(), self.side.next())
+    }
+}
@@ -0,0 +1,118 @@
+use meadow_test_signals::noise_24_bit;
+
+use crate::{MidSideMergeDsp, MidSideParams, MidSideSplitDsp, SMOOTHING_MS};
+
+const SAMPLE_RATE: f64 = 48_000.0;
+const LEN: usize = 8192;
+
+fn split(dsp: &mut MidSideSplitDsp, l: &[f32], r: &[f32]) -> (Vec<f32>, Vec<f32>) {
+    let mut mid = l.to_vec();
+    let mut side = vec![0.0; l.len()];
+    for ((mid, side), r) in mid
+        .chunks_mut(100)
+        .zip(side.chunks_mut(100))
+        .zip(r.chunks(100))
+    {
+        dsp.process(mid, r, side);
+    }
+
+    (mid, side)
+}
+
+fn merge(dsp: &mut MidSideMergeDsp, mid: &[f32], side: &[f32]) -> (Vec<f32>, Vec<f32>) {
+    let mut l = mid.to_vec();
+    let mut r = vec![0.0; mid.len()];
+    for ((l, r), side) in l
+        .chunks_mut(100)
+        .zip(r.chunks_mut(100))
+        .zip(side.chunks(100))
+    {
+        dsp.process(l, side, r);
+    }
+
+    (l, r)
+}
+
+#[test]
+fn split_and_merge_are_bit_transparent() {
+    let l = noise_24_bit(LEN, 1);
+    let r = noise_24_bit(LEN, 2);
+    // Full scale, silence on one side, identical and inverted channels.
+    let cases = [
+        (l.clone(), r.clone()),
+        (l.clone(), vec![0.0; LEN]),
+        (l.clone(), l.clone()),
+        (l.clone(), l.iter().map(|x| -x).collect()),
+        (vec![1.0; LEN], vec![-1.0; LEN]),
+    ];
+
+    for (i, (l, r)) in cases.into_iter().enumerate() {
+        let (mid, side) = split(&mut MidSideSplitDsp::new(SAMPLE_RATE), &l, &r);
+        let (out_l, out_r) = merge(&mut MidSideMergeDsp::new(SAMPLE_RATE), &mid, &side);
+
+        assert!(out_l == l && out_r == r, "case {i} isn't transparent");
+    }
+}
+
+#[test]
+fn split_produces_mid_and_side() {
+    let l = noise_24_bit(LEN, 3);
+    let r = noise_24_bit(LEN, 4);
+    let (mid, side) = split(&mut MidSideSplitDsp::new(SAMPLE_RATE), &l, &r);
+
+    for n in 0..LEN {
+        assert_eq!(mid[n], 0.5 * (l[n] + r[n]));
+        assert_eq!(side[n], 0.5 * (l[n] - r[n]));
+    }
+}
+
+#[test]
+fn gains_apply_to_mid_and_side() {
+    let params = MidSideParams {
+        mid_gain_db: -6.0,
+        side_gain_db: 6.0,
+    };
+    let (mid_gain, side_gain) = (10.0f32.powf(-6.0 / 20.0), 10.0f32.powf(6.0 / 20.0));
+    let l = noise_24_bit(LEN, 5);
+    let r = noise_24_bit(LEN, 6);
+
+    let mut split_dsp = MidSideSplitDsp::new(SAMPLE_RATE);
+    split_dsp.set_params(params);
+    split_dsp.reset();
+    let (mid, side) = split(&mut split_dsp, &l, &r);
+
+    let mut merge_dsp = MidSideMergeDsp::new(SAMPLE_RATE);
+    merge_dsp.set_params(params);
+    merge_dsp.reset();
+    let (out_l, out_r) = merge(&mut merge_dsp, &mid, &side);
+
+    // Both plugins apply their gains, so they add up.
+    for n in 0..LEN {
+        let expected_mid = mid_gain * mid_gain * 0.5 * (l[n] + r[n]);
+        let expected_side = side_gain * side_gain * 0.5 * (l[n] - r[n]);
+        assert!((out_l[n] - (expected_mid + expected_side)).abs() < 1e-5);
+        assert!((out_r[n] - (expected_mid - expected_side)).abs() < 1e-5);
+    }
+}
+
+#[test]
+fn gain_changes_are_ramped() {
+    let ramp_len = (SMOOTHING_MS / 1000.0 * SAMPLE_RATE) as usize;
+    let mut dsp = MidSideSplitDsp::new(SAMPLE_RATE);
+    dsp.set_params(MidSideParams {
+        mid_gain_db: -60.0,
+        side_gain_db: 0.0,
+    });
+
+    let (mid, _) = split(&mut dsp, &vec![1.0; 2 * ramp_len], &vec![1.0; 2 * ramp_len]);
+    let max_step = 1.0 / ramp_len as f32 + 1e-6;
+    let mut previous = 1.0;
+    for (n, &x) in mid.iter().enumerate() {
+        assert!(
+            x <= previous && previous - x <= max_step,
+            "sample {n}: {x} after {previous}"
+        );
+        previous = x;
+    }
+    assert_eq!(mid[ramp_len - 1], 0.001);
+}
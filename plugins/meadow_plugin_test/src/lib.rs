@@ -0,0 +1,701 @@
+//! Runs every plugin in a CLAP bundle the way a host would, through nih-plug's CLAP wrapper.
+//!
+//! Going through the wrapper means parameters are set and automated with events, exactly as
+//! a host does it, and the plugins get a playing transport. With nih-plug's
+//! `assert_process_allocs` feature, the wrapper aborts as soon as memory is allocated or
+//! freed while it resets a plugin or processes a block, so a plugin that allocates on the
+//! audio thread aborts its test binary and fails `cargo test`. A panic allocates its payload
+//! and is caught the same way.
+//!
+//! nih-plug only installs that allocator with `debug_assertions`, so these checks have to
+//! run in a debug build. Under `--release` nothing but the outputs would be checked.
+//!
+//! Locks are not checked. An uncontended `std` or `parking_lot` lock is a single atomic
+//! operation that calls nothing a host could intercept, so keeping locks off the audio
+//! thread is left to review: process paths should not have access to anything they could
+//! lock in the first place.
+
+use clap_sys::audio_buffer::clap_audio_buffer;
+use clap_sys::entry::clap_plugin_entry;
+use clap_sys::events::{
+    CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_PARAM_VALUE, CLAP_EVENT_TRANSPORT,
+    CLAP_TRANSPORT_HAS_BEATS_TIMELINE, CLAP_TRANSPORT_HAS_SECONDS_TIMELINE,
+    CLAP_TRANSPORT_HAS_TEMPO, CLAP_TRANSPORT_HAS_TIME_SIGNATURE, CLAP_TRANSPORT_IS_PLAYING,
+    clap_event_header, clap_event_param_value, clap_event_transport, clap_input_events,
+    clap_output_events,
+};
+use clap_sys::ext::audio_ports::{
+    CLAP_EXT_AUDIO_PORTS, clap_audio_port_info, clap_plugin_audio_ports,
+};
+use clap_sys::ext::audio_ports_config::{
+    CLAP_EXT_AUDIO_PORTS_CONFIG, clap_audio_ports_config, clap_plugin_audio_ports_config,
+};
+use clap_sys::ext::params::{
+    CLAP_EXT_PARAMS, CLAP_PARAM_IS_AUTOMATABLE, CLAP_PARAM_IS_STEPPED, clap_param_info,
+    clap_plugin_params,
+};
+use clap_sys::factory::plugin_factory::{CLAP_PLUGIN_FACTORY_ID, clap_plugin_factory};
+use clap_sys::fixedpoint::{CLAP_BEATTIME_FACTOR, CLAP_SECTIME_FACTOR};
+use clap_sys::host::clap_host;
+use clap_sys::id::clap_id;
+use clap_sys::plugin::clap_plugin;
+use clap_sys::process::{CLAP_PROCESS_ERROR, clap_process};
+use clap_sys::version::CLAP_VERSION;
+use std::ffi::{CStr, CString, c_char, c_void};
+use std::mem::MaybeUninit;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// The sample rates every plugin is activated with.
+pub const SAMPLE_RATES: [f64; 4] = [22_050.0, 44_100.0, 48_000.0, 192_000.0];
+
+/// Block sizes to process, including the tiny and odd ones hosts send when they split
+/// blocks for sample accurate automation.
+pub const BLOCK_SIZES: [usize; 6] = [1, 2, 63, 64, 512, MAX_BLOCK_SIZE];
+
+pub const MAX_BLOCK_SIZE: usize = 4096;
+
+/// The number of blocks processed at every block size.
+const NUM_BLOCKS: usize = 4;
+
+/// The most value changes an automatable parameter gets in one block.
+const MAX_CHANGES_PER_BLOCK: usize = 2;
+
+const TEMPO: f64 = 120.0;
+
+/// Calls one of the function pointers in a CLAP struct. All of the ones used here are
+/// required by the spec.
+macro_rules! clap_call {
+    ($obj:expr => $fn:ident($($arg:expr),* $(,)?)) => {
+        ((*$obj).$fn.expect(concat!("missing ", stringify!($fn))))($($arg),*)
+    };
+}
+
+/// Creates every plugin in the bundle for every audio port configuration and sample rate,
+/// and processes blocks of every size in [`BLOCK_SIZES`]. The main and auxiliary inputs are
+/// fed full scale noise, and every output has to stay finite.
+///
+/// The first block sets every parameter to a random value, and the blocks after it move the
+/// automatable ones at random times. Values are as often at either end of a parameter's
+/// range as anywhere in between. The host resets the plugin and jumps the transport between
+/// block sizes, and calls `on_main_thread` after any block that asked for it.
+///
+/// # Safety
+///
+/// `entry` must point to the `clap_entry` of a bundle linked into the calling binary.
+pub unsafe fn check_bundle(entry: *const clap_plugin_entry) {
+    // SAFETY: The caller guarantees `entry` is a valid entry point.
+    let factory = unsafe { init_bundle(entry) };
+
+    // SAFETY: The factory stays valid until `deinit`.
+    let num_plugins = unsafe { clap_call!(factory => get_plugin_count(factory)) };
+    assert!(num_plugins > 0, "the bundle has no plugins");
+    for index in 0..num_plugins {
+        // SAFETY: `index` is below the plugin count.
+        let id = unsafe {
+            let descriptor = clap_call!(factory => get_plugin_descriptor(factory, index));
+            assert!(!descriptor.is_null(), "plugin {index} has no descriptor");
+            CStr::from_ptr((*descriptor).id)
+        };
+
+        let num_configs = Instance::new(factory, id).audio_ports_configs().len();
+        for config in 0..num_configs.max(1) {
+            for sample_rate in SAMPLE_RATES {
+                let mut instance = Instance::new(factory, id);
+                if num_configs > 0 {
+                    instance.select_audio_ports_config(config);
+                }
+                instance.run(sample_rate);
+            }
+        }
+    }
+
+    // SAFETY: Every instance has been destroyed.
+    unsafe { clap_call!(entry => deinit()) };
+}
+
+/// Creates the plugin with the CLAP ID `id` and processes one block at `sample_rate`, with
+/// its parameters at their defaults and in its default audio port configuration. `inputs`
+/// holds the channels of every input port, which all need to be as long as the block.
+/// Returns the output ports' channels.
+///
+/// # Safety
+///
+/// `entry` must point to the `clap_entry` of a bundle linked into the calling binary.
+pub unsafe fn process_block(
+    entry: *const clap_plugin_entry,
+    id: &str,
+    sample_rate: f64,
+    inputs: &[Vec<Vec<f32>>],
+) -> Vec<Vec<Vec<f32>>> {
+    // SAFETY: The caller guarantees `entry` is a valid entry point.
+    let factory = unsafe { init_bundle(entry) };
+
+    let id = CString::new(id).expect("a CLAP ID has no null bytes");
+    let mut instance = Instance::new(factory, &id);
+    let block_size = inputs
+        .iter()
+        .flatten()
+        .map(Vec::len)
+        .next()
+        .unwrap_or(MAX_BLOCK_SIZE);
+    assert!(block_size <= MAX_BLOCK_SIZE);
+    for channel in inputs.iter().flatten() {
+        assert_eq!(
+            channel.len(),
+            block_size,
+            "the input channels differ in length"
+        );
+    }
+    let input_ports: Ports = inputs.iter().map(Vec::len).collect();
+    assert_eq!(
+        input_ports,
+        instance.audio_ports(true),
+        "{} has other input ports",
+        instance.name
+    );
+
+    let mut inputs = inputs.to_vec();
+    let mut outputs: Vec<Vec<Vec<f32>>> = instance
+        .audio_ports(false)
+        .into_iter()
+        .map(|count| vec![vec![0.0; block_size]; count])
+        .collect();
+    instance.start(sample_rate);
+    instance.process(
+        block_size,
+        0,
+        &transport(sample_rate, 0),
+        &[],
+        &mut inputs,
+        &mut outputs,
+    );
+    instance.stop();
+
+    drop(instance);
+    // SAFETY: The only instance has been destroyed.
+    unsafe { clap_call!(entry => deinit()) };
+
+    outputs
+}
+
+/// Full scale white noise, the same for every `seed`.
+pub fn noise(len: usize, seed: u32) -> Vec<f32> {
+    let mut buffer = vec![0.0; len];
+    // Xorshift gets stuck at zero.
+    let mut rng_state = seed.max(1);
+    fill_noise(&mut buffer, &mut rng_state);
+
+    buffer
+}
+
+/// Initializes the bundle and returns its plugin factory.
+///
+/// # Safety
+///
+/// `entry` must point to the `clap_entry` of a bundle linked into the calling binary, and
+/// every call must be followed by a call to its `deinit`.
+unsafe fn init_bundle(entry: *const clap_plugin_entry) -> *const clap_plugin_factory {
+    // SAFETY: The caller guarantees `entry` is a valid entry point.
+    let factory = unsafe {
+        assert!(
+            clap_call!(entry => init(c"".as_ptr())),
+            "the bundle failed to initialize"
+        );
+        clap_call!(entry => get_factory(CLAP_PLUGIN_FACTORY_ID.as_ptr()))
+            .cast::<clap_plugin_factory>()
+    };
+    assert!(!factory.is_null(), "the bundle has no plugin factory");
+
+    factory
+}
+
+/// The host side of a plugin instance. Owned by [`Instance`] because the plugin keeps a
+/// pointer to it, and may call it from other threads.
+struct Host {
+    clap_host: clap_host,
+    callback_requested: AtomicBool,
+}
+
+impl Host {
+    fn new() -> Box<Self> {
+        let mut host = Box::new(Self {
+            clap_host: clap_host {
+                clap_version: CLAP_VERSION,
+                host_data: ptr::null_mut(),
+                name: c"meadow_plugin_test".as_ptr(),
+                vendor: c"Billy Messenger".as_ptr(),
+                url: c"".as_ptr(),
+                version: c"0.1.0".as_ptr(),
+                get_extension: Some(host_get_extension),
+                request_restart: Some(host_request_restart),
+                request_process: Some(host_request_process),
+                request_callback: Some(host_request_callback),
+            },
+            callback_requested: AtomicBool::new(false),
+        });
+        host.clap_host.host_data = ptr::from_mut(&mut *host).cast();
+
+        host
+    }
+}
+
+/// The host doesn't implement any extensions, which the wrapper has to cope with anyway.
+unsafe extern "C" fn host_get_extension(
+    _host: *const clap_host,
+    _extension_id: *const c_char,
+) -> *const c_void {
+    ptr::null()
+}
+
+/// Restarts are only requested for latency changes, which need the latency extension.
+unsafe extern "C" fn host_request_restart(_host: *const clap_host) {}
+
+/// The host processes the plugin all the time anyway.
+unsafe extern "C" fn host_request_process(_host: *const clap_host) {}
+
+unsafe extern "C" fn host_request_callback(host: *const clap_host) {
+    // SAFETY: `host_data` points to the `Host` that owns `host`.
+    let host = unsafe { &*(*host).host_data.cast::<Host>() };
+    host.callback_requested.store(true, Ordering::Release);
+}
+
+/// A parameter as the plugin describes it.
+struct Param {
+    id: clap_id,
+    cookie: *mut c_void,
+    name: String,
+    min_value: f64,
+    max_value: f64,
+    stepped: bool,
+    automatable: bool,
+}
+
+/// The channel counts of one direction's audio ports.
+type Ports = Vec<usize>;
+
+/// A created and initialized plugin, destroyed when dropped.
+struct Instance {
+    plugin: *const clap_plugin,
+    name: String,
+    /// Only dropped after `Drop::drop` has destroyed the plugin.
+    host: Box<Host>,
+}
+
+impl Instance {
+    fn new(factory: *const clap_plugin_factory, id: &CStr) -> Self {
+        let host = Host::new();
+        // SAFETY: `host` lives as long as the plugin, and `id` is one of the factory's.
+        let plugin =
+            unsafe { clap_call!(factory => create_plugin(factory, &host.clap_host, id.as_ptr())) };
+        assert!(!plugin.is_null(), "{id:?} could not be created");
+
+        // SAFETY: The plugin was just created, and its descriptor lives as long as it does.
+        let name = unsafe {
+            assert!(
+                clap_call!(plugin => init(plugin)),
+                "{id:?} failed to initialize"
+            );
+            CStr::from_ptr((*(*plugin).desc).name)
+                .to_string_lossy()
+                .into_owned()
+        };
+
+        Self { plugin, name, host }
+    }
+
+    /// Returns a plugin extension, or null when the plugin doesn't implement it.
+    fn extension<T>(&self, id: &CStr) -> *const T {
+        // SAFETY: `self.plugin` is valid and initialized.
+        unsafe { clap_call!(self.plugin => get_extension(self.plugin, id.as_ptr())).cast() }
+    }
+
+    fn audio_ports_configs(&self) -> Vec<clap_audio_ports_config> {
+        let ext = self.extension::<clap_plugin_audio_ports_config>(CLAP_EXT_AUDIO_PORTS_CONFIG);
+        if ext.is_null() {
+            return Vec::new();
+        }
+
+        // SAFETY: The extension belongs to `self.plugin`, and the configs are plain data the
+        //         plugin fills in.
+        unsafe {
+            (0..clap_call!(ext => count(self.plugin)))
+                .map(|index| {
+                    let mut config = MaybeUninit::<clap_audio_ports_config>::zeroed();
+                    assert!(
+                        clap_call!(ext => get(self.plugin, index, config.as_mut_ptr())),
+                        "{} has no audio ports config {index}",
+                        self.name
+                    );
+                    config.assume_init()
+                })
+                .collect()
+        }
+    }
+
+    fn select_audio_ports_config(&mut self, index: usize) {
+        let config_id = self.audio_ports_configs()[index].id;
+        let ext = self.extension::<clap_plugin_audio_ports_config>(CLAP_EXT_AUDIO_PORTS_CONFIG);
+        // SAFETY: The plugin isn't active yet, and `config_id` is one of its configs.
+        let selected = unsafe { clap_call!(ext => select(self.plugin, config_id)) };
+        assert!(
+            selected,
+            "{} rejected audio ports config {index}",
+            self.name
+        );
+    }
+
+    fn audio_ports(&self, is_input: bool) -> Ports {
+        let ext = self.extension::<clap_plugin_audio_ports>(CLAP_EXT_AUDIO_PORTS);
+        if ext.is_null() {
+            return Vec::new();
+        }
+
+        // SAFETY: The extension belongs to `self.plugin`, and the port infos are plain data
+        //         the plugin fills in.
+        unsafe {
+            (0..clap_call!(ext => count(self.plugin, is_input)))
+                .map(|index| {
+                    let mut info = MaybeUninit::<clap_audio_port_info>::zeroed();
+                    assert!(
+                        clap_call!(ext => get(self.plugin, index, is_input, info.as_mut_ptr())),
+                        "{} has no audio port {index}",
+                        self.name
+                    );
+                    info.assume_init().channel_count as usize
+                })
+                .collect()
+        }
+    }
+
+    fn params(&self) -> Vec<Param> {
+        let ext = self.extension::<clap_plugin_params>(CLAP_EXT_PARAMS);
+        if ext.is_null() {
+            return Vec::new();
+        }
+
+        // SAFETY: The extension belongs to `self.plugin`, and the infos are plain data the
+        //         plugin fills in.
+        unsafe {
+            (0..clap_call!(ext => count(self.plugin)))
+                .map(|index| {
+                    let mut info = MaybeUninit::<clap_param_info>::zeroed();
+                    assert!(
+                        clap_call!(ext => get_info(self.plugin, index, info.as_mut_ptr())),
+                        "{} has no parameter {index}",
+                        self.name
+                    );
+                    let info = info.assume_init();
+
+                    Param {
+                        id: info.id,
+                        cookie: info.cookie,
+                        name: CStr::from_ptr(info.name.as_ptr())
+                            .to_string_lossy()
+                            .into_owned(),
+                        min_value: info.min_value,
+                        max_value: info.max_value,
+                        stepped: info.flags & CLAP_PARAM_IS_STEPPED != 0,
+                        automatable: info.flags & CLAP_PARAM_IS_AUTOMATABLE != 0,
+                    }
+                })
+                .collect()
+        }
+    }
+
+    /// Activates the plugin at `sample_rate` and processes blocks like [`check_bundle`]
+    /// describes.
+    fn run(&mut self, sample_rate: f64) {
+        let params = self.params();
+        let channels = |ports: Ports| -> Vec<Vec<Vec<f32>>> {
+            ports
+                .into_iter()
+                .map(|count| vec![vec![0.0; MAX_BLOCK_SIZE]; count])
+                .collect()
+        };
+        let mut inputs = channels(self.audio_ports(true));
+        let mut outputs = channels(self.audio_ports(false));
+
+        self.start(sample_rate);
+
+        let mut rng_state = 1;
+        let mut position = 0;
+        let mut steady_time = 0;
+        for block_size in BLOCK_SIZES {
+            for block in 0..NUM_BLOCKS {
+                for channel in inputs.iter_mut().flatten() {
+                    fill_noise(&mut channel[..block_size], &mut rng_state);
+                }
+
+                let events = if steady_time == 0 {
+                    initial_events(&params, &mut rng_state)
+                } else {
+                    automation_events(&params, block_size, &mut rng_state)
+                };
+                let transport = transport(sample_rate, position);
+                self.process(
+                    block_size,
+                    steady_time,
+                    &transport,
+                    &events,
+                    &mut inputs,
+                    &mut outputs,
+                );
+
+                for sample in outputs.iter().flatten().flat_map(|c| &c[..block_size]) {
+                    assert!(
+                        sample.is_finite(),
+                        "{} produced {sample} in block {block} of {block_size} samples at \
+                         {sample_rate} Hz",
+                        self.name
+                    );
+                }
+
+                if self.host.callback_requested.swap(false, Ordering::AcqRel) {
+                    // SAFETY: Called from the thread that created the plugin.
+                    unsafe { clap_call!(self.plugin => on_main_thread(self.plugin)) };
+                }
+
+                position += block_size as i64;
+                steady_time += block_size as i64;
+            }
+
+            // Like a host that relocates playback.
+            // SAFETY: The plugin is processing.
+            unsafe { clap_call!(self.plugin => reset(self.plugin)) };
+            position += sample_rate as i64;
+        }
+
+        self.stop();
+    }
+
+    /// Activates the plugin and starts processing.
+    fn start(&mut self, sample_rate: f64) {
+        // SAFETY: The plugin is initialized and not active yet.
+        unsafe {
+            assert!(
+                clap_call!(self.plugin => activate(self.plugin, sample_rate, 1, MAX_BLOCK_SIZE as u32)),
+                "{} failed to activate at {sample_rate} Hz",
+                self.name
+            );
+            assert!(
+                clap_call!(self.plugin => start_processing(self.plugin)),
+                "{} failed to start processing at {sample_rate} Hz",
+                self.name
+            );
+        }
+    }
+
+    /// Stops processing and deactivates the plugin.
+    fn stop(&mut self) {
+        // SAFETY: The plugin is active and processing.
+        unsafe {
+            clap_call!(self.plugin => stop_processing(self.plugin));
+            clap_call!(self.plugin => deactivate(self.plugin));
+        }
+    }
+
+    fn process(
+        &mut self,
+        block_size: usize,
+        steady_time: i64,
+        transport: &clap_event_transport,
+        events: &[clap_event_param_value],
+        inputs: &mut [Vec<Vec<f32>>],
+        outputs: &mut [Vec<Vec<f32>>],
+    ) {
+        // The channel pointers have to stay where they are until `process` returns.
+        let mut input_channels: Vec<Vec<*mut f32>> = inputs
+            .iter_mut()
+            .map(|port| port.iter_mut().map(|c| c.as_mut_ptr()).collect())
+            .collect();
+        let mut output_channels: Vec<Vec<*mut f32>> = outputs
+            .iter_mut()
+            .map(|port| port.iter_mut().map(|c| c.as_mut_ptr()).collect())
+            .collect();
+        let audio_buffer = |channels: &mut Vec<*mut f32>| clap_audio_buffer {
+            data32: channels.as_mut_ptr(),
+            data64: ptr::null_mut(),
+            channel_count: channels.len() as u32,
+            latency: 0,
+            constant_mask: 0,
+        };
+        let input_buffers: Vec<_> = input_channels.iter_mut().map(audio_buffer).collect();
+        let mut output_buffers: Vec<_> = output_channels.iter_mut().map(audio_buffer).collect();
+
+        let in_events = clap_input_events {
+            ctx: ptr::from_ref(&events).cast_mut().cast(),
+            size: Some(input_events_size),
+            get: Some(input_events_get),
+        };
+        let out_events = clap_output_events {
+            ctx: ptr::null_mut(),
+            try_push: Some(output_events_try_push),
+        };
+
+        let process = clap_process {
+            steady_time,
+            frames_count: block_size as u32,
+            transport,
+            audio_inputs: input_buffers.as_ptr(),
+            audio_outputs: output_buffers.as_mut_ptr(),
+            audio_inputs_count: input_buffers.len() as u32,
+            audio_outputs_count: output_buffers.len() as u32,
+            in_events: &in_events,
+            out_events: &out_events,
+        };
+
+        // SAFETY: Everything `process` points to lives until the call returns, and every
+        //         buffer holds at least `block_size` samples.
+        let status = unsafe { clap_call!(self.plugin => process(self.plugin, &process)) };
+        assert_ne!(
+            status, CLAP_PROCESS_ERROR,
+            "{} returned an error for a block of {block_size} samples",
+            self.name
+        );
+    }
+}
+
+impl Drop for Instance {
+    fn drop(&mut self) {
+        // SAFETY: The plugin is deactivated, and nothing uses it after this.
+        unsafe { clap_call!(self.plugin => destroy(self.plugin)) };
+    }
+}
+
+/// Sets every parameter at the start of the first block, like a host restoring a session.
+fn initial_events(params: &[Param], rng_state: &mut u32) -> Vec<clap_event_param_value> {
+    params
+        .iter()
+        .map(|param| param_event(param, 0, rng_state))
+        .collect()
+}
+
+/// Moves the automatable parameters at random times within the block.
+fn automation_events(
+    params: &[Param],
+    block_size: usize,
+    rng_state: &mut u32,
+) -> Vec<clap_event_param_value> {
+    let mut events = Vec::new();
+    for param in params.iter().filter(|p| p.automatable) {
+        let num_changes = (next_random(rng_state) * (MAX_CHANGES_PER_BLOCK + 1) as f64) as usize;
+        for _ in 0..num_changes {
+            let time = (next_random(rng_state) * block_size as f64) as u32;
+            events.push(param_event(param, time, rng_state));
+        }
+    }
+    events.sort_by_key(|event| event.header.time);
+
+    events
+}
+
+/// A value change for `param`. A fifth of the values are at either end of its range.
+fn param_event(param: &Param, time: u32, rng_state: &mut u32) -> clap_event_param_value {
+    let mut value = match next_random(rng_state) {
+        r if r < 0.1 => param.min_value,
+        r if r > 0.9 => param.max_value,
+        _ => param.min_value + next_random(rng_state) * (param.max_value - param.min_value),
+    };
+    if param.stepped {
+        value = value.round();
+    }
+    assert!(
+        value.is_finite(),
+        "{} has a range of {} to {}",
+        param.name,
+        param.min_value,
+        param.max_value
+    );
+
+    clap_event_param_value {
+        header: clap_event_header {
+            size: size_of::<clap_event_param_value>() as u32,
+            time,
+            space_id: CLAP_CORE_EVENT_SPACE_ID,
+            type_: CLAP_EVENT_PARAM_VALUE,
+            flags: 0,
+        },
+        param_id: param.id,
+        cookie: param.cookie,
+        note_id: -1,
+        port_index: -1,
+        channel: -1,
+        key: -1,
+        value,
+    }
+}
+
+/// A playing transport in 4/4, `position` samples into the song.
+fn transport(sample_rate: f64, position: i64) -> clap_event_transport {
+    let seconds = position as f64 / sample_rate;
+    let beats = seconds * TEMPO / 60.0;
+    let bar = (beats / 4.0).floor();
+
+    clap_event_transport {
+        header: clap_event_header {
+            size: size_of::<clap_event_transport>() as u32,
+            time: 0,
+            space_id: CLAP_CORE_EVENT_SPACE_ID,
+            type_: CLAP_EVENT_TRANSPORT,
+            flags: 0,
+        },
+        flags: CLAP_TRANSPORT_HAS_TEMPO
+            | CLAP_TRANSPORT_HAS_BEATS_TIMELINE
+            | CLAP_TRANSPORT_HAS_SECONDS_TIMELINE
+            | CLAP_TRANSPORT_HAS_TIME_SIGNATURE
+            | CLAP_TRANSPORT_IS_PLAYING,
+        song_pos_beats: (beats * CLAP_BEATTIME_FACTOR as f64).round() as i64,
+        song_pos_seconds: (seconds * CLAP_SECTIME_FACTOR as f64).round() as i64,
+        tempo: TEMPO,
+        tempo_inc: 0.0,
+        loop_start_beats: 0,
+        loop_end_beats: 0,
+        loop_start_seconds: 0,
+        loop_end_seconds: 0,
+        bar_start: (bar * 4.0 * CLAP_BEATTIME_FACTOR as f64) as i64,
+        bar_number: bar as i32,
+        tsig_num: 4,
+        tsig_denom: 4,
+    }
+}
+
+unsafe extern "C" fn input_events_size(list: *const clap_input_events) -> u32 {
+    // SAFETY: `ctx` points to the block's events for as long as the list is used.
+    let events = unsafe { &*(*list).ctx.cast::<&[clap_event_param_value]>() };
+    events.len() as u32
+}
+
+unsafe extern "C" fn input_events_get(
+    list: *const clap_input_events,
+    index: u32,
+) -> *const clap_event_header {
+    // SAFETY: `ctx` points to the block's events for as long as the list is used.
+    let events = unsafe { &*(*list).ctx.cast::<&[clap_event_param_value]>() };
+    events
+        .get(index as usize)
+        .map_or(ptr::null(), |event| ptr::from_ref(event).cast())
+}
+
+/// Output events are accepted and dropped.
+unsafe extern "C" fn output_events_try_push(
+    _list: *const clap_output_events,
+    _event: *const clap_event_header,
+) -> bool {
+    true
+}
+
+/// A uniformly distributed number in `[0, 1)` from a xorshift generator.
+fn next_random(rng_state: &mut u32) -> f64 {
+    *rng_state ^= *rng_state << 13;
+    *rng_state ^= *rng_state >> 17;
+    *rng_state ^= *rng_state << 5;
+    *rng_state as f64 / (u32::MAX as f64 + 1.0)
+}
+
+/// Full scale white noise.
+fn fill_noise(buffer: &mut [f32], rng_state: &mut u32) {
+    for sample in buffer {
+        *sample = (next_random(rng_state) * 2.0 - 1.0) as f32;
+    }
+}
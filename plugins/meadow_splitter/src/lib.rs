@@ -0,0 +1,42 @@
+//! The 2-band and 3-band splitters. Each band goes to its own auxiliary output, and the main
+//! output carries the sum of the bands.
+
+use nih_plug::prelude::*;
+
+mod three_band;
+mod two_band;
+
+pub use three_band::MeadowSplitter3Band;
+pub use two_band::MeadowSplitter2Band;
+
+fn crossover_param(name: &str, default_hz: f32) -> FloatParam {
+    FloatParam::new(
+        name,
+        default_hz,
+        FloatRange::Skewed {
+            min: meadow_splitter_dsp::MIN_CROSSOVER_HZ,
+            max: meadow_splitter_dsp::MAX_CROSSOVER_HZ,
+            factor: FloatRange::skew_factor(-2.0),
+        },
+    )
+    .with_unit(" Hz")
+}
+
+fn gain_param(name: &str) -> FloatParam {
+    FloatParam::new(
+        name,
+        0.0,
+        FloatRange::SymmetricalSkewed {
+            min: -30.0,
+            max: 30.0,
+            factor: 0.4,
+            center: 0.0,
+        },
+    )
+    .with_unit(" dB")
+}
+
+#[cfg(not(feature = "bundled"))]
+nih_export_clap!(MeadowSplitter2Band, MeadowSplitter3Band);
+#[cfg(not(feature = "bundled"))]
+nih_export_vst3!(MeadowSplitter2Band, MeadowSplitter3Band);
@@ -0,0 +1,168 @@
+use meadow_splitter_dsp::{ThreeBandParams, ThreeBandSplitterDsp};
+use nih_plug::prelude::*;
+use std::sync::Arc;
+
+use crate::{crossover_param, gain_param};
+
+pub struct MeadowSplitter3Band {
+    params: Arc<MeadowSplitter3BandParams>,
+    dsp: ThreeBandSplitterDsp,
+}
+
+#[derive(Params)]
+struct MeadowSplitter3BandParams {
+    #[id = "low_crossover_hz"]
+    pub low_crossover_hz: FloatParam,
+    #[id = "high_crossover_hz"]
+    pub high_crossover_hz: FloatParam,
+    #[id = "low_gain_db"]
+    pub low_gain_db: FloatParam,
+    #[id = "mid_gain_db"]
+    pub mid_gain_db: FloatParam,
+    #[id = "high_gain_db"]
+    pub high_gain_db: FloatParam,
+}
+
+impl Default for MeadowSplitter3Band {
+    fn default() -> Self {
+        Self {
+            params: Arc::new(MeadowSplitter3BandParams::default()),
+            dsp: ThreeBandSplitterDsp::new(44_100.0),
+        }
+    }
+}
+
+impl Default for MeadowSplitter3BandParams {
+    fn default() -> Self {
+        let [low_hz, high_hz] = ThreeBandParams::default().crossovers_hz;
+
+        Self {
+            low_crossover_hz: crossover_param("Low crossover", low_hz),
+            high_crossover_hz: crossover_param("High crossover", high_hz),
+            low_gain_db: gain_param("Low gain"),
+            mid_gain_db: gain_param("Mid gain"),
+            high_gain_db: gain_param("High gain"),
+        }
+    }
+}
+
+impl MeadowSplitter3BandParams {
+    /// Collects the current parameter values into the form used by the DSP.
+    pub fn splitter_params(&self) -> ThreeBandParams {
+        ThreeBandParams {
+            crossovers_hz: [
+                self.low_crossover_hz.value(),
+                self.high_crossover_hz.value(),
+            ],
+            gains_db: [
+                self.low_gain_db.value(),
+                self.mid_gain_db.value(),
+                self.high_gain_db.value(),
+            ],
+        }
+    }
+}
+
+impl Plugin for MeadowSplitter3Band {
+    const NAME: &'static str = "Meadow Splitter 3-Band";
+    const VENDOR: &'static str = "Billy Messenger";
+    const URL: &'static str = env!("CARGO_PKG_HOMEPAGE");
+    const EMAIL: &'static str = "60663878+BillyDM@users.noreply.github.com";
+
+    const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+
+    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[AudioIOLayout {
+        main_input_channels: NonZeroU32::new(2),
+        main_output_channels: NonZeroU32::new(2),
+
+        aux_input_ports: &[],
+        aux_output_ports: &[new_nonzero_u32(2); 3],
+
+        names: PortNames {
+            aux_outputs: &["Low", "Mid", "High"],
+            ..PortNames::const_default()
+        },
+    }];
+
+    const MIDI_INPUT: MidiConfig = MidiConfig::None;
+    const MIDI_OUTPUT: MidiConfig = MidiConfig::None;
+
+    const SAMPLE_ACCURATE_AUTOMATION: bool = true;
+
+    type SysExMessage = ();
+    type BackgroundTask = ();
+
+    fn params(&self) -> Arc<dyn Params> {
+        self.params.clone()
+    }
+
+    fn initialize(
+        &mut self,
+        _audio_io_layout: &AudioIOLayout,
+        config: &BufferConfig,
+        _context: &mut impl InitContext<Self>,
+    ) -> bool {
+        self.dsp = ThreeBandSplitterDsp::new(config.sample_rate as f64);
+        self.dsp.set_params(self.params.splitter_params());
+
+        true
+    }
+
+    fn reset(&mut self) {
+        self.dsp.reset();
+    }
+
+    fn process(
+        &mut self,
+        buffer: &mut Buffer,
+        aux: &mut AuxiliaryBuffers,
+        _context: &mut impl ProcessContext<Self>,
+    ) -> ProcessStatus {
+        self.dsp.set_params(self.params.splitter_params());
+
+        let [out_l, out_r, ..] = buffer.as_slice() else {
+            return ProcessStatus::Normal;
+        };
+        let [low, mid, high, ..] = &mut *aux.outputs else {
+            return ProcessStatus::Normal;
+        };
+        let ([low_l, low_r, ..], [mid_l, mid_r, ..], [high_l, high_r, ..]) =
+            (low.as_slice(), mid.as_slice(), high.as_slice())
+        else {
+            return ProcessStatus::Normal;
+        };
+
+        self.dsp.process(
+            out_l,
+            out_r,
+            [
+                [&mut low_l[..], &mut low_r[..]],
+                [&mut mid_l[..], &mut mid_r[..]],
+                [&mut high_l[..], &mut high_r[..]],
+            ],
+        );
+
+        ProcessStatus::Normal
+    }
+}
+
+impl ClapPlugin for MeadowSplitter3Band {
+    const CLAP_ID: &'static str = "app.meadowlark.meadow-splitter-3-band";
+    const CLAP_DESCRIPTION: Option<&'static str> =
+        Some("Splits the signal into three bands with Linkwitz-Riley crossovers");
+    const CLAP_MANUAL_URL: Option<&'static str> = Some(Self::URL);
+    const CLAP_SUPPORT_URL: Option<&'static str> = None;
+
+    const CLAP_FEATURES: &'static [ClapFeature] = &[
+        ClapFeature::AudioEffect,
+        ClapFeature::Stereo,
+        ClapFeature::Utility,
+    ];
+}
+
+impl Vst3Plugin for MeadowSplitter3Band {
+    const VST3_CLASS_ID: [u8; 16] = *b"Meadowlark.Spl3B";
+
+    const VST3_SUBCATEGORIES: &'static [Vst3SubCategory] =
+        &[Vst3SubCategory::Fx, Vst3SubCategory::Tools];
+}
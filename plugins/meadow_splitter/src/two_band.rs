@@ -0,0 +1,152 @@
+use meadow_splitter_dsp::{TwoBandParams, TwoBandSplitterDsp};
+use nih_plug::prelude::*;
+use std::sync::Arc;
+
+use crate::{crossover_param, gain_param};
+
+pub struct MeadowSplitter2Band {
+    params: Arc<MeadowSplitter2BandParams>,
+    dsp: TwoBandSplitterDsp,
+}
+
+#[derive(Params)]
+struct MeadowSplitter2BandParams {
+    #[id = "crossover_hz"]
+    pub crossover_hz: FloatParam,
+    #[id = "low_gain_db"]
+    pub low_gain_db: FloatParam,
+    #[id = "high_gain_db"]
+    pub high_gain_db: FloatParam,
+}
+
+impl Default for MeadowSplitter2Band {
+    fn default() -> Self {
+        Self {
+            params: Arc::new(MeadowSplitter2BandParams::default()),
+            dsp: TwoBandSplitterDsp::new(44_100.0),
+        }
+    }
+}
+
+impl Default for MeadowSplitter2BandParams {
+    fn default() -> Self {
+        let defaults = TwoBandParams::default();
+
+        Self {
+            crossover_hz: crossover_param("Crossover", defaults.crossover_hz),
+            low_gain_db: gain_param("Low gain"),
+            high_gain_db: gain_param("High gain"),
+        }
+    }
+}
+
+impl MeadowSplitter2BandParams {
+    /// Collects the current parameter values into the form used by the DSP.
+    pub fn splitter_params(&self) -> TwoBandParams {
+        TwoBandParams {
+            crossover_hz: self.crossover_hz.value(),
+            gains_db: [self.low_gain_db.value(), self.high_gain_db.value()],
+        }
+    }
+}
+
+impl Plugin for MeadowSplitter2Band {
+    const NAME: &'static str = "Meadow Splitter 2-Band";
+    const VENDOR: &'static str = "Billy Messenger";
+    const URL: &'static str = env!("CARGO_PKG_HOMEPAGE");
+    const EMAIL: &'static str = "60663878+BillyDM@users.noreply.github.com";
+
+    const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+
+    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[AudioIOLayout {
+        main_input_channels: NonZeroU32::new(2),
+        main_output_channels: NonZeroU32::new(2),
+
+        aux_input_ports: &[],
+        aux_output_ports: &[new_nonzero_u32(2); 2],
+
+        names: PortNames {
+            aux_outputs: &["Low", "High"],
+            ..PortNames::const_default()
+        },
+    }];
+
+    const MIDI_INPUT: MidiConfig = MidiConfig::None;
+    const MIDI_OUTPUT: MidiConfig = MidiConfig::None;
+
+    const SAMPLE_ACCURATE_AUTOMATION: bool = true;
+
+    type SysExMessage = ();
+    type BackgroundTask = ();
+
+    fn params(&self) -> Arc<dyn Params> {
+        self.params.clone()
+    }
+
+    fn initialize(
+        &mut self,
+        _audio_io_layout: &AudioIOLayout,
+        config: &BufferConfig,
+        _context: &mut impl InitContext<Self>,
+    ) -> bool {
+        self.dsp = TwoBandSplitterDsp::new(config.sample_rate as f64);
+        self.dsp.set_params(self.params.splitter_params());
+
+        true
+    }
+
+    fn reset(&mut self) {
+        self.dsp.reset();
+    }
+
+    fn process(
+        &mut self,
+        buffer: &mut Buffer,
+        aux: &mut AuxiliaryBuffers,
+        _context: &mut impl ProcessContext<Self>,
+    ) -> ProcessStatus {
+        self.dsp.set_params(self.params.splitter_params());
+
+        let [out_l, out_r, ..] = buffer.as_slice() else {
+            return ProcessStatus::Normal;
+        };
+        let [low, high, ..] = &mut *aux.outputs else {
+            return ProcessStatus::Normal;
+        };
+        let ([low_l, low_r, ..], [high_l, high_r, ..]) = (low.as_slice(), high.as_slice()) else {
+            return ProcessStatus::Normal;
+        };
+
+        self.dsp.process(
+            out_l,
+            out_r,
+            [
+                [&mut low_l[..], &mut low_r[..]],
+                [&mut high_l[..], &mut high_r[..]],
+            ],
+        );
+
+        ProcessStatus::Normal
+    }
+}
+
+impl ClapPlugin for MeadowSplitter2Band {
+    const CLAP_ID: &'static str = "app.meadowlark.meadow-splitter-2-band";
+    const CLAP_DESCRIPTION: Option<&'static str> =
+        Some("Splits the signal into two bands with a Linkwitz-Riley crossover");
+    const CLAP_MANUAL_URL: Option<&'static str> = Some(Self::URL);
+    const CLAP_SUPPORT_URL: Option<&'static str> = None;
+
+    const CLAP_FEATURES: &'static [ClapFeature] = &[
+        ClapFeature::AudioEffect,
+        ClapFeature::Stereo,
+        ClapFeature::Utility,
+    ];
+}
+
+impl Vst3Plugin for MeadowSplitter2Band {
+    const VST3_CLASS_ID: [u8; 16] = *b"Meadowlark.Spl2B";
+
+    const VST3_SUBCATEGORIES: &'static [Vst3SubCategory] =
+        &[Vst3SubCategory::Fx, Vst3SubCategory::Tools];
+}
@@ -0,0 +1,328 @@
+//! Band splitters built from 4th order Linkwitz-Riley crossovers.
+//!
+//! The lowpass and highpass outputs of a Linkwitz-Riley crossover are in phase at every
+//! frequency and sum to a 2nd order allpass at the crossover frequency, so the bands add back
+//! up to the input with only its phase changed. With more than one crossover, the bands
+//! that didn't go through a crossover get its allpass instead, so every band ends up with
+//! the same phase response.
+
+use meadow_common_dsp::{Ramp, db_to_gain};
+use meadow_eq_dsp::{DEFAULT_Q, SvfCoeff, SvfState};
+
+#[cfg(test)]
+mod tests;
+
+/// How long it takes to move to new parameters.
+pub const SMOOTHING_MS: f64 = 20.0;
+
+pub const MIN_CROSSOVER_HZ: f32 = 20.0;
+pub const MAX_CROSSOVER_HZ: f32 = 20_000.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TwoBandParams {
+    pub crossover_hz: f32,
+    /// The gain of the low and high bands.
+    pub gains_db: [f32; 2],
+}
+
+impl Default for TwoBandParams {
+    fn default() -> Self {
+        Self {
+            crossover_hz: 500.0,
+            gains_db: [0.0; 2],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThreeBandParams {
+    /// The low/mid and mid/high crossovers. The second one is kept at or above the first.
+    pub crossovers_hz: [f32; 2],
+    /// The gain of the low, mid and high bands.
+    pub gains_db: [f32; 3],
+}
+
+impl Default for ThreeBandParams {
+    fn default() -> Self {
+        Self {
+            crossovers_hz: [200.0, 2000.0],
+            gains_db: [0.0; 3],
+        }
+    }
+}
+
+/// A stereo output buffer for one band.
+pub type BandBuffers<'a> = [&'a mut [f32]; 2];
+
+pub struct TwoBandSplitterDsp {
+    params: TwoBandParams,
+    sample_rate: f64,
+    ramp_len: usize,
+
+    crossover: Crossover,
+    states: [CrossoverState; 2],
+    gains: [Ramp; 2],
+}
+
+impl TwoBandSplitterDsp {
+    pub fn new(sample_rate: f64) -> Self {
+        assert!(sample_rate > 0.0);
+
+        let params = TwoBandParams::default();
+
+        Self {
+            params,
+            sample_rate,
+            ramp_len: ramp_len(sample_rate),
+            crossover: Crossover::new(params.crossover_hz, sample_rate),
+            states: [CrossoverState::default(); 2],
+            gains: params
+                .gains_db
+                .map(|gain_db| Ramp::new(db_to_gain(gain_db))),
+        }
+    }
+
+    pub fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+
+    /// Sets new parameters, which are ramped to over [`SMOOTHING_MS`].
+    pub fn set_params(&mut self, params: TwoBandParams) {
+        if self.params == params {
+            return;
+        }
+
+        self.crossover
+            .set_target(params.crossover_hz, self.ramp_len);
+        for (gain, gain_db) in self.gains.iter_mut().zip(params.gains_db) {
+            gain.set_target(db_to_gain(gain_db), self.ramp_len);
+        }
+
+        self.params = params;
+    }
+
+    /// Clears the filters and jumps to the current parameters without ramping.
+    pub fn reset(&mut self) {
+        self.crossover.snap(self.sample_rate);
+        self.states = [CrossoverState::default(); 2];
+        for gain in self.gains.iter_mut() {
+            gain.snap();
+        }
+    }
+
+    /// Splits the signal in `buf_l` and `buf_r` into `bands`, from low to high. The main
+    /// buffers are then overwritten with the sum of the bands.
+    pub fn process(&mut self, buf_l: &mut [f32], buf_r: &mut [f32], bands: [BandBuffers; 2]) {
+        let [[low_l, low_r], [high_l, high_r]] = bands;
+        let len = [buf_l.len(), buf_r.len(), low_l.len(), low_r.len()]
+            .into_iter()
+            .chain([high_l.len(), high_r.len()])
+            .min()
+            .unwrap();
+
+        for i in 0..len {
+            self.crossover.next(self.sample_rate);
+            let gains = [0, 1].map(|band| self.gains[band].next());
+
+            for (channel, buf, low, high) in [
+                (0, &mut *buf_l, &mut *low_l, &mut *high_l),
+                (1, &mut *buf_r, &mut *low_r, &mut *high_r),
+            ] {
+                let (l, h) = self.states[channel].split(buf[i], &self.crossover);
+                low[i] = gains[0] * l;
+                high[i] = gains[1] * h;
+                buf[i] = low[i] + high[i];
+            }
+        }
+    }
+}
+
+pub struct ThreeBandSplitterDsp {
+    params: ThreeBandParams,
+    sample_rate: f64,
+    ramp_len: usize,
+
+    crossovers: [Crossover; 2],
+    states: [[CrossoverState; 2]; 2],
+    /// Matches the low band's phase to the mid/high crossover's.
+    low_allpass: [SvfState; 2],
+    gains: [Ramp; 3],
+}
+
+impl ThreeBandSplitterDsp {
+    pub fn new(sample_rate: f64) -> Self {
+        assert!(sample_rate > 0.0);
+
+        let params = ThreeBandParams::default();
+
+        Self {
+            params,
+            sample_rate,
+            ramp_len: ramp_len(sample_rate),
+            crossovers: params
+                .crossovers_hz
+                .map(|crossover_hz| Crossover::new(crossover_hz, sample_rate)),
+            states: [[CrossoverState::default(); 2]; 2],
+            low_allpass: [SvfState::default(); 2],
+            gains: params
+                .gains_db
+                .map(|gain_db| Ramp::new(db_to_gain(gain_db))),
+        }
+    }
+
+    pub fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+
+    /// Sets new parameters, which are ramped to over [`SMOOTHING_MS`].
+    pub fn set_params(&mut self, params: ThreeBandParams) {
+        if self.params == params {
+            return;
+        }
+
+        let [low_hz, high_hz] = params.crossovers_hz;
+        self.crossovers[0].set_target(low_hz, self.ramp_len);
+        self.crossovers[1].set_target(high_hz.max(low_hz), self.ramp_len);
+        for (gain, gain_db) in self.gains.iter_mut().zip(params.gains_db) {
+            gain.set_target(db_to_gain(gain_db), self.ramp_len);
+        }
+
+        self.params = params;
+    }
+
+    /// Clears the filters and jumps to the current parameters without ramping.
+    pub fn reset(&mut self) {
+        for crossover in self.crossovers.iter_mut() {
+            crossover.snap(self.sample_rate);
+        }
+        self.states = [[CrossoverState::default(); 2]; 2];
+        self.low_allpass = [SvfState::default(); 2];
+        for gain in self.gains.iter_mut() {
+            gain.snap();
+        }
+    }
+
+    /// Splits the signal in `buf_l` and `buf_r` into `bands`, from low to high. The main
+    /// buffers are then overwritten with the sum of the bands.
+    pub fn process(&mut self, buf_l: &mut [f32], buf_r: &mut [f32], bands: [BandBuffers; 3]) {
+        let [[low_l, low_r], [mid_l, mid_r], [high_l, high_r]] = bands;
+        let len = [buf_l.len(), buf_r.len(), low_l.len(), low_r.len()]
+            .into_iter()
+            .chain([mid_l.len(), mid_r.len(), high_l.len(), high_r.len()])
+            .min()
+            .unwrap();
+
+        for i in 0..len {
+            for crossover in self.crossovers.iter_mut() {
+                crossover.next(self.sample_rate);
+            }
+            let gains = [0, 1, 2].map(|band| self.gains[band].next());
+
+            for (channel, buf, low, mid, high) in [
+                (0, &mut *buf_l, &mut *low_l, &mut *mid_l, &mut *high_l),
+                (1, &mut *buf_r, &mut *low_r, &mut *mid_r, &mut *high_r),
+            ] {
+                let [low_state, high_state] = &mut self.states[channel];
+                let (l, rest) = low_state.split(buf[i], &self.crossovers[0]);
+                let (m, h) = high_state.split(rest, &self.crossovers[1]);
+                let l = self.low_allpass[channel].tick(l, &self.crossovers[1].allpass);
+
+                low[i] = gains[0] * l;
+                mid[i] = gains[1] * m;
+                high[i] = gains[2] * h;
+                buf[i] = low[i] + mid[i] + high[i];
+            }
+        }
+    }
+}
+
+fn ramp_len(sample_rate: f64) -> usize {
+    ((SMOOTHING_MS / 1000.0 * sample_rate).round() as usize).max(1)
+}
+
+/// The coefficients of one 4th order Linkwitz-Riley crossover. The frequency glides to new
+/// values on a logarithmic scale, with the coefficients recomputed every sample along the way.
+#[derive(Clone, Copy)]
+pub struct Crossover {
+    lowpass: SvfCoeff,
+    highpass: SvfCoeff,
+    /// What the lowpass and highpass outputs sum to.
+    allpass: SvfCoeff,
+
+    log2_hz: Ramp,
+}
+
+impl Crossover {
+    pub fn new(cutoff_hz: f32, sample_rate: f64) -> Self {
+        let mut new_self = Self {
+            lowpass: SvfCoeff::default(),
+            highpass: SvfCoeff::default(),
+            allpass: SvfCoeff::default(),
+            log2_hz: Ramp::new(Self::log2_hz(cutoff_hz)),
+        };
+        new_self.update(sample_rate);
+
+        new_self
+    }
+
+    fn log2_hz(cutoff_hz: f32) -> f32 {
+        cutoff_hz.clamp(MIN_CROSSOVER_HZ, MAX_CROSSOVER_HZ).log2()
+    }
+
+    /// Glides to `cutoff_hz`, clamped to [`MIN_CROSSOVER_HZ`] and [`MAX_CROSSOVER_HZ`], over
+    /// `ramp_len` samples.
+    pub fn set_target(&mut self, cutoff_hz: f32, ramp_len: usize) {
+        self.log2_hz.set_target(Self::log2_hz(cutoff_hz), ramp_len);
+    }
+
+    /// Jumps to the target frequency.
+    pub fn snap(&mut self, sample_rate: f64) {
+        self.log2_hz.snap();
+        self.update(sample_rate);
+    }
+
+    /// Moves one sample further along the glide.
+    pub fn next(&mut self, sample_rate: f64) {
+        if self.log2_hz.is_ramping() {
+            self.log2_hz.next();
+            self.update(sample_rate);
+        }
+    }
+
+    fn update(&mut self, sample_rate: f64) {
+        let cutoff_hz = (self.log2_hz.value() as f64).exp2();
+        let sample_rate_recip = sample_rate.recip();
+        // A 4th order Linkwitz-Riley filter is a squared 2nd order Butterworth filter.
+        let q = DEFAULT_Q as f64;
+
+        self.lowpass = SvfCoeff::lowpass_ord2(cutoff_hz, sample_rate_recip, q);
+        self.highpass = SvfCoeff::highpass_ord2(cutoff_hz, sample_rate_recip, q);
+        self.allpass = SvfCoeff::allpass(cutoff_hz, sample_rate_recip, q);
+    }
+
+    /// An allpass with the same phase response as the sum of the two bands. Running another
+    /// band through it keeps that band in phase with them.
+    pub fn allpass(&self) -> &SvfCoeff {
+        &self.allpass
+    }
+}
+
+/// The filter state of one channel of a crossover.
+#[derive(Default, Clone, Copy)]
+pub struct CrossoverState {
+    lowpass: [SvfState; 2],
+    highpass: [SvfState; 2],
+}
+
+impl CrossoverState {
+    /// Returns the low and high bands of `input`.
+    #[inline(always)]
+    pub fn split(&mut self, input: f32, coeff: &Crossover) -> (f32, f32) {
+        let low = self.lowpass[0].tick(input, &coeff.lowpass);
+        let low = self.lowpass[1].tick(low, &coeff.lowpass);
+        let high = self.highpass[0].tick(input, &coeff.highpass);
+        let high = self.highpass[1].tick(high, &coeff.highpass);
+
+        (low, high)
+    }
+}
@@ -0,0 +1,237 @@
+use meadow_common_dsp::gain_to_db;
+use meadow_eq_dsp::{DEFAULT_Q, SvfCoeff, SvfState};
+use meadow_test_signals::{noise, rms, sine};
+
+use crate::{ThreeBandParams, ThreeBandSplitterDsp, TwoBandParams, TwoBandSplitterDsp};
+
+const SAMPLE_RATES: [f64; 3] = [44_100.0, 48_000.0, 192_000.0];
+const LEN: usize = 16_384;
+
+/// How far below the input the difference to the reference may be.
+const NULL_DB: f32 = -90.0;
+
+/// Runs `input` through the allpasses a Linkwitz-Riley crossover at each of `crossovers_hz`
+/// sums to.
+fn reference_allpass(input: &[f32], crossovers_hz: &[f32], sample_rate: f64) -> Vec<f32> {
+    let mut output = input.to_vec();
+    for &crossover_hz in crossovers_hz {
+        let coeff = SvfCoeff::allpass(crossover_hz as f64, sample_rate.recip(), DEFAULT_Q as f64);
+        let mut state = SvfState::default();
+        for x in output.iter_mut() {
+            *x = state.tick(*x, &coeff);
+        }
+    }
+
+    output
+}
+
+fn assert_nulls(output: &[f32], reference: &[f32], input: &[f32], label: &str) {
+    let difference: Vec<f32> = output.iter().zip(reference).map(|(a, b)| a - b).collect();
+    let null_db = gain_to_db(rms(&difference) / rms(input));
+    assert!(
+        null_db < NULL_DB,
+        "{label}: the sum only nulls to {null_db} dB"
+    );
+}
+
+struct Bands<const N: usize> {
+    sum: [Vec<f32>; 2],
+    bands: [[Vec<f32>; 2]; N],
+}
+
+fn split_two(dsp: &mut TwoBandSplitterDsp, input: &[Vec<f32>; 2]) -> Bands<2> {
+    let [mut l, mut r] = input.clone();
+    let mut bands: [[Vec<f32>; 2]; 2] = Default::default();
+    for band in bands.iter_mut() {
+        *band = [vec![0.0; l.len()], vec![0.0; l.len()]];
+    }
+
+    // Odd block sizes, to make sure nothing depends on them.
+    let mut start = 0;
+    while start < l.len() {
+        let end = (start + 1000).min(l.len());
+        let [[a, b], [c, d]] = &mut bands;
+        dsp.process(
+            &mut l[start..end],
+            &mut r[start..end],
+            [
+                [&mut a[start..end], &mut b[start..end]],
+                [&mut c[start..end], &mut d[start..end]],
+            ],
+        );
+        start = end;
+    }
+
+    Bands { sum: [l, r], bands }
+}
+
+fn split_three(dsp: &mut ThreeBandSplitterDsp, input: &[Vec<f32>; 2]) -> Bands<3> {
+    let [mut l, mut r] = input.clone();
+    let mut bands: [[Vec<f32>; 2]; 3] = Default::default();
+    for band in bands.iter_mut() {
+        *band = [vec![0.0; l.len()], vec![0.0; l.len()]];
+    }
+
+    let mut start = 0;
+    while start < l.len() {
+        let end = (start + 1000).min(l.len());
+        let [[a, b], [c, d], [e, f]] = &mut bands;
+        dsp.process(
+            &mut l[start..end],
+            &mut r[start..end],
+            [
+                [&mut a[start..end], &mut b[start..end]],
+                [&mut c[start..end], &mut d[start..end]],
+                [&mut e[start..end], &mut f[start..end]],
+            ],
+        );
+        start = end;
+    }
+
+    Bands { sum: [l, r], bands }
+}
+
+fn two_band(crossover_hz: f32, gains_db: [f32; 2], sample_rate: f64) -> TwoBandSplitterDsp {
+    let mut dsp = TwoBandSplitterDsp::new(sample_rate);
+    dsp.set_params(TwoBandParams {
+        crossover_hz,
+        gains_db,
+    });
+    dsp.reset();
+    dsp
+}
+
+fn three_band(
+    crossovers_hz: [f32; 2],
+    gains_db: [f32; 3],
+    sample_rate: f64,
+) -> ThreeBandSplitterDsp {
+    let mut dsp = ThreeBandSplitterDsp::new(sample_rate);
+    dsp.set_params(ThreeBandParams {
+        crossovers_hz,
+        gains_db,
+    });
+    dsp.reset();
+    dsp
+}
+
+#[test]
+fn two_band_sum_nulls_against_an_allpass() {
+    let input = [noise(LEN, 1), noise(LEN, 2)];
+
+    for sample_rate in SAMPLE_RATES {
+        for crossover_hz in [20.0, 120.0, 1000.0, 8000.0, 20_000.0] {
+            let mut dsp = two_band(crossover_hz, [0.0; 2], sample_rate);
+            let output = split_two(&mut dsp, &input);
+
+            for (channel, (input, output)) in input.iter().zip(&output.sum).enumerate() {
+                let reference = reference_allpass(input, &[crossover_hz], sample_rate);
+                let label = format!("{crossover_hz} Hz at {sample_rate} Hz, channel {channel}");
+                assert_nulls(output, &reference, input, &label);
+            }
+        }
+    }
+}
+
+#[test]
+fn three_band_sum_nulls_against_an_allpass() {
+    let input = [noise(LEN, 3), noise(LEN, 4)];
+
+    for sample_rate in SAMPLE_RATES {
+        for crossovers_hz in [
+            [20.0, 20_000.0],
+            [120.0, 2500.0],
+            [800.0, 1000.0],
+            [1000.0, 1000.0],
+            [5000.0, 15_000.0],
+        ] {
+            let mut dsp = three_band(crossovers_hz, [0.0; 3], sample_rate);
+            let output = split_three(&mut dsp, &input);
+
+            for (channel, (input, output)) in input.iter().zip(&output.sum).enumerate() {
+                let reference = reference_allpass(input, &crossovers_hz, sample_rate);
+                let label = format!("{crossovers_hz:?} Hz at {sample_rate} Hz, channel {channel}");
+                assert_nulls(output, &reference, input, &label);
+            }
+        }
+    }
+}
+
+#[test]
+fn main_output_is_the_sum_of_the_bands() {
+    let input = [noise(LEN, 5), noise(LEN, 6)];
+
+    let mut dsp = three_band([300.0, 3000.0], [-6.0, 3.0, -20.0], 48_000.0);
+    let output = split_three(&mut dsp, &input);
+    for channel in 0..2 {
+        for n in 0..LEN {
+            let sum: f32 = output.bands.iter().map(|band| band[channel][n]).sum();
+            assert!((output.sum[channel][n] - sum).abs() < 1e-6);
+        }
+    }
+}
+
+#[test]
+fn bands_are_split_at_the_crossovers() {
+    const SAMPLE_RATE: f64 = 48_000.0;
+    let crossovers_hz = [200.0, 4000.0];
+    let gains_db = [-6.0, 0.0, 6.0];
+
+    // The level of each band for tones well inside each band and at the crossovers.
+    let cases: [(f32, [f32; 3]); 5] = [
+        (20.0, [-6.0, f32::NEG_INFINITY, f32::NEG_INFINITY]),
+        (200.0, [-12.0, -6.0, f32::NEG_INFINITY]),
+        (900.0, [f32::NEG_INFINITY, 0.0, f32::NEG_INFINITY]),
+        (4000.0, [f32::NEG_INFINITY, -6.0, 0.0]),
+        (20_000.0, [f32::NEG_INFINITY, f32::NEG_INFINITY, 6.0]),
+    ];
+
+    for (freq_hz, expected_db) in cases {
+        let tone = sine(freq_hz, SAMPLE_RATE, 2 * 48_000);
+        let mut dsp = three_band(crossovers_hz, gains_db, SAMPLE_RATE);
+        let output = split_three(&mut dsp, &[tone.clone(), tone.clone()]);
+
+        let settled = tone.len() / 2;
+        for (band, expected_db) in expected_db.into_iter().enumerate() {
+            let level_db =
+                gain_to_db(rms(&output.bands[band][0][settled..]) / rms(&tone[settled..]));
+            // Well outside the band only a ceiling is checked, since it depends on how far
+            // outside it is.
+            let ok = if expected_db == f32::NEG_INFINITY {
+                level_db < -20.0
+            } else {
+                (level_db - expected_db).abs() < 0.25
+            };
+            assert!(
+                ok,
+                "{freq_hz} Hz, band {band}: {level_db} dB, expected {expected_db} dB"
+            );
+        }
+    }
+}
+
+/// A crossover sweeping across the whole range every 100 ms keeps summing to a nearly flat
+/// magnitude response, and gain changes don't jump.
+#[test]
+fn automation_is_smooth() {
+    const SAMPLE_RATE: f64 = 48_000.0;
+    let tone = sine(1000.0, SAMPLE_RATE, 4800);
+    let mut dsp = two_band(20.0, [0.0; 2], SAMPLE_RATE);
+    let mut previous = 0.0;
+
+    for (i, chunk) in tone.chunks(64).enumerate() {
+        let crossover_hz = if (i / 75) % 2 == 0 { 20_000.0 } else { 20.0 };
+        dsp.set_params(TwoBandParams {
+            crossover_hz,
+            gains_db: if i >= 40 { [-30.0, -30.0] } else { [0.0; 2] },
+        });
+
+        let output = split_two(&mut dsp, &[chunk.to_vec(), chunk.to_vec()]);
+        for &x in &output.sum[0] {
+            assert!(x.is_finite() && x.abs() <= 1.1, "{x}");
+            // A 1 kHz sine moves by at most 0.131 per sample at 48 kHz.
+            assert!((x - previous).abs() < 0.15, "{previous} to {x}");
+            previous = x;
+        }
+    }
+}
@@ -0,0 +1,43 @@
+//! The signals the DSP crates' tests run through their processors. Only meant as a
+//! dev-dependency.
+
+use std::f32::consts::PI;
+
+/// White noise between -1 and 1. The same `seed` always gives the same noise, and it must
+/// not be zero.
+pub fn noise(len: usize, seed: u32) -> Vec<f32> {
+    let mut state = Xorshift(seed);
+    (0..len)
+        .map(|_| (state.next() as f32 / u32::MAX as f32) * 2.0 - 1.0)
+        .collect()
+}
+
+/// Noise quantized to 24 bits, like anything recorded through an audio interface.
+pub fn noise_24_bit(len: usize, seed: u32) -> Vec<f32> {
+    let mut state = Xorshift(seed);
+    (0..len)
+        .map(|_| (state.next() as i32 >> 8) as f32 / (1 << 23) as f32)
+        .collect()
+}
+
+/// A full scale sine.
+pub fn sine(freq_hz: f32, sample_rate: f64, len: usize) -> Vec<f32> {
+    (0..len)
+        .map(|n| (2.0 * PI * freq_hz * n as f32 / sample_rate as f32).sin())
+        .collect()
+}
+
+pub fn rms(signal: &[f32]) -> f32 {
+    (signal.iter().map(|x| x * x).sum::<f32>() / signal.len() as f32).sqrt()
+}
+
+struct Xorshift(u32);
+
+impl Xorshift {
+    fn next(&mut self) -> u32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0
+    }
+}
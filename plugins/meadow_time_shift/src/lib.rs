@@ -0,0 +1,172 @@
+use meadow_time_shift_dsp::{MAX_DELAY_MS, TimeShiftDsp};
+use nih_plug::prelude::*;
+use std::sync::Arc;
+
+/// The range of the delay in samples, which is as long as [`MAX_DELAY_MS`] at 48 kHz.
+const MAX_DELAY_SAMPLES: f32 = 48_000.0;
+
+pub struct MeadowTimeShift {
+    params: Arc<MeadowTimeShiftParams>,
+    dsp: TimeShiftDsp,
+}
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+enum DelayUnit {
+    #[id = "samples"]
+    Samples,
+    #[id = "ms"]
+    Milliseconds,
+}
+
+#[derive(Params)]
+struct MeadowTimeShiftParams {
+    #[id = "unit"]
+    pub unit: EnumParam<DelayUnit>,
+    /// Used when the unit is [`DelayUnit::Samples`].
+    ///
+    /// Negative delays come from latency. The plugin always reports the latency of the most
+    /// negative delay, so the delay can be automated without the host restarting processing,
+    /// but even a positive delay costs a second of latency.
+    #[id = "delay_samples"]
+    pub delay_samples: FloatParam,
+    /// Used when the unit is [`DelayUnit::Milliseconds`], with the same latency as
+    /// [`Self::delay_samples`].
+    #[id = "delay_ms"]
+    pub delay_ms: FloatParam,
+}
+
+impl Default for MeadowTimeShift {
+    fn default() -> Self {
+        Self {
+            params: Arc::new(MeadowTimeShiftParams::default()),
+            dsp: TimeShiftDsp::new(44_100.0),
+        }
+    }
+}
+
+fn delay_range(max: f32) -> FloatRange {
+    FloatRange::SymmetricalSkewed {
+        min: -max,
+        max,
+        factor: FloatRange::skew_factor(-2.0),
+        center: 0.0,
+    }
+}
+
+impl Default for MeadowTimeShiftParams {
+    fn default() -> Self {
+        Self {
+            unit: EnumParam::new("Unit", DelayUnit::Milliseconds),
+            delay_samples: FloatParam::new("Delay", 0.0, delay_range(MAX_DELAY_SAMPLES))
+                .with_unit(" samples")
+                .with_value_to_string(formatters::v2s_f32_rounded(2)),
+            delay_ms: FloatParam::new("Delay (ms)", 0.0, delay_range(MAX_DELAY_MS as f32))
+                .with_unit(" ms")
+                .with_value_to_string(formatters::v2s_f32_rounded(3)),
+        }
+    }
+}
+
+impl MeadowTimeShiftParams {
+    /// The delay in samples at `sample_rate`, in whichever unit is selected.
+    pub fn delay_samples(&self, sample_rate: f64) -> f64 {
+        match self.unit.value() {
+            DelayUnit::Samples => self.delay_samples.value() as f64,
+            DelayUnit::Milliseconds => self.delay_ms.value() as f64 / 1000.0 * sample_rate,
+        }
+    }
+}
+
+impl Plugin for MeadowTimeShift {
+    const NAME: &'static str = "Meadow Time Shift";
+    const VENDOR: &'static str = "Billy Messenger";
+    const URL: &'static str = env!("CARGO_PKG_HOMEPAGE");
+    const EMAIL: &'static str = "60663878+BillyDM@users.noreply.github.com";
+
+    const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+
+    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[AudioIOLayout {
+        main_input_channels: NonZeroU32::new(2),
+        main_output_channels: NonZeroU32::new(2),
+
+        aux_input_ports: &[],
+        aux_output_ports: &[],
+
+        names: PortNames::const_default(),
+    }];
+
+    const MIDI_INPUT: MidiConfig = MidiConfig::None;
+    const MIDI_OUTPUT: MidiConfig = MidiConfig::None;
+
+    const SAMPLE_ACCURATE_AUTOMATION: bool = true;
+
+    type SysExMessage = ();
+    type BackgroundTask = ();
+
+    fn params(&self) -> Arc<dyn Params> {
+        self.params.clone()
+    }
+
+    fn initialize(
+        &mut self,
+        _audio_io_layout: &AudioIOLayout,
+        config: &BufferConfig,
+        context: &mut impl InitContext<Self>,
+    ) -> bool {
+        let sample_rate = config.sample_rate as f64;
+        self.dsp = TimeShiftDsp::new(sample_rate);
+        self.dsp
+            .set_delay_samples(self.params.delay_samples(sample_rate));
+        context.set_latency_samples(self.dsp.latency_samples());
+
+        true
+    }
+
+    fn reset(&mut self) {
+        self.dsp.reset();
+    }
+
+    fn process(
+        &mut self,
+        buffer: &mut Buffer,
+        _aux: &mut AuxiliaryBuffers,
+        _context: &mut impl ProcessContext<Self>,
+    ) -> ProcessStatus {
+        self.dsp
+            .set_delay_samples(self.params.delay_samples(self.dsp.sample_rate()));
+
+        let [out_l, out_r, ..] = buffer.as_slice() else {
+            return ProcessStatus::Normal;
+        };
+
+        self.dsp.process(out_l, out_r);
+
+        ProcessStatus::Normal
+    }
+}
+
+impl ClapPlugin for MeadowTimeShift {
+    const CLAP_ID: &'static str = "app.meadowlark.meadow-time-shift";
+    const CLAP_DESCRIPTION: Option<&'static str> =
+        Some("Delays the signal by a fractional or negative amount of time");
+    const CLAP_MANUAL_URL: Option<&'static str> = Some(Self::URL);
+    const CLAP_SUPPORT_URL: Option<&'static str> = None;
+
+    const CLAP_FEATURES: &'static [ClapFeature] = &[
+        ClapFeature::AudioEffect,
+        ClapFeature::Stereo,
+        ClapFeature::Utility,
+    ];
+}
+
+impl Vst3Plugin for MeadowTimeShift {
+    const VST3_CLASS_ID: [u8; 16] = *b"Meadowlark.TimeS";
+
+    const VST3_SUBCATEGORIES: &'static [Vst3SubCategory] =
+        &[Vst3SubCategory::Fx, Vst3SubCategory::Tools];
+}
+
+#[cfg(not(feature = "bundled"))]
+nih_export_clap!(MeadowTimeShift);
+#[cfg(not(feature = "bundled"))]
+nih_export_vst3!(MeadowTimeShift);
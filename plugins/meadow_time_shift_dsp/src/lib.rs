@@ -0,0 +1,151 @@
+//! Delays a signal by a fractional number of samples, which can also be negative.
+//!
+//! Fractional delays read between samples with the 4-point, 3rd order Hermite interpolator
+//! (x-form) from Olli Niemitalo's "Polynomial Interpolators for High-Quality Resampling of
+//! Oversampled Audio". It passes through the samples themselves at whole delays, so those
+//! are bit exact.
+//!
+//! A delay line can't look into the future, so negative delays come from latency: the DSP
+//! always reports enough latency for the most negative delay, and delays by the latency plus
+//! the (negative) setting. Once the host compensates for the latency, the signal comes out
+//! early by the set amount. Interpolating also needs one sample of lookahead, which adds one
+//! more sample of latency.
+//!
+//! The latency never changes with the delay, so automating the delay doesn't make the host
+//! restart processing and every change glides. The price is [`MAX_DELAY_MS`] of latency even
+//! when the delay is positive.
+
+use meadow_common_dsp::Ramp;
+
+#[cfg(test)]
+mod tests;
+
+/// The longest delay in either direction.
+pub const MAX_DELAY_MS: f64 = 1000.0;
+
+/// How long it takes to glide to a new delay.
+pub const SMOOTHING_MS: f64 = 50.0;
+
+pub struct TimeShiftDsp {
+    sample_rate: f64,
+    max_delay_samples: f64,
+
+    delay_samples: f64,
+    latency: u32,
+
+    /// The delay the delay line runs at, which is the set delay plus the latency. This glides
+    /// to its target.
+    line_delay: Ramp<f64>,
+    ramp_len: usize,
+
+    buffers: [Vec<f32>; 2],
+    /// The buffer length minus one, as the buffers are a power of two long.
+    mask: usize,
+    write_pos: usize,
+}
+
+impl TimeShiftDsp {
+    pub fn new(sample_rate: f64) -> Self {
+        assert!(sample_rate > 0.0);
+
+        let max_delay_samples = (MAX_DELAY_MS / 1000.0 * sample_rate).floor();
+        // The most negative delay that isn't whole still needs one sample of lookahead.
+        let latency = max_delay_samples as u32 + 1;
+        // The longest line delay is the latency plus a full positive delay, and the
+        // interpolator reads two samples further back.
+        let len = (2 * max_delay_samples as usize + 4).next_power_of_two();
+
+        Self {
+            sample_rate,
+            max_delay_samples,
+            delay_samples: 0.0,
+            latency,
+            line_delay: Ramp::new(latency as f64),
+            ramp_len: ((SMOOTHING_MS / 1000.0 * sample_rate).round() as usize).max(1),
+            buffers: [vec![0.0; len], vec![0.0; len]],
+            mask: len - 1,
+            write_pos: 0,
+        }
+    }
+
+    pub fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+
+    /// The longest delay in either direction at this sample rate.
+    pub fn max_delay_samples(&self) -> f64 {
+        self.max_delay_samples
+    }
+
+    /// The latency to report to the host, which only depends on the sample rate.
+    pub fn latency_samples(&self) -> u32 {
+        self.latency
+    }
+
+    /// Sets the delay in samples, which is clamped to [`Self::max_delay_samples()`].
+    /// Negative values make the signal come out early once the host compensates for
+    /// [`Self::latency_samples()`].
+    ///
+    /// The delay glides to the new value over [`SMOOTHING_MS`].
+    pub fn set_delay_samples(&mut self, delay_samples: f64) {
+        let delay_samples = delay_samples.clamp(-self.max_delay_samples, self.max_delay_samples);
+        if delay_samples == self.delay_samples {
+            return;
+        }
+
+        self.line_delay
+            .set_target(delay_samples + self.latency as f64, self.ramp_len);
+        self.delay_samples = delay_samples;
+    }
+
+    /// Clears the delay line and jumps to the current delay.
+    pub fn reset(&mut self) {
+        for buffer in self.buffers.iter_mut() {
+            buffer.fill(0.0);
+        }
+        self.line_delay.snap();
+    }
+
+    pub fn process(&mut self, buf_l: &mut [f32], buf_r: &mut [f32]) {
+        for (l, r) in buf_l.iter_mut().zip(buf_r.iter_mut()) {
+            self.line_delay.next();
+
+            self.buffers[0][self.write_pos] = *l;
+            self.buffers[1][self.write_pos] = *r;
+
+            *l = self.read(0);
+            *r = self.read(1);
+
+            self.write_pos = (self.write_pos + 1) & self.mask;
+        }
+    }
+
+    /// Reads the sample `self.line_delay` samples before the one just written.
+    #[inline(always)]
+    fn read(&self, channel: usize) -> f32 {
+        let buffer = &self.buffers[channel];
+        let line_delay = self.line_delay.value();
+        let whole = line_delay.floor();
+        let frac = (line_delay - whole) as f32;
+        let newer_pos = self.write_pos.wrapping_sub(whole as usize) & self.mask;
+
+        if frac == 0.0 {
+            return buffer[newer_pos];
+        }
+
+        // The interpolator runs from `y0` (older) to `y1` (newer). The latency keeps the line
+        // delay at one sample or more, so the sample after `y1` has always been written.
+        let at = |offset: usize| buffer[newer_pos.wrapping_sub(offset) & self.mask];
+        let y1 = buffer[newer_pos];
+        let y2 = buffer[(newer_pos + 1) & self.mask];
+        let (ym1, y0) = (at(2), at(1));
+        let x = 1.0 - frac;
+
+        let c0 = y0;
+        let c1 = 0.5 * (y1 - ym1);
+        let c2 = ym1 - 2.5 * y0 + 2.0 * y1 - 0.5 * y2;
+        let c3 = 0.5 * (y2 - ym1) + 1.5 * (y0 - y1);
+
+        ((c3 * x + c2) * x + c1) * x + c0
+    }
+}
@@ -0,0 +1,188 @@
+use meadow_test_signals::noise;
+use std::f64::consts::PI;
+
+use crate::{SMOOTHING_MS, TimeShiftDsp};
+
+const SAMPLE_RATE: f64 = 48_000.0;
+
+/// The latency at [`SAMPLE_RATE`]: a second, plus one sample for the interpolator.
+const LATENCY: usize = 48_001;
+
+/// A `TimeShiftDsp` that starts out at `delay_samples`.
+fn time_shift(delay_samples: f64) -> TimeShiftDsp {
+    let mut dsp = TimeShiftDsp::new(SAMPLE_RATE);
+    dsp.set_delay_samples(delay_samples);
+    dsp.reset();
+    dsp
+}
+
+/// Runs `input` through `dsp` and drops the latency from the output, like a host
+/// compensating for it would.
+fn run(dsp: &mut TimeShiftDsp, input: &[f32]) -> Vec<f32> {
+    let mut l = input.to_vec();
+    l.resize(input.len() + LATENCY, 0.0);
+    let mut r = l.clone();
+    for (l, r) in l.chunks_mut(100).zip(r.chunks_mut(100)) {
+        dsp.process(l, r);
+    }
+    assert_eq!(l, r);
+
+    l.split_off(LATENCY)
+}
+
+/// The group delay of `impulse_response` at `freq_hz`, from the slope of its phase.
+fn group_delay(impulse_response: &[f32], freq_hz: f64) -> f64 {
+    let phase = |freq_hz: f64| {
+        let w = 2.0 * PI * freq_hz / SAMPLE_RATE;
+        let (re, im) = impulse_response
+            .iter()
+            .enumerate()
+            .fold((0.0, 0.0), |(re, im), (n, &h)| {
+                let h = h as f64;
+                (re + h * (w * n as f64).cos(), im - h * (w * n as f64).sin())
+            });
+        f64::atan2(im, re)
+    };
+
+    let dw = 2.0 * PI * 0.01 / SAMPLE_RATE;
+    -(phase(freq_hz + 0.01) - phase(freq_hz)) / dw
+}
+
+#[test]
+fn latency_does_not_depend_on_the_delay() {
+    let mut dsp = time_shift(0.0);
+    assert_eq!(dsp.latency_samples(), LATENCY as u32);
+
+    for delay_samples in [5.0, 0.5, -0.5, -2.25, -48_000.0, 48_000.0] {
+        dsp.set_delay_samples(delay_samples);
+        assert_eq!(
+            dsp.latency_samples(),
+            LATENCY as u32,
+            "{delay_samples} samples"
+        );
+    }
+}
+
+#[test]
+fn whole_delays_are_bit_exact() {
+    let input = noise(4096, 1);
+
+    for delay_samples in [0isize, 1, 7, 1000, -3, -1000] {
+        let mut dsp = time_shift(delay_samples as f64);
+        let output = run(&mut dsp, &input);
+
+        let shift = delay_samples.unsigned_abs();
+        if delay_samples >= 0 {
+            assert!(output[..shift].iter().all(|&x| x == 0.0));
+            assert_eq!(
+                output[shift..],
+                input[..input.len() - shift],
+                "{delay_samples} samples"
+            );
+        } else {
+            assert_eq!(
+                output[..input.len() - shift],
+                input[shift..],
+                "{delay_samples} samples"
+            );
+        }
+    }
+}
+
+#[test]
+fn fractional_delays_have_the_exact_group_delay() {
+    // The impulse comes late enough that negative delays can move it earlier.
+    const IMPULSE_AT: usize = 32;
+
+    for delay_samples in [0.25, 0.5, 1.75, 3.7, 10.1, -0.5, -2.75, -20.3] {
+        let mut dsp = time_shift(delay_samples);
+        let mut impulse = vec![0.0; 2 * IMPULSE_AT];
+        impulse[IMPULSE_AT] = 1.0;
+        let impulse_response = run(&mut dsp, &impulse);
+
+        let sum: f32 = impulse_response.iter().sum();
+        let centroid = impulse_response
+            .iter()
+            .enumerate()
+            .map(|(n, &h)| n as f64 * h as f64)
+            .sum::<f64>()
+            - IMPULSE_AT as f64;
+        assert!(
+            (sum - 1.0).abs() < 1e-6,
+            "{delay_samples} samples: DC gain {sum}"
+        );
+        assert!(
+            (centroid - delay_samples).abs() < 1e-5,
+            "{delay_samples} samples: the group delay at DC is {centroid}"
+        );
+
+        for (freq_hz, tolerance) in [(100.0, 1e-4), (1000.0, 1e-3), (4000.0, 0.02)] {
+            let delay = group_delay(&impulse_response, freq_hz) - IMPULSE_AT as f64;
+            assert!(
+                (delay - delay_samples).abs() < tolerance,
+                "{delay_samples} samples: the group delay at {freq_hz} Hz is {delay}"
+            );
+        }
+    }
+}
+
+#[test]
+fn delay_changes_glide() {
+    let ramp_len = (SMOOTHING_MS / 1000.0 * SAMPLE_RATE) as usize;
+    let input: Vec<f32> = (0..4 * ramp_len)
+        .map(|n| (2.0 * PI * 100.0 * n as f64 / SAMPLE_RATE).sin() as f32)
+        .collect();
+
+    // Into negative delays too, since the latency stays where it is.
+    for delay_samples in [480.5, -480.5] {
+        let mut dsp = time_shift(0.0);
+        let mut l = input.clone();
+        l.resize(input.len() + LATENCY, 0.0);
+        let mut r = l.clone();
+        let (l_before, l_after) = l.split_at_mut(ramp_len);
+        let (r_before, r_after) = r.split_at_mut(ramp_len);
+        dsp.process(l_before, r_before);
+        dsp.set_delay_samples(delay_samples);
+        dsp.process(l_after, r_after);
+        let output = l.split_off(LATENCY);
+
+        // The pitch changes while the delay glides, but the output never jumps.
+        for (n, pair) in output.windows(2).enumerate() {
+            assert!(
+                (pair[1] - pair[0]).abs() < 0.05,
+                "{delay_samples} samples, sample {n}: {pair:?}"
+            );
+        }
+
+        // Halfway between two samples, which differ by at most 0.013 at this frequency.
+        let whole = delay_samples.floor() as isize;
+        for n in 3 * ramp_len..4 * ramp_len - 481 {
+            let at = |offset: isize| input[(n as isize - offset) as usize];
+            let expected = 0.5 * (at(whole) + at(whole + 1));
+            assert!(
+                (output[n] - expected).abs() < 1e-4,
+                "{delay_samples} samples, sample {n}"
+            );
+        }
+    }
+}
+
+#[test]
+fn delays_are_clamped() {
+    let mut dsp = TimeShiftDsp::new(SAMPLE_RATE);
+    assert_eq!(dsp.max_delay_samples(), 48_000.0);
+
+    dsp.set_delay_samples(1e9);
+    dsp.reset();
+    let mut impulse = vec![0.0; 48_001];
+    impulse[0] = 1.0;
+    let output = run(&mut dsp, &impulse);
+    assert_eq!(output[48_000], 1.0);
+
+    dsp.set_delay_samples(-1e9);
+    dsp.reset();
+    let mut impulse = vec![0.0; 48_001];
+    impulse[48_000] = 1.0;
+    let output = run(&mut dsp, &impulse);
+    assert_eq!(output[0], 1.0);
+}
@@ -0,0 +1,222 @@
+use meadow_utility_dsp::{PanLaw, PanMode, UtilityDsp, UtilityParams};
+use nih_plug::prelude::*;
+use std::sync::Arc;
+
+pub struct MeadowUtility {
+    params: Arc<MeadowUtilityParams>,
+    dsp: UtilityDsp,
+}
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+enum PanLawParam {
+    #[id = "-3db"]
+    #[name = "-3 dB"]
+    Minus3Db,
+    #[id = "-4.5db"]
+    #[name = "-4.5 dB"]
+    Minus4_5Db,
+    #[id = "-6db"]
+    #[name = "-6 dB"]
+    Minus6Db,
+}
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+enum PanModeParam {
+    #[id = "balance"]
+    Balance,
+    #[id = "stereo_pan"]
+    #[name = "Stereo pan"]
+    StereoPan,
+}
+
+#[derive(Params)]
+struct MeadowUtilityParams {
+    #[id = "gain_db"]
+    pub gain_db: FloatParam,
+    #[id = "pan"]
+    pub pan: FloatParam,
+    #[id = "pan_law"]
+    pub pan_law: EnumParam<PanLawParam>,
+    #[id = "pan_mode"]
+    pub pan_mode: EnumParam<PanModeParam>,
+    #[id = "width"]
+    pub width: FloatParam,
+}
+
+impl Default for MeadowUtility {
+    fn default() -> Self {
+        Self {
+            params: Arc::new(MeadowUtilityParams::default()),
+            dsp: UtilityDsp::new(44_100.0),
+        }
+    }
+}
+
+impl Default for MeadowUtilityParams {
+    fn default() -> Self {
+        Self {
+            gain_db: FloatParam::new(
+                "Gain",
+                0.0,
+                FloatRange::SymmetricalSkewed {
+                    min: -60.0,
+                    max: 24.0,
+                    factor: FloatRange::skew_factor(-1.0),
+                    center: 0.0,
+                },
+            )
+            .with_unit(" dB")
+            .with_step_size(0.1),
+            pan: FloatParam::new(
+                "Pan",
+                0.0,
+                FloatRange::Linear {
+                    min: -1.0,
+                    max: 1.0,
+                },
+            )
+            .with_value_to_string(Arc::new(|v| {
+                let percent = (v * 100.0).round() as i32;
+                match percent {
+                    0 => String::from("C"),
+                    p if p < 0 => format!("L{}", -p),
+                    p => format!("R{p}"),
+                }
+            })),
+            pan_law: EnumParam::new("Pan law", PanLawParam::Minus3Db),
+            pan_mode: EnumParam::new("Pan mode", PanModeParam::Balance),
+            width: FloatParam::new(
+                "Width",
+                100.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 200.0,
+                },
+            )
+            .with_unit(" %")
+            .with_step_size(1.0),
+        }
+    }
+}
+
+impl MeadowUtilityParams {
+    /// Collects the current parameter values into the form used by the DSP.
+    pub fn utility_params(&self) -> UtilityParams {
+        UtilityParams {
+            gain_db: self.gain_db.value(),
+            pan: self.pan.value(),
+            pan_law: match self.pan_law.value() {
+                PanLawParam::Minus3Db => PanLaw::Minus3Db,
+                PanLawParam::Minus4_5Db => PanLaw::Minus4_5Db,
+                PanLawParam::Minus6Db => PanLaw::Minus6Db,
+            },
+            pan_mode: match self.pan_mode.value() {
+                PanModeParam::Balance => PanMode::Balance,
+                PanModeParam::StereoPan => PanMode::StereoPan,
+            },
+            width: self.width.value() / 100.0,
+        }
+    }
+}
+
+impl Plugin for MeadowUtility {
+    const NAME: &'static str = "Meadow Utility";
+    const VENDOR: &'static str = "Billy Messenger";
+    const URL: &'static str = env!("CARGO_PKG_HOMEPAGE");
+    const EMAIL: &'static str = "60663878+BillyDM@users.noreply.github.com";
+
+    const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+
+    // Pan and width only apply to the stereo layout. In mono, only the gain does anything.
+    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[
+        AudioIOLayout {
+            main_input_channels: NonZeroU32::new(2),
+            main_output_channels: NonZeroU32::new(2),
+
+            aux_input_ports: &[],
+            aux_output_ports: &[],
+
+            names: PortNames::const_default(),
+        },
+        AudioIOLayout {
+            main_input_channels: NonZeroU32::new(1),
+            main_output_channels: NonZeroU32::new(1),
+
+            aux_input_ports: &[],
+            aux_output_ports: &[],
+
+            names: PortNames::const_default(),
+        },
+    ];
+
+    const MIDI_INPUT: MidiConfig = MidiConfig::None;
+    const MIDI_OUTPUT: MidiConfig = MidiConfig::None;
+
+    const SAMPLE_ACCURATE_AUTOMATION: bool = true;
+
+    type SysExMessage = ();
+    type BackgroundTask = ();
+
+    fn params(&self) -> Arc<dyn Params> {
+        self.params.clone()
+    }
+
+    fn initialize(
+        &mut self,
+        _audio_io_layout: &AudioIOLayout,
+        config: &BufferConfig,
+        _context: &mut impl InitContext<Self>,
+    ) -> bool {
+        self.dsp = UtilityDsp::new(config.sample_rate as f64);
+        self.dsp.set_params(self.params.utility_params());
+
+        true
+    }
+
+    fn reset(&mut self) {
+        self.dsp.reset();
+    }
+
+    fn process(
+        &mut self,
+        buffer: &mut Buffer,
+        _aux: &mut AuxiliaryBuffers,
+        _context: &mut impl ProcessContext<Self>,
+    ) -> ProcessStatus {
+        self.dsp.set_params(self.params.utility_params());
+
+        match buffer.as_slice() {
+            [out_l, out_r, ..] => self.dsp.process(out_l, out_r),
+            [out] => self.dsp.process_mono(out),
+            [] => {}
+        }
+
+        ProcessStatus::Normal
+    }
+}
+
+impl ClapPlugin for MeadowUtility {
+    const CLAP_ID: &'static str = "app.meadowlark.meadow-utility";
+    const CLAP_DESCRIPTION: Option<&'static str> = Some("Gain, pan and stereo width");
+    const CLAP_MANUAL_URL: Option<&'static str> = Some(Self::URL);
+    const CLAP_SUPPORT_URL: Option<&'static str> = None;
+
+    const CLAP_FEATURES: &'static [ClapFeature] = &[
+        ClapFeature::AudioEffect,
+        ClapFeature::Stereo,
+        ClapFeature::Mono,
+        ClapFeature::Utility,
+    ];
+}
+
+impl Vst3Plugin for MeadowUtility {
+    const VST3_CLASS_ID: [u8; 16] = *b"Meadowlark.Utils";
+
+    const VST3_SUBCATEGORIES: &'static [Vst3SubCategory] =
+        &[Vst3SubCategory::Fx, Vst3SubCategory::Tools];
+}
+
+#[cfg(not(feature = "bundled"))]
+nih_export_clap!(MeadowUtility);
+#[cfg(not(feature = "bundled"))]
+nih_export_vst3!(MeadowUtility);
@@ -0,0 +1,207 @@
+//! Gain, panning and stereo width.
+//!
+//! All three are folded into one 2x2 matrix that is applied to every stereo sample frame.
+//! Parameter changes ramp the matrix towards its new value, so automating any of them doesn't
+//! click.
+
+use meadow_common_dsp::{Ramp, db_to_gain};
+use std::f32::consts::FRAC_PI_2;
+
+#[cfg(test)]
+mod tests;
+
+/// How long it takes to move to new parameters.
+pub const SMOOTHING_MS: f64 = 20.0;
+
+/// How loud a centered signal is in each channel, relative to the same signal panned hard to
+/// one side.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PanLaw {
+    /// Constant power, so the loudness doesn't change while panning.
+    #[default]
+    Minus3Db,
+    /// A compromise between the other two.
+    Minus4_5Db,
+    /// Constant amplitude, so the channels still sum to the same level in mono.
+    Minus6Db,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PanMode {
+    /// Turns down the channel on the side being panned away from. Nothing from one channel
+    /// ends up in the other.
+    #[default]
+    Balance,
+    /// Pans the channel on the side being panned away from towards the other one, so the
+    /// stereo image moves as a whole and collapses into one side when panned hard.
+    StereoPan,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UtilityParams {
+    pub gain_db: f32,
+    /// From `-1.0` (hard left) to `1.0` (hard right).
+    pub pan: f32,
+    pub pan_law: PanLaw,
+    pub pan_mode: PanMode,
+    /// The level of the side signal, from `0.0` (mono) through `1.0` (unchanged) to `2.0`.
+    pub width: f32,
+}
+
+impl Default for UtilityParams {
+    fn default() -> Self {
+        Self {
+            gain_db: 0.0,
+            pan: 0.0,
+            pan_law: PanLaw::default(),
+            pan_mode: PanMode::default(),
+            width: 1.0,
+        }
+    }
+}
+
+/// The gains a mono signal at `position`, from `-1.0` (hard left) to `1.0` (hard right), is
+/// sent to the left and right channels with.
+pub fn pan_gains(law: PanLaw, position: f32) -> (f32, f32) {
+    let t = 0.5 * (position.clamp(-1.0, 1.0) + 1.0);
+    let linear = (1.0 - t, t);
+    let constant_power = ((t * FRAC_PI_2).cos(), (t * FRAC_PI_2).sin());
+
+    match law {
+        PanLaw::Minus3Db => constant_power,
+        PanLaw::Minus4_5Db => (
+            (linear.0 * constant_power.0).sqrt(),
+            (linear.1 * constant_power.1).sqrt(),
+        ),
+        PanLaw::Minus6Db => linear,
+    }
+}
+
+/// A gain matrix, where `[ll, rl, lr, rr]` maps the input channels to the output channels as
+/// `out_l = ll * l + rl * r` and `out_r = lr * l + rr * r`.
+type Matrix = [f32; 4];
+
+fn multiply(a: Matrix, b: Matrix) -> Matrix {
+    [
+        a[0] * b[0] + a[1] * b[2],
+        a[0] * b[1] + a[1] * b[3],
+        a[2] * b[0] + a[3] * b[2],
+        a[2] * b[1] + a[3] * b[3],
+    ]
+}
+
+fn pan_matrix(params: &UtilityParams) -> Matrix {
+    let pan = params.pan.clamp(-1.0, 1.0);
+
+    match params.pan_mode {
+        PanMode::Balance => {
+            // Scaled so the side being panned towards stays at unity gain.
+            let (center, _) = pan_gains(params.pan_law, 0.0);
+            let (l, r) = pan_gains(params.pan_law, pan);
+            let (l, r) = ((l / center).min(1.0), (r / center).min(1.0));
+
+            [l, 0.0, 0.0, r]
+        }
+        PanMode::StereoPan => {
+            if pan >= 0.0 {
+                let (l, r) = pan_gains(params.pan_law, 2.0 * pan - 1.0);
+                [l, 0.0, r, 1.0]
+            } else {
+                let (l, r) = pan_gains(params.pan_law, 2.0 * pan + 1.0);
+                [1.0, l, 0.0, r]
+            }
+        }
+    }
+}
+
+fn width_matrix(width: f32) -> Matrix {
+    // Mid stays as it is and side is scaled by the width.
+    let width = width.max(0.0);
+    let same = 0.5 * (1.0 + width);
+    let other = 0.5 * (1.0 - width);
+
+    [same, other, other, same]
+}
+
+pub struct UtilityDsp {
+    params: UtilityParams,
+    sample_rate: f64,
+    ramp_len: usize,
+
+    /// The gain on its own, for mono signals.
+    gain: Ramp,
+    /// The gain, pan and width together, for stereo signals.
+    matrix: [Ramp; 4],
+}
+
+impl UtilityDsp {
+    pub fn new(sample_rate: f64) -> Self {
+        assert!(sample_rate > 0.0);
+
+        let params = UtilityParams::default();
+        let matrix = Self::matrix(&params);
+
+        Self {
+            params,
+            sample_rate,
+            ramp_len: ((SMOOTHING_MS / 1000.0 * sample_rate).round() as usize).max(1),
+            gain: Ramp::new(db_to_gain(params.gain_db)),
+            matrix: matrix.map(Ramp::new),
+        }
+    }
+
+    pub fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+
+    fn matrix(params: &UtilityParams) -> Matrix {
+        let gain = db_to_gain(params.gain_db);
+
+        multiply(pan_matrix(params), width_matrix(params.width)).map(|x| gain * x)
+    }
+
+    /// Sets new parameters, which are ramped to over [`SMOOTHING_MS`].
+    pub fn set_params(&mut self, params: UtilityParams) {
+        if self.params == params {
+            return;
+        }
+
+        self.gain
+            .set_target(db_to_gain(params.gain_db), self.ramp_len);
+        for (ramp, target) in self.matrix.iter_mut().zip(Self::matrix(&params)) {
+            ramp.set_target(target, self.ramp_len);
+        }
+
+        self.params = params;
+    }
+
+    /// Jumps to the current parameters without ramping.
+    pub fn reset(&mut self) {
+        self.gain.snap();
+        for ramp in self.matrix.iter_mut() {
+            ramp.snap();
+        }
+    }
+
+    pub fn process(&mut self, buf_l: &mut [f32], buf_r: &mut [f32]) {
+        for (l, r) in buf_l.iter_mut().zip(buf_r.iter_mut()) {
+            let [ll, rl, lr, rr] = [0, 1, 2, 3].map(|i| self.matrix[i].next());
+            // Keeps the mono ramp in step, in case the channel layout changes.
+            self.gain.next();
+
+            (*l, *r) = (ll * *l + rl * *r, lr * *l + rr * *r);
+        }
+    }
+
+    /// Processes a mono signal. Only the gain applies, since there is nothing to pan or
+    /// widen.
+    pub fn process_mono(&mut self, buf: &mut [f32]) {
+        for x in buf.iter_mut() {
+            for ramp in self.matrix.iter_mut() {
+                ramp.next();
+            }
+
+            *x *= self.gain.next();
+        }
+    }
+}
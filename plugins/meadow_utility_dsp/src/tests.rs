@@ -0,0 +1,160 @@
+use meadow_test_signals::noise;
+
+use crate::{PanLaw, PanMode, SMOOTHING_MS, UtilityDsp, UtilityParams, pan_gains};
+
+const SAMPLE_RATE: f64 = 48_000.0;
+const RAMP_LEN: usize = (SMOOTHING_MS / 1000.0 * SAMPLE_RATE) as usize;
+
+/// Runs noise through `dsp` with `params` already in effect, and returns the inputs and the
+/// outputs.
+fn run(params: UtilityParams) -> ([Vec<f32>; 2], [Vec<f32>; 2]) {
+    let mut dsp = UtilityDsp::new(SAMPLE_RATE);
+    dsp.set_params(params);
+    dsp.reset();
+
+    let input = [noise(1024, 1), noise(1024, 2)];
+    let [mut l, mut r] = input.clone();
+    dsp.process(&mut l, &mut r);
+
+    (input, [l, r])
+}
+
+fn assert_close(actual: &[f32], expected: impl IntoIterator<Item = f32>, label: &str) {
+    for (n, (&actual, expected)) in actual.iter().zip(expected).enumerate() {
+        assert!(
+            (actual - expected).abs() < 1e-5,
+            "{label}, sample {n}: {actual}, expected {expected}"
+        );
+    }
+}
+
+#[test]
+fn default_params_pass_the_signal_through() {
+    let ([in_l, in_r], [out_l, out_r]) = run(UtilityParams::default());
+
+    assert_close(&out_l, in_l.iter().copied(), "left");
+    assert_close(&out_r, in_r.iter().copied(), "right");
+}
+
+#[test]
+fn pan_laws_attenuate_the_center() {
+    for (law, expected_db) in [
+        (PanLaw::Minus3Db, -3.0),
+        (PanLaw::Minus4_5Db, -4.5),
+        (PanLaw::Minus6Db, -6.0),
+    ] {
+        let (l, r) = pan_gains(law, 0.0);
+        assert_eq!(l, r, "{law:?}");
+        let center_db = 20.0 * l.log10();
+        assert!(
+            (center_db - expected_db).abs() < 0.1,
+            "{law:?}: {center_db} dB at the center"
+        );
+
+        assert_eq!(pan_gains(law, -1.0), (1.0, 0.0), "{law:?}");
+        let (l, r) = pan_gains(law, 1.0);
+        assert!(
+            l.abs() < 1e-6 && r == 1.0,
+            "{law:?}: ({l}, {r}) panned hard right"
+        );
+    }
+}
+
+#[test]
+fn balance_keeps_the_near_side() {
+    for law in [PanLaw::Minus3Db, PanLaw::Minus4_5Db, PanLaw::Minus6Db] {
+        for pan in [0.5, 1.0] {
+            let ([in_l, in_r], [out_l, out_r]) = run(UtilityParams {
+                pan,
+                pan_law: law,
+                pan_mode: PanMode::Balance,
+                ..UtilityParams::default()
+            });
+
+            let label = format!("{law:?}, pan {pan}");
+            assert_close(&out_r, in_r.iter().copied(), &label);
+            let (l, _) = pan_gains(law, pan);
+            let far_gain = l / pan_gains(law, 0.0).0;
+            assert!(far_gain < 1.0, "{label}");
+            assert_close(&out_l, in_l.iter().map(|x| far_gain * x), &label);
+        }
+    }
+}
+
+#[test]
+fn stereo_pan_collapses_into_one_side() {
+    let ([in_l, in_r], [out_l, out_r]) = run(UtilityParams {
+        pan: -1.0,
+        pan_mode: PanMode::StereoPan,
+        ..UtilityParams::default()
+    });
+    assert_close(&out_l, in_l.iter().zip(&in_r).map(|(l, r)| l + r), "left");
+    assert_close(&out_r, [0.0; 1024], "right");
+
+    // Halfway, the right channel sits in the center.
+    let ([in_l, in_r], [out_l, out_r]) = run(UtilityParams {
+        pan: -0.5,
+        pan_law: PanLaw::Minus6Db,
+        pan_mode: PanMode::StereoPan,
+        ..UtilityParams::default()
+    });
+    assert_close(
+        &out_l,
+        in_l.iter().zip(&in_r).map(|(l, r)| l + 0.5 * r),
+        "left",
+    );
+    assert_close(&out_r, in_r.iter().map(|r| 0.5 * r), "right");
+}
+
+#[test]
+fn width_scales_the_side_signal() {
+    for width in [0.0, 0.5, 1.0, 2.0] {
+        let ([in_l, in_r], [out_l, out_r]) = run(UtilityParams {
+            width,
+            ..UtilityParams::default()
+        });
+
+        let mid = in_l.iter().zip(&in_r).map(|(l, r)| 0.5 * (l + r));
+        let side = in_l.iter().zip(&in_r).map(|(l, r)| 0.5 * (l - r));
+        let label = format!("width {width}");
+        assert_close(
+            &out_l,
+            mid.clone().zip(side.clone()).map(|(m, s)| m + width * s),
+            &label,
+        );
+        assert_close(&out_r, mid.zip(side).map(|(m, s)| m - width * s), &label);
+    }
+}
+
+#[test]
+fn gain_changes_are_ramped() {
+    let target = 10.0f32.powf(-12.0 / 20.0);
+    let max_step = (1.0 - target) / RAMP_LEN as f32 + 1e-6;
+
+    let mut dsp = UtilityDsp::new(SAMPLE_RATE);
+    dsp.set_params(UtilityParams {
+        gain_db: -12.0,
+        ..UtilityParams::default()
+    });
+
+    let mut l = vec![1.0; 2 * RAMP_LEN];
+    let mut r = vec![1.0; 2 * RAMP_LEN];
+    let mut mono = vec![1.0; 2 * RAMP_LEN];
+    dsp.process(&mut l[..RAMP_LEN / 2], &mut r[..RAMP_LEN / 2]);
+    dsp.process_mono(&mut mono[RAMP_LEN / 2..]);
+    // Let the stereo output pick up where the mono one is.
+    l[RAMP_LEN / 2..].copy_from_slice(&mono[RAMP_LEN / 2..]);
+
+    let mut previous = 1.0;
+    for (n, &x) in l.iter().enumerate() {
+        assert!(
+            x <= previous && previous - x <= max_step,
+            "sample {n}: {x} after {previous}"
+        );
+        previous = x;
+    }
+    assert!((l[0] - 1.0).abs() <= max_step);
+    assert_eq!(l[RAMP_LEN - 1], target);
+    assert_eq!(*l.last().unwrap(), target);
+    assert_eq!(r[..RAMP_LEN / 2], l[..RAMP_LEN / 2]);
+}
@@ -1,14 +1,177 @@
-pub fn add(left: u64, right: u64) -> u64 {
-    left + right
-}
+//! The whole plugin suite in a single bundle.
+//!
+//! Every plugin crate also builds its own bundle. Here they are depended on with their
+//! `bundled` feature, which leaves out their entry points so the ones below are the only ones
+//! in the library.
+
+use meadow_bus_compressor::MeadowBusCompressor;
+use meadow_compressor::MeadowCompressor;
+use meadow_dynamic_eq::MeadowDynamicEq;
+use meadow_dynamics::MeadowDynamics;
+use meadow_eq::MeadowEq;
+use meadow_eq_linear::MeadowEqLinear;
+use meadow_gate::MeadowGate;
+use meadow_left_right::{MeadowLeftRightMerge, MeadowLeftRightSplit};
+use meadow_limiter::MeadowLimiter;
+use meadow_mid_side::{MeadowMidSideMerge, MeadowMidSideSplit};
+use meadow_splitter::{MeadowSplitter2Band, MeadowSplitter3Band};
+use meadow_time_shift::MeadowTimeShift;
+use meadow_utility::MeadowUtility;
+use nih_plug::prelude::*;
+
+nih_export_clap!(
+    MeadowEq,
+    MeadowEqLinear,
+    MeadowDynamicEq,
+    MeadowUtility,
+    MeadowSplitter2Band,
+    MeadowSplitter3Band,
+    MeadowMidSideSplit,
+    MeadowMidSideMerge,
+    MeadowLeftRightSplit,
+    MeadowLeftRightMerge,
+    MeadowTimeShift,
+    MeadowCompressor,
+    MeadowDynamics,
+    MeadowLimiter,
+    MeadowGate,
+    MeadowBusCompressor
+);
+nih_export_vst3!(
+    MeadowEq,
+    MeadowEqLinear,
+    MeadowDynamicEq,
+    MeadowUtility,
+    MeadowSplitter2Band,
+    MeadowSplitter3Band,
+    MeadowMidSideSplit,
+    MeadowMidSideMerge,
+    MeadowLeftRightSplit,
+    MeadowLeftRightMerge,
+    MeadowTimeShift,
+    MeadowCompressor,
+    MeadowDynamics,
+    MeadowLimiter,
+    MeadowGate,
+    MeadowBusCompressor
+);
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// Hosts identify the plugins in a bundle by these, so they must not collide.
+    #[test]
+    fn plugin_ids_are_unique() {
+        let clap_ids = [
+            MeadowEq::CLAP_ID,
+            MeadowEqLinear::CLAP_ID,
+            MeadowDynamicEq::CLAP_ID,
+            MeadowUtility::CLAP_ID,
+            MeadowSplitter2Band::CLAP_ID,
+            MeadowSplitter3Band::CLAP_ID,
+            MeadowMidSideSplit::CLAP_ID,
+            MeadowMidSideMerge::CLAP_ID,
+            MeadowLeftRightSplit::CLAP_ID,
+            MeadowLeftRightMerge::CLAP_ID,
+            MeadowTimeShift::CLAP_ID,
+            MeadowCompressor::CLAP_ID,
+            MeadowDynamics::CLAP_ID,
+            MeadowLimiter::CLAP_ID,
+            MeadowGate::CLAP_ID,
+            MeadowBusCompressor::CLAP_ID,
+        ];
+        let vst3_ids = [
+            MeadowEq::VST3_CLASS_ID,
+            MeadowEqLinear::VST3_CLASS_ID,
+            MeadowDynamicEq::VST3_CLASS_ID,
+            MeadowUtility::VST3_CLASS_ID,
+            MeadowSplitter2Band::VST3_CLASS_ID,
+            MeadowSplitter3Band::VST3_CLASS_ID,
+            MeadowMidSideSplit::VST3_CLASS_ID,
+            MeadowMidSideMerge::VST3_CLASS_ID,
+            MeadowLeftRightSplit::VST3_CLASS_ID,
+            MeadowLeftRightMerge::VST3_CLASS_ID,
+            MeadowTimeShift::VST3_CLASS_ID,
+            MeadowCompressor::VST3_CLASS_ID,
+            MeadowDynamics::VST3_CLASS_ID,
+            MeadowLimiter::VST3_CLASS_ID,
+            MeadowGate::VST3_CLASS_ID,
+            MeadowBusCompressor::VST3_CLASS_ID,
+        ];
+
+        for i in 0..clap_ids.len() {
+            for j in i + 1..clap_ids.len() {
+                assert_ne!(clap_ids[i], clap_ids[j]);
+                assert_ne!(vst3_ids[i], vst3_ids[j]);
+            }
+        }
+    }
+
+    /// Every plugin in the bundle, reached through the entry point hosts use. This only
+    /// catches allocations in debug builds, see [`meadow_plugin_test`].
     #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
+    fn process_never_allocates() {
+        assert!(
+            cfg!(debug_assertions),
+            "the allocation checks only exist in debug builds, run this without --release"
+        );
+
+        // SAFETY: `clap_entry` is this bundle's entry point.
+        unsafe { meadow_plugin_test::check_bundle(std::ptr::addr_of!(clap_entry).cast()) };
+    }
+
+    #[test]
+    fn mid_side_split_then_merge_is_transparent() {
+        split_then_merge_is_transparent(MeadowMidSideSplit::CLAP_ID, MeadowMidSideMerge::CLAP_ID);
+    }
+
+    #[test]
+    fn left_right_split_then_merge_is_transparent() {
+        split_then_merge_is_transparent(
+            MeadowLeftRightSplit::CLAP_ID,
+            MeadowLeftRightMerge::CLAP_ID,
+        );
+    }
+
+    /// Runs a stereo signal through a split plugin and the merge plugin with the same
+    /// layout the other way round, with both at their defaults.
+    fn split_then_merge_is_transparent(split_id: &str, merge_id: &str) {
+        const SAMPLE_RATE: f64 = 48_000.0;
+        const BLOCK_SIZE: usize = 512;
+
+        let entry = std::ptr::addr_of!(clap_entry).cast();
+        let l = meadow_plugin_test::noise(BLOCK_SIZE, 1);
+        let r = meadow_plugin_test::noise(BLOCK_SIZE, 2);
+
+        // SAFETY: `clap_entry` is this bundle's entry point.
+        let split = unsafe {
+            meadow_plugin_test::process_block(
+                entry,
+                split_id,
+                SAMPLE_RATE,
+                &[vec![l.clone(), r.clone()]],
+            )
+        };
+        let [main, aux] = &split[..] else {
+            panic!("{split_id} should have a main and an aux output");
+        };
+        // The first channel is on both channels of the main output.
+        assert_eq!(main[0], main[1]);
+
+        // SAFETY: `clap_entry` is this bundle's entry point.
+        let merged = unsafe {
+            meadow_plugin_test::process_block(
+                entry,
+                merge_id,
+                SAMPLE_RATE,
+                &[vec![main[0].clone()], vec![aux[0].clone()]],
+            )
+        };
+        for (output, input) in merged[0].iter().zip([&l, &r]) {
+            for (a, b) in output.iter().zip(input) {
+                assert!((a - b).abs() < 1e-6, "{a} != {b}");
+            }
+        }
     }
 }